@@ -16,6 +16,7 @@ use std::ffi::CStr;
 use std::ffi::CString;
 use std::fmt;
 use std::os::raw::c_char;
+use std::os::raw::c_int;
 use std::os::raw::c_void;
 use std::os::raw::c_ulong;
 
@@ -101,6 +102,9 @@ pub const ERROR_OUT_OF_DATE_KHR: u32 = -1000001004i32 as u32;
 pub const ERROR_INCOMPATIBLE_DISPLAY_KHR: u32 = -1000003001i32 as u32;
 pub const ERROR_VALIDATION_FAILED_EXT: u32 = -1000011001i32 as u32;
 pub const ERROR_OUT_OF_POOL_MEMORY_KHR: u32 = -1000069000i32 as u32;
+pub const ERROR_NOT_PERMITTED_EXT: u32 = -1000174001i32 as u32;
+pub const ERROR_INVALID_EXTERNAL_HANDLE_KHR: u32 = -1000072003i32 as u32;
+pub const ERROR_FULL_SCREEN_EXCLUSIVE_MODE_LOST_EXT: u32 = -1000255000i32 as u32;
 
 pub type StructureType = u32;
 pub const STRUCTURE_TYPE_APPLICATION_INFO: u32 = 0;
@@ -174,8 +178,25 @@ pub const STRUCTURE_TYPE_PHYSICAL_DEVICE_MEMORY_PROPERTIES_2_KHR: u32 = 10000590
 pub const STRUCTURE_TYPE_SPARSE_IMAGE_FORMAT_PROPERTIES_2_KHR: u32 = 1000059007;
 pub const STRUCTURE_TYPE_PHYSICAL_DEVICE_SPARSE_IMAGE_FORMAT_INFO_2_KHR: u32 = 1000059008;
 pub const STRUCTURE_TYPE_VI_SURFACE_CREATE_INFO_NN: u32 = 1000062000;
+pub const STRUCTURE_TYPE_EXTERNAL_MEMORY_IMAGE_CREATE_INFO_KHR: u32 = 1000072001;
+pub const STRUCTURE_TYPE_IMPORT_MEMORY_FD_INFO_KHR: u32 = 1000074000;
+pub const STRUCTURE_TYPE_MEMORY_FD_PROPERTIES_KHR: u32 = 1000074001;
+pub const STRUCTURE_TYPE_IMPORT_SEMAPHORE_FD_INFO_KHR: u32 = 1000079000;
 pub const STRUCTURE_TYPE_PHYSICAL_DEVICE_PUSH_DESCRIPTOR_PROPERTIES_KHR: u32 = 1000080000;
 pub const STRUCTURE_TYPE_DESCRIPTOR_UPDATE_TEMPLATE_CREATE_INFO_KHR: u32 = 1000085000;
+pub const STRUCTURE_TYPE_IMPORT_FENCE_FD_INFO_KHR: u32 = 1000115000;
+pub const STRUCTURE_TYPE_PHYSICAL_DEVICE_PERFORMANCE_QUERY_FEATURES_KHR: u32 = 1000116000;
+pub const STRUCTURE_TYPE_PHYSICAL_DEVICE_PERFORMANCE_QUERY_PROPERTIES_KHR: u32 = 1000116001;
+pub const STRUCTURE_TYPE_QUERY_POOL_PERFORMANCE_CREATE_INFO_KHR: u32 = 1000116002;
+pub const STRUCTURE_TYPE_PERFORMANCE_QUERY_SUBMIT_INFO_KHR: u32 = 1000116003;
+pub const STRUCTURE_TYPE_ACQUIRE_PROFILING_LOCK_INFO_KHR: u32 = 1000116004;
+pub const STRUCTURE_TYPE_PERFORMANCE_COUNTER_KHR: u32 = 1000116005;
+pub const STRUCTURE_TYPE_PERFORMANCE_COUNTER_DESCRIPTION_KHR: u32 = 1000116006;
+pub const STRUCTURE_TYPE_DEVICE_QUEUE_GLOBAL_PRIORITY_CREATE_INFO_EXT: u32 = 1000174000;
+pub const STRUCTURE_TYPE_PHYSICAL_DEVICE_PRESENT_WAIT_FEATURES_KHR: u32 = 1000248000;
+pub const STRUCTURE_TYPE_PRESENT_ID_KHR: u32 = 1000294000;
+pub const STRUCTURE_TYPE_PHYSICAL_DEVICE_PRESENT_ID_FEATURES_KHR: u32 = 1000294001;
+pub const STRUCTURE_TYPE_PHYSICAL_DEVICE_EXTENDED_DYNAMIC_STATE_2_FEATURES_EXT: u32 = 1000377000;
 
 pub type SystemAllocationScope = u32;
 pub const SYSTEM_ALLOCATION_SCOPE_COMMAND: u32 = 0;
@@ -394,6 +415,33 @@ pub type QueryType = u32;
 pub const QUERY_TYPE_OCCLUSION: u32 = 0;
 pub const QUERY_TYPE_PIPELINE_STATISTICS: u32 = 1;
 pub const QUERY_TYPE_TIMESTAMP: u32 = 2;
+pub const QUERY_TYPE_PERFORMANCE_QUERY_KHR: u32 = 1000116000;
+
+pub type PerformanceCounterUnitKHR = u32;
+pub const PERFORMANCE_COUNTER_UNIT_GENERIC_KHR: u32 = 0;
+pub const PERFORMANCE_COUNTER_UNIT_PERCENTAGE_KHR: u32 = 1;
+pub const PERFORMANCE_COUNTER_UNIT_NANOSECONDS_KHR: u32 = 2;
+pub const PERFORMANCE_COUNTER_UNIT_BYTES_KHR: u32 = 3;
+pub const PERFORMANCE_COUNTER_UNIT_BYTES_PER_SECOND_KHR: u32 = 4;
+pub const PERFORMANCE_COUNTER_UNIT_KELVIN_KHR: u32 = 5;
+pub const PERFORMANCE_COUNTER_UNIT_WATTS_KHR: u32 = 6;
+pub const PERFORMANCE_COUNTER_UNIT_VOLTS_KHR: u32 = 7;
+pub const PERFORMANCE_COUNTER_UNIT_AMPS_KHR: u32 = 8;
+pub const PERFORMANCE_COUNTER_UNIT_HERTZ_KHR: u32 = 9;
+pub const PERFORMANCE_COUNTER_UNIT_CYCLES_KHR: u32 = 10;
+
+pub type PerformanceCounterScopeKHR = u32;
+pub const PERFORMANCE_COUNTER_SCOPE_COMMAND_BUFFER_KHR: u32 = 0;
+pub const PERFORMANCE_COUNTER_SCOPE_RENDER_PASS_KHR: u32 = 1;
+pub const PERFORMANCE_COUNTER_SCOPE_COMMAND_KHR: u32 = 2;
+
+pub type PerformanceCounterStorageKHR = u32;
+pub const PERFORMANCE_COUNTER_STORAGE_INT32_KHR: u32 = 0;
+pub const PERFORMANCE_COUNTER_STORAGE_INT64_KHR: u32 = 1;
+pub const PERFORMANCE_COUNTER_STORAGE_UINT32_KHR: u32 = 2;
+pub const PERFORMANCE_COUNTER_STORAGE_UINT64_KHR: u32 = 3;
+pub const PERFORMANCE_COUNTER_STORAGE_FLOAT32_KHR: u32 = 4;
+pub const PERFORMANCE_COUNTER_STORAGE_FLOAT64_KHR: u32 = 5;
 
 pub type SharingMode = u32;
 pub const SHARING_MODE_EXCLUSIVE: u32 = 0;
@@ -531,6 +579,11 @@ pub const DYNAMIC_STATE_DEPTH_BOUNDS: u32 = 5;
 pub const DYNAMIC_STATE_STENCIL_COMPARE_MASK: u32 = 6;
 pub const DYNAMIC_STATE_STENCIL_WRITE_MASK: u32 = 7;
 pub const DYNAMIC_STATE_STENCIL_REFERENCE: u32 = 8;
+pub const DYNAMIC_STATE_PATCH_CONTROL_POINTS_EXT: u32 = 1000377000;
+pub const DYNAMIC_STATE_RASTERIZER_DISCARD_ENABLE_EXT: u32 = 1000377001;
+pub const DYNAMIC_STATE_DEPTH_BIAS_ENABLE_EXT: u32 = 1000377002;
+pub const DYNAMIC_STATE_LOGIC_OP_EXT: u32 = 1000377003;
+pub const DYNAMIC_STATE_PRIMITIVE_RESTART_ENABLE_EXT: u32 = 1000377004;
 
 pub type Filter = u32;
 pub const FILTER_NEAREST: u32 = 0;
@@ -689,6 +742,15 @@ pub const PIPELINE_STAGE_BOTTOM_OF_PIPE_BIT: u32 = 0x00002000;
 pub const PIPELINE_STAGE_HOST_BIT: u32 = 0x00004000;
 pub const PIPELINE_STAGE_ALL_GRAPHICS_BIT: u32 = 0x00008000;
 pub const PIPELINE_STAGE_ALL_COMMANDS_BIT: u32 = 0x00010000;
+pub const PIPELINE_STAGE_COMMAND_PROCESS_BIT_NVX: u32 = 0x00020000;
+pub const PIPELINE_STAGE_CONDITIONAL_RENDERING_BIT_EXT: u32 = 0x00040000;
+pub const PIPELINE_STAGE_TASK_SHADER_BIT_NV: u32 = 0x00080000;
+pub const PIPELINE_STAGE_MESH_SHADER_BIT_NV: u32 = 0x00100000;
+pub const PIPELINE_STAGE_RAY_TRACING_SHADER_BIT_KHR: u32 = 0x00200000;
+pub const PIPELINE_STAGE_SHADING_RATE_IMAGE_BIT_NV: u32 = 0x00400000;
+pub const PIPELINE_STAGE_FRAGMENT_DENSITY_PROCESS_BIT_EXT: u32 = 0x00800000;
+pub const PIPELINE_STAGE_TRANSFORM_FEEDBACK_BIT_EXT: u32 = 0x01000000;
+pub const PIPELINE_STAGE_ACCELERATION_STRUCTURE_BUILD_BIT_KHR: u32 = 0x02000000;
 pub type PipelineStageFlags = Flags;
 pub type MemoryMapFlags = Flags;
 
@@ -848,6 +910,8 @@ pub const ACCESS_HOST_READ_BIT: u32 = 0x00002000;
 pub const ACCESS_HOST_WRITE_BIT: u32 = 0x00004000;
 pub const ACCESS_MEMORY_READ_BIT: u32 = 0x00008000;
 pub const ACCESS_MEMORY_WRITE_BIT: u32 = 0x00010000;
+pub const ACCESS_ACCELERATION_STRUCTURE_READ_BIT_KHR: u32 = 0x00200000;
+pub const ACCESS_ACCELERATION_STRUCTURE_WRITE_BIT_KHR: u32 = 0x00400000;
 pub type AccessFlags = Flags;
 
 
@@ -999,6 +1063,12 @@ pub const DESCRIPTOR_UPDATE_TEMPLATE_TYPE_END_RANGE_KHR: u32 = DESCRIPTOR_UPDATE
 pub const DESCRIPTOR_UPDATE_TEMPLATE_TYPE_RANGE_SIZE_KHR: u32 = (DESCRIPTOR_UPDATE_TEMPLATE_TYPE_PUSH_DESCRIPTORS_KHR - DESCRIPTOR_UPDATE_TEMPLATE_TYPE_DESCRIPTOR_SET_KHR + 1);
 pub type DescriptorUpdateTemplateCreateFlagsKHR = Flags;
 
+pub type QueueGlobalPriorityEXT = u32;
+pub const QUEUE_GLOBAL_PRIORITY_LOW_EXT: u32 = 128;
+pub const QUEUE_GLOBAL_PRIORITY_MEDIUM_EXT: u32 = 256;
+pub const QUEUE_GLOBAL_PRIORITY_HIGH_EXT: u32 = 512;
+pub const QUEUE_GLOBAL_PRIORITY_REALTIME_EXT: u32 = 1024;
+
 pub type PFN_vkAllocationFunction = extern "system" fn(*mut c_void, usize, usize, SystemAllocationScope) -> *mut c_void;
 pub type PFN_vkReallocationFunction = extern "system" fn(*mut c_void, *mut c_void, usize, usize, SystemAllocationScope) -> *mut c_void;
 pub type PFN_vkFreeFunction = extern "system" fn(*mut c_void, *mut c_void);
@@ -2257,6 +2327,102 @@ pub struct PresentInfoKHR {
     pub pResults: *mut Result,
 }
 
+#[repr(C)]
+pub struct PresentIdKHR {
+    pub sType: StructureType,
+    pub pNext: *const c_void,
+    pub swapchainCount: u32,
+    pub pPresentIds: *const u64,
+}
+
+#[repr(C)]
+pub struct PhysicalDevicePresentIdFeaturesKHR {
+    pub sType: StructureType,
+    pub pNext: *mut c_void,
+    pub presentId: Bool32,
+}
+
+#[repr(C)]
+pub struct PhysicalDeviceExtendedDynamicState2FeaturesEXT {
+    pub sType: StructureType,
+    pub pNext: *mut c_void,
+    pub extendedDynamicState2: Bool32,
+    pub extendedDynamicState2LogicOp: Bool32,
+    pub extendedDynamicState2PatchControlPoints: Bool32,
+}
+
+#[repr(C)]
+pub struct PhysicalDevicePresentWaitFeaturesKHR {
+    pub sType: StructureType,
+    pub pNext: *mut c_void,
+    pub presentWait: Bool32,
+}
+
+#[repr(C)]
+pub struct PhysicalDevicePerformanceQueryFeaturesKHR {
+    pub sType: StructureType,
+    pub pNext: *mut c_void,
+    pub performanceCounterQueryPools: Bool32,
+    pub performanceCounterMultipleQueryPools: Bool32,
+}
+
+#[repr(C)]
+pub struct PerformanceCounterKHR {
+    pub sType: StructureType,
+    pub pNext: *mut c_void,
+    pub unit: PerformanceCounterUnitKHR,
+    pub scope: PerformanceCounterScopeKHR,
+    pub storage: PerformanceCounterStorageKHR,
+    pub uuid: [u8; UUID_SIZE as usize],
+}
+
+#[repr(C)]
+pub struct PerformanceCounterDescriptionKHR {
+    pub sType: StructureType,
+    pub pNext: *mut c_void,
+    pub flags: Flags,
+    pub name: [c_char; MAX_DESCRIPTION_SIZE as usize],
+    pub category: [c_char; MAX_DESCRIPTION_SIZE as usize],
+    pub description: [c_char; MAX_DESCRIPTION_SIZE as usize],
+}
+
+#[repr(C)]
+pub struct QueryPoolPerformanceCreateInfoKHR {
+    pub sType: StructureType,
+    pub pNext: *const c_void,
+    pub queueFamilyIndex: u32,
+    pub counterIndexCount: u32,
+    pub pCounterIndices: *const u32,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct PerformanceCounterResultKHR(u64);
+
+impl PerformanceCounterResultKHR {
+    #[inline] pub fn int32(&self) -> i32 { unsafe { *(&self.0 as *const u64 as *const i32) } }
+    #[inline] pub fn int64(&self) -> i64 { self.0 as i64 }
+    #[inline] pub fn uint32(&self) -> u32 { unsafe { *(&self.0 as *const u64 as *const u32) } }
+    #[inline] pub fn uint64(&self) -> u64 { self.0 }
+    #[inline] pub fn float32(&self) -> f32 { unsafe { *(&self.0 as *const u64 as *const f32) } }
+    #[inline] pub fn float64(&self) -> f64 { unsafe { mem::transmute(self.0) } }
+}
+
+#[repr(C)]
+pub struct AcquireProfilingLockInfoKHR {
+    pub sType: StructureType,
+    pub pNext: *const c_void,
+    pub flags: Flags,
+    pub timeout: u64,
+}
+
+#[repr(C)]
+pub struct PerformanceQuerySubmitInfoKHR {
+    pub sType: StructureType,
+    pub pNext: *const c_void,
+    pub counterPassIndex: u32,
+}
+
 
 #[repr(C)]
 pub struct DisplayPropertiesKHR {
@@ -2522,6 +2688,87 @@ pub struct DescriptorUpdateTemplateCreateInfoKHR {
     pub set: u32,
 }
 
+#[repr(C)]
+pub struct DeviceQueueGlobalPriorityCreateInfoEXT {
+    pub sType: StructureType,
+    pub pNext: *const c_void,
+    pub globalPriority: QueueGlobalPriorityEXT,
+}
+
+pub type ExternalMemoryHandleTypeFlagBitsKHR = u32;
+pub const EXTERNAL_MEMORY_HANDLE_TYPE_OPAQUE_FD_BIT_KHR: u32 = 0x00000001;
+pub const EXTERNAL_MEMORY_HANDLE_TYPE_OPAQUE_WIN32_BIT_KHR: u32 = 0x00000002;
+pub const EXTERNAL_MEMORY_HANDLE_TYPE_OPAQUE_WIN32_KMT_BIT_KHR: u32 = 0x00000004;
+pub const EXTERNAL_MEMORY_HANDLE_TYPE_D3D11_TEXTURE_BIT_KHR: u32 = 0x00000008;
+pub const EXTERNAL_MEMORY_HANDLE_TYPE_D3D11_TEXTURE_KMT_BIT_KHR: u32 = 0x00000010;
+pub const EXTERNAL_MEMORY_HANDLE_TYPE_D3D12_HEAP_BIT_KHR: u32 = 0x00000020;
+pub const EXTERNAL_MEMORY_HANDLE_TYPE_D3D12_RESOURCE_BIT_KHR: u32 = 0x00000040;
+pub const EXTERNAL_MEMORY_HANDLE_TYPE_DMA_BUF_BIT_EXT: u32 = 0x00000200;
+pub type ExternalMemoryHandleTypeFlagsKHR = Flags;
+
+#[repr(C)]
+pub struct ExternalMemoryImageCreateInfoKHR {
+    pub sType: StructureType,
+    pub pNext: *const c_void,
+    pub handleTypes: ExternalMemoryHandleTypeFlagsKHR,
+}
+
+#[repr(C)]
+pub struct ImportMemoryFdInfoKHR {
+    pub sType: StructureType,
+    pub pNext: *const c_void,
+    pub handleType: ExternalMemoryHandleTypeFlagBitsKHR,
+    pub fd: c_int,
+}
+
+#[repr(C)]
+pub struct MemoryFdPropertiesKHR {
+    pub sType: StructureType,
+    pub pNext: *mut c_void,
+    pub memoryTypeBits: u32,
+}
+
+pub type ExternalSemaphoreHandleTypeFlagBitsKHR = u32;
+pub const EXTERNAL_SEMAPHORE_HANDLE_TYPE_OPAQUE_FD_BIT_KHR: u32 = 0x00000001;
+pub const EXTERNAL_SEMAPHORE_HANDLE_TYPE_OPAQUE_WIN32_BIT_KHR: u32 = 0x00000002;
+pub const EXTERNAL_SEMAPHORE_HANDLE_TYPE_OPAQUE_WIN32_KMT_BIT_KHR: u32 = 0x00000004;
+pub const EXTERNAL_SEMAPHORE_HANDLE_TYPE_D3D12_FENCE_BIT_KHR: u32 = 0x00000008;
+pub const EXTERNAL_SEMAPHORE_HANDLE_TYPE_SYNC_FD_BIT_KHR: u32 = 0x00000010;
+
+pub type SemaphoreImportFlagBitsKHR = u32;
+pub const SEMAPHORE_IMPORT_TEMPORARY_BIT_KHR: u32 = 0x00000001;
+pub type SemaphoreImportFlagsKHR = Flags;
+
+#[repr(C)]
+pub struct ImportSemaphoreFdInfoKHR {
+    pub sType: StructureType,
+    pub pNext: *const c_void,
+    pub semaphore: Semaphore,
+    pub flags: SemaphoreImportFlagsKHR,
+    pub handleType: ExternalSemaphoreHandleTypeFlagBitsKHR,
+    pub fd: c_int,
+}
+
+pub type ExternalFenceHandleTypeFlagBitsKHR = u32;
+pub const EXTERNAL_FENCE_HANDLE_TYPE_OPAQUE_FD_BIT_KHR: u32 = 0x00000001;
+pub const EXTERNAL_FENCE_HANDLE_TYPE_OPAQUE_WIN32_BIT_KHR: u32 = 0x00000002;
+pub const EXTERNAL_FENCE_HANDLE_TYPE_OPAQUE_WIN32_KMT_BIT_KHR: u32 = 0x00000004;
+pub const EXTERNAL_FENCE_HANDLE_TYPE_SYNC_FD_BIT_KHR: u32 = 0x00000008;
+
+pub type FenceImportFlagBitsKHR = u32;
+pub const FENCE_IMPORT_TEMPORARY_BIT_KHR: u32 = 0x00000001;
+pub type FenceImportFlagsKHR = Flags;
+
+#[repr(C)]
+pub struct ImportFenceFdInfoKHR {
+    pub sType: StructureType,
+    pub pNext: *const c_void,
+    pub fence: Fence,
+    pub flags: FenceImportFlagsKHR,
+    pub handleType: ExternalFenceHandleTypeFlagBitsKHR,
+    pub fd: c_int,
+}
+
 
 macro_rules! ptrs {
     ($struct_name:ident, { $($name:ident => ($($param_n:ident: $param_ty:ty),*) -> $ret:ty,)+ }) => (
@@ -2626,6 +2873,8 @@ ptrs!(InstancePointers, {
     GetPhysicalDeviceQueueFamilyProperties2KHR => (physicalDevice: PhysicalDevice, pQueueFamilyPropertiesCount: *mut u32, pQueueFamilyProperties: *mut QueueFamilyProperties2KHR) -> (),
     GetPhysicalDeviceMemoryProperties2KHR => (physicalDevice: PhysicalDevice, pMemoryProperties: *mut PhysicalDeviceMemoryProperties2KHR) -> (),
     GetPhysicalDeviceSparseImageFormatProperties2KHR => (physicalDevice: PhysicalDevice, pFormatInfo: *const PhysicalDeviceSparseImageFormatInfo2KHR, pPropertyCount: *mut u32, pProperties: *mut SparseImageFormatProperties2KHR) -> (),
+    EnumeratePhysicalDeviceQueueFamilyPerformanceQueryCountersKHR => (physicalDevice: PhysicalDevice, queueFamilyIndex: u32, pCounterCount: *mut u32, pCounters: *mut PerformanceCounterKHR, pCounterDescriptions: *mut PerformanceCounterDescriptionKHR) -> Result,
+    GetPhysicalDeviceQueueFamilyPerformanceQueryPassesKHR => (physicalDevice: PhysicalDevice, pPerformanceQueryCreateInfo: *const QueryPoolPerformanceCreateInfoKHR, pNumPasses: *mut u32) -> (),
 });
 
 ptrs!(DevicePointers, {
@@ -2753,12 +3002,20 @@ ptrs!(DevicePointers, {
     CreateSwapchainKHR => (device: Device, pCreateInfo: *const SwapchainCreateInfoKHR, pAllocator: *const AllocationCallbacks, pSwapchain: *mut SwapchainKHR) -> Result,
     DestroySwapchainKHR => (device: Device, swapchain: SwapchainKHR, pAllocator: *const AllocationCallbacks) -> (),
     GetSwapchainImagesKHR => (device: Device, swapchain: SwapchainKHR, pSwapchainImageCount: *mut u32, pSwapchainImages: *mut Image) -> Result,
+    GetMemoryFdPropertiesKHR => (device: Device, handleType: ExternalMemoryHandleTypeFlagBitsKHR, fd: c_int, pMemoryFdProperties: *mut MemoryFdPropertiesKHR) -> Result,
+    ImportSemaphoreFdKHR => (device: Device, pImportSemaphoreFdInfo: *const ImportSemaphoreFdInfoKHR) -> Result,
+    ImportFenceFdKHR => (device: Device, pImportFenceFdInfo: *const ImportFenceFdInfoKHR) -> Result,
     AcquireNextImageKHR => (device: Device, swapchain: SwapchainKHR, timeout: u64, semaphore: Semaphore, fence: Fence, pImageIndex: *mut u32) -> Result,
     QueuePresentKHR => (queue: Queue, pPresentInfo: *const PresentInfoKHR) -> Result,
+    WaitForPresentKHR => (device: Device, swapchain: SwapchainKHR, presentId: u64, timeout: u64) -> Result,
+    AcquireProfilingLockKHR => (device: Device, pInfo: *const AcquireProfilingLockInfoKHR) -> Result,
+    ReleaseProfilingLockKHR => (device: Device) -> (),
     CreateSharedSwapchainsKHR => (device: Device, swapchainCount: u32, pCreateInfos: *const SwapchainCreateInfoKHR, pAllocator: *const AllocationCallbacks, pSwapchains: *mut SwapchainKHR) -> Result,
     CmdPushDescriptorSetKHR => (commandBuffer: CommandBuffer, pipelineBindPoint: PipelineBindPoint, layout: PipelineLayout, set: u32, descriptorWriteCount: u32, pDescriptorWrites: *const WriteDescriptorSet) -> (),
     CreateDescriptorUpdateTemplateKHR => (device: Device, pCreateInfo: *const DescriptorUpdateTemplateCreateInfoKHR, pAllocator: *const AllocationCallbacks, pDescriptorUpdateTemplate: *mut DescriptorUpdateTemplateKHR) -> Result,
     DestroyDescriptorUpdateTemplateKHR => (device: Device, descriptorUpdateTemplate: DescriptorUpdateTemplateKHR, pAllocator: *const AllocationCallbacks) -> (),
     UpdateDescriptorSetWithTemplateKHR => (device: Device, descriptorSet: DescriptorSet, descriptorUpdateTemplate: DescriptorUpdateTemplateKHR, pData: *const c_void) -> (),
     CmdPushDescriptorSetWithTemplateKHR => (commandBuffer: CommandBuffer, descriptorUpdateTemplate: DescriptorUpdateTemplateKHR, layout: PipelineLayout, set: u32, pData: *const c_void) -> (),
+    CmdSetPatchControlPointsEXT => (commandBuffer: CommandBuffer, patchControlPoints: u32) -> (),
+    CmdSetPrimitiveRestartEnableEXT => (commandBuffer: CommandBuffer, primitiveRestartEnable: Bool32) -> (),
 });
@@ -0,0 +1,89 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+// Compares the cost of the access checks of a `FenceSignalFuture` through `GpuFuture`, which
+// locks the state of the future for each check, and through `GpuFutureMut`, which doesn't.
+//
+// Run with `cargo bench --bench access_checks`. Requires a Vulkan implementation.
+
+extern crate vulkano;
+
+use std::time::Duration;
+use std::time::Instant;
+
+use vulkano::buffer::BufferUsage;
+use vulkano::buffer::CpuAccessibleBuffer;
+use vulkano::command_buffer::AutoCommandBufferBuilder;
+use vulkano::command_buffer::CommandBuffer;
+use vulkano::command_buffer::CommandBufferBuild;
+use vulkano::device::Device;
+use vulkano::device::DeviceExtensions;
+use vulkano::instance::Features;
+use vulkano::instance::Instance;
+use vulkano::instance::InstanceExtensions;
+use vulkano::instance::PhysicalDevice;
+use vulkano::sync::GpuFuture;
+use vulkano::sync::GpuFutureMut;
+
+// Number of buffers checked for each submission, as for a scene with many resources.
+const RESOURCES: usize = 4096;
+// Number of times all the buffers are checked.
+const ROUNDS: u32 = 100;
+
+fn main() {
+    let instance = match Instance::new(None, &InstanceExtensions::none(), None) {
+        Ok(i) => i,
+        Err(_) => {
+            println!("no Vulkan implementation available, skipping the benchmark");
+            return;
+        }
+    };
+
+    let physical = PhysicalDevice::enumerate(&instance).next().expect("no device available");
+    let family = physical.queue_families().find(|q| q.supports_graphics())
+                         .expect("couldn't find a graphical queue family");
+    let (device, mut queues) = Device::new(&physical, &Features::none(),
+                                           &DeviceExtensions::none(),
+                                           [(family, 0.5)].iter().cloned())
+                                   .expect("failed to create device");
+    let queue = queues.next().unwrap();
+
+    let buffers = (0 .. RESOURCES).map(|_| {
+        CpuAccessibleBuffer::from_data(&device, &BufferUsage::all(), Some(queue.family()), 0u32)
+            .unwrap()
+    }).collect::<Vec<_>>();
+
+    let cb = AutoCommandBufferBuilder::new(device.clone(), queue.family()).unwrap()
+        .build().unwrap();
+    let mut future = cb.execute(queue.clone()).then_signal_fence();
+
+    let start = Instant::now();
+    for _ in 0 .. ROUNDS {
+        for buffer in buffers.iter() {
+            let _ = future.check_buffer_access(buffer, false, &queue);
+        }
+    }
+    report("GpuFuture::check_buffer_access", start.elapsed());
+
+    let start = Instant::now();
+    for _ in 0 .. ROUNDS {
+        for buffer in buffers.iter() {
+            let _ = future.check_buffer_access_mut(buffer, false, &queue);
+        }
+    }
+    report("GpuFutureMut::check_buffer_access_mut", start.elapsed());
+
+    future.flush().unwrap();
+}
+
+fn report(name: &str, elapsed: Duration) {
+    let nanos = elapsed.as_secs() * 1_000_000_000 + elapsed.subsec_nanos() as u64;
+    let checks = RESOURCES as u64 * ROUNDS as u64;
+    println!("{}: {} ns per check", name, nanos / checks);
+}
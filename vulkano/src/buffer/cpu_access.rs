@@ -20,6 +20,7 @@ use std::marker::PhantomData;
 use std::mem;
 use std::ops::Deref;
 use std::ops::DerefMut;
+use std::ops::Range;
 use std::ptr;
 use std::sync::Arc;
 use std::sync::RwLock;
@@ -283,6 +284,25 @@ impl<T: ?Sized, A> CpuAccessibleBuffer<T, A> where T: Content + 'static, A: Memo
             lock: lock,
         })
     }
+
+    /// Makes the host writes to the given range of bytes of the buffer available to the device,
+    /// if the memory of the buffer isn't host-coherent.
+    ///
+    /// The range is widened to the `non_coherent_atom_size` limit of the physical device. See
+    /// `MappedDeviceMemory::flush_range()`.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if the range is out of range of the buffer.
+    ///
+    pub fn flush_range(&self, range: Range<usize>) -> Result<(), OomError> {
+        assert!(range.start <= range.end);
+        assert!(range.end <= self.inner.size());
+
+        let offset = self.memory.offset();
+        self.memory.mapped_memory().unwrap().flush_range(offset + range.start ..
+                                                         offset + range.end)
+    }
 }
 
 // FIXME: wrong
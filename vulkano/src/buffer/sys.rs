@@ -51,6 +51,8 @@ pub struct UnsafeBuffer {
     device: Arc<Device>,
     size: usize,
     usage: vk::BufferUsageFlags,
+    // True if the buffer was created with `Sharing::Exclusive`.
+    exclusive_sharing: bool,
 }
 
 impl UnsafeBuffer {
@@ -87,6 +89,11 @@ impl UnsafeBuffer {
             return Err(BufferCreationError::SparseResidencyAliasedFeatureNotEnabled);
         }
 
+        let exclusive_sharing = match sharing {
+            Sharing::Exclusive => true,
+            Sharing::Concurrent(_) => false,
+        };
+
         let buffer = {
             let (sh_mode, sh_indices) = match sharing {
                 Sharing::Exclusive => (vk::SHARING_MODE_EXCLUSIVE, SmallVec::<[u32; 8]>::new()),
@@ -145,6 +152,7 @@ impl UnsafeBuffer {
             device: device.clone(),
             size: size as usize,
             usage: usage_bits,
+            exclusive_sharing: exclusive_sharing,
         };
 
         Ok((obj, mem_reqs))
@@ -190,6 +198,13 @@ impl UnsafeBuffer {
         self.size
     }
 
+    /// Returns true if the buffer was created with exclusive sharing. Using such a buffer from a
+    /// queue family other than the one that owns it requires a queue family ownership transfer.
+    #[inline]
+    pub fn exclusive_sharing(&self) -> bool {
+        self.exclusive_sharing
+    }
+
     #[inline]
     pub fn usage_transfer_src(&self) -> bool {
         (self.usage & vk::BUFFER_USAGE_TRANSFER_SRC_BIT) != 0
@@ -31,6 +31,8 @@ use pipeline::ComputePipelineAbstract;
 use pipeline::GraphicsPipelineAbstract;
 use pipeline::vertex::VertexSource;
 use pipeline::input_assembly::Index;
+use sync::AccessFlagBits;
+use sync::PipelineStages;
 
 ///
 /// > **Note**: This trait is just a utility trait. Do not implement it yourself. Instead
@@ -171,6 +173,29 @@ pub unsafe trait CommandBufferBuilder: DeviceOwned {
         self.add(cmd)
     }
 
+    /// Adds a pipeline barrier with a single global memory barrier.
+    ///
+    /// All the memory writes performed by the previous commands at the `src_stages` stages with
+    /// the `src_access` accesses will be visible to the following commands at the `dst_stages`
+    /// stages for the `dst_access` accesses, whatever the buffer or image.
+    ///
+    /// Can only be used from outside a render pass.
+    ///
+    /// # Safety
+    ///
+    /// See the documentation of `CmdGlobalMemoryBarrier::new`.
+    ///
+    #[inline]
+    unsafe fn global_memory_barrier<O>(self, src_stages: PipelineStages,
+                                       dst_stages: PipelineStages, src_access: AccessFlagBits,
+                                       dst_access: AccessFlagBits) -> Result<O, CommandAddError>
+        where Self: Sized + AddCommand<commands_raw::CmdGlobalMemoryBarrier, Out = O>
+    {
+        let cmd = commands_raw::CmdGlobalMemoryBarrier::new(src_stages, dst_stages, src_access,
+                                                            dst_access);
+        self.add(cmd)
+    }
+
     /// Adds a command that draws.
     ///
     /// Can only be used from inside a render pass.
@@ -334,6 +359,15 @@ pub enum CommandAddError {
     /// Trying to execute a secondary command buffer in a primary command buffer of a different
     /// queue family.
     QueueFamilyMismatch,
+
+    /// The vertex buffers bound by the command exceed the `max_vertex_input_bindings` limit of
+    /// the device.
+    MaxVertexInputBindingsExceeded {
+        /// Index of the first binding plus the number of bound buffers.
+        requested: u32,
+        /// Maximum allowed value.
+        max: u32,
+    },
 }
 
 impl error::Error for CommandAddError {
@@ -359,6 +393,10 @@ impl error::Error for CommandAddError {
                 "trying to execute a secondary command buffer in a primary command buffer of a \
                  different queue family"
             },
+            CommandAddError::MaxVertexInputBindingsExceeded { .. } => {
+                "the vertex buffers bound by the command exceed the maximum number of vertex \
+                 input bindings"
+            },
         }
     }
 }
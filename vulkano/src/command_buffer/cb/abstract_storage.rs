@@ -143,9 +143,17 @@ pass_through!((), commands_raw::CmdDrawRaw);
 pass_through!((), commands_raw::CmdEndRenderPass);
 pass_through!((C), commands_raw::CmdExecuteCommands<C>);
 pass_through!((B), commands_raw::CmdFillBuffer<B>);
+pass_through!((), commands_raw::CmdGlobalMemoryBarrier);
 pass_through!((), commands_raw::CmdNextSubpass);
 pass_through!((Pc, Pl), commands_raw::CmdPushConstants<Pc, Pl>);
 pass_through!((S, D), commands_raw::CmdResolveImage<S, D>);
 pass_through!((), commands_raw::CmdSetEvent);
+pass_through!((), commands_raw::CmdSetPatchControlPoints);
+pass_through!((), commands_raw::CmdSetPrimitiveRestartEnable);
 pass_through!((), commands_raw::CmdSetState);
 pass_through!((B, D), commands_raw::CmdUpdateBuffer<B, D>);
+pass_through!((), commands_raw::CmdWaitEvents);
+pass_through!((), commands_raw::CmdWriteTimestamp);
+pass_through!((), commands_raw::CmdResetQueryPool);
+pass_through!((), commands_raw::CmdBeginQuery);
+pass_through!((), commands_raw::CmdEndQuery);
@@ -95,7 +95,9 @@ q_ty_impl_always!((S, D), commands_raw::CmdCopyBuffer<S, D>);
 q_ty_impl_always!((S, D), commands_raw::CmdCopyBufferToImage<S, D>);
 q_ty_impl_always!((S, D), commands_raw::CmdCopyImage<S, D>);
 q_ty_impl_always!((B), commands_raw::CmdFillBuffer<B>);
+q_ty_impl_always!((), commands_raw::CmdGlobalMemoryBarrier);
 q_ty_impl_always!((B, D), commands_raw::CmdUpdateBuffer<B, D>);
+q_ty_impl_always!((), commands_raw::CmdWriteTimestamp);
 
 macro_rules! q_ty_impl_graphics {
     (($($param:ident),*), $cmd:ty) => {
@@ -126,6 +128,8 @@ q_ty_impl_graphics!((), commands_raw::CmdClearAttachments);
 q_ty_impl_graphics!((), commands_raw::CmdDrawIndexedRaw);
 q_ty_impl_graphics!((B), commands_raw::CmdDrawIndirectRaw<B>);
 q_ty_impl_graphics!((), commands_raw::CmdDrawRaw);
+q_ty_impl_graphics!((), commands_raw::CmdSetPatchControlPoints);
+q_ty_impl_graphics!((), commands_raw::CmdSetPrimitiveRestartEnable);
 q_ty_impl_graphics!((), commands_raw::CmdEndRenderPass);
 q_ty_impl_graphics!((), commands_raw::CmdNextSubpass);
 q_ty_impl_graphics!((S, D), commands_raw::CmdResolveImage<S, D>);
@@ -173,7 +177,11 @@ macro_rules! q_ty_impl_graphics_or_compute {
 
 q_ty_impl_graphics_or_compute!((Pc, Pl), commands_raw::CmdPushConstants<Pc, Pl>);
 q_ty_impl_graphics_or_compute!((), commands_raw::CmdSetEvent);
+q_ty_impl_graphics_or_compute!((), commands_raw::CmdResetQueryPool);
+q_ty_impl_graphics_or_compute!((), commands_raw::CmdBeginQuery);
+q_ty_impl_graphics_or_compute!((), commands_raw::CmdEndQuery);
 q_ty_impl_graphics_or_compute!((), commands_raw::CmdSetState);
+q_ty_impl_graphics_or_compute!((), commands_raw::CmdWaitEvents);
 
 unsafe impl<I, O, Pl> AddCommand<commands_raw::CmdBindPipeline<Pl>> for QueueTyCheckLayer<I>
     where I: CommandBufferBuilder + AddCommand<commands_raw::CmdBindPipeline<Pl>, Out = O>
@@ -429,6 +429,36 @@ unsafe impl<I, O> AddCommand<commands_raw::CmdSetEvent> for SubmitSyncBuilderLay
     }
 }
 
+unsafe impl<I, O> AddCommand<commands_raw::CmdSetPatchControlPoints> for SubmitSyncBuilderLayer<I>
+    where I: AddCommand<commands_raw::CmdSetPatchControlPoints, Out = O>
+{
+    type Out = SubmitSyncBuilderLayer<O>;
+
+    #[inline]
+    fn add(self, command: commands_raw::CmdSetPatchControlPoints) -> Result<Self::Out, CommandAddError> {
+        Ok(SubmitSyncBuilderLayer {
+            inner: AddCommand::add(self.inner, command)?,
+            buffers: self.buffers,
+            images: self.images,
+        })
+    }
+}
+
+unsafe impl<I, O> AddCommand<commands_raw::CmdSetPrimitiveRestartEnable> for SubmitSyncBuilderLayer<I>
+    where I: AddCommand<commands_raw::CmdSetPrimitiveRestartEnable, Out = O>
+{
+    type Out = SubmitSyncBuilderLayer<O>;
+
+    #[inline]
+    fn add(self, command: commands_raw::CmdSetPrimitiveRestartEnable) -> Result<Self::Out, CommandAddError> {
+        Ok(SubmitSyncBuilderLayer {
+            inner: AddCommand::add(self.inner, command)?,
+            buffers: self.buffers,
+            images: self.images,
+        })
+    }
+}
+
 unsafe impl<I, O> AddCommand<commands_raw::CmdSetState> for SubmitSyncBuilderLayer<I>
     where I: AddCommand<commands_raw::CmdSetState, Out = O>
 {
@@ -462,6 +492,96 @@ unsafe impl<I, O, B, D> AddCommand<commands_raw::CmdUpdateBuffer<B, D>> for Subm
     }
 }
 
+unsafe impl<I, O> AddCommand<commands_raw::CmdWaitEvents> for SubmitSyncBuilderLayer<I>
+    where I: AddCommand<commands_raw::CmdWaitEvents, Out = O>
+{
+    type Out = SubmitSyncBuilderLayer<O>;
+
+    #[inline]
+    fn add(self, command: commands_raw::CmdWaitEvents) -> Result<Self::Out, CommandAddError> {
+        Ok(SubmitSyncBuilderLayer {
+            inner: AddCommand::add(self.inner, command)?,
+            buffers: self.buffers,
+            images: self.images,
+        })
+    }
+}
+
+unsafe impl<I, O> AddCommand<commands_raw::CmdWriteTimestamp> for SubmitSyncBuilderLayer<I>
+    where I: AddCommand<commands_raw::CmdWriteTimestamp, Out = O>
+{
+    type Out = SubmitSyncBuilderLayer<O>;
+
+    #[inline]
+    fn add(self, command: commands_raw::CmdWriteTimestamp) -> Result<Self::Out, CommandAddError> {
+        Ok(SubmitSyncBuilderLayer {
+            inner: AddCommand::add(self.inner, command)?,
+            buffers: self.buffers,
+            images: self.images,
+        })
+    }
+}
+
+unsafe impl<I, O> AddCommand<commands_raw::CmdResetQueryPool> for SubmitSyncBuilderLayer<I>
+    where I: AddCommand<commands_raw::CmdResetQueryPool, Out = O>
+{
+    type Out = SubmitSyncBuilderLayer<O>;
+
+    #[inline]
+    fn add(self, command: commands_raw::CmdResetQueryPool) -> Result<Self::Out, CommandAddError> {
+        Ok(SubmitSyncBuilderLayer {
+            inner: AddCommand::add(self.inner, command)?,
+            buffers: self.buffers,
+            images: self.images,
+        })
+    }
+}
+
+unsafe impl<I, O> AddCommand<commands_raw::CmdBeginQuery> for SubmitSyncBuilderLayer<I>
+    where I: AddCommand<commands_raw::CmdBeginQuery, Out = O>
+{
+    type Out = SubmitSyncBuilderLayer<O>;
+
+    #[inline]
+    fn add(self, command: commands_raw::CmdBeginQuery) -> Result<Self::Out, CommandAddError> {
+        Ok(SubmitSyncBuilderLayer {
+            inner: AddCommand::add(self.inner, command)?,
+            buffers: self.buffers,
+            images: self.images,
+        })
+    }
+}
+
+unsafe impl<I, O> AddCommand<commands_raw::CmdEndQuery> for SubmitSyncBuilderLayer<I>
+    where I: AddCommand<commands_raw::CmdEndQuery, Out = O>
+{
+    type Out = SubmitSyncBuilderLayer<O>;
+
+    #[inline]
+    fn add(self, command: commands_raw::CmdEndQuery) -> Result<Self::Out, CommandAddError> {
+        Ok(SubmitSyncBuilderLayer {
+            inner: AddCommand::add(self.inner, command)?,
+            buffers: self.buffers,
+            images: self.images,
+        })
+    }
+}
+
+unsafe impl<I, O> AddCommand<commands_raw::CmdGlobalMemoryBarrier> for SubmitSyncBuilderLayer<I>
+    where I: AddCommand<commands_raw::CmdGlobalMemoryBarrier, Out = O>
+{
+    type Out = SubmitSyncBuilderLayer<O>;
+
+    #[inline]
+    fn add(self, command: commands_raw::CmdGlobalMemoryBarrier) -> Result<Self::Out, CommandAddError> {
+        Ok(SubmitSyncBuilderLayer {
+            inner: AddCommand::add(self.inner, command)?,
+            buffers: self.buffers,
+            images: self.images,
+        })
+    }
+}
+
 /// Layer around a command buffer that handles synchronization between command buffers.
 pub struct SubmitSyncLayer<I> {
     inner: I,
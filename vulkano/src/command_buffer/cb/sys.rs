@@ -293,6 +293,14 @@ pub struct UnsafeCommandBuffer<P> where P: CommandPool {
     secondary_cb: bool
 }
 
+impl<P> UnsafeCommandBuffer<P> where P: CommandPool {
+    /// Returns the flags that were passed when creating the command buffer.
+    #[inline]
+    pub fn flags(&self) -> Flags {
+        self.flags
+    }
+}
+
 unsafe impl<P> CommandBuffer for UnsafeCommandBuffer<P> where P: CommandPool {
     type Pool = P;
 
@@ -301,6 +309,12 @@ unsafe impl<P> CommandBuffer for UnsafeCommandBuffer<P> where P: CommandPool {
         self
     }
 
+    #[inline]
+    fn queue_family(&self) -> QueueFamily {
+        // The default implementation goes through `inner()`, which would recurse forever here.
+        self.cmd.queue_family()
+    }
+
     #[inline]
     fn submit_check(&self, _: &GpuFuture, _: &Queue) -> Result<(), Box<Error>> {
         // Not our job to check.
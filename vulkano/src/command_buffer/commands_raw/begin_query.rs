@@ -0,0 +1,91 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::sync::Arc;
+use command_buffer::CommandAddError;
+use command_buffer::cb::AddCommand;
+use command_buffer::cb::UnsafeCommandBufferBuilder;
+use command_buffer::pool::CommandPool;
+use device::Device;
+use device::DeviceOwned;
+use query::UnsafeQueryPool;
+use VulkanObject;
+use VulkanPointers;
+use vk;
+
+/// Command that starts a query. The commands that follow are taken into account by the query,
+/// until it is ended with `CmdEndQuery`.
+///
+/// A query must be started and ended in the same command buffer.
+#[derive(Clone)]
+pub struct CmdBeginQuery {
+    // The query pool that contains the query.
+    pool: Arc<UnsafeQueryPool>,
+    // The index of the query within the pool.
+    query: u32,
+    // Flags to pass to `vkCmdBeginQuery`.
+    flags: vk::QueryControlFlags,
+}
+
+impl CmdBeginQuery {
+    /// Builds a command that starts the query of index `query` of `pool`.
+    ///
+    /// If `precise` is true, occlusion queries return the exact number of samples that passed
+    /// instead of any non-zero value. Requires the `occlusion_query_precise` feature.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if `query` is out of range of the pool.
+    ///
+    pub fn new(pool: Arc<UnsafeQueryPool>, query: u32, precise: bool) -> CmdBeginQuery {
+        assert!(query < pool.num_slots());
+
+        CmdBeginQuery {
+            pool: pool,
+            query: query,
+            flags: if precise { vk::QUERY_CONTROL_PRECISE_BIT } else { 0 },
+        }
+    }
+
+    /// Returns the query pool that contains the query.
+    #[inline]
+    pub fn pool(&self) -> &Arc<UnsafeQueryPool> {
+        &self.pool
+    }
+
+    /// Returns the index of the query within the pool.
+    #[inline]
+    pub fn query(&self) -> u32 {
+        self.query
+    }
+}
+
+unsafe impl DeviceOwned for CmdBeginQuery {
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        self.pool.device()
+    }
+}
+
+unsafe impl<'a, P> AddCommand<&'a CmdBeginQuery> for UnsafeCommandBufferBuilder<P>
+    where P: CommandPool
+{
+    type Out = UnsafeCommandBufferBuilder<P>;
+
+    #[inline]
+    fn add(self, command: &'a CmdBeginQuery) -> Result<Self::Out, CommandAddError> {
+        unsafe {
+            let vk = self.device().pointers();
+            let cmd = self.internal_object();
+            vk.CmdBeginQuery(cmd, command.pool.internal_object(), command.query, command.flags);
+        }
+
+        Ok(self)
+    }
+}
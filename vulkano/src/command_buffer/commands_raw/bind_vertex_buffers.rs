@@ -7,6 +7,8 @@
 // notice may not be copied, modified, or distributed except
 // according to those terms.
 
+use std::error;
+use std::fmt;
 use std::sync::Arc;
 use smallvec::SmallVec;
 
@@ -27,6 +29,8 @@ pub struct CmdBindVertexBuffers<B> {
     raw_buffers: SmallVec<[vk::Buffer; 4]>,
     // Raw offsets of the buffers to bind.
     offsets: SmallVec<[vk::DeviceSize; 4]>,
+    // Index of the binding that the first buffer is bound to.
+    first_binding: u32,
     // The device of the buffer, so that we can compare it with the command buffer's device.
     device: Arc<Device>,
     // The buffers to bind. Unused, but we need to keep it alive.
@@ -35,26 +39,92 @@ pub struct CmdBindVertexBuffers<B> {
 
 impl<B> CmdBindVertexBuffers<B> {
     /// Builds the command.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if the vertex source doesn't contain any buffer, if the buffers don't all belong
+    ///   to the same device, or if one of them wasn't created with the vertex buffer usage. See
+    ///   `try_new()`.
+    ///
     #[inline]
     pub fn new<S>(source_def: &S, buffers: B) -> CmdBindVertexBuffers<B>
         where S: VertexSource<B>
+    {
+        CmdBindVertexBuffers::try_new(source_def, buffers).unwrap()
+    }
+
+    /// Builds the command, binding the buffers to the bindings starting at `first_binding`
+    /// instead of 0.
+    ///
+    /// The bindings before `first_binding` keep the buffers that were bound to them previously.
+    /// This makes it possible to bind per-instance data in a separate slot, or to only update
+    /// some of the bindings.
+    ///
+    /// # Panic
+    ///
+    /// - Panics for the same reasons as `new()`.
+    ///
+    #[inline]
+    pub fn new_with_first_binding<S>(source_def: &S, buffers: B, first_binding: u32)
+                                     -> CmdBindVertexBuffers<B>
+        where S: VertexSource<B>
+    {
+        CmdBindVertexBuffers::try_new_with_first_binding(source_def, buffers, first_binding)
+            .unwrap()
+    }
+
+    /// Same as `new()`, but returns an error instead of panicking.
+    #[inline]
+    pub fn try_new<S>(source_def: &S, buffers: B)
+                      -> Result<CmdBindVertexBuffers<B>, CmdBindVertexBuffersError>
+        where S: VertexSource<B>
+    {
+        CmdBindVertexBuffers::try_new_with_first_binding(source_def, buffers, 0)
+    }
+
+    /// Same as `new_with_first_binding()`, but returns an error instead of panicking.
+    pub fn try_new_with_first_binding<S>(source_def: &S, buffers: B, first_binding: u32)
+                                         -> Result<CmdBindVertexBuffers<B>,
+                                                   CmdBindVertexBuffersError>
+        where S: VertexSource<B>
     {
         let (device, raw_buffers, offsets) = {
             let (buffers, _, _) = source_def.decode(&buffers);
 
-            let device = buffers.first().unwrap().buffer.device().clone();
+            let device = match buffers.first() {
+                Some(first) => first.buffer.device().clone(),
+                None => return Err(CmdBindVertexBuffersError::NoBuffer),
+            };
+
+            if buffers.iter().any(|b| b.buffer.device().internal_object() !=
+                                      device.internal_object())
+            {
+                return Err(CmdBindVertexBuffersError::DeviceMismatch);
+            }
+
+            if buffers.iter().any(|b| !b.buffer.usage_vertex_buffer()) {
+                return Err(CmdBindVertexBuffersError::BufferMissingUsage);
+            }
+
             let raw_buffers = buffers.iter().map(|b| b.buffer.internal_object()).collect();
             let offsets = buffers.iter().map(|b| b.offset as vk::DeviceSize).collect();
 
             (device, raw_buffers, offsets)
         };
 
-        CmdBindVertexBuffers {
+        Ok(CmdBindVertexBuffers {
             raw_buffers: raw_buffers,
             offsets: offsets,
+            first_binding: first_binding,
             device: device,
             buffers: buffers,
-        }
+        })
+    }
+
+    /// Returns the index of the binding that the first buffer is bound to.
+    #[inline]
+    pub fn first_binding(&self) -> u32 {
+        self.first_binding
     }
 }
 
@@ -72,13 +142,62 @@ unsafe impl<'a, P, B> AddCommand<&'a CmdBindVertexBuffers<B>> for UnsafeCommandB
 
     #[inline]
     fn add(self, command: &'a CmdBindVertexBuffers<B>) -> Result<Self::Out, CommandAddError> {
+        // Binding buffers of another device would be undefined behavior.
+        assert_eq!(command.device().internal_object(), self.device().internal_object(),
+                   "The vertex buffers don't belong to the device of the command buffer");
+
+        let max = self.device().physical_device().limits().max_vertex_input_bindings();
+        let requested = command.first_binding.saturating_add(command.raw_buffers.len() as u32);
+        if requested > max {
+            return Err(CommandAddError::MaxVertexInputBindingsExceeded {
+                requested: requested,
+                max: max,
+            });
+        }
+
         unsafe {
             let vk = self.device().pointers();
             let cmd = self.internal_object();
-            vk.CmdBindVertexBuffers(cmd, 0, command.raw_buffers.len() as u32,
+            vk.CmdBindVertexBuffers(cmd, command.first_binding,
+                                    command.raw_buffers.len() as u32,
                                     command.raw_buffers.as_ptr(), command.offsets.as_ptr());
         }
 
         Ok(self)
     }
 }
+
+/// Error that can happen when creating a `CmdBindVertexBuffers`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CmdBindVertexBuffersError {
+    /// The vertex source doesn't contain any buffer.
+    NoBuffer,
+    /// The buffers don't all belong to the same device.
+    DeviceMismatch,
+    /// The "vertex buffer" usage must be enabled on all the buffers.
+    BufferMissingUsage,
+}
+
+impl error::Error for CmdBindVertexBuffersError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            CmdBindVertexBuffersError::NoBuffer => {
+                "the vertex source doesn't contain any buffer"
+            },
+            CmdBindVertexBuffersError::DeviceMismatch => {
+                "the vertex buffers don't all belong to the same device"
+            },
+            CmdBindVertexBuffersError::BufferMissingUsage => {
+                "the vertex buffer usage must be enabled on all the vertex buffers"
+            },
+        }
+    }
+}
+
+impl fmt::Display for CmdBindVertexBuffersError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
@@ -7,6 +7,7 @@
 // notice may not be copied, modified, or distributed except
 // according to those terms.
 
+use std::cmp;
 use std::error;
 use std::fmt;
 use std::sync::Arc;
@@ -16,6 +17,8 @@ use command_buffer::cb::UnsafeCommandBufferBuilder;
 use command_buffer::pool::CommandPool;
 use device::Device;
 use device::DeviceOwned;
+use image::ImageAccess;
+use sampler::Filter;
 use VulkanObject;
 use VulkanPointers;
 use vk;
@@ -52,7 +55,148 @@ pub struct CmdBlitImage<S, D> {
     filter: vk::Filter,
 }
 
-// TODO: add constructor
+impl<S, D> CmdBlitImage<S, D> where S: ImageAccess, D: ImageAccess {
+    /// Builds a command that blits the region between `source_offset1` and `source_offset2` of
+    /// the source into the region between `destination_offset1` and `destination_offset2` of the
+    /// destination, scaling it if the regions don't have the same size.
+    ///
+    /// `layer_count` layers are blitted, starting at `source_base_array_layer` and
+    /// `destination_base_array_layer`.
+    ///
+    /// The source must be in the `TransferSrcOptimal` layout and the destination in the
+    /// `TransferDstOptimal` layout when the command is executed.
+    ///
+    /// The offsets must be within the dimensions of the given mipmap level, and the layers within
+    /// the array layers of the images.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if the source and destination don't belong to the same device.
+    ///
+    pub fn new(source: S, source_offset1: [i32; 3], source_offset2: [i32; 3],
+               source_base_array_layer: u32, source_mip_level: u32,
+               destination: D, destination_offset1: [i32; 3], destination_offset2: [i32; 3],
+               destination_base_array_layer: u32, destination_mip_level: u32,
+               layer_count: u32, filter: Filter)
+               -> Result<CmdBlitImage<S, D>, CmdBlitImageError>
+    {
+        assert_eq!(source.inner().device().internal_object(),
+                   destination.inner().device().internal_object());
+
+        if !region_in_range(&source, source_offset1, source_offset2, source_base_array_layer,
+                            layer_count, source_mip_level)
+        {
+            return Err(CmdBlitImageError::SourceOutOfImageRange);
+        }
+
+        if !region_in_range(&destination, destination_offset1, destination_offset2,
+                            destination_base_array_layer, layer_count, destination_mip_level)
+        {
+            return Err(CmdBlitImageError::DestinationOutOfImageRange);
+        }
+
+        if !source.inner().usage_transfer_src() {
+            return Err(CmdBlitImageError::SourceMissingTransferUsage);
+        }
+
+        if !destination.inner().usage_transfer_dest() {
+            return Err(CmdBlitImageError::DestinationMissingTransferUsage);
+        }
+
+        if !source.supports_blit_source() {
+            return Err(CmdBlitImageError::SourceFormatNotSupported);
+        }
+
+        if !destination.supports_blit_destination() {
+            return Err(CmdBlitImageError::DestinationFormatNotSupported);
+        }
+
+        if source.samples() != 1 || destination.samples() != 1 {
+            return Err(CmdBlitImageError::Multisampled);
+        }
+
+        if source.has_color() != destination.has_color() ||
+           source.has_depth() != destination.has_depth() ||
+           source.has_stencil() != destination.has_stencil()
+        {
+            return Err(CmdBlitImageError::IncompatibleFormats);
+        }
+
+        if (source.has_depth() || source.has_stencil()) && filter != Filter::Nearest {
+            return Err(CmdBlitImageError::DepthStencilFilterNotNearest);
+        }
+
+        if source.conflicts_image(source_base_array_layer, layer_count, source_mip_level, 1,
+                                  &destination, destination_base_array_layer, layer_count,
+                                  destination_mip_level, 1)
+        {
+            return Err(CmdBlitImageError::OverlappingRanges);
+        }
+
+        let aspect_mask = if source.has_color() {
+            vk::IMAGE_ASPECT_COLOR_BIT
+        } else {
+            let mut mask = 0;
+            if source.has_depth() { mask |= vk::IMAGE_ASPECT_DEPTH_BIT; }
+            if source.has_stencil() { mask |= vk::IMAGE_ASPECT_STENCIL_BIT; }
+            mask
+        };
+
+        let source_raw = source.inner().internal_object();
+        let destination_raw = destination.inner().internal_object();
+
+        Ok(CmdBlitImage {
+            source: source,
+            source_raw: source_raw,
+            source_layout: vk::IMAGE_LAYOUT_TRANSFER_SRC_OPTIMAL,
+            source_offset1: source_offset1,
+            source_offset2: source_offset2,
+            source_aspect_mask: aspect_mask,
+            source_mip_level: source_mip_level,
+            source_base_array_layer: source_base_array_layer,
+            source_layer_count: layer_count,
+            destination: destination,
+            destination_raw: destination_raw,
+            destination_layout: vk::IMAGE_LAYOUT_TRANSFER_DST_OPTIMAL,
+            destination_offset1: destination_offset1,
+            destination_offset2: destination_offset2,
+            destination_aspect_mask: aspect_mask,
+            destination_mip_level: destination_mip_level,
+            destination_base_array_layer: destination_base_array_layer,
+            destination_layer_count: layer_count,
+            filter: filter as u32,
+        })
+    }
+}
+
+// Returns true if the region between `offset1` and `offset2` is within the dimensions of the
+// mipmap level `mip_level` of `image`, and the layers within its array layers.
+fn region_in_range<I>(image: &I, offset1: [i32; 3], offset2: [i32; 3], base_array_layer: u32,
+                      layer_count: u32, mip_level: u32) -> bool
+    where I: ImageAccess
+{
+    if mip_level >= image.inner().mipmap_levels() {
+        return false;
+    }
+
+    let dimensions = image.dimensions();
+    match base_array_layer.checked_add(layer_count) {
+        Some(end) if layer_count >= 1 && end <= dimensions.array_layers() => (),
+        _ => return false,
+    }
+
+    let level_dimensions = dimensions.width_height_depth();
+    for axis in 0 .. 3 {
+        let max = cmp::max(1, level_dimensions[axis] >> mip_level) as i64;
+        for &offset in &[offset1[axis], offset2[axis]] {
+            if offset < 0 || offset as i64 > max {
+                return false;
+            }
+        }
+    }
+
+    true
+}
 
 impl<S, D> CmdBlitImage<S, D> {
     /// Returns the source image.
@@ -141,12 +285,62 @@ unsafe impl<'a, P, S, D> AddCommand<&'a CmdBlitImage<S, D>> for UnsafeCommandBuf
 /// Error that can happen when creating a `CmdBlitImage`.
 #[derive(Debug, Copy, Clone)]
 pub enum CmdBlitImageError {
+    /// The source image is missing the transfer source usage.
+    SourceMissingTransferUsage,
+    /// The destination image is missing the transfer destination usage.
+    DestinationMissingTransferUsage,
+    /// The format of the source image doesn't support being the source of a blit.
+    SourceFormatNotSupported,
+    /// The format of the destination image doesn't support being the destination of a blit.
+    DestinationFormatNotSupported,
+    /// The source or the destination has more than one sample per pixel.
+    Multisampled,
+    /// The source and destination don't have the same aspects.
+    IncompatibleFormats,
+    /// Depth or stencil images can only be blitted with `Filter::Nearest`.
+    DepthStencilFilterNotNearest,
+    /// The source and destination are overlapping in memory.
+    OverlappingRanges,
+    /// The source region or layers are out of range of the source image.
+    SourceOutOfImageRange,
+    /// The destination region or layers are out of range of the destination image.
+    DestinationOutOfImageRange,
 }
 
 impl error::Error for CmdBlitImageError {
     #[inline]
     fn description(&self) -> &str {
         match *self {
+            CmdBlitImageError::SourceMissingTransferUsage => {
+                "the source image is missing the transfer source usage"
+            },
+            CmdBlitImageError::DestinationMissingTransferUsage => {
+                "the destination image is missing the transfer destination usage"
+            },
+            CmdBlitImageError::SourceFormatNotSupported => {
+                "the format of the source image doesn't support blits"
+            },
+            CmdBlitImageError::DestinationFormatNotSupported => {
+                "the format of the destination image doesn't support blits"
+            },
+            CmdBlitImageError::Multisampled => {
+                "the source or the destination has more than one sample per pixel"
+            },
+            CmdBlitImageError::IncompatibleFormats => {
+                "the source and destination don't have the same aspects"
+            },
+            CmdBlitImageError::DepthStencilFilterNotNearest => {
+                "depth or stencil images can only be blitted with the nearest filter"
+            },
+            CmdBlitImageError::OverlappingRanges => {
+                "the source and destination are overlapping in memory"
+            },
+            CmdBlitImageError::SourceOutOfImageRange => {
+                "the source region or layers are out of range of the source image"
+            },
+            CmdBlitImageError::DestinationOutOfImageRange => {
+                "the destination region or layers are out of range of the destination image"
+            },
         }
     }
 }
@@ -157,3 +351,54 @@ impl fmt::Display for CmdBlitImageError {
         write!(fmt, "{}", error::Error::description(self))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use command_buffer::commands_raw::CmdBlitImage;
+    use command_buffer::commands_raw::CmdBlitImageError;
+    use format::R8G8B8A8Unorm;
+    use image::Dimensions;
+    use image::Image;
+    use image::ImmutableImage;
+    use image::MipmapsCount;
+    use sampler::Filter;
+
+    #[test]
+    fn destination_out_of_range() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let source = ImmutableImage::new(&device, Dimensions::Dim2d { width: 32, height: 32 },
+                                         R8G8B8A8Unorm, Some(queue.family())).unwrap();
+        let destination = ImmutableImage::new(&device, Dimensions::Dim2d { width: 32, height: 32 },
+                                              R8G8B8A8Unorm, Some(queue.family())).unwrap();
+
+        match CmdBlitImage::new(source.access(), [0, 0, 0], [32, 32, 1], 0, 0,
+                                destination.access(), [0, 0, 0], [64, 64, 1], 0, 0, 1,
+                                Filter::Linear)
+        {
+            Err(CmdBlitImageError::DestinationOutOfImageRange) => (),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn source_mip_level_out_of_range() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let source = ImmutableImage::with_mipmaps(&device,
+                                                  Dimensions::Dim2d { width: 32, height: 32 },
+                                                  R8G8B8A8Unorm, MipmapsCount::Specific(2),
+                                                  Some(queue.family())).unwrap();
+        let destination = ImmutableImage::new(&device, Dimensions::Dim2d { width: 32, height: 32 },
+                                              R8G8B8A8Unorm, Some(queue.family())).unwrap();
+
+        // The second level of the source is 16x16.
+        match CmdBlitImage::new(source.access(), [0, 0, 0], [32, 32, 1], 0, 1,
+                                destination.access(), [0, 0, 0], [32, 32, 1], 0, 0, 1,
+                                Filter::Linear)
+        {
+            Err(CmdBlitImageError::SourceOutOfImageRange) => (),
+            _ => panic!(),
+        }
+    }
+}
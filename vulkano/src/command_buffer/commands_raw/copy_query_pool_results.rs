@@ -0,0 +1,184 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::error;
+use std::fmt;
+use std::sync::Arc;
+
+use buffer::BufferAccess;
+use buffer::BufferInner;
+use command_buffer::CommandAddError;
+use command_buffer::cb::AddCommand;
+use command_buffer::cb::UnsafeCommandBufferBuilder;
+use command_buffer::pool::CommandPool;
+use device::Device;
+use device::DeviceOwned;
+use query::QueryResultFlags;
+use query::UnsafeQueryPool;
+use VulkanObject;
+use VulkanPointers;
+use vk;
+
+/// Command that copies the results of a range of queries of a query pool into a buffer.
+pub struct CmdCopyQueryPoolResults<B> {
+    // The query pool that contains the queries.
+    pool: Arc<UnsafeQueryPool>,
+    // The index of the first query to copy.
+    first_query: u32,
+    // The number of queries to copy.
+    count: u32,
+    // The buffer to write to.
+    buffer: B,
+    // Raw buffer handle.
+    buffer_handle: vk::Buffer,
+    // Offset of the buffer within its raw buffer.
+    offset: vk::DeviceSize,
+    // Number of bytes between the results of two consecutive queries.
+    stride: vk::DeviceSize,
+    // How the results are written.
+    flags: QueryResultFlags,
+}
+
+impl<B> CmdCopyQueryPoolResults<B> where B: BufferAccess {
+    /// Builds a command that writes the results of the `count` queries of `pool` starting at
+    /// `first_query` to `buffer`, one query every `stride` bytes.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if `count` is 0.
+    /// - Panics if the range of queries is out of range of the pool.
+    /// - Panics if the buffer and the pool don't belong to the same device.
+    ///
+    pub fn new(pool: Arc<UnsafeQueryPool>, first_query: u32, count: u32, buffer: B,
+               stride: usize, flags: QueryResultFlags)
+               -> Result<CmdCopyQueryPoolResults<B>, CmdCopyQueryPoolResultsError>
+    {
+        assert!(count >= 1);
+        assert!(first_query.checked_add(count).map(|end| end <= pool.num_slots())
+                                              .unwrap_or(false));
+        assert_eq!(buffer.inner().buffer.device().internal_object(),
+                   pool.device().internal_object());
+
+        let element_size = flags.element_size();
+        if stride % element_size != 0 {
+            return Err(CmdCopyQueryPoolResultsError::WrongAlignment);
+        }
+
+        // We don't know how many integers a query writes, as it depends on the type of the pool.
+        // At least one per query, plus the availability integer, must fit.
+        let query_size = if flags.with_availability { 2 * element_size } else { element_size };
+        if buffer.size() < stride * (count as usize - 1) + query_size {
+            return Err(CmdCopyQueryPoolResultsError::BufferTooSmall);
+        }
+
+        let (buffer_handle, offset) = {
+            let BufferInner { buffer: buffer_inner, offset } = buffer.inner();
+            if !buffer_inner.usage_transfer_dest() {
+                return Err(CmdCopyQueryPoolResultsError::BufferMissingUsage);
+            }
+            if offset % element_size != 0 {
+                return Err(CmdCopyQueryPoolResultsError::WrongAlignment);
+            }
+            (buffer_inner.internal_object(), offset)
+        };
+
+        Ok(CmdCopyQueryPoolResults {
+            pool: pool,
+            first_query: first_query,
+            count: count,
+            buffer: buffer,
+            buffer_handle: buffer_handle,
+            offset: offset as vk::DeviceSize,
+            stride: stride as vk::DeviceSize,
+            flags: flags,
+        })
+    }
+}
+
+impl<B> CmdCopyQueryPoolResults<B> {
+    /// Returns the query pool that contains the queries.
+    #[inline]
+    pub fn pool(&self) -> &Arc<UnsafeQueryPool> {
+        &self.pool
+    }
+
+    /// Returns the buffer that the results are written to.
+    #[inline]
+    pub fn buffer(&self) -> &B {
+        &self.buffer
+    }
+
+    /// Returns how the results are written.
+    #[inline]
+    pub fn flags(&self) -> QueryResultFlags {
+        self.flags
+    }
+}
+
+unsafe impl<B> DeviceOwned for CmdCopyQueryPoolResults<B> {
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        self.pool.device()
+    }
+}
+
+unsafe impl<'a, P, B> AddCommand<&'a CmdCopyQueryPoolResults<B>> for UnsafeCommandBufferBuilder<P>
+    where P: CommandPool
+{
+    type Out = UnsafeCommandBufferBuilder<P>;
+
+    #[inline]
+    fn add(self, command: &'a CmdCopyQueryPoolResults<B>) -> Result<Self::Out, CommandAddError> {
+        unsafe {
+            let vk = self.device().pointers();
+            let cmd = self.internal_object();
+            vk.CmdCopyQueryPoolResults(cmd, command.pool.internal_object(), command.first_query,
+                                       command.count, command.buffer_handle, command.offset,
+                                       command.stride, command.flags.into());
+        }
+
+        Ok(self)
+    }
+}
+
+/// Error that can happen when creating a `CmdCopyQueryPoolResults`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CmdCopyQueryPoolResultsError {
+    /// The "transfer destination" usage must be enabled on the buffer.
+    BufferMissingUsage,
+    /// The offset of the buffer or the stride isn't a multiple of the size of the integers that
+    /// are written.
+    WrongAlignment,
+    /// The buffer is too small to contain the results of all the queries.
+    BufferTooSmall,
+}
+
+impl error::Error for CmdCopyQueryPoolResultsError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            CmdCopyQueryPoolResultsError::BufferMissingUsage => {
+                "the transfer destination usage must be enabled on the buffer"
+            },
+            CmdCopyQueryPoolResultsError::WrongAlignment => {
+                "the offset or stride are not aligned to the size of the results"
+            },
+            CmdCopyQueryPoolResultsError::BufferTooSmall => {
+                "the buffer is too small to contain the results"
+            },
+        }
+    }
+}
+
+impl fmt::Display for CmdCopyQueryPoolResultsError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
@@ -0,0 +1,81 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::sync::Arc;
+use command_buffer::CommandAddError;
+use command_buffer::cb::AddCommand;
+use command_buffer::cb::UnsafeCommandBufferBuilder;
+use command_buffer::pool::CommandPool;
+use device::Device;
+use device::DeviceOwned;
+use query::UnsafeQueryPool;
+use VulkanObject;
+use VulkanPointers;
+
+/// Command that ends a query previously started with `CmdBeginQuery`.
+#[derive(Clone)]
+pub struct CmdEndQuery {
+    // The query pool that contains the query.
+    pool: Arc<UnsafeQueryPool>,
+    // The index of the query within the pool.
+    query: u32,
+}
+
+impl CmdEndQuery {
+    /// Builds a command that ends the query of index `query` of `pool`.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if `query` is out of range of the pool.
+    ///
+    pub fn new(pool: Arc<UnsafeQueryPool>, query: u32) -> CmdEndQuery {
+        assert!(query < pool.num_slots());
+
+        CmdEndQuery {
+            pool: pool,
+            query: query,
+        }
+    }
+
+    /// Returns the query pool that contains the query.
+    #[inline]
+    pub fn pool(&self) -> &Arc<UnsafeQueryPool> {
+        &self.pool
+    }
+
+    /// Returns the index of the query within the pool.
+    #[inline]
+    pub fn query(&self) -> u32 {
+        self.query
+    }
+}
+
+unsafe impl DeviceOwned for CmdEndQuery {
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        self.pool.device()
+    }
+}
+
+unsafe impl<'a, P> AddCommand<&'a CmdEndQuery> for UnsafeCommandBufferBuilder<P>
+    where P: CommandPool
+{
+    type Out = UnsafeCommandBufferBuilder<P>;
+
+    #[inline]
+    fn add(self, command: &'a CmdEndQuery) -> Result<Self::Out, CommandAddError> {
+        unsafe {
+            let vk = self.device().pointers();
+            let cmd = self.internal_object();
+            vk.CmdEndQuery(cmd, command.pool.internal_object(), command.query);
+        }
+
+        Ok(self)
+    }
+}
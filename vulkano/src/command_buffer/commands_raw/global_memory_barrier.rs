@@ -0,0 +1,93 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::ptr;
+use command_buffer::CommandAddError;
+use command_buffer::cb::AddCommand;
+use command_buffer::cb::UnsafeCommandBufferBuilder;
+use command_buffer::pool::CommandPool;
+use device::DeviceOwned;
+use sync::AccessFlagBits;
+use sync::PipelineStages;
+use VulkanObject;
+use VulkanPointers;
+use vk;
+
+/// Command that adds a pipeline barrier containing a single global memory barrier.
+///
+/// Contrary to `CmdPipelineBarrier`, this command doesn't reference any buffer or image. All
+/// the memory writes performed by the source stages with the source accesses are made visible to
+/// the destination stages for the destination accesses, whatever the resource.
+///
+/// This is appropriate between two phases that touch many resources, for example between a
+/// compute shader that writes indirect draw parameters and the indirect draw that reads them.
+#[derive(Debug, Copy, Clone)]
+pub struct CmdGlobalMemoryBarrier {
+    src_stage_mask: vk::PipelineStageFlags,
+    dst_stage_mask: vk::PipelineStageFlags,
+    src_access_mask: vk::AccessFlags,
+    dst_access_mask: vk::AccessFlags,
+}
+
+impl CmdGlobalMemoryBarrier {
+    /// Builds the command.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if `src_stages` or `dst_stages` is empty.
+    ///
+    /// # Safety
+    ///
+    /// - If the pipeline stages include geometry or tessellation stages, then the corresponding
+    ///   features must have been enabled in the device.
+    /// - The stages must be supported by the queue family of the command buffer.
+    ///
+    pub unsafe fn new(src_stages: PipelineStages, dst_stages: PipelineStages,
+                      src_access: AccessFlagBits, dst_access: AccessFlagBits)
+                      -> CmdGlobalMemoryBarrier
+    {
+        let src_stage_mask: vk::PipelineStageFlags = src_stages.into();
+        let dst_stage_mask: vk::PipelineStageFlags = dst_stages.into();
+        assert!(src_stage_mask != 0);
+        assert!(dst_stage_mask != 0);
+
+        CmdGlobalMemoryBarrier {
+            src_stage_mask: src_stage_mask,
+            dst_stage_mask: dst_stage_mask,
+            src_access_mask: src_access.into(),
+            dst_access_mask: dst_access.into(),
+        }
+    }
+}
+
+unsafe impl<'a, P> AddCommand<&'a CmdGlobalMemoryBarrier> for UnsafeCommandBufferBuilder<P>
+    where P: CommandPool
+{
+    type Out = UnsafeCommandBufferBuilder<P>;
+
+    #[inline]
+    fn add(self, command: &'a CmdGlobalMemoryBarrier) -> Result<Self::Out, CommandAddError> {
+        unsafe {
+            let vk = self.device().pointers();
+            let cmd = self.internal_object();
+
+            let barrier = vk::MemoryBarrier {
+                sType: vk::STRUCTURE_TYPE_MEMORY_BARRIER,
+                pNext: ptr::null(),
+                srcAccessMask: command.src_access_mask,
+                dstAccessMask: command.dst_access_mask,
+            };
+
+            vk.CmdPipelineBarrier(cmd, command.src_stage_mask, command.dst_stage_mask, 0,
+                                  1, &barrier, 0, ptr::null(), 0, ptr::null());
+        }
+
+        Ok(self)
+    }
+}
@@ -11,31 +11,41 @@
 //!
 //! This module only contains the base commands that have direct equivalents in the Vulkan API.
 
+pub use self::begin_query::CmdBeginQuery;
 pub use self::begin_render_pass::CmdBeginRenderPass;
 pub use self::bind_index_buffer::CmdBindIndexBuffer;
 pub use self::bind_descriptor_sets::{CmdBindDescriptorSets, CmdBindDescriptorSetsError};
 pub use self::bind_pipeline::{CmdBindPipeline, CmdBindPipelineSys};
-pub use self::bind_vertex_buffers::CmdBindVertexBuffers;
+pub use self::bind_vertex_buffers::{CmdBindVertexBuffers, CmdBindVertexBuffersError};
 pub use self::blit_image::{CmdBlitImage, CmdBlitImageError};
 pub use self::clear_attachments::CmdClearAttachments;
 pub use self::copy_buffer::{CmdCopyBuffer, CmdCopyBufferError};
 pub use self::copy_buffer_to_image::{CmdCopyBufferToImage, CmdCopyBufferToImageError};
 pub use self::copy_image::{CmdCopyImage, CmdCopyImageError};
+pub use self::copy_query_pool_results::{CmdCopyQueryPoolResults, CmdCopyQueryPoolResultsError};
 pub use self::dispatch_raw::{CmdDispatchRaw, CmdDispatchRawError};
 pub use self::draw_indexed_raw::CmdDrawIndexedRaw;
 pub use self::draw_indirect_raw::CmdDrawIndirectRaw;
 pub use self::draw_raw::CmdDrawRaw;
+pub use self::end_query::CmdEndQuery;
 pub use self::end_render_pass::CmdEndRenderPass;
 pub use self::execute::CmdExecuteCommands;
 pub use self::fill_buffer::{CmdFillBuffer, CmdFillBufferError};
+pub use self::global_memory_barrier::CmdGlobalMemoryBarrier;
 pub use self::next_subpass::CmdNextSubpass;
 pub use self::pipeline_barrier::CmdPipelineBarrier;
 pub use self::push_constants::{CmdPushConstants, CmdPushConstantsError};
+pub use self::reset_query_pool::CmdResetQueryPool;
 pub use self::resolve_image::{CmdResolveImage, CmdResolveImageError};
 pub use self::set_event::CmdSetEvent;
+pub use self::set_patch_control_points::{CmdSetPatchControlPoints, CmdSetPatchControlPointsError};
+pub use self::set_primitive_restart_enable::{CmdSetPrimitiveRestartEnable, CmdSetPrimitiveRestartEnableError};
 pub use self::set_state::{CmdSetState};
 pub use self::update_buffer::{CmdUpdateBuffer, CmdUpdateBufferError};
+pub use self::wait_events::CmdWaitEvents;
+pub use self::write_timestamp::{CmdWriteTimestamp, CmdWriteTimestampError};
 
+mod begin_query;
 mod begin_render_pass;
 mod bind_descriptor_sets;
 mod bind_index_buffer;
@@ -46,17 +56,25 @@ mod clear_attachments;
 mod copy_buffer;
 mod copy_buffer_to_image;
 mod copy_image;
+mod copy_query_pool_results;
 mod dispatch_raw;
 mod draw_indexed_raw;
 mod draw_indirect_raw;
 mod draw_raw;
+mod end_query;
 mod end_render_pass;
 mod execute;
 mod fill_buffer;
+mod global_memory_barrier;
 mod next_subpass;
 mod pipeline_barrier;
 mod push_constants;
+mod reset_query_pool;
 mod resolve_image;
 mod set_event;
+mod set_patch_control_points;
+mod set_primitive_restart_enable;
 mod set_state;
 mod update_buffer;
+mod wait_events;
+mod write_timestamp;
@@ -0,0 +1,94 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::sync::Arc;
+use command_buffer::CommandAddError;
+use command_buffer::cb::AddCommand;
+use command_buffer::cb::UnsafeCommandBufferBuilder;
+use command_buffer::pool::CommandPool;
+use device::Device;
+use device::DeviceOwned;
+use query::UnsafeQueryPool;
+use VulkanObject;
+use VulkanPointers;
+
+/// Command that resets a range of queries of a query pool, so that they can be used again.
+#[derive(Clone)]
+pub struct CmdResetQueryPool {
+    // The query pool that contains the queries.
+    pool: Arc<UnsafeQueryPool>,
+    // The index of the first query to reset.
+    first_query: u32,
+    // The number of queries to reset.
+    count: u32,
+}
+
+impl CmdResetQueryPool {
+    /// Builds a command that resets the `count` queries of `pool` starting at `first_query`.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if `count` is 0.
+    /// - Panics if the range of queries is out of range of the pool.
+    ///
+    pub fn new(pool: Arc<UnsafeQueryPool>, first_query: u32, count: u32) -> CmdResetQueryPool {
+        assert!(count >= 1);
+        assert!(first_query.checked_add(count).map(|end| end <= pool.num_slots())
+                                              .unwrap_or(false));
+
+        CmdResetQueryPool {
+            pool: pool,
+            first_query: first_query,
+            count: count,
+        }
+    }
+
+    /// Returns the query pool that contains the queries.
+    #[inline]
+    pub fn pool(&self) -> &Arc<UnsafeQueryPool> {
+        &self.pool
+    }
+
+    /// Returns the index of the first query to reset.
+    #[inline]
+    pub fn first_query(&self) -> u32 {
+        self.first_query
+    }
+
+    /// Returns the number of queries to reset.
+    #[inline]
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+}
+
+unsafe impl DeviceOwned for CmdResetQueryPool {
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        self.pool.device()
+    }
+}
+
+unsafe impl<'a, P> AddCommand<&'a CmdResetQueryPool> for UnsafeCommandBufferBuilder<P>
+    where P: CommandPool
+{
+    type Out = UnsafeCommandBufferBuilder<P>;
+
+    #[inline]
+    fn add(self, command: &'a CmdResetQueryPool) -> Result<Self::Out, CommandAddError> {
+        unsafe {
+            let vk = self.device().pointers();
+            let cmd = self.internal_object();
+            vk.CmdResetQueryPool(cmd, command.pool.internal_object(), command.first_query,
+                                 command.count);
+        }
+
+        Ok(self)
+    }
+}
@@ -15,6 +15,7 @@ use command_buffer::pool::CommandPool;
 use device::Device;
 use device::DeviceOwned;
 use sync::Event;
+use sync::PipelineStages;
 use VulkanObject;
 use VulkanPointers;
 use vk;
@@ -26,11 +27,65 @@ pub struct CmdSetEvent {
     event: Arc<Event>,
     // The pipeline stages after which the event should be set or reset.
     stages: vk::PipelineStageFlags,
-    // If true calls `vkCmdSetEvent`, otherwise `vkCmdSetEvent`.
+    // If true calls `vkCmdSetEvent`, otherwise `vkCmdResetEvent`.
     set: bool,
 }
 
-// TODO: add constructor
+impl CmdSetEvent {
+    /// Builds a command that sets `event` once the previous commands have finished executing
+    /// `stages`.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if `stages` is empty or includes `host`.
+    ///
+    /// # Safety
+    ///
+    /// - The stages must be supported by the queue family of the command buffer.
+    /// - If the stages include geometry or tessellation stages, then the corresponding features
+    ///   must have been enabled in the device.
+    ///
+    #[inline]
+    pub unsafe fn set(event: Arc<Event>, stages: PipelineStages) -> CmdSetEvent {
+        CmdSetEvent::new(event, stages, true)
+    }
+
+    /// Builds a command that resets `event` once the previous commands have finished executing
+    /// `stages`.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if `stages` is empty or includes `host`.
+    ///
+    /// # Safety
+    ///
+    /// Same as `set()`.
+    ///
+    #[inline]
+    pub unsafe fn reset(event: Arc<Event>, stages: PipelineStages) -> CmdSetEvent {
+        CmdSetEvent::new(event, stages, false)
+    }
+
+    #[inline]
+    fn new(event: Arc<Event>, stages: PipelineStages, set: bool) -> CmdSetEvent {
+        assert!(!stages.host, "An event can't be set or reset by the host stage of a command \
+                               buffer");
+        let stages: vk::PipelineStageFlags = stages.into();
+        assert!(stages != 0);
+
+        CmdSetEvent {
+            event: event,
+            stages: stages,
+            set: set,
+        }
+    }
+
+    /// Returns the event that the command sets or resets.
+    #[inline]
+    pub fn event(&self) -> &Arc<Event> {
+        &self.event
+    }
+}
 
 unsafe impl DeviceOwned for CmdSetEvent {
     #[inline]
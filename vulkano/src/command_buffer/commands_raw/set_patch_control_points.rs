@@ -0,0 +1,146 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::error;
+use std::fmt;
+use std::sync::Arc;
+
+use command_buffer::CommandAddError;
+use command_buffer::cb::AddCommand;
+use command_buffer::cb::UnsafeCommandBufferBuilder;
+use command_buffer::pool::CommandPool;
+use device::Device;
+use device::DeviceOwned;
+use VulkanObject;
+use VulkanPointers;
+
+/// Command that sets the number of control points per patch for the following draw commands.
+///
+/// This allows using the same tessellation pipeline with patches of different sizes. The value
+/// is only taken into account if the pipeline used for drawing was created with the
+/// `PATCH_CONTROL_POINTS` dynamic state.
+// TODO: graphics pipelines can't be created with this dynamic state yet
+pub struct CmdSetPatchControlPoints {
+    // The device, so that we can compare it with the command buffer's device.
+    device: Arc<Device>,
+    // The value to set.
+    control_points: u32,
+}
+
+impl CmdSetPatchControlPoints {
+    /// Builds the command.
+    ///
+    /// Returns an error if the `ext_extended_dynamic_state2` extension isn't enabled on the
+    /// device, if the `extended_dynamic_state2_patch_control_points` feature isn't enabled,
+    /// or if `control_points` is 0 or greater than the `max_tessellation_patch_size` limit.
+    pub fn new(device: Arc<Device>, control_points: u32)
+               -> Result<CmdSetPatchControlPoints, CmdSetPatchControlPointsError>
+    {
+        if !device.loaded_extensions().ext_extended_dynamic_state2 {
+            return Err(CmdSetPatchControlPointsError::ExtensionNotEnabled);
+        }
+
+        if !device.dynamic_patch_control_points_enabled() {
+            return Err(CmdSetPatchControlPointsError::FeatureNotEnabled);
+        }
+
+        let max = device.physical_device().limits().max_tessellation_patch_size();
+        if control_points == 0 || control_points > max {
+            return Err(CmdSetPatchControlPointsError::ControlPointsOutOfRange);
+        }
+
+        Ok(CmdSetPatchControlPoints {
+            device: device,
+            control_points: control_points,
+        })
+    }
+
+    /// Returns the number of control points per patch set by this command.
+    #[inline]
+    pub fn control_points(&self) -> u32 {
+        self.control_points
+    }
+}
+
+unsafe impl DeviceOwned for CmdSetPatchControlPoints {
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+}
+
+unsafe impl<'a, P> AddCommand<&'a CmdSetPatchControlPoints> for UnsafeCommandBufferBuilder<P>
+    where P: CommandPool
+{
+    type Out = UnsafeCommandBufferBuilder<P>;
+
+    #[inline]
+    fn add(self, command: &'a CmdSetPatchControlPoints) -> Result<Self::Out, CommandAddError> {
+        unsafe {
+            let vk = self.device().pointers();
+            let cmd = self.internal_object();
+            vk.CmdSetPatchControlPointsEXT(cmd, command.control_points);
+        }
+
+        Ok(self)
+    }
+}
+
+/// Error that can happen when creating a `CmdSetPatchControlPoints`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CmdSetPatchControlPointsError {
+    /// The `ext_extended_dynamic_state2` extension isn't enabled on the device.
+    ExtensionNotEnabled,
+    /// The `extended_dynamic_state2_patch_control_points` feature isn't enabled on the device.
+    FeatureNotEnabled,
+    /// The number of control points is 0 or greater than the `max_tessellation_patch_size`
+    /// limit.
+    ControlPointsOutOfRange,
+}
+
+impl error::Error for CmdSetPatchControlPointsError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            CmdSetPatchControlPointsError::ExtensionNotEnabled => {
+                "the `ext_extended_dynamic_state2` extension isn't enabled on the device"
+            },
+            CmdSetPatchControlPointsError::FeatureNotEnabled => {
+                "setting the number of patch control points dynamically isn't enabled on the \
+                 device"
+            },
+            CmdSetPatchControlPointsError::ControlPointsOutOfRange => {
+                "the number of control points is 0 or exceeds the maximum patch size"
+            },
+        }
+    }
+}
+
+impl fmt::Display for CmdSetPatchControlPointsError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use command_buffer::commands_raw::CmdSetPatchControlPoints;
+    use command_buffer::commands_raw::CmdSetPatchControlPointsError;
+
+    #[test]
+    fn extension_not_enabled() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        match CmdSetPatchControlPoints::new(device, 3) {
+            Err(CmdSetPatchControlPointsError::ExtensionNotEnabled) => (),
+            _ => panic!()
+        }
+    }
+}
@@ -0,0 +1,134 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::error;
+use std::fmt;
+use std::sync::Arc;
+
+use command_buffer::CommandAddError;
+use command_buffer::cb::AddCommand;
+use command_buffer::cb::UnsafeCommandBufferBuilder;
+use command_buffer::pool::CommandPool;
+use device::Device;
+use device::DeviceOwned;
+use VulkanObject;
+use VulkanPointers;
+use vk;
+
+/// Command that enables or disables primitive restart for the following draw commands.
+///
+/// This allows using the same graphics pipeline with both list and strip topologies. The value
+/// is only taken into account if the pipeline used for drawing was created with the
+/// `PRIMITIVE_RESTART_ENABLE` dynamic state.
+// TODO: graphics pipelines can't be created with this dynamic state yet
+pub struct CmdSetPrimitiveRestartEnable {
+    // The device, so that we can compare it with the command buffer's device.
+    device: Arc<Device>,
+    // The value to set.
+    enable: vk::Bool32,
+}
+
+impl CmdSetPrimitiveRestartEnable {
+    /// Builds the command.
+    ///
+    /// Returns an error if the `ext_extended_dynamic_state2` extension or its
+    /// `extended_dynamic_state2` feature isn't enabled on the device.
+    pub fn new(device: Arc<Device>, enable: bool)
+               -> Result<CmdSetPrimitiveRestartEnable, CmdSetPrimitiveRestartEnableError>
+    {
+        if !device.loaded_extensions().ext_extended_dynamic_state2 {
+            return Err(CmdSetPrimitiveRestartEnableError::ExtensionNotEnabled);
+        }
+
+        if !device.enabled_features().extended_dynamic_state2 {
+            return Err(CmdSetPrimitiveRestartEnableError::FeatureNotEnabled);
+        }
+
+        Ok(CmdSetPrimitiveRestartEnable {
+            device: device,
+            enable: if enable { vk::TRUE } else { vk::FALSE },
+        })
+    }
+
+    /// Returns true if the command enables primitive restart.
+    #[inline]
+    pub fn enable(&self) -> bool {
+        self.enable != vk::FALSE
+    }
+}
+
+unsafe impl DeviceOwned for CmdSetPrimitiveRestartEnable {
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+}
+
+unsafe impl<'a, P> AddCommand<&'a CmdSetPrimitiveRestartEnable> for UnsafeCommandBufferBuilder<P>
+    where P: CommandPool
+{
+    type Out = UnsafeCommandBufferBuilder<P>;
+
+    #[inline]
+    fn add(self, command: &'a CmdSetPrimitiveRestartEnable) -> Result<Self::Out, CommandAddError> {
+        unsafe {
+            let vk = self.device().pointers();
+            let cmd = self.internal_object();
+            vk.CmdSetPrimitiveRestartEnableEXT(cmd, command.enable);
+        }
+
+        Ok(self)
+    }
+}
+
+/// Error that can happen when creating a `CmdSetPrimitiveRestartEnable`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CmdSetPrimitiveRestartEnableError {
+    /// The `ext_extended_dynamic_state2` extension isn't enabled on the device.
+    ExtensionNotEnabled,
+    /// The `extended_dynamic_state2` feature isn't enabled on the device.
+    FeatureNotEnabled,
+}
+
+impl error::Error for CmdSetPrimitiveRestartEnableError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            CmdSetPrimitiveRestartEnableError::ExtensionNotEnabled => {
+                "the `ext_extended_dynamic_state2` extension isn't enabled on the device"
+            },
+            CmdSetPrimitiveRestartEnableError::FeatureNotEnabled => {
+                "the `extended_dynamic_state2` feature isn't enabled on the device"
+            },
+        }
+    }
+}
+
+impl fmt::Display for CmdSetPrimitiveRestartEnableError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use command_buffer::commands_raw::CmdSetPrimitiveRestartEnable;
+    use command_buffer::commands_raw::CmdSetPrimitiveRestartEnableError;
+
+    #[test]
+    fn extension_not_enabled() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        match CmdSetPrimitiveRestartEnable::new(device, true) {
+            Err(CmdSetPrimitiveRestartEnableError::ExtensionNotEnabled) => (),
+            _ => panic!()
+        }
+    }
+}
@@ -0,0 +1,150 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::ptr;
+use std::sync::Arc;
+use smallvec::SmallVec;
+
+use command_buffer::CommandAddError;
+use command_buffer::cb::AddCommand;
+use command_buffer::cb::UnsafeCommandBufferBuilder;
+use command_buffer::pool::CommandPool;
+use device::Device;
+use device::DeviceOwned;
+use sync::AccessFlagBits;
+use sync::Event;
+use sync::PipelineStages;
+use VulkanObject;
+use VulkanPointers;
+use vk;
+
+/// Command that blocks the execution of the destination stages until one or more events are set.
+///
+/// The events can be set either by the host with `Event::set()`, or by a previous `CmdSetEvent`.
+#[derive(Debug, Clone)]
+pub struct CmdWaitEvents {
+    // The events to wait upon. Never empty.
+    events: SmallVec<[Arc<Event>; 4]>,
+    // The raw events, in the same order as `events`.
+    raw_events: SmallVec<[vk::Event; 4]>,
+    src_stage_mask: vk::PipelineStageFlags,
+    dst_stage_mask: vk::PipelineStageFlags,
+    // Source and destination access masks of the global memory barriers.
+    memory_barriers: SmallVec<[(vk::AccessFlags, vk::AccessFlags); 1]>,
+}
+
+impl CmdWaitEvents {
+    /// Builds the command, waiting on a single event.
+    ///
+    /// `src_stages` are the stages that set the events. They must include `host` if an event is
+    /// set by the host.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if `src_stages` or `dst_stages` is empty.
+    ///
+    /// # Safety
+    ///
+    /// - The events must be set before the command buffer is submitted, or be set by the host
+    ///   shortly after. Otherwise the device may be lost.
+    /// - If the pipeline stages include geometry or tessellation stages, then the corresponding
+    ///   features must have been enabled in the device.
+    /// - The stages must be supported by the queue family of the command buffer.
+    ///
+    pub unsafe fn new(event: Arc<Event>, src_stages: PipelineStages, dst_stages: PipelineStages)
+                      -> CmdWaitEvents
+    {
+        let src_stage_mask: vk::PipelineStageFlags = src_stages.into();
+        let dst_stage_mask: vk::PipelineStageFlags = dst_stages.into();
+        assert!(src_stage_mask != 0);
+        assert!(dst_stage_mask != 0);
+
+        let mut raw_events = SmallVec::new();
+        raw_events.push(event.internal_object());
+        let mut events = SmallVec::new();
+        events.push(event);
+
+        CmdWaitEvents {
+            events: events,
+            raw_events: raw_events,
+            src_stage_mask: src_stage_mask,
+            dst_stage_mask: dst_stage_mask,
+            memory_barriers: SmallVec::new(),
+        }
+    }
+
+    /// Adds another event to wait upon.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if the event doesn't belong to the same device as the other events.
+    ///
+    pub fn add_event(&mut self, event: Arc<Event>) {
+        assert_eq!(event.device().internal_object(),
+                   self.events[0].device().internal_object());
+        self.raw_events.push(event.internal_object());
+        self.events.push(event);
+    }
+
+    /// Adds a global memory barrier to the command.
+    ///
+    /// The memory writes performed by the source stages with the source accesses before the
+    /// events were set are made visible to the destination stages for the destination accesses.
+    /// For example, use `host_write` as the source access in order to make the writes that the
+    /// host performed before calling `Event::set()` visible.
+    #[inline]
+    pub fn add_memory_barrier(&mut self, src_access: AccessFlagBits, dst_access: AccessFlagBits) {
+        self.memory_barriers.push((src_access.into(), dst_access.into()));
+    }
+
+    /// Returns the events that the command waits upon.
+    #[inline]
+    pub fn events(&self) -> &[Arc<Event>] {
+        &self.events
+    }
+}
+
+unsafe impl DeviceOwned for CmdWaitEvents {
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        self.events[0].device()
+    }
+}
+
+unsafe impl<'a, P> AddCommand<&'a CmdWaitEvents> for UnsafeCommandBufferBuilder<P>
+    where P: CommandPool
+{
+    type Out = UnsafeCommandBufferBuilder<P>;
+
+    #[inline]
+    fn add(self, command: &'a CmdWaitEvents) -> Result<Self::Out, CommandAddError> {
+        unsafe {
+            let vk = self.device().pointers();
+            let cmd = self.internal_object();
+
+            let barriers: SmallVec<[vk::MemoryBarrier; 1]> = command.memory_barriers.iter()
+                .map(|&(src_access_mask, dst_access_mask)| {
+                    vk::MemoryBarrier {
+                        sType: vk::STRUCTURE_TYPE_MEMORY_BARRIER,
+                        pNext: ptr::null(),
+                        srcAccessMask: src_access_mask,
+                        dstAccessMask: dst_access_mask,
+                    }
+                })
+                .collect();
+
+            vk.CmdWaitEvents(cmd, command.raw_events.len() as u32, command.raw_events.as_ptr(),
+                             command.src_stage_mask, command.dst_stage_mask,
+                             barriers.len() as u32, barriers.as_ptr(), 0, ptr::null(),
+                             0, ptr::null());
+        }
+
+        Ok(self)
+    }
+}
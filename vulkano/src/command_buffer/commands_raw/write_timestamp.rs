@@ -0,0 +1,151 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::error;
+use std::fmt;
+use std::sync::Arc;
+
+use command_buffer::CommandAddError;
+use command_buffer::cb::AddCommand;
+use command_buffer::cb::UnsafeCommandBufferBuilder;
+use command_buffer::pool::CommandPool;
+use device::Device;
+use device::DeviceOwned;
+use query::UnsafeQueryPool;
+use sync::PipelineStages;
+use VulkanObject;
+use VulkanPointers;
+use vk;
+
+/// Command that writes a timestamp to a query.
+#[derive(Clone)]
+pub struct CmdWriteTimestamp {
+    // The query pool that contains the query.
+    pool: Arc<UnsafeQueryPool>,
+    // The index of the query within the pool.
+    query: u32,
+    // The pipeline stage after which the timestamp is written.
+    stage: vk::PipelineStageFlagBits,
+}
+
+impl CmdWriteTimestamp {
+    /// Builds a command that writes a timestamp to the query of index `query` of `pool`, once
+    /// all the previous commands have reached the given stage.
+    ///
+    /// Returns an error if `pool` wasn't created with `QueryType::Timestamp`.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if `query` is out of range of the pool.
+    /// - Panics if `stage` doesn't contain exactly one stage.
+    ///
+    pub fn new(pool: Arc<UnsafeQueryPool>, query: u32, stage: PipelineStages)
+               -> Result<CmdWriteTimestamp, CmdWriteTimestampError>
+    {
+        assert!(query < pool.num_slots());
+
+        let stage: vk::PipelineStageFlagBits = stage.into();
+        assert!(stage != 0 && (stage & (stage - 1)) == 0);
+
+        if !pool.is_timestamp() {
+            return Err(CmdWriteTimestampError::WrongQueryType);
+        }
+
+        Ok(CmdWriteTimestamp {
+            pool: pool,
+            query: query,
+            stage: stage,
+        })
+    }
+
+    /// Returns the query pool that contains the query.
+    #[inline]
+    pub fn pool(&self) -> &Arc<UnsafeQueryPool> {
+        &self.pool
+    }
+
+    /// Returns the index of the query within the pool.
+    #[inline]
+    pub fn query(&self) -> u32 {
+        self.query
+    }
+}
+
+unsafe impl DeviceOwned for CmdWriteTimestamp {
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        self.pool.device()
+    }
+}
+
+unsafe impl<'a, P> AddCommand<&'a CmdWriteTimestamp> for UnsafeCommandBufferBuilder<P>
+    where P: CommandPool
+{
+    type Out = UnsafeCommandBufferBuilder<P>;
+
+    #[inline]
+    fn add(self, command: &'a CmdWriteTimestamp) -> Result<Self::Out, CommandAddError> {
+        unsafe {
+            let vk = self.device().pointers();
+            let cmd = self.internal_object();
+            vk.CmdWriteTimestamp(cmd, command.stage, command.pool.internal_object(),
+                                 command.query);
+        }
+
+        Ok(self)
+    }
+}
+
+/// Error that can happen when creating a `CmdWriteTimestamp`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CmdWriteTimestampError {
+    /// The query pool wasn't created with `QueryType::Timestamp`.
+    WrongQueryType,
+}
+
+impl error::Error for CmdWriteTimestampError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            CmdWriteTimestampError::WrongQueryType => {
+                "the query pool doesn't contain timestamp queries"
+            },
+        }
+    }
+}
+
+impl fmt::Display for CmdWriteTimestampError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use command_buffer::commands_raw::CmdWriteTimestamp;
+    use command_buffer::commands_raw::CmdWriteTimestampError;
+    use query::QueryType;
+    use query::UnsafeQueryPool;
+    use sync::PipelineStages;
+
+    #[test]
+    fn occlusion_pool_rejected() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let pool = Arc::new(UnsafeQueryPool::new(device, QueryType::Occlusion, 1).unwrap());
+        let stage = PipelineStages { bottom_of_pipe: true, .. PipelineStages::none() };
+        match CmdWriteTimestamp::new(pool, 0, stage) {
+            Err(CmdWriteTimestampError::WrongQueryType) => (),
+            _ => panic!()
+        }
+    }
+}
@@ -79,6 +79,7 @@ pub use self::builder::CommandAddError;
 pub use self::builder::CommandBufferBuilder;
 pub use self::traits::CommandBuffer;
 pub use self::traits::CommandBufferBuild;
+pub use self::traits::CommandBufferExecError;
 pub use self::traits::CommandBufferExecFuture;
 
 use pipeline::viewport::Viewport;
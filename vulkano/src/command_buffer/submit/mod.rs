@@ -15,8 +15,10 @@
 
 pub use self::queue_present::SubmitPresentBuilder;
 pub use self::queue_present::SubmitPresentError;
+pub use self::queue_present::SwapchainPresentResult;
 pub use self::queue_submit::SubmitCommandBufferBuilder;
 pub use self::queue_submit::SubmitCommandBufferError;
+pub use self::queue_submit::SubmitInfoSnapshot;
 pub use self::semaphores_wait::SubmitSemaphoresWaitBuilder;
 
 mod queue_present;
@@ -41,4 +43,34 @@ impl<'a> SubmitAnyBuilder<'a> {
             _ => false,
         }
     }
+
+    /// Returns the name of the variant of this builder.
+    ///
+    /// This is meant to be used for diagnostic purposes, for example when logging which kind of
+    /// submission each side of a join has produced.
+    #[inline]
+    pub fn variant_name(&self) -> &'static str {
+        match self {
+            &SubmitAnyBuilder::Empty => "Empty",
+            &SubmitAnyBuilder::SemaphoresWait(_) => "SemaphoresWait",
+            &SubmitAnyBuilder::CommandBuffer(_) => "CommandBuffer",
+            &SubmitAnyBuilder::QueuePresent(_) => "QueuePresent",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn variant_name() {
+        assert_eq!(SubmitAnyBuilder::Empty.variant_name(), "Empty");
+        assert_eq!(SubmitAnyBuilder::SemaphoresWait(SubmitSemaphoresWaitBuilder::new())
+                       .variant_name(), "SemaphoresWait");
+        assert_eq!(SubmitAnyBuilder::CommandBuffer(SubmitCommandBufferBuilder::new())
+                       .variant_name(), "CommandBuffer");
+        assert_eq!(SubmitAnyBuilder::QueuePresent(SubmitPresentBuilder::new())
+                       .variant_name(), "QueuePresent");
+    }
 }
@@ -7,10 +7,11 @@
 // notice may not be copied, modified, or distributed except
 // according to those terms.
 
+#[cfg(debug_assertions)]
+use std::cell::Cell;
 use std::error;
 use std::fmt;
 use std::marker::PhantomData;
-use std::mem;
 use std::ptr;
 use smallvec::SmallVec;
 
@@ -19,6 +20,7 @@ use swapchain::Swapchain;
 use sync::Semaphore;
 
 use check_errors;
+use Success;
 use vk;
 use Error;
 use OomError;
@@ -28,11 +30,17 @@ use SynchronizedVulkanObject;
 
 /// Prototype for a submission that presents a swapchain on the screen.
 // TODO: example here
-#[derive(Debug)]
 pub struct SubmitPresentBuilder<'a> {
     wait_semaphores: SmallVec<[vk::Semaphore; 8]>,
     swapchains: SmallVec<[vk::SwapchainKHR; 4]>,
+    // The same swapchains, whose present identifiers are recorded once they are presented.
+    swapchain_objects: SmallVec<[&'a Swapchain; 4]>,
     image_indices: SmallVec<[u32; 4]>,
+    // One entry per swapchain. Zero means that the present has no identifier.
+    present_ids: SmallVec<[u64; 4]>,
+    // Used to detect double submissions in debug builds.
+    #[cfg(debug_assertions)]
+    submitted: Cell<bool>,
     marker: PhantomData<&'a ()>,
 }
 
@@ -43,7 +51,11 @@ impl<'a> SubmitPresentBuilder<'a> {
         SubmitPresentBuilder {
             wait_semaphores: SmallVec::new(),
             swapchains: SmallVec::new(),
+            swapchain_objects: SmallVec::new(),
             image_indices: SmallVec::new(),
+            present_ids: SmallVec::new(),
+            #[cfg(debug_assertions)]
+            submitted: Cell::new(false),
             marker: PhantomData,
         }
     }
@@ -83,29 +95,104 @@ impl<'a> SubmitPresentBuilder<'a> {
     pub unsafe fn add_swapchain(&mut self, swapchain: &'a Swapchain, image_num: u32) {
         debug_assert!(image_num < swapchain.num_images());
         self.swapchains.push(swapchain.internal_object());
+        self.swapchain_objects.push(swapchain);
         self.image_indices.push(image_num);
+        self.present_ids.push(0);
+    }
+
+    /// Adds an image of a swapchain to be presented, and associates an identifier with the
+    /// present operation. The identifier can then be passed to `Swapchain::wait_for_present`.
+    ///
+    /// # Safety
+    ///
+    /// - Same as `add_swapchain`.
+    ///
+    /// - The `present_id` feature must be enabled on the device.
+    ///
+    /// - `present_id` must be greater than all the identifiers previously used with this
+    ///   swapchain. Zero means that the present has no identifier.
+    ///
+    #[inline]
+    pub unsafe fn add_swapchain_with_present_id(&mut self, swapchain: &'a Swapchain,
+                                                image_num: u32, present_id: u64)
+    {
+        self.add_swapchain(swapchain, image_num);
+        *self.present_ids.last_mut().unwrap() = present_id;
     }
 
     /// Submits the command. Calls `vkQueuePresentKHR`.
     ///
+    /// Returns an error if the present of any of the swapchains failed. See
+    /// `submit_with_results` in order to know which ones.
+    ///
+    /// Like `SubmitCommandBufferBuilder::submit`, this consumes the builder so that it can't be
+    /// submitted twice.
+    ///
     /// # Panic
     ///
-    /// Panics if no swapchain image has been added to the builder.
+    /// - Panics if no swapchain image has been added to the builder.
+    /// - In debug builds, panics if this builder has already been submitted.
     ///
     pub fn submit(self, queue: &Queue) -> Result<(), SubmitPresentError> {
+        for result in try!(self.submit_with_results(queue)) {
+            if let SwapchainPresentResult::Error(err) = result {
+                return Err(err);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Submits the command. Calls `vkQueuePresentKHR`. Returns the result of the present of each
+    /// swapchain, in the order in which they were added.
+    ///
+    /// The presents whose result is `OutOfDate` or `SurfaceLost` don't happen, but the
+    /// semaphores are still waited upon and the submission still counts as being in the queue.
+    /// Only the errors that affect the whole submission are returned in the `Err`.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if no swapchain image has been added to the builder.
+    /// - In debug builds, panics if this builder has already been submitted.
+    ///
+    pub fn submit_with_results(self, queue: &Queue)
+                               -> Result<SmallVec<[SwapchainPresentResult; 4]>,
+                                         SubmitPresentError>
+    {
+        #[cfg(debug_assertions)]
+        {
+            assert!(!self.submitted.get(), "The same submission builder was submitted twice");
+            self.submitted.set(true);
+        }
+
         unsafe {
             debug_assert_eq!(self.swapchains.len(), self.image_indices.len());
+            debug_assert_eq!(self.swapchains.len(), self.present_ids.len());
             assert!(!self.swapchains.is_empty(),
                     "Tried to submit a present command without any swapchain");
 
             let vk = queue.device().pointers();
             let queue = queue.internal_object_guard();
 
-            let mut results = vec![mem::uninitialized(); self.swapchains.len()];       // TODO: alloca
+            // Some drivers don't write the results, in which case they stay successful and we
+            // fall back to the global result below.
+            let mut results: SmallVec<[vk::Result; 4]> =
+                self.swapchains.iter().map(|_| vk::SUCCESS).collect();
+
+            let present_ids = vk::PresentIdKHR {
+                sType: vk::STRUCTURE_TYPE_PRESENT_ID_KHR,
+                pNext: ptr::null(),
+                swapchainCount: self.present_ids.len() as u32,
+                pPresentIds: self.present_ids.as_ptr(),
+            };
 
             let infos = vk::PresentInfoKHR {
                 sType: vk::STRUCTURE_TYPE_PRESENT_INFO_KHR,
-                pNext: ptr::null(),
+                pNext: if self.present_ids.iter().any(|&id| id != 0) {
+                    &present_ids as *const _ as *const _
+                } else {
+                    ptr::null()
+                },
                 waitSemaphoreCount: self.wait_semaphores.len() as u32,
                 pWaitSemaphores: self.wait_semaphores.as_ptr(),
                 swapchainCount: self.swapchains.len() as u32,
@@ -114,18 +201,77 @@ impl<'a> SubmitPresentBuilder<'a> {
                 pResults: results.as_mut_ptr(),
             };
 
-            try!(check_errors(vk.QueuePresentKHR(*queue, &infos)));
+            let global = match check_errors(vk.QueuePresentKHR(*queue, &infos)) {
+                Ok(_) => None,
+                Err(err @ Error::OutOfDate) | Err(err @ Error::SurfaceLost) => {
+                    Some(SubmitPresentError::from(err))
+                },
+                Err(err) => return Err(SubmitPresentError::from(err)),
+            };
+
+            let mut results = results.into_iter().map(|result| {
+                match check_errors(result) {
+                    Ok(Success::Suboptimal) => SwapchainPresentResult::Suboptimal,
+                    Ok(_) => SwapchainPresentResult::Success,
+                    Err(err) => SwapchainPresentResult::Error(SubmitPresentError::from(err)),
+                }
+            }).collect::<SmallVec<[_; 4]>>();
+
+            if let Some(global) = global {
+                let any_error = results.iter().any(|r| match *r {
+                    SwapchainPresentResult::Error(_) => true,
+                    _ => false,
+                });
 
-            for result in results {
-                // TODO: AMD driver initially didn't write the results ; check that it's been fixed
-                //try!(check_errors(result));
+                if !any_error {
+                    for result in results.iter_mut() {
+                        *result = SwapchainPresentResult::Error(global);
+                    }
+                }
             }
 
-            Ok(())
+            // Only the presents that happened can be waited upon with their identifier.
+            for (n, result) in results.iter().enumerate() {
+                if let SwapchainPresentResult::Error(_) = *result {
+                    continue;
+                }
+
+                if self.present_ids[n] != 0 {
+                    self.swapchain_objects[n].record_present_id(self.image_indices[n],
+                                                                self.present_ids[n]);
+                }
+            }
+
+            Ok(results)
         }
     }
 }
 
+impl<'a> fmt::Debug for SubmitPresentBuilder<'a> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        fmt.debug_struct("SubmitPresentBuilder")
+           .field("wait_semaphores", &self.wait_semaphores)
+           .field("swapchains", &self.swapchains)
+           .field("image_indices", &self.image_indices)
+           .field("present_ids", &self.present_ids)
+           .finish()
+    }
+}
+
+/// Result of the present of one of the swapchains of a `SubmitPresentBuilder`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SwapchainPresentResult {
+    /// The image has been presented.
+    Success,
+
+    /// The image has been presented, but the swapchain no longer matches the properties of the
+    /// surface exactly. You should recreate it.
+    Suboptimal,
+
+    /// The image couldn't be presented.
+    Error(SubmitPresentError),
+}
+
 /// Error that can happen when submitting the present prototype.
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 #[repr(u32)]
@@ -142,6 +288,10 @@ pub enum SubmitPresentError {
     /// The surface has changed in a way that makes the swapchain unusable. You must query the
     /// surface's new properties and recreate a new swapchain if you want to continue drawing.
     OutOfDate,
+
+    /// The swapchain has lost its full-screen exclusive mode, which was granted by the
+    /// implementation. The swapchain is still usable, but no longer exclusive.
+    FullScreenExclusiveLost,
 }
 
 impl error::Error for SubmitPresentError {
@@ -152,6 +302,9 @@ impl error::Error for SubmitPresentError {
             SubmitPresentError::DeviceLost => "the connection to the device has been lost",
             SubmitPresentError::SurfaceLost => "the surface of this swapchain is no longer valid",
             SubmitPresentError::OutOfDate => "the swapchain needs to be recreated",
+            SubmitPresentError::FullScreenExclusiveLost => {
+                "the swapchain has lost its full-screen exclusive mode"
+            },
         }
     }
 
@@ -180,6 +333,7 @@ impl From<Error> for SubmitPresentError {
             Error::DeviceLost => SubmitPresentError::DeviceLost,
             Error::SurfaceLost => SubmitPresentError::SurfaceLost,
             Error::OutOfDate => SubmitPresentError::OutOfDate,
+            Error::FullScreenExclusiveLost => SubmitPresentError::FullScreenExclusiveLost,
             _ => panic!("unexpected error: {:?}", err)
         }
     }
@@ -195,4 +349,16 @@ mod tests {
         let (_, queue) = gfx_dev_and_queue!();
         let _ = SubmitPresentBuilder::new().submit(&queue);
     }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "The same submission builder was submitted twice")]
+    fn submit_twice() {
+        let (_, queue) = gfx_dev_and_queue!();
+
+        let builder = SubmitPresentBuilder::new();
+        // Simulates a builder that has already been submitted.
+        builder.submitted.set(true);
+        let _ = builder.submit(&queue);
+    }
 }
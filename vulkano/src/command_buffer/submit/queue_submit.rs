@@ -7,6 +7,8 @@
 // notice may not be copied, modified, or distributed except
 // according to those terms.
 
+#[cfg(debug_assertions)]
+use std::cell::Cell;
 use std::error;
 use std::fmt;
 use std::marker::PhantomData;
@@ -37,6 +39,10 @@ pub struct SubmitCommandBufferBuilder<'a> {
     signal_semaphores: SmallVec<[vk::Semaphore; 16]>,
     command_buffers: SmallVec<[vk::CommandBuffer; 4]>,
     fence: vk::Fence,
+    performance_query_pass: Option<u32>,
+    // Used to detect double submissions in debug builds.
+    #[cfg(debug_assertions)]
+    submitted: Cell<bool>,
     marker: PhantomData<&'a ()>,
 }
 
@@ -50,6 +56,9 @@ impl<'a> SubmitCommandBufferBuilder<'a> {
             signal_semaphores: SmallVec::new(),
             command_buffers: SmallVec::new(),
             fence: 0,
+            performance_query_pass: None,
+            #[cfg(debug_assertions)]
+            submitted: Cell::new(false),
             marker: PhantomData,
         }
     }
@@ -141,14 +150,80 @@ impl<'a> SubmitCommandBufferBuilder<'a> {
     ///
     /// - The fence, command buffers, and semaphores must all belong to the same device.
     ///
+    /// If the stages require a device extension that isn't enabled, `submit()` returns
+    /// `SubmitCommandBufferError::UnsupportedPipelineStages`.
+    ///
     #[inline]
     pub unsafe fn add_wait_semaphore(&mut self, semaphore: &'a Semaphore, stages: PipelineStages) {
         debug_assert!(Into::<vk::PipelineStageFlagBits>::into(stages) != 0);
-        // TODO: debug assert that the device supports the stages
         self.wait_semaphores.push(semaphore.internal_object());
         self.dest_stages.push(stages.into());
     }
 
+    /// Replaces the stages that wait upon each of the semaphores added so far with `stages`.
+    ///
+    /// # Safety
+    ///
+    /// - The stages must be supported by the device.
+    ///
+    /// - The command buffers must not access, outside of `stages`, any resource whose accesses
+    ///   are synchronized by one of the semaphores.
+    ///
+    #[inline]
+    pub unsafe fn set_wait_dest_stages(&mut self, stages: PipelineStages) {
+        debug_assert!(Into::<vk::PipelineStageFlagBits>::into(stages) != 0);
+        for dest in self.dest_stages.iter_mut() {
+            *dest = stages.into();
+        }
+    }
+
+    /// Returns the semaphores that will be waited upon, alongside with the stages that will wait
+    /// upon each of them.
+    ///
+    /// This corresponds to the `pWaitDstStageMask` that will be passed to Vulkan, and can be
+    /// used to check that a submission doesn't wait upon more stages than necessary.
+    pub fn wait_dest_stages(&self) -> Vec<(vk::Semaphore, PipelineStages)> {
+        debug_assert_eq!(self.wait_semaphores.len(), self.dest_stages.len());
+        self.wait_semaphores.iter().zip(self.dest_stages.iter())
+            .map(|(&sem, &stages)| (sem, PipelineStages::from(stages)))
+            .collect()
+    }
+
+    /// Returns a snapshot of what would be passed to `vkQueueSubmit` if this builder was
+    /// submitted now.
+    ///
+    /// Nothing is submitted. The snapshot doesn't borrow the builder, which makes it possible to
+    /// compare the submissions built by futures against expected values in tests, or to forward
+    /// them to a custom layer.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use vulkano::command_buffer::submit::SubmitCommandBufferBuilder;
+    /// use vulkano::sync::Fence;
+    /// use vulkano::VulkanObject;
+    /// # let device: std::sync::Arc<vulkano::device::Device> = return;
+    ///
+    /// unsafe {
+    ///     let fence = Fence::new(device.clone()).unwrap();
+    ///
+    ///     let mut builder = SubmitCommandBufferBuilder::new();
+    ///     builder.set_fence_signal(&fence);
+    ///
+    ///     let snapshot = builder.peek_submit_info();
+    ///     assert!(snapshot.wait_semaphores.is_empty());
+    ///     assert_eq!(snapshot.fence, Some(fence.internal_object()));
+    /// }
+    /// ```
+    pub fn peek_submit_info(&self) -> SubmitInfoSnapshot {
+        SubmitInfoSnapshot {
+            wait_semaphores: self.wait_dest_stages(),
+            command_buffers: self.command_buffers.iter().cloned().collect(),
+            signal_semaphores: self.signal_semaphores.iter().cloned().collect(),
+            fence: if self.fence != 0 { Some(self.fence) } else { None },
+        }
+    }
+
     /// Adds a command buffer that is executed as part of this command.
     ///
     /// The command buffers are submitted in the order in which they are added.
@@ -174,6 +249,38 @@ impl<'a> SubmitCommandBufferBuilder<'a> {
         self.command_buffers.push(command_buffer.internal_object());
     }
 
+    /// Returns a new builder that executes the same command buffers as this one, but that
+    /// doesn't wait upon or signal any semaphore or fence.
+    ///
+    /// # Safety
+    ///
+    /// - If you submit both builders, the command buffers must have been created with the
+    ///   simultaneous use flag, or the first submission must have finished executing when the
+    ///   second one is submitted.
+    ///
+    #[inline]
+    pub unsafe fn clone_command_buffers(&self) -> SubmitCommandBufferBuilder<'a> {
+        SubmitCommandBufferBuilder {
+            command_buffers: self.command_buffers.clone(),
+            .. SubmitCommandBufferBuilder::new()
+        }
+    }
+
+    /// Sets the index of the counter pass of the performance queries that are executed by the
+    /// command buffers of this submission.
+    ///
+    /// # Safety
+    ///
+    /// - The `khr_performance_query` extension must be enabled on the device.
+    ///
+    /// - `pass` must be inferior to the number of passes required by all the performance query
+    ///   pools used by the command buffers.
+    ///
+    #[inline]
+    pub unsafe fn set_performance_query_pass(&mut self, pass: u32) {
+        self.performance_query_pass = Some(pass);
+    }
+
     /// Returns the number of semaphores to signal.
     ///
     /// In other words, this is the number of times `add_signal_semaphore` has been called.
@@ -204,16 +311,61 @@ impl<'a> SubmitCommandBufferBuilder<'a> {
     /// > **Note**: This is an expensive operation, so you may want to merge as many builders as
     /// > possible together and avoid submitting them one by one.
     ///
+    /// The builder is consumed, so the same builder can't be submitted twice:
+    ///
+    /// ```compile_fail
+    /// use vulkano::command_buffer::submit::SubmitCommandBufferBuilder;
+    /// # let queue: std::sync::Arc<vulkano::device::Queue> = return;
+    ///
+    /// let builder = SubmitCommandBufferBuilder::new();
+    /// builder.submit(&queue).unwrap();
+    /// builder.submit(&queue).unwrap();
+    /// ```
+    ///
+    /// This doesn't prevent a future from building the same submission twice out of two
+    /// different builders, which is why `GpuFuture::build_submission` must only return a
+    /// non-empty builder once.
+    ///
+    /// # Panic
+    ///
+    /// In debug builds, panics if this builder has already been submitted.
+    ///
     pub fn submit(self, queue: &Queue) -> Result<(), SubmitCommandBufferError> {
+        #[cfg(debug_assertions)]
+        {
+            assert!(!self.submitted.get(), "The same submission builder was submitted twice");
+            self.submitted.set(true);
+        }
+
+        // Passing a stage of an extension that isn't enabled would produce an invalid
+        // `VkSubmitInfo`.
+        let extensions = queue.device().loaded_extensions();
+        for &stages in self.dest_stages.iter() {
+            if !PipelineStages::from(stages).is_supported_by(extensions) {
+                return Err(SubmitCommandBufferError::UnsupportedPipelineStages);
+            }
+        }
+
         unsafe {
             let vk = queue.device().pointers();
             let queue = queue.internal_object_guard();
 
             debug_assert_eq!(self.wait_semaphores.len(), self.dest_stages.len());
 
+            let performance_query = self.performance_query_pass.map(|pass| {
+                vk::PerformanceQuerySubmitInfoKHR {
+                    sType: vk::STRUCTURE_TYPE_PERFORMANCE_QUERY_SUBMIT_INFO_KHR,
+                    pNext: ptr::null(),
+                    counterPassIndex: pass,
+                }
+            });
+
             let batch = vk::SubmitInfo {
                 sType: vk::STRUCTURE_TYPE_SUBMIT_INFO,
-                pNext: ptr::null(),
+                pNext: match performance_query {
+                    Some(ref infos) => infos as *const _ as *const _,
+                    None => ptr::null(),
+                },
                 waitSemaphoreCount: self.wait_semaphores.len() as u32,
                 pWaitSemaphores: self.wait_semaphores.as_ptr(),
                 pWaitDstStageMask: self.dest_stages.as_ptr(),
@@ -232,11 +384,15 @@ impl<'a> SubmitCommandBufferBuilder<'a> {
     ///
     /// # Panic
     ///
-    /// Panics if both builders have a fence already set.
+    /// Panics if both builders have a fence already set, or if they have different performance
+    /// query passes.
     // TODO: create multiple batches instead
     pub fn merge(mut self, other: Self) -> Self {
         assert!(self.fence == 0 || other.fence == 0,
                "Can't merge two queue submits that both have a fence");
+        assert!(self.performance_query_pass.is_none() || other.performance_query_pass.is_none() ||
+                self.performance_query_pass == other.performance_query_pass,
+                "Can't merge two queue submits that execute different performance query passes");
 
         self.wait_semaphores.extend(other.wait_semaphores);
         self.dest_stages.extend(other.dest_stages);     // TODO: meh? will be solved if we submit multiple batches
@@ -246,11 +402,30 @@ impl<'a> SubmitCommandBufferBuilder<'a> {
         if self.fence == 0 {
             self.fence = other.fence;
         }
+        if self.performance_query_pass.is_none() {
+            self.performance_query_pass = other.performance_query_pass;
+        }
 
         self
     }
 }
 
+/// Owned copy of the content of a `SubmitCommandBufferBuilder`.
+///
+/// Returned by `SubmitCommandBufferBuilder::peek_submit_info`. Each field corresponds to a
+/// member of the `VkSubmitInfo` that is passed to `vkQueueSubmit`, in the same order.
+#[derive(Debug, Clone)]
+pub struct SubmitInfoSnapshot {
+    /// The semaphores to wait upon, alongside with the stages that will wait upon each of them.
+    pub wait_semaphores: Vec<(vk::Semaphore, PipelineStages)>,
+    /// The command buffers to execute, in the order in which they are executed.
+    pub command_buffers: Vec<vk::CommandBuffer>,
+    /// The semaphores to signal at the end of the submission.
+    pub signal_semaphores: Vec<vk::Semaphore>,
+    /// The fence to signal at the end of the submission, if any.
+    pub fence: Option<vk::Fence>,
+}
+
 /// Error that can happen when submitting the prototype.
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 #[repr(u32)]
@@ -260,6 +435,13 @@ pub enum SubmitCommandBufferError {
 
     /// The connection to the device has been lost.
     DeviceLost,
+
+    /// The global priority of the queue isn't permitted by the implementation.
+    NotPermitted,
+
+    /// The submission waits with pipeline stages that require a device extension that isn't
+    /// enabled.
+    UnsupportedPipelineStages,
 }
 
 impl error::Error for SubmitCommandBufferError {
@@ -268,6 +450,12 @@ impl error::Error for SubmitCommandBufferError {
         match *self {
             SubmitCommandBufferError::OomError(_) => "not enough memory",
             SubmitCommandBufferError::DeviceLost => "the connection to the device has been lost",
+            SubmitCommandBufferError::NotPermitted => {
+                "the global priority of the queue isn't permitted"
+            },
+            SubmitCommandBufferError::UnsupportedPipelineStages => {
+                "the submission uses pipeline stages whose extension isn't enabled"
+            },
         }
     }
 
@@ -294,6 +482,7 @@ impl From<Error> for SubmitCommandBufferError {
             err @ Error::OutOfHostMemory => SubmitCommandBufferError::OomError(OomError::from(err)),
             err @ Error::OutOfDeviceMemory => SubmitCommandBufferError::OomError(OomError::from(err)),
             Error::DeviceLost => SubmitCommandBufferError::DeviceLost,
+            Error::NotPermitted => SubmitCommandBufferError::NotPermitted,
             _ => panic!("unexpected error: {:?}", err)
         }
     }
@@ -304,6 +493,7 @@ mod tests {
     use std::time::Duration;
     use super::*;
     use sync::Fence;
+    use sync::Semaphore;
 
     #[test]
     fn empty_submit() {
@@ -360,4 +550,101 @@ mod tests {
             let _ = builder1.merge(builder2);
         }
     }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "The same submission builder was submitted twice")]
+    fn submit_twice() {
+        let (_, queue) = gfx_dev_and_queue!();
+
+        let builder = SubmitCommandBufferBuilder::new();
+        // Simulates a builder that has already been submitted.
+        builder.submitted.set(true);
+        let _ = builder.submit(&queue);
+    }
+
+    #[test]
+    fn wait_dest_stages() {
+        unsafe {
+            let (device, _) = gfx_dev_and_queue!();
+
+            let sem1 = Semaphore::new(device.clone()).unwrap();
+            let sem2 = Semaphore::new(device.clone()).unwrap();
+
+            let mut builder = SubmitCommandBufferBuilder::new();
+            builder.add_wait_semaphore(&sem1, PipelineStages {
+                transfer: true,
+                .. PipelineStages::none()
+            });
+            builder.add_wait_semaphore(&sem2, PipelineStages {
+                vertex_shader: true,
+                fragment_shader: true,
+                .. PipelineStages::none()
+            });
+
+            let stages = builder.wait_dest_stages();
+            assert_eq!(stages.len(), 2);
+
+            assert_eq!(stages[0].0, sem1.internal_object());
+            assert!(stages[0].1.transfer);
+            assert!(!stages[0].1.vertex_shader);
+
+            assert_eq!(stages[1].0, sem2.internal_object());
+            assert!(stages[1].1.vertex_shader);
+            assert!(stages[1].1.fragment_shader);
+            assert!(!stages[1].1.transfer);
+        }
+    }
+
+    #[test]
+    fn set_wait_dest_stages() {
+        unsafe {
+            let (device, _) = gfx_dev_and_queue!();
+
+            let sem = Semaphore::new(device.clone()).unwrap();
+
+            let mut builder = SubmitCommandBufferBuilder::new();
+            builder.add_wait_semaphore(&sem, PipelineStages {
+                all_commands: true,
+                .. PipelineStages::none()
+            });
+            builder.set_wait_dest_stages(PipelineStages {
+                color_attachment_output: true,
+                .. PipelineStages::none()
+            });
+
+            let stages = builder.wait_dest_stages();
+            assert!(stages[0].1.color_attachment_output);
+            assert!(!stages[0].1.all_commands);
+        }
+    }
+
+    #[test]
+    fn peek_submit_info() {
+        unsafe {
+            let (device, _) = gfx_dev_and_queue!();
+
+            let wait = Semaphore::new(device.clone()).unwrap();
+            let signal = Semaphore::new(device.clone()).unwrap();
+            let fence = Fence::new(device.clone()).unwrap();
+
+            let mut builder = SubmitCommandBufferBuilder::new();
+            assert!(builder.peek_submit_info().fence.is_none());
+
+            builder.add_wait_semaphore(&wait, PipelineStages {
+                transfer: true,
+                .. PipelineStages::none()
+            });
+            builder.add_signal_semaphore(&signal);
+            builder.set_fence_signal(&fence);
+
+            let snapshot = builder.peek_submit_info();
+            assert_eq!(snapshot.wait_semaphores.len(), 1);
+            assert_eq!(snapshot.wait_semaphores[0].0, wait.internal_object());
+            assert!(snapshot.wait_semaphores[0].1.transfer);
+            assert!(snapshot.command_buffers.is_empty());
+            assert_eq!(snapshot.signal_semaphores, vec![signal.internal_object()]);
+            assert_eq!(snapshot.fence, Some(fence.internal_object()));
+        }
+    }
 }
@@ -20,7 +20,9 @@ use sync::Semaphore;
 /// However you can convert it into another builder prototype through the `Into` trait.
 #[derive(Debug)]
 pub struct SubmitSemaphoresWaitBuilder<'a> {
-    semaphores: SmallVec<[&'a Semaphore; 8]>,
+    // Each semaphore, along with the stages of the command buffers that wait upon it if the
+    // builder is turned into a `SubmitCommandBufferBuilder`.
+    semaphores: SmallVec<[(&'a Semaphore, PipelineStages); 8]>,
 }
 
 impl<'a> SubmitSemaphoresWaitBuilder<'a> {
@@ -35,9 +37,32 @@ impl<'a> SubmitSemaphoresWaitBuilder<'a> {
     /// Adds an operation that waits on a semaphore.
     ///
     /// The semaphore must be signaled by a previous submission.
+    ///
+    /// If the builder is turned into a `SubmitCommandBufferBuilder`, all the stages of the
+    /// command buffers wait upon the semaphore.
     #[inline]
     pub unsafe fn add_wait_semaphore(&mut self, semaphore: &'a Semaphore) {
-        self.semaphores.push(semaphore);
+        self.add_wait_semaphore_with_stages(semaphore, PipelineStages {
+            all_commands: true,
+            .. PipelineStages::none()
+        });
+    }
+
+    /// Same as `add_wait_semaphore()`, but only the given `stages` of the command buffers wait
+    /// upon the semaphore if the builder is turned into a `SubmitCommandBufferBuilder`.
+    ///
+    /// The stages are ignored if the builder is turned into a `SubmitPresentBuilder`.
+    ///
+    /// # Safety
+    ///
+    /// Same as `add_wait_semaphore()`. In addition, the stages must follow the same rules as in
+    /// `SubmitCommandBufferBuilder::add_wait_semaphore()`.
+    ///
+    #[inline]
+    pub unsafe fn add_wait_semaphore_with_stages(&mut self, semaphore: &'a Semaphore,
+                                                 stages: PipelineStages)
+    {
+        self.semaphores.push((semaphore, stages));
     }
 
     /// Merges this builder with another builder.
@@ -52,12 +77,8 @@ impl<'a> Into<SubmitCommandBufferBuilder<'a>> for SubmitSemaphoresWaitBuilder<'a
     fn into(mut self) -> SubmitCommandBufferBuilder<'a> {
         unsafe {
             let mut builder = SubmitCommandBufferBuilder::new();
-            for sem in self.semaphores.drain() {
-                builder.add_wait_semaphore(sem, PipelineStages {
-                    // TODO: correct stages ; hard
-                    all_commands: true,
-                    .. PipelineStages::none()
-                });
+            for (sem, stages) in self.semaphores.drain() {
+                builder.add_wait_semaphore(sem, stages);
             }
             builder
         }
@@ -69,7 +90,7 @@ impl<'a> Into<SubmitPresentBuilder<'a>> for SubmitSemaphoresWaitBuilder<'a> {
     fn into(mut self) -> SubmitPresentBuilder<'a> {
         unsafe {
             let mut builder = SubmitPresentBuilder::new();
-            for sem in self.semaphores.drain() {
+            for (sem, _) in self.semaphores.drain() {
                 builder.add_wait_semaphore(sem);
             }
             builder
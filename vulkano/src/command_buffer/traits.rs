@@ -8,12 +8,14 @@
 // according to those terms.
 
 use std::error;
+use std::fmt;
 use std::sync::Arc;
 use std::sync::Mutex;
 use std::sync::atomic::AtomicBool;
 use std::sync::atomic::Ordering;
 
 use buffer::BufferAccess;
+use command_buffer::cb::Flags;
 use command_buffer::cb::UnsafeCommandBuffer;
 use command_buffer::pool::CommandPool;
 use command_buffer::submit::SubmitAnyBuilder;
@@ -23,9 +25,12 @@ use device::DeviceOwned;
 use device::Queue;
 use image::ImageAccess;
 use instance::QueueFamily;
+use swapchain::Swapchain;
 use sync::AccessFlagBits;
 use sync::DummyFuture;
+use sync::FlushError;
 use sync::GpuFuture;
+use sync::GpuFutureMut;
 use sync::PipelineStages;
 use SafeDeref;
 use VulkanObject;
@@ -94,6 +99,9 @@ pub unsafe trait CommandBuffer: DeviceOwned {
     /// `std::mem::forget` on that object and "unlock" these resources. For more information about
     /// this problem, search the web for "rust thread scoped leakpocalypse".
     ///
+    /// If the command buffer was created for a queue family different from the one of `queue`,
+    /// flushing the returned future fails with `CommandBufferExecError::QueueFamilyMismatch`.
+    ///
     /// # Panic
     ///
     /// Panics if the device of the command buffer is not the same as the device of the future.
@@ -101,6 +109,30 @@ pub unsafe trait CommandBuffer: DeviceOwned {
     fn execute_after<F>(self, future: F, queue: Arc<Queue>) -> CommandBufferExecFuture<F, Self>
         where Self: Sized + 'static, F: GpuFuture
     {
+        self.execute_after_repeated(future, queue, 1)
+    }
+
+    /// Executes the command buffer `count` times in a row after an existing future.
+    ///
+    /// All the executions are part of the same submission, which is cheaper than chaining
+    /// `count` calls to `execute_after`. No state is carried between the executions other than
+    /// what the command buffer itself sets, so any push constant or descriptor that must differ
+    /// between executions has to be set from within the command buffer.
+    ///
+    /// Since the same command buffer appears multiple times in the submission, it must have been
+    /// created with the `SimultaneousUse` flag if `count` is greater than 1. Otherwise flushing
+    /// the returned future fails with `CommandBufferExecError::SimultaneousUseRequired`.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if the device of the command buffer is not the same as the device of the future.
+    /// - Panics if `count` is 0.
+    ///
+    fn execute_after_repeated<F>(self, future: F, queue: Arc<Queue>, count: u32)
+                                 -> CommandBufferExecFuture<F, Self>
+        where Self: Sized + 'static, F: GpuFuture
+    {
+        assert!(count >= 1);
         assert_eq!(self.device().internal_object(), future.device().internal_object());
 
         self.submit_check(&future, &queue).expect("Forbidden");     // TODO: error
@@ -113,6 +145,7 @@ pub unsafe trait CommandBuffer: DeviceOwned {
             previous: future,
             command_buffer: self,
             queue: queue,
+            count: count,
             submitted: Mutex::new(false),
             finished: AtomicBool::new(false),
         }
@@ -173,6 +206,8 @@ pub struct CommandBufferExecFuture<F, Cb> where F: GpuFuture, Cb: CommandBuffer
     previous: F,
     command_buffer: Cb,
     queue: Arc<Queue>,
+    // Number of times the command buffer is added to the submission.
+    count: u32,
     // True if the command buffer has already been submitted.
     // If flush is called multiple times, we want to block so that only one flushing is executed.
     // Therefore we use a `Mutex<bool>` and not an `AtomicBool`.
@@ -180,6 +215,16 @@ pub struct CommandBufferExecFuture<F, Cb> where F: GpuFuture, Cb: CommandBuffer
     finished: AtomicBool,
 }
 
+impl<F, Cb> CommandBufferExecFuture<F, Cb> where F: GpuFuture, Cb: CommandBuffer {
+    // Adds the command buffer to the builder as many times as requested.
+    #[inline]
+    unsafe fn add_to_builder<'a>(&'a self, builder: &mut SubmitCommandBufferBuilder<'a>) {
+        for _ in 0 .. self.count {
+            builder.add_command_buffer(self.command_buffer.inner());
+        }
+    }
+}
+
 unsafe impl<F, Cb> GpuFuture for CommandBufferExecFuture<F, Cb>
     where F: GpuFuture, Cb: CommandBuffer
 {
@@ -188,21 +233,29 @@ unsafe impl<F, Cb> GpuFuture for CommandBufferExecFuture<F, Cb>
         self.previous.cleanup_finished();
     }
 
-    unsafe fn build_submission(&self) -> Result<SubmitAnyBuilder, Box<error::Error>> {
+    unsafe fn build_submission(&self) -> Result<SubmitAnyBuilder, FlushError> {
+        if self.command_buffer.queue_family().id() != self.queue.family().id() {
+            return Err(FlushError::from(CommandBufferExecError::QueueFamilyMismatch));
+        }
+
+        if self.count > 1 && self.command_buffer.inner().flags() != Flags::SimultaneousUse {
+            return Err(FlushError::from(CommandBufferExecError::SimultaneousUseRequired));
+        }
+
         Ok(match try!(self.previous.build_submission()) {
             SubmitAnyBuilder::Empty => {
                 let mut builder = SubmitCommandBufferBuilder::new();
-                builder.add_command_buffer(self.command_buffer.inner());
+                self.add_to_builder(&mut builder);
                 SubmitAnyBuilder::CommandBuffer(builder)
             },
             SubmitAnyBuilder::SemaphoresWait(sem) => {
                 let mut builder: SubmitCommandBufferBuilder = sem.into();
-                builder.add_command_buffer(self.command_buffer.inner());
+                self.add_to_builder(&mut builder);
                 SubmitAnyBuilder::CommandBuffer(builder)
             },
             SubmitAnyBuilder::CommandBuffer(mut builder) => {
                 // FIXME: add pipeline barrier
-                builder.add_command_buffer(self.command_buffer.inner());
+                self.add_to_builder(&mut builder);
                 SubmitAnyBuilder::CommandBuffer(builder)
             },
             SubmitAnyBuilder::QueuePresent(present) => {
@@ -216,7 +269,7 @@ unsafe impl<F, Cb> GpuFuture for CommandBufferExecFuture<F, Cb>
     }
 
     #[inline]
-    fn flush(&self) -> Result<(), Box<error::Error>> {
+    fn flush(&self) -> Result<(), FlushError> {
         unsafe {
             let mut submitted = self.submitted.lock().unwrap();
             if *submitted {
@@ -274,6 +327,55 @@ unsafe impl<F, Cb> GpuFuture for CommandBufferExecFuture<F, Cb>
             Err(()) => self.previous.check_image_access(image, exclusive, queue),
         }
     }
+
+    #[inline]
+    fn acquired_swapchain_image(&self, swapchain: &Swapchain) -> Option<usize> {
+        self.previous.acquired_swapchain_image(swapchain)
+    }
+
+    #[inline]
+    fn validate_resources(&self) {
+        self.previous.validate_resources();
+    }
+
+    #[inline]
+    fn wait_semaphore_count(&self) -> usize {
+        self.previous.wait_semaphore_count()
+    }
+
+    #[inline]
+    fn is_flushed(&self) -> bool {
+        *self.submitted.lock().unwrap()
+    }
+
+    #[inline]
+    fn shared_identities(&self, ids: &mut Vec<usize>) {
+        self.previous.shared_identities(ids);
+    }
+}
+
+unsafe impl<F, Cb> GpuFutureMut for CommandBufferExecFuture<F, Cb>
+    where F: GpuFutureMut, Cb: CommandBuffer
+{
+    #[inline]
+    fn check_buffer_access_mut(&mut self, buffer: &BufferAccess, exclusive: bool, queue: &Queue)
+                               -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        match self.command_buffer.check_buffer_access(buffer, exclusive, queue) {
+            Ok(v) => Ok(v),
+            Err(()) => self.previous.check_buffer_access_mut(buffer, exclusive, queue),
+        }
+    }
+
+    #[inline]
+    fn check_image_access_mut(&mut self, image: &ImageAccess, exclusive: bool, queue: &Queue)
+                              -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        match self.command_buffer.check_image_access(image, exclusive, queue) {
+            Ok(v) => Ok(v),
+            Err(()) => self.previous.check_image_access_mut(image, exclusive, queue),
+        }
+    }
 }
 
 unsafe impl<F, Cb> DeviceOwned for CommandBufferExecFuture<F, Cb>
@@ -298,3 +400,118 @@ impl<F, Cb> Drop for CommandBufferExecFuture<F, Cb> where F: GpuFuture, Cb: Comm
         }
     }
 }
+
+/// Error that can happen when submitting a command buffer.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CommandBufferExecError {
+    /// The command buffer was created for a queue family different from the one of the queue it
+    /// is submitted to.
+    QueueFamilyMismatch,
+
+    /// The command buffer is added multiple times to the same submission but wasn't created with
+    /// the `SimultaneousUse` flag.
+    SimultaneousUseRequired,
+}
+
+impl error::Error for CommandBufferExecError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            CommandBufferExecError::QueueFamilyMismatch => {
+                "the command buffer was created for a different queue family than the queue it \
+                 is submitted to"
+            },
+            CommandBufferExecError::SimultaneousUseRequired => {
+                "the command buffer is submitted multiple times at once but wasn't created with \
+                 the simultaneous use flag"
+            },
+        }
+    }
+}
+
+impl fmt::Display for CommandBufferExecError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::mem;
+    use command_buffer::AutoCommandBufferBuilder;
+    use command_buffer::CommandBuffer;
+    use command_buffer::CommandBufferBuild;
+    use command_buffer::CommandBufferExecError;
+    use command_buffer::cb::Flags;
+    use command_buffer::cb::Kind;
+    use command_buffer::cb::UnsafeCommandBufferBuilder;
+    use device::Device;
+    use device::DeviceExtensions;
+    use features::Features;
+    use instance::PhysicalDevice;
+    use sync::DummyFuture;
+    use sync::FlushError;
+    use sync::GpuFuture;
+
+    #[test]
+    fn queue_family_mismatch() {
+        let instance = instance!();
+
+        let physical = match PhysicalDevice::enumerate(&instance).next() {
+            Some(p) => p,
+            None => return
+        };
+
+        let graphics = match physical.queue_families().find(|q| q.supports_graphics()) {
+            Some(q) => q,
+            None => return
+        };
+
+        let other = match physical.queue_families().find(|q| q.id() != graphics.id()) {
+            Some(q) => q,
+            None => return
+        };
+
+        let (device, mut queues) = match Device::new(&physical, &Features::none(),
+                                                     &DeviceExtensions::none(),
+                                                     [(graphics, 0.5), (other, 0.5)]
+                                                         .iter().cloned())
+        {
+            Ok(r) => r,
+            Err(_) => return
+        };
+
+        let _graphics_queue = queues.next().unwrap();
+        let other_queue = queues.next().unwrap();
+
+        let cb = AutoCommandBufferBuilder::new(device.clone(), graphics).unwrap()
+            .build().unwrap();
+
+        let future = cb.execute_after(DummyFuture::new(device.clone()), other_queue);
+        let err = future.flush().unwrap_err();
+        assert_eq!(err,
+                   FlushError::ResourceAccessError(CommandBufferExecError::QueueFamilyMismatch));
+
+        // Nothing was submitted, so there is nothing to wait for.
+        mem::forget(future);
+    }
+
+    #[test]
+    fn repeated_requires_simultaneous_use() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let cb = unsafe {
+            let pool = Device::standard_command_pool(&device, queue.family());
+            UnsafeCommandBufferBuilder::new(&pool, Kind::primary(), Flags::None).unwrap()
+                .build().unwrap()
+        };
+
+        let future = cb.execute_after_repeated(DummyFuture::new(device.clone()), queue, 2);
+        let err = future.flush().unwrap_err();
+        let expected = CommandBufferExecError::SimultaneousUseRequired;
+        assert_eq!(err, FlushError::ResourceAccessError(expected));
+
+        mem::forget(future);
+    }
+}
@@ -90,17 +90,22 @@
 //! TODO: write
 
 use std::collections::HashMap;
+use std::collections::VecDeque;
 use std::collections::hash_map::Entry;
 use std::fmt;
 use std::error;
 use std::hash::BuildHasherDefault;
 use std::mem;
 use std::ops::Deref;
+use std::os::raw::c_void;
 use std::ptr;
 use std::sync::Arc;
 use std::sync::Mutex;
 use std::sync::MutexGuard;
 use std::sync::Weak;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
 use smallvec::SmallVec;
 use fnv::FnvHasher;
 
@@ -111,6 +116,10 @@ use instance::Instance;
 use instance::PhysicalDevice;
 use instance::QueueFamily;
 use memory::pool::StdMemoryPool;
+use sync::Fence;
+use sync::FenceSignalFuture;
+use sync::FenceWaitError;
+use sync::GpuFuture;
 
 use Error;
 use OomError;
@@ -133,6 +142,7 @@ pub struct Device {
     standard_command_pools: Mutex<HashMap<u32, Weak<StandardCommandPool>, BuildHasherDefault<FnvHasher>>>,
     features: Features,
     extensions: DeviceExtensions,
+    global_priority: Option<QueueGlobalPriority>,
 }
 
 // The `StandardCommandPool` type doesn't implement Send/Sync, so we have to manually reimplement
@@ -165,6 +175,41 @@ impl Device {
                       extensions: &DeviceExtensions, queue_families: I)
                       -> Result<(Arc<Device>, QueuesIter), DeviceCreationError>
         where I: IntoIterator<Item = (QueueFamily<'a>, f32)>
+    {
+        Device::new_impl(phys, requested_features, extensions, queue_families, None)
+    }
+
+    /// Same as `new`, but also assigns a system-wide priority to all the queues of the device.
+    ///
+    /// Contrary to the priorities passed with each queue, which only apply between the queues of
+    /// the device, the global priority applies between all the queues of the system, including
+    /// the ones of other processes. The `ext_global_priority` extension must be enabled.
+    ///
+    /// Returns `DeviceCreationError::NotPermitted` if the process isn't allowed to use the
+    /// requested priority. This is typically the case for `High` and `Realtime` priorities
+    /// without special privileges.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if one of the queue families doesn't belong to the given device.
+    /// - Panics if the `ext_global_priority` extension isn't in `extensions`.
+    ///
+    pub fn with_global_priority<'a, I>(phys: &'a PhysicalDevice, requested_features: &Features,
+                                       extensions: &DeviceExtensions, queue_families: I,
+                                       global_priority: QueueGlobalPriority)
+                                       -> Result<(Arc<Device>, QueuesIter), DeviceCreationError>
+        where I: IntoIterator<Item = (QueueFamily<'a>, f32)>
+    {
+        assert!(extensions.ext_global_priority);
+        Device::new_impl(phys, requested_features, extensions, queue_families,
+                         Some(global_priority))
+    }
+
+    fn new_impl<'a, I>(phys: &'a PhysicalDevice, requested_features: &Features,
+                       extensions: &DeviceExtensions, queue_families: I,
+                       global_priority: Option<QueueGlobalPriority>)
+                       -> Result<(Arc<Device>, QueuesIter), DeviceCreationError>
+        where I: IntoIterator<Item = (QueueFamily<'a>, f32)>
     {
         let queue_families = queue_families.into_iter();
 
@@ -172,6 +217,19 @@ impl Device {
             return Err(DeviceCreationError::UnsupportedFeatures);
         }
 
+        // The features of a device extension can only be enabled along with their extension.
+        if (requested_features.present_id && !extensions.khr_present_id) ||
+           (requested_features.present_wait && !extensions.khr_present_wait) ||
+           (requested_features.extended_dynamic_state2 &&
+            !extensions.ext_extended_dynamic_state2) ||
+           (requested_features.extended_dynamic_state2_patch_control_points &&
+            !extensions.ext_extended_dynamic_state2) ||
+           (requested_features.performance_counter_query_pools &&
+            !extensions.khr_performance_query)
+        {
+            return Err(DeviceCreationError::FeatureRequiresExtension);
+        }
+
         let vk_i = phys.instance().pointers();
 
         // this variable will contain the queue family ID and queue ID of each requested queue
@@ -222,11 +280,23 @@ impl Device {
                 output_queues.push((queue_family.id(), 0));
             }
 
+            // the global priority is shared by all the queues
+            let global_priority = global_priority.map(|priority| {
+                vk::DeviceQueueGlobalPriorityCreateInfoEXT {
+                    sType: vk::STRUCTURE_TYPE_DEVICE_QUEUE_GLOBAL_PRIORITY_CREATE_INFO_EXT,
+                    pNext: ptr::null(),
+                    globalPriority: priority as u32,
+                }
+            });
+
             // turning `queues` into an array of `vkDeviceQueueCreateInfo` suitable for Vulkan
             let queues = queues.iter().map(|&(queue_id, ref priorities)| {
                 vk::DeviceQueueCreateInfo {
                     sType: vk::STRUCTURE_TYPE_DEVICE_QUEUE_CREATE_INFO,
-                    pNext: ptr::null(),
+                    pNext: match global_priority {
+                        Some(ref infos) => infos as *const _ as *const _,
+                        None => ptr::null(),
+                    },
                     flags: 0,   // reserved
                     queueFamilyIndex: queue_id,
                     queueCount: priorities.len() as u32,
@@ -253,9 +323,58 @@ impl Device {
                 features
             };
 
+            // The features of the device extensions are passed by chaining the structs of their
+            // extensions, which are only added if one of their features is requested.
+            let to_vk = |enabled: bool| if enabled { vk::TRUE } else { vk::FALSE };
+            let mut present_wait_features = vk::PhysicalDevicePresentWaitFeaturesKHR {
+                sType: vk::STRUCTURE_TYPE_PHYSICAL_DEVICE_PRESENT_WAIT_FEATURES_KHR,
+                pNext: ptr::null_mut(),
+                presentWait: to_vk(requested_features.present_wait),
+            };
+            let mut present_id_features = vk::PhysicalDevicePresentIdFeaturesKHR {
+                sType: vk::STRUCTURE_TYPE_PHYSICAL_DEVICE_PRESENT_ID_FEATURES_KHR,
+                pNext: ptr::null_mut(),
+                presentId: to_vk(requested_features.present_id),
+            };
+            let mut dynamic_state2_features = vk::PhysicalDeviceExtendedDynamicState2FeaturesEXT {
+                sType: vk::STRUCTURE_TYPE_PHYSICAL_DEVICE_EXTENDED_DYNAMIC_STATE_2_FEATURES_EXT,
+                pNext: ptr::null_mut(),
+                extendedDynamicState2: to_vk(requested_features.extended_dynamic_state2),
+                extendedDynamicState2LogicOp: vk::FALSE,
+                extendedDynamicState2PatchControlPoints:
+                    to_vk(requested_features.extended_dynamic_state2_patch_control_points),
+            };
+            // Using multiple pools in the same command buffer isn't supported by vulkano.
+            let mut performance_query_features = vk::PhysicalDevicePerformanceQueryFeaturesKHR {
+                sType: vk::STRUCTURE_TYPE_PHYSICAL_DEVICE_PERFORMANCE_QUERY_FEATURES_KHR,
+                pNext: ptr::null_mut(),
+                performanceCounterQueryPools:
+                    to_vk(requested_features.performance_counter_query_pools),
+                performanceCounterMultipleQueryPools: vk::FALSE,
+            };
+            let mut features_chain: *mut c_void = ptr::null_mut();
+            if requested_features.present_wait {
+                present_wait_features.pNext = features_chain;
+                features_chain = &mut present_wait_features as *mut _ as *mut c_void;
+            }
+            if requested_features.present_id {
+                present_id_features.pNext = features_chain;
+                features_chain = &mut present_id_features as *mut _ as *mut c_void;
+            }
+            if requested_features.extended_dynamic_state2 ||
+               requested_features.extended_dynamic_state2_patch_control_points
+            {
+                dynamic_state2_features.pNext = features_chain;
+                features_chain = &mut dynamic_state2_features as *mut _ as *mut c_void;
+            }
+            if requested_features.performance_counter_query_pools {
+                performance_query_features.pNext = features_chain;
+                features_chain = &mut performance_query_features as *mut _ as *mut c_void;
+            }
+
             let infos = vk::DeviceCreateInfo {
                 sType: vk::STRUCTURE_TYPE_DEVICE_CREATE_INFO,
-                pNext: ptr::null(),
+                pNext: features_chain as *const c_void,
                 flags: 0,   // reserved
                 queueCreateInfoCount: queues.len() as u32,
                 pQueueCreateInfos: queues.as_ptr(),
@@ -287,6 +406,7 @@ impl Device {
             standard_command_pools: Mutex::new(Default::default()),
             features: requested_features.clone(),
             extensions: extensions.clone(),
+            global_priority: global_priority,
         });
 
         // Iterator for the produced queues.
@@ -348,6 +468,15 @@ impl Device {
         &self.extensions
     }
 
+    /// Returns true if the number of control points of patches can be set with
+    /// `CmdSetPatchControlPoints`.
+    ///
+    /// This is the case if the `extended_dynamic_state2_patch_control_points` feature is enabled.
+    #[inline]
+    pub fn dynamic_patch_control_points_enabled(&self) -> bool {
+        self.features.extended_dynamic_state2_patch_control_points
+    }
+
     /// Returns the standard memory pool used by default if you don't provide any other pool.
     pub fn standard_pool(me: &Arc<Self>) -> Arc<StdMemoryPool> {
         let mut pool = me.standard_pool.lock().unwrap();
@@ -485,6 +614,9 @@ impl Iterator for QueuesIter {
                 device: self.device.clone(),
                 family: family,
                 id: id,
+                last_throttle: Mutex::new(None),
+                inflight_fences: Mutex::new(VecDeque::new()),
+                leaked: Mutex::new(Vec::new()),
             }))
         }
     }
@@ -513,6 +645,10 @@ pub enum DeviceCreationError {
     PriorityOutOfRange,
     /// Some of the requested device extensions are not supported by the physical device.
     ExtensionNotPresent,
+    /// Some of the requested features belong to a device extension that isn't enabled.
+    FeatureRequiresExtension,
+    /// The requested global priority isn't permitted for this process.
+    NotPermitted,
 }
 
 impl error::Error for DeviceCreationError {
@@ -534,7 +670,13 @@ impl error::Error for DeviceCreationError {
             },
             DeviceCreationError::ExtensionNotPresent => {
                 "some of the requested device extensions are not supported by the physical device"
-            }
+            },
+            DeviceCreationError::FeatureRequiresExtension => {
+                "some of the requested features belong to a device extension that isn't enabled"
+            },
+            DeviceCreationError::NotPermitted => {
+                "the requested global priority isn't permitted for this process"
+            },
         }
     }
 }
@@ -553,11 +695,28 @@ impl From<Error> for DeviceCreationError {
             Error::OutOfHostMemory => DeviceCreationError::OutOfHostMemory,
             Error::OutOfDeviceMemory => DeviceCreationError::OutOfDeviceMemory,
             Error::ExtensionNotPresent => DeviceCreationError::ExtensionNotPresent,
+            Error::NotPermitted => DeviceCreationError::NotPermitted,
             _ => panic!("Unexpected error value: {}", err as i32)
         }
     }
 }
 
+/// System-wide priority of the queues of a device.
+///
+/// See `Device::with_global_priority`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(u32)]
+pub enum QueueGlobalPriority {
+    /// Lower than the default priority. Suitable for background work.
+    Low = vk::QUEUE_GLOBAL_PRIORITY_LOW_EXT,
+    /// The default priority, used when no global priority is specified.
+    Medium = vk::QUEUE_GLOBAL_PRIORITY_MEDIUM_EXT,
+    /// Higher than the default priority.
+    High = vk::QUEUE_GLOBAL_PRIORITY_HIGH_EXT,
+    /// The highest priority. Usually requires special privileges.
+    Realtime = vk::QUEUE_GLOBAL_PRIORITY_REALTIME_EXT,
+}
+
 /// Represents a queue where commands can be submitted.
 // TODO: should use internal synchronization?
 #[derive(Debug)]
@@ -566,6 +725,55 @@ pub struct Queue {
     device: Arc<Device>,
     family: u32,
     id: u32,    // id within family
+    // Moment when `throttle` last returned.
+    last_throttle: Mutex<Option<Instant>>,
+    // Fences of the submissions registered with `add_inflight_fence` that may still be running.
+    inflight_fences: Mutex<VecDeque<Arc<Fence>>>,
+    // Futures registered with `keep_alive_until_finished` or `keep_alive_until_signaled` that
+    // may still be running.
+    leaked: Mutex<Vec<LeakedFuture>>,
+}
+
+// A future that is kept alive until its fence is signaled.
+enum LeakedFuture {
+    // The fence is signaled after the future, which is marked as finished once it is.
+    Fenced(Fence, Box<GpuFuture + Send>),
+    // A `FenceSignalFuture`, which marks the future it wraps as finished when it is destroyed.
+    Signaling(Box<SignalingFuture + Send>),
+}
+
+impl LeakedFuture {
+    #[inline]
+    fn is_signaled(&self) -> bool {
+        match *self {
+            LeakedFuture::Fenced(ref fence, _) => fence.ready().unwrap_or(false),
+            LeakedFuture::Signaling(ref future) => future.is_signaled(),
+        }
+    }
+}
+
+impl fmt::Debug for LeakedFuture {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            LeakedFuture::Fenced(ref fence, _) => {
+                write!(fmt, "<Leaked future with fence {:?}>", fence)
+            },
+            LeakedFuture::Signaling(_) => write!(fmt, "<Leaked fence signal future>"),
+        }
+    }
+}
+
+// Type-erased `FenceSignalFuture`.
+trait SignalingFuture {
+    fn is_signaled(&self) -> bool;
+}
+
+impl<F> SignalingFuture for FenceSignalFuture<F> where F: GpuFuture {
+    #[inline]
+    fn is_signaled(&self) -> bool {
+        FenceSignalFuture::is_signaled(self).unwrap_or(false)
+    }
 }
 
 impl Queue {
@@ -595,6 +803,12 @@ impl Queue {
         self.id
     }
 
+    /// Returns the global priority that was requested when creating the device, if any.
+    #[inline]
+    pub fn global_priority(&self) -> Option<QueueGlobalPriority> {
+        self.device.global_priority
+    }
+
     /// Waits until all work on this queue has finished.
     ///
     /// Just like `Device::wait()`, you shouldn't have to call this function in a typical program.
@@ -604,7 +818,121 @@ impl Queue {
             let vk = self.device.pointers();
             let queue = self.queue.lock().unwrap();
             try!(check_errors(vk.QueueWaitIdle(*queue)));
-            Ok(())
+        }
+
+        self.cleanup_leaked();
+        Ok(())
+    }
+
+    /// Blocks the current thread until at least `min_interval` has elapsed since the last time
+    /// this function returned for this queue.
+    ///
+    /// This is used by `GpuFuture::throttle()` in order to limit the rate of submissions.
+    pub fn throttle(&self, min_interval: Duration) {
+        let mut last = self.last_throttle.lock().unwrap();
+
+        if let Some(last) = *last {
+            let elapsed = last.elapsed();
+            if elapsed < min_interval {
+                thread::sleep(min_interval - elapsed);
+            }
+        }
+
+        *last = Some(Instant::now());
+    }
+
+    /// Blocks the current thread until fewer than `max` of the submissions registered with
+    /// `add_inflight_fence` are still being executed.
+    ///
+    /// This is used by `GpuFuture::max_inflight()` in order to limit the number of submissions
+    /// in progress on this queue.
+    pub fn wait_inflight(&self, max: usize) -> Result<(), FenceWaitError> {
+        let mut fences = self.inflight_fences.lock().unwrap();
+
+        // Removing the fences that are already signaled.
+        while fences.front().map(|f| f.ready().unwrap_or(false)).unwrap_or(false) {
+            fences.pop_front();
+        }
+
+        while fences.len() >= max {
+            // The fence is only removed once it's signaled, so that it's still taken into account
+            // if waiting fails.
+            // TODO: arbitrary timeout?
+            try!(fences.front().unwrap().wait(Duration::from_secs(600)));
+            fences.pop_front();
+        }
+
+        Ok(())
+    }
+
+    /// Registers the fence of a submission, so that `wait_inflight` takes it into account.
+    #[inline]
+    pub fn add_inflight_fence(&self, fence: Arc<Fence>) {
+        self.inflight_fences.lock().unwrap().push_back(fence);
+    }
+
+    /// Keeps `future` alive until `fence` is signaled.
+    ///
+    /// The future is destroyed and marked as finished by the first call to `cleanup_leaked` (or
+    /// `wait`) that happens after the fence is signaled. The futures that are already finished
+    /// are destroyed when this function is called, so that the list doesn't grow forever.
+    ///
+    /// This is used by `GpuFuture::leak_on_drop()` so that dropping a future doesn't block.
+    ///
+    /// > **Note**: The future usually holds an `Arc` to this queue, and therefore keeps it alive
+    /// > until it is destroyed.
+    ///
+    /// # Safety
+    ///
+    /// - `future` must have been flushed.
+    /// - `fence` must be signaled by a submission made on this queue after all the submissions
+    ///   of `future`, so that `future` is finished once the fence is signaled. If it isn't the
+    ///   case, `signal_finished()` is called on a future whose resources are still in use.
+    ///
+    #[inline]
+    pub unsafe fn keep_alive_until_finished(&self, future: Box<GpuFuture + Send>, fence: Fence) {
+        self.cleanup_leaked();
+
+        self.leaked.lock().unwrap().push(LeakedFuture::Fenced(fence, future));
+    }
+
+    /// Keeps a `FenceSignalFuture` alive until its fence is signaled.
+    ///
+    /// Same as `keep_alive_until_finished`, except that the fence of the future is used. Since
+    /// that fence is part of the submission of the future, this is correct even if the future
+    /// waits for submissions made on other queues. The future should have been flushed, otherwise
+    /// it stays alive until the queue is destroyed.
+    #[inline]
+    pub fn keep_alive_until_signaled<F>(&self, future: FenceSignalFuture<F>)
+        where F: GpuFuture + Send + 'static
+    {
+        self.cleanup_leaked();
+        self.leaked.lock().unwrap().push(LeakedFuture::Signaling(Box::new(future)));
+    }
+
+    /// Destroys the futures registered with `keep_alive_until_finished` whose submission is
+    /// finished.
+    pub fn cleanup_leaked(&self) {
+        let finished = {
+            let mut leaked = self.leaked.lock().unwrap();
+            let mut finished = Vec::new();
+            let mut n = 0;
+            while n < leaked.len() {
+                if leaked[n].is_signaled() {
+                    finished.push(leaked.swap_remove(n));
+                } else {
+                    n += 1;
+                }
+            }
+            finished
+        };
+
+        // The futures are destroyed outside of the lock, as their destructor could access
+        // this queue.
+        for leaked in finished {
+            if let LeakedFuture::Fenced(_, future) = leaked {
+                unsafe { future.signal_finished(); }
+            }
         }
     }
 }
@@ -672,6 +1000,27 @@ mod tests {
         };
     }
 
+    #[test]
+    fn extension_feature_without_extension() {
+        let instance = instance!();
+        let physical = match instance::PhysicalDevice::enumerate(&instance).next() {
+            Some(p) => p,
+            None => return
+        };
+
+        let family = physical.queue_families().next().unwrap();
+
+        let features = Features { present_wait: true, .. Features::none() };
+        if !physical.supported_features().superset_of(&features) {
+            return;
+        }
+
+        match Device::new(&physical, &features, &DeviceExtensions::none(), Some((family, 1.0))) {
+            Err(DeviceCreationError::FeatureRequiresExtension) => return,     // Success
+            _ => panic!()
+        };
+    }
+
     #[test]
     fn priority_out_of_range() {
         let instance = instance!();
@@ -10,13 +10,18 @@
 use vk;
 
 macro_rules! features {
-    ($($name:ident => $vk:ident,)+) => (
+    ($($name:ident => $vk:ident,)+ ; $($ext_name:ident,)+) => (
         /// Represents all the features that are available on a physical device or enabled on
         /// a logical device.
         ///
         /// Note that the `robust_buffer_access` is guaranteed to be supported by all Vulkan
         /// implementations.
         ///
+        /// Some features belong to a device extension, and can only be enabled if this extension
+        /// is enabled as well. Their support can only be queried if the instance has loaded the
+        /// `khr_get_physical_device_properties2` extension, otherwise they are reported as
+        /// unsupported.
+        ///
         /// # Example
         ///
         /// ```
@@ -46,6 +51,9 @@ macro_rules! features {
             $(
                 pub $name: bool,
             )+
+            $(
+                pub $ext_name: bool,
+            )+
         }
 
         impl Features {
@@ -55,6 +63,9 @@ macro_rules! features {
                     $(
                         $name: false,
                     )+
+                    $(
+                        $ext_name: false,
+                    )+
                 }
             }
 
@@ -67,6 +78,9 @@ macro_rules! features {
                     $(
                         $name: true,
                     )+
+                    $(
+                        $ext_name: true,
+                    )+
                 }
             }
 
@@ -75,7 +89,8 @@ macro_rules! features {
             /// That is, for each feature of the parameter that is true, the corresponding value
             /// in self is true as well.
             pub fn superset_of(&self, other: &Features) -> bool {
-                $((self.$name == true || other.$name == false))&&+
+                $((self.$name == true || other.$name == false))&&+ &&
+                $((self.$ext_name == true || other.$ext_name == false))&&+
             }
 
             /// Builds a `Features` that is the intersection of `self` and another `Features`
@@ -87,6 +102,9 @@ macro_rules! features {
                     $(
                         $name: self.$name && other.$name,
                     )+
+                    $(
+                        $ext_name: self.$ext_name && other.$ext_name,
+                    )+
                 }
             }
         }
@@ -98,6 +116,9 @@ macro_rules! features {
                     $(
                         $name: features.$vk != 0,
                     )+
+                    $(
+                        $ext_name: false,
+                    )+
                 }
             }
         }
@@ -171,4 +192,11 @@ features!{
     sparse_residency_aliased => sparseResidencyAliased,
     variable_multisample_rate => variableMultisampleRate,
     inherited_queries => inheritedQueries,
+    ;
+    // Features of device extensions, filled separately.
+    present_id,                                     // khr_present_id
+    present_wait,                                   // khr_present_wait
+    extended_dynamic_state2,                        // ext_extended_dynamic_state2
+    extended_dynamic_state2_patch_control_points,   // ext_extended_dynamic_state2
+    performance_counter_query_pools,                // khr_performance_query
 }
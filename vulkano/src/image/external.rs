@@ -0,0 +1,359 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::error;
+use std::fmt;
+use std::iter::Empty;
+use std::os::raw::c_int;
+use std::sync::Arc;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+use device::Device;
+use device::Queue;
+use format::ClearValue;
+use format::Format;
+use format::FormatDesc;
+use image::Dimensions;
+use image::ImageDimensions;
+use image::sys::ImageCreationError;
+use image::sys::Layout;
+use image::sys::UnsafeImage;
+use image::sys::UnsafeImageView;
+use image::sys::Usage;
+use image::traits::ImageAccess;
+use image::traits::ImageClearValue;
+use image::traits::ImageContent;
+use image::traits::ImageViewAccess;
+use image::traits::Image;
+use image::traits::ImageView;
+use memory::DeviceMemory;
+use memory::ExternalMemoryHandleType;
+use memory::MemoryImportError;
+use sync::Sharing;
+use OomError;
+
+/// Image whose memory was exported by another API or process, for example a camera or a video
+/// decoder.
+///
+/// The image is used like any other image: it can be accessed by command buffers and takes part
+/// in the synchronization of the futures. The accesses of the external producer, however, are
+/// invisible to vulkano. Use an `ExternalAcquireFuture` with the semaphore signaled by the
+/// producer to order the accesses of vulkano after its writes.
+#[derive(Debug)]
+pub struct ExternalImage<F> {
+    // Inner implementation.
+    image: UnsafeImage,
+
+    // We maintain a view of the whole image.
+    view: UnsafeImageView,
+
+    // Imported memory that backs the image.
+    memory: DeviceMemory,
+
+    // Dimensions of the image view.
+    dimensions: Dimensions,
+
+    // Format.
+    format: F,
+
+    // Number of times this image is locked on the GPU side.
+    gpu_lock: AtomicUsize,
+}
+
+impl<F> ExternalImage<F> {
+    /// Imports an image whose memory was exported as a POSIX file descriptor.
+    ///
+    /// The image is created with a single mipmap, optimal tiling and the exclusive sharing mode.
+    /// The exporter must have created its image with the same parameters, dimensions, format and
+    /// usage. The image is expected to be in the `General` layout whenever vulkano accesses it.
+    ///
+    /// On success, the ownership of `fd` is transferred to the Vulkan implementation. On error,
+    /// the file descriptor still belongs to the caller.
+    ///
+    /// # Safety
+    ///
+    /// - `fd` must be a valid file descriptor of the given handle type.
+    /// - The memory of `fd` must be compatible with an image created with these parameters.
+    ///
+    // TODO: support linear tiling and DRM format modifiers, which are common for dma-bufs
+    pub unsafe fn import_fd(device: &Arc<Device>, dimensions: Dimensions, format: F,
+                            usage: Usage, handle_type: ExternalMemoryHandleType, fd: c_int)
+                            -> Result<Arc<ExternalImage<F>>, ExternalImageError>
+        where F: FormatDesc
+    {
+        if !handle_type.is_supported(device) {
+            return Err(ExternalImageError::MemoryImportError(MemoryImportError::MissingExtension));
+        }
+
+        let (image, mem_reqs) = try!(UnsafeImage::new_external(device, &usage, format.format(),
+                                                               dimensions.to_image_dimensions(),
+                                                               1, 1,
+                                                               Sharing::Exclusive::<Empty<u32>>,
+                                                               false, handle_type));
+
+        let allowed_types = mem_reqs.memory_type_bits &
+                            try!(handle_type.memory_type_bits(device, fd));
+        let mem_ty = {
+            let device_local = device.physical_device().memory_types()
+                                     .filter(|t| (allowed_types & (1 << t.id())) != 0)
+                                     .filter(|t| t.is_device_local());
+            let any = device.physical_device().memory_types()
+                            .filter(|t| (allowed_types & (1 << t.id())) != 0);
+            match device_local.chain(any).next() {
+                Some(t) => t,
+                None => return Err(ExternalImageError::NoSuitableMemoryType),
+            }
+        };
+
+        let memory = try!(DeviceMemory::import_fd(device, mem_ty, mem_reqs.size, handle_type,
+                                                  fd));
+        try!(image.bind_memory(&memory, 0));
+
+        let view = try!(UnsafeImageView::raw(&image, dimensions.to_view_type(),
+                                             0 .. image.mipmap_levels(),
+                                             0 .. image.dimensions().array_layers()));
+
+        Ok(Arc::new(ExternalImage {
+            image: image,
+            view: view,
+            memory: memory,
+            dimensions: dimensions,
+            format: format,
+            gpu_lock: AtomicUsize::new(0),
+        }))
+    }
+
+    /// Returns the dimensions of the image.
+    #[inline]
+    pub fn dimensions(&self) -> Dimensions {
+        self.dimensions
+    }
+
+    /// Returns the imported memory that backs the image.
+    #[inline]
+    pub fn memory(&self) -> &DeviceMemory {
+        &self.memory
+    }
+}
+
+unsafe impl<F> Image for Arc<ExternalImage<F>> where F: 'static + Send + Sync {
+    type Access = Self;
+
+    #[inline]
+    fn access(self) -> Self {
+        self
+    }
+
+    #[inline]
+    fn format(&self) -> Format {
+        self.image.format()
+    }
+
+    #[inline]
+    fn samples(&self) -> u32 {
+        self.image.samples()
+    }
+
+    #[inline]
+    fn dimensions(&self) -> ImageDimensions {
+        self.image.dimensions()
+    }
+}
+
+unsafe impl<F> ImageView for Arc<ExternalImage<F>> where F: 'static + Send + Sync {
+    type Access = Self;
+
+    #[inline]
+    fn access(self) -> Self {
+        self
+    }
+}
+
+unsafe impl<F> ImageAccess for ExternalImage<F> where F: 'static + Send + Sync {
+    #[inline]
+    fn inner(&self) -> &UnsafeImage {
+        &self.image
+    }
+
+    #[inline]
+    fn default_layout(&self) -> Layout {
+        Layout::General
+    }
+
+    #[inline]
+    fn conflict_key(&self, _: u32, _: u32, _: u32, _: u32) -> u64 {
+        self.image.key()
+    }
+
+    #[inline]
+    fn try_gpu_lock(&self, _: bool, _: &Queue) -> bool {
+        // The image can only be locked once at a time, like the other images.
+        if self.gpu_lock.compare_and_swap(0, 1, Ordering::SeqCst) == 0 {
+            true
+        } else {
+            false
+        }
+    }
+
+    #[inline]
+    unsafe fn increase_gpu_lock(&self) {
+        let val = self.gpu_lock.fetch_add(1, Ordering::SeqCst);
+        debug_assert!(val >= 1);
+    }
+}
+
+unsafe impl<F> ImageClearValue<F::ClearValue> for ExternalImage<F>
+    where F: FormatDesc + 'static + Send + Sync
+{
+    #[inline]
+    fn decode(&self, value: F::ClearValue) -> Option<ClearValue> {
+        Some(self.format.decode_clear_value(value))
+    }
+}
+
+unsafe impl<P, F> ImageContent<P> for ExternalImage<F> where F: 'static + Send + Sync {
+    #[inline]
+    fn matches_format(&self) -> bool {
+        true        // FIXME:
+    }
+}
+
+unsafe impl<F> ImageViewAccess for ExternalImage<F> where F: 'static + Send + Sync {
+    #[inline]
+    fn parent(&self) -> &ImageAccess {
+        self
+    }
+
+    #[inline]
+    fn dimensions(&self) -> Dimensions {
+        self.dimensions
+    }
+
+    #[inline]
+    fn inner(&self) -> &UnsafeImageView {
+        &self.view
+    }
+
+    #[inline]
+    fn descriptor_set_storage_image_layout(&self) -> Layout {
+        Layout::General
+    }
+
+    #[inline]
+    fn descriptor_set_combined_image_sampler_layout(&self) -> Layout {
+        Layout::General
+    }
+
+    #[inline]
+    fn descriptor_set_sampled_image_layout(&self) -> Layout {
+        Layout::General
+    }
+
+    #[inline]
+    fn descriptor_set_input_attachment_layout(&self) -> Layout {
+        Layout::General
+    }
+
+    #[inline]
+    fn identity_swizzle(&self) -> bool {
+        true
+    }
+}
+
+/// Error that can happen when importing an external image.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ExternalImageError {
+    /// Error while creating the image.
+    ImageCreationError(ImageCreationError),
+
+    /// Error while importing the memory of the image.
+    MemoryImportError(MemoryImportError),
+
+    /// None of the memory types that the memory can be imported on is suitable for the image.
+    NoSuitableMemoryType,
+}
+
+impl error::Error for ExternalImageError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            ExternalImageError::ImageCreationError(_) => "error while creating the image",
+            ExternalImageError::MemoryImportError(_) => "error while importing the memory",
+            ExternalImageError::NoSuitableMemoryType => {
+                "the memory can't be imported on a memory type suitable for the image"
+            },
+        }
+    }
+
+    #[inline]
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            ExternalImageError::ImageCreationError(ref err) => Some(err),
+            ExternalImageError::MemoryImportError(ref err) => Some(err),
+            _ => None
+        }
+    }
+}
+
+impl fmt::Display for ExternalImageError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+impl From<ImageCreationError> for ExternalImageError {
+    #[inline]
+    fn from(err: ImageCreationError) -> ExternalImageError {
+        ExternalImageError::ImageCreationError(err)
+    }
+}
+
+impl From<MemoryImportError> for ExternalImageError {
+    #[inline]
+    fn from(err: MemoryImportError) -> ExternalImageError {
+        ExternalImageError::MemoryImportError(err)
+    }
+}
+
+impl From<OomError> for ExternalImageError {
+    #[inline]
+    fn from(err: OomError) -> ExternalImageError {
+        ExternalImageError::ImageCreationError(ImageCreationError::OomError(err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use format::Format;
+    use image::Dimensions;
+    use image::ExternalImage;
+    use image::ExternalImageError;
+    use image::Usage;
+    use memory::ExternalMemoryHandleType;
+    use memory::MemoryImportError;
+
+    #[test]
+    fn import_missing_extension() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let usage = Usage { sampled: true, .. Usage::none() };
+        let result = unsafe {
+            ExternalImage::import_fd(&device, Dimensions::Dim2d { width: 32, height: 32 },
+                                     Format::R8G8B8A8Unorm, usage,
+                                     ExternalMemoryHandleType::DmaBuf, -1)
+        };
+
+        match result {
+            Err(ExternalImageError::MemoryImportError(MemoryImportError::MissingExtension)) => (),
+            _ => panic!()
+        }
+    }
+}
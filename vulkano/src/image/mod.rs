@@ -47,6 +47,8 @@
 //!
 
 pub use self::attachment::AttachmentImage;
+pub use self::external::ExternalImage;
+pub use self::external::ExternalImageError;
 pub use self::immutable::ImmutableImage;
 pub use self::storage::StorageImage;
 pub use self::swapchain::SwapchainImage;
@@ -59,6 +61,7 @@ pub use self::traits::Image;
 pub use self::traits::ImageView;
 
 pub mod attachment;     // TODO: make private
+mod external;
 pub mod immutable;      // TODO: make private
 mod storage;
 pub mod swapchain;      // TODO: make private
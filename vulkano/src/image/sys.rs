@@ -28,6 +28,7 @@ use image::ImageDimensions;
 use image::MipmapsCount;
 use image::ViewType;
 use memory::DeviceMemory;
+use memory::ExternalMemoryHandleType;
 use memory::MemoryRequirements;
 use sync::Sharing;
 
@@ -65,6 +66,9 @@ pub struct UnsafeImage {
     // Features that are supported for this particular format.
     format_features: vk::FormatFeatureFlagBits,
 
+    // True if the image was created with `Sharing::Exclusive`.
+    exclusive_sharing: bool,
+
     // `vkDestroyImage` is called only if `needs_destruction` is true.
     needs_destruction: bool,
 }
@@ -92,14 +96,46 @@ impl UnsafeImage {
         };
 
         UnsafeImage::new_impl(device, usage, format, dimensions, num_samples, mipmaps.into(),
-                              sharing, linear_tiling, preinitialized_layout)
+                              sharing, linear_tiling, preinitialized_layout, 0)
+    }
+
+    /// Same as `new`, except that the memory of the image is going to be imported from a handle
+    /// of the given type instead of being allocated by vulkano. See `ExternalImage`.
+    ///
+    /// The extensions required by the handle type must be enabled on the device. See
+    /// `ExternalMemoryHandleType::is_supported`.
+    ///
+    /// # Panic
+    ///
+    /// Same panics as `new`.
+    ///
+    #[inline]
+    pub unsafe fn new_external<'a, Mi, I>(device: &Arc<Device>, usage: &Usage, format: Format,
+                                          dimensions: ImageDimensions, num_samples: u32,
+                                          mipmaps: Mi, sharing: Sharing<I>, linear_tiling: bool,
+                                          handle_type: ExternalMemoryHandleType)
+                                          -> Result<(UnsafeImage, MemoryRequirements),
+                                                    ImageCreationError>
+        where Mi: Into<MipmapsCount>, I: Iterator<Item = u32>
+    {
+        let sharing = match sharing {
+            Sharing::Exclusive => (vk::SHARING_MODE_EXCLUSIVE, SmallVec::<[u32; 8]>::new()),
+            Sharing::Concurrent(ids) => (vk::SHARING_MODE_CONCURRENT, ids.collect()),
+        };
+
+        UnsafeImage::new_impl(device, usage, format, dimensions, num_samples, mipmaps.into(),
+                              sharing, linear_tiling, false, handle_type.into())
     }
 
     // Non-templated version to avoid inlining and improve compile times.
+    //
+    // `external` contains the handle types that the memory of the image can be imported from,
+    // or 0 if the memory is allocated normally.
     unsafe fn new_impl(device: &Arc<Device>, usage: &Usage, format: Format,
                        dimensions: ImageDimensions, num_samples: u32, mipmaps: MipmapsCount,
                        (sh_mode, sh_indices): (vk::SharingMode, SmallVec<[u32; 8]>),
-                       linear_tiling: bool, preinitialized_layout: bool)
+                       linear_tiling: bool, preinitialized_layout: bool,
+                       external: vk::ExternalMemoryHandleTypeFlagsKHR)
                        -> Result<(UnsafeImage, MemoryRequirements), ImageCreationError>
     {
         // TODO: doesn't check that the proper features are enabled
@@ -395,9 +431,19 @@ impl UnsafeImage {
 
         // Everything now ok. Creating the image.
         let image = {
+            let external_infos = vk::ExternalMemoryImageCreateInfoKHR {
+                sType: vk::STRUCTURE_TYPE_EXTERNAL_MEMORY_IMAGE_CREATE_INFO_KHR,
+                pNext: ptr::null(),
+                handleTypes: external,
+            };
+
             let infos = vk::ImageCreateInfo {
                 sType: vk::STRUCTURE_TYPE_IMAGE_CREATE_INFO,
-                pNext: ptr::null(),
+                pNext: if external != 0 {
+                    &external_infos as *const _ as *const _
+                } else {
+                    ptr::null()
+                },
                 flags: flags,
                 imageType: ty,
                 format: format as u32,
@@ -443,6 +489,7 @@ impl UnsafeImage {
             samples: num_samples,
             mipmaps: mipmaps,
             format_features: format_features,
+            exclusive_sharing: sh_mode == vk::SHARING_MODE_EXCLUSIVE,
             needs_destruction: true,
         };
 
@@ -473,6 +520,7 @@ impl UnsafeImage {
             samples: samples,
             mipmaps: mipmaps,
             format_features: output.optimalTilingFeatures,
+            exclusive_sharing: false,       // TODO: pass as parameter
             needs_destruction: false,       // TODO: pass as parameter
         }
     }
@@ -630,6 +678,15 @@ impl UnsafeImage {
         (self.format_features & vk::FORMAT_FEATURE_SAMPLED_IMAGE_FILTER_LINEAR_BIT) != 0
     }
 
+    /// Returns true if the image was created with exclusive sharing. Using such an image from a
+    /// queue family other than the one that owns it requires a queue family ownership transfer.
+    ///
+    /// Images created with `from_raw` are considered to not be exclusive.
+    #[inline]
+    pub fn exclusive_sharing(&self) -> bool {
+        self.exclusive_sharing
+    }
+
     #[inline]
     pub fn usage_transfer_src(&self) -> bool {
         (self.usage & vk::IMAGE_USAGE_TRANSFER_SRC_BIT) != 0
@@ -203,6 +203,7 @@ instance_extensions! {
     ext_debug_report => b"VK_EXT_debug_report",
     nn_vi_surface => b"VK_NN_vi_surface",
     ext_swapchain_colorspace => b"VK_EXT_swapchain_colorspace",
+    khr_get_physical_device_properties2 => b"VK_KHR_get_physical_device_properties2",
 }
 
 device_extensions! {
@@ -211,6 +212,26 @@ device_extensions! {
     khr_display_swapchain => b"VK_KHR_display_swapchain",
     khr_sampler_mirror_clamp_to_edge => b"VK_KHR_sampler_mirror_clamp_to_edge",
     khr_maintenance1 => b"VK_KHR_maintenance1",
+    khr_acceleration_structure => b"VK_KHR_acceleration_structure",
+    khr_ray_tracing_pipeline => b"VK_KHR_ray_tracing_pipeline",
+    ext_global_priority => b"VK_EXT_global_priority",
+    khr_external_memory => b"VK_KHR_external_memory",
+    khr_external_memory_fd => b"VK_KHR_external_memory_fd",
+    ext_external_memory_dma_buf => b"VK_EXT_external_memory_dma_buf",
+    khr_external_semaphore => b"VK_KHR_external_semaphore",
+    khr_external_semaphore_fd => b"VK_KHR_external_semaphore_fd",
+    khr_external_fence => b"VK_KHR_external_fence",
+    khr_external_fence_fd => b"VK_KHR_external_fence_fd",
+    khr_performance_query => b"VK_KHR_performance_query",
+    khr_present_id => b"VK_KHR_present_id",
+    khr_present_wait => b"VK_KHR_present_wait",
+    ext_extended_dynamic_state2 => b"VK_EXT_extended_dynamic_state2",
+    ext_transform_feedback => b"VK_EXT_transform_feedback",
+    ext_conditional_rendering => b"VK_EXT_conditional_rendering",
+    nvx_device_generated_commands => b"VK_NVX_device_generated_commands",
+    nv_shading_rate_image => b"VK_NV_shading_rate_image",
+    ext_fragment_density_map => b"VK_EXT_fragment_density_map",
+    nv_mesh_shader => b"VK_NV_mesh_shader",
 }
 
 /// Error that can happen when loading the list of layers.
@@ -13,6 +13,7 @@ use std::ffi::CStr;
 use std::ffi::CString;
 use std::fmt;
 use std::mem;
+use std::os::raw::c_void;
 use std::ptr;
 use std::slice;
 use std::sync::Arc;
@@ -252,12 +253,17 @@ impl Instance {
                     output
                 };
 
+                let mut available_features = Features::from(available_features);
+                if extensions.khr_get_physical_device_properties2 {
+                    unsafe { query_extension_features(&vk, device, &mut available_features); }
+                }
+
                 output.push(PhysicalDeviceInfos {
                     device: device,
                     properties: properties,
                     memory: memory,
                     queue_families: queue_families,
-                    available_features: Features::from(available_features),
+                    available_features: available_features,
                 });
             }
             output
@@ -473,6 +479,52 @@ struct PhysicalDeviceInfos {
     available_features: Features,
 }
 
+// Fills the features of `features` that belong to device extensions, as they can't be queried
+// with `vkGetPhysicalDeviceFeatures`.
+//
+// The instance must have loaded `khr_get_physical_device_properties2`.
+unsafe fn query_extension_features(vk: &vk::InstancePointers, device: vk::PhysicalDevice,
+                                   features: &mut Features)
+{
+    let mut present_id = vk::PhysicalDevicePresentIdFeaturesKHR {
+        sType: vk::STRUCTURE_TYPE_PHYSICAL_DEVICE_PRESENT_ID_FEATURES_KHR,
+        pNext: ptr::null_mut(),
+        presentId: vk::FALSE,
+    };
+    let mut present_wait = vk::PhysicalDevicePresentWaitFeaturesKHR {
+        sType: vk::STRUCTURE_TYPE_PHYSICAL_DEVICE_PRESENT_WAIT_FEATURES_KHR,
+        pNext: &mut present_id as *mut _ as *mut c_void,
+        presentWait: vk::FALSE,
+    };
+    let mut dynamic_state2 = vk::PhysicalDeviceExtendedDynamicState2FeaturesEXT {
+        sType: vk::STRUCTURE_TYPE_PHYSICAL_DEVICE_EXTENDED_DYNAMIC_STATE_2_FEATURES_EXT,
+        pNext: &mut present_wait as *mut _ as *mut c_void,
+        extendedDynamicState2: vk::FALSE,
+        extendedDynamicState2LogicOp: vk::FALSE,
+        extendedDynamicState2PatchControlPoints: vk::FALSE,
+    };
+    let mut performance_query = vk::PhysicalDevicePerformanceQueryFeaturesKHR {
+        sType: vk::STRUCTURE_TYPE_PHYSICAL_DEVICE_PERFORMANCE_QUERY_FEATURES_KHR,
+        pNext: &mut dynamic_state2 as *mut _ as *mut c_void,
+        performanceCounterQueryPools: vk::FALSE,
+        performanceCounterMultipleQueryPools: vk::FALSE,
+    };
+    let mut output = vk::PhysicalDeviceFeatures2KHR {
+        sType: vk::STRUCTURE_TYPE_PHYSICAL_DEVICE_FEATURES_2_KHR,
+        pNext: &mut performance_query as *mut _ as *const c_void,
+        features: mem::zeroed(),
+    };
+    vk.GetPhysicalDeviceFeatures2KHR(device, &mut output);
+
+    features.present_id = present_id.presentId != 0;
+    features.present_wait = present_wait.presentWait != 0;
+    features.extended_dynamic_state2 = dynamic_state2.extendedDynamicState2 != 0;
+    features.extended_dynamic_state2_patch_control_points =
+        dynamic_state2.extendedDynamicState2PatchControlPoints != 0;
+    features.performance_counter_query_pools =
+        performance_query.performanceCounterQueryPools != 0;
+}
+
 /// Represents one of the available devices on this machine.
 ///
 /// This struct simply contains a pointer to an instance and a number representing the physical
@@ -857,6 +909,14 @@ impl<'a> QueueFamily<'a> {
         (self.flags() & vk::QUEUE_SPARSE_BINDING_BIT) != 0
     }
 
+    /// Returns the number of meaningful bits of the timestamps written by queues of this family.
+    ///
+    /// Returns 0 if the queues of this family don't support timestamps.
+    #[inline]
+    pub fn timestamp_valid_bits(&self) -> u32 {
+        self.physical_device.infos().queue_families[self.id as usize].timestampValidBits
+    }
+
     /// Internal utility function that returns the flags of this queue family.
     #[inline]
     fn flags(&self) -> u32 {
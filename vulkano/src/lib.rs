@@ -202,6 +202,9 @@ pub enum Error {
     IncompatibleDisplay = vk::ERROR_INCOMPATIBLE_DISPLAY_KHR,
     ValidationFailed = vk::ERROR_VALIDATION_FAILED_EXT,
     OutOfPoolMemory = vk::ERROR_OUT_OF_POOL_MEMORY_KHR,
+    NotPermitted = vk::ERROR_NOT_PERMITTED_EXT,
+    InvalidExternalHandle = vk::ERROR_INVALID_EXTERNAL_HANDLE_KHR,
+    FullScreenExclusiveLost = vk::ERROR_FULL_SCREEN_EXCLUSIVE_MODE_LOST_EXT,
 }
 
 /// Checks whether the result returned correctly.
@@ -231,6 +234,9 @@ fn check_errors(result: vk::Result) -> Result<Success, Error> {
         vk::ERROR_INCOMPATIBLE_DISPLAY_KHR => Err(Error::IncompatibleDisplay),
         vk::ERROR_VALIDATION_FAILED_EXT => Err(Error::ValidationFailed),
         vk::ERROR_OUT_OF_POOL_MEMORY_KHR => Err(Error::OutOfPoolMemory),
+        vk::ERROR_NOT_PERMITTED_EXT => Err(Error::NotPermitted),
+        vk::ERROR_INVALID_EXTERNAL_HANDLE_KHR => Err(Error::InvalidExternalHandle),
+        vk::ERROR_FULL_SCREEN_EXCLUSIVE_MODE_LOST_EXT => Err(Error::FullScreenExclusiveLost),
         c => unreachable!("Unexpected error code returned by Vulkan: {}", c)
     }
 }
@@ -7,17 +7,22 @@
 // notice may not be copied, modified, or distributed except
 // according to those terms.
 
+use std::cmp;
+use std::error;
+use std::fmt;
 use std::mem;
 use std::ptr;
 use std::ops::Deref;
 use std::ops::DerefMut;
 use std::ops::Range;
+use std::os::raw::c_int;
 use std::os::raw::c_void;
 use std::sync::Arc;
 
 use instance::MemoryType;
 use device::Device;
 use memory::Content;
+use Error;
 use OomError;
 use SafeDeref;
 use VulkanObject;
@@ -131,6 +136,63 @@ impl<D> DeviceMemory<D> where D: SafeDeref<Target = Device> {
         })
     }
 
+    /// Imports memory that was exported by another API or process as a POSIX file descriptor.
+    ///
+    /// On success, the ownership of `fd` is transferred to the Vulkan implementation. On error,
+    /// the file descriptor still belongs to the caller.
+    ///
+    /// # Safety
+    ///
+    /// - `fd` must be a valid file descriptor of the given handle type.
+    /// - `memory_type` and `size` must be compatible with the exported memory. For `DmaBuf`
+    ///   handles, `ExternalMemoryHandleType::memory_type_bits` returns the allowed memory types.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if `size` is 0.
+    /// - Panics if `memory_type` doesn't belong to the same physical device as `device`.
+    ///
+    pub unsafe fn import_fd(device: &D, memory_type: MemoryType, size: usize,
+                            handle_type: ExternalMemoryHandleType, fd: c_int)
+                            -> Result<DeviceMemory<D>, MemoryImportError>
+        where D: Clone
+    {
+        assert!(size >= 1);
+        assert_eq!(device.physical_device().internal_object(),
+                   memory_type.physical_device().internal_object());
+
+        if !handle_type.is_supported(device) {
+            return Err(MemoryImportError::MissingExtension);
+        }
+
+        let vk = device.pointers();
+
+        let import = vk::ImportMemoryFdInfoKHR {
+            sType: vk::STRUCTURE_TYPE_IMPORT_MEMORY_FD_INFO_KHR,
+            pNext: ptr::null(),
+            handleType: handle_type.into(),
+            fd: fd,
+        };
+
+        let infos = vk::MemoryAllocateInfo {
+            sType: vk::STRUCTURE_TYPE_MEMORY_ALLOCATE_INFO,
+            pNext: &import as *const _ as *const _,
+            allocationSize: size as u64,
+            memoryTypeIndex: memory_type.id(),
+        };
+
+        let mut output = mem::uninitialized();
+        try!(check_errors(vk.AllocateMemory(device.internal_object(), &infos,
+                                            ptr::null(), &mut output)));
+
+        Ok(DeviceMemory {
+            memory: output,
+            device: device.clone(),
+            size: size,
+            memory_type_index: memory_type.id(),
+        })
+    }
+
     /// Returns the memory type this chunk was allocated on.
     #[inline]
     pub fn memory_type(&self) -> MemoryType {
@@ -214,6 +276,53 @@ impl<D> MappedDeviceMemory<D> where D: SafeDeref<Target = Device> {
         &self.memory
     }
 
+    /// Returns true if the memory is host-coherent, in which case writes from the host don't
+    /// need to be flushed.
+    #[inline]
+    pub fn is_coherent(&self) -> bool {
+        self.coherent
+    }
+
+    /// Calls `vkFlushMappedMemoryRanges` on the given range of the memory, which makes the host
+    /// writes to this range available to the device. Does nothing if the memory is coherent.
+    ///
+    /// The range is widened to multiples of the `non_coherent_atom_size` limit of the physical
+    /// device, as required by Vulkan, without going past the end of the memory. The bytes around
+    /// the range may therefore be flushed as well.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if the range is out of range of the memory.
+    ///
+    pub fn flush_range(&self, range: Range<usize>) -> Result<(), OomError> {
+        assert!(range.start <= range.end);
+        assert!(range.end <= self.memory.size());
+
+        if self.coherent || range.start == range.end {
+            return Ok(());
+        }
+
+        let vk = self.memory.device().pointers();
+
+        let atom_size = self.memory.device().physical_device().limits().non_coherent_atom_size();
+        let range = align_to_atoms(range, atom_size as usize, self.memory.size());
+
+        let range = vk::MappedMemoryRange {
+            sType: vk::STRUCTURE_TYPE_MAPPED_MEMORY_RANGE,
+            pNext: ptr::null(),
+            memory: self.memory.internal_object(),
+            offset: range.start as u64,
+            size: (range.end - range.start) as u64,
+        };
+
+        unsafe {
+            try!(check_errors(vk.FlushMappedMemoryRanges(self.memory.device().internal_object(),
+                                                         1, &range)));
+        }
+
+        Ok(())
+    }
+
     /// Gives access to the content of the memory.
     ///
     /// This function takes care of calling `vkInvalidateMappedMemoryRanges` and
@@ -258,6 +367,19 @@ impl<D> MappedDeviceMemory<D> where D: SafeDeref<Target = Device> {
     }
 }
 
+// Widens `range` so that its start and its end are multiples of `atom_size`, except for an end
+// that would go past `memory_size`, which is set to `memory_size` instead.
+#[inline]
+fn align_to_atoms(range: Range<usize>, atom_size: usize, memory_size: usize) -> Range<usize> {
+    debug_assert!(atom_size >= 1);
+    let start = range.start - range.start % atom_size;
+    let end = match range.end % atom_size {
+        0 => range.end,
+        rem => range.end + (atom_size - rem),
+    };
+    start .. cmp::min(end, memory_size)
+}
+
 unsafe impl<D> Send for MappedDeviceMemory<D> where D: SafeDeref<Target = Device> {}
 unsafe impl<D> Sync for MappedDeviceMemory<D> where D: SafeDeref<Target = Device> {}
 
@@ -349,10 +471,151 @@ impl<'a, T: ?Sized + 'a, D: 'a> Drop for CpuAccess<'a, T, D> where D: SafeDeref<
     }
 }
 
+/// Type of a handle that memory can be imported from.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ExternalMemoryHandleType {
+    /// An opaque file descriptor, exported by Vulkan or a compatible API.
+    OpaqueFd,
+    /// A Linux dma-buf file descriptor, for example one exported by a camera or a video decoder.
+    DmaBuf,
+}
+
+impl ExternalMemoryHandleType {
+    /// Returns true if the extensions required to import handles of this type are enabled on
+    /// `device`.
+    ///
+    /// `OpaqueFd` requires `khr_external_memory_fd`, and `DmaBuf` additionally requires
+    /// `ext_external_memory_dma_buf`.
+    #[inline]
+    pub fn is_supported(&self, device: &Device) -> bool {
+        let extensions = device.loaded_extensions();
+        match *self {
+            ExternalMemoryHandleType::OpaqueFd => extensions.khr_external_memory_fd,
+            ExternalMemoryHandleType::DmaBuf => {
+                extensions.khr_external_memory_fd && extensions.ext_external_memory_dma_buf
+            },
+        }
+    }
+
+    /// Returns the memory types that the memory of `fd` can be imported on, as a bit mask
+    /// indexed by the memory type id.
+    ///
+    /// All memory types are allowed for `OpaqueFd` handles, as their memory type must be the
+    /// one used by the exporter.
+    ///
+    /// # Safety
+    ///
+    /// - `fd` must be a valid file descriptor of this handle type.
+    ///
+    pub unsafe fn memory_type_bits(&self, device: &Device, fd: c_int)
+                                   -> Result<u32, MemoryImportError>
+    {
+        if !self.is_supported(device) {
+            return Err(MemoryImportError::MissingExtension);
+        }
+
+        match *self {
+            ExternalMemoryHandleType::OpaqueFd => Ok(!0),
+            ExternalMemoryHandleType::DmaBuf => {
+                let mut output = vk::MemoryFdPropertiesKHR {
+                    sType: vk::STRUCTURE_TYPE_MEMORY_FD_PROPERTIES_KHR,
+                    pNext: ptr::null_mut(),
+                    memoryTypeBits: 0,
+                };
+
+                let vk = device.pointers();
+                try!(check_errors(vk.GetMemoryFdPropertiesKHR(device.internal_object(),
+                                                              (*self).into(), fd,
+                                                              &mut output)));
+                Ok(output.memoryTypeBits)
+            },
+        }
+    }
+}
+
+impl Into<vk::ExternalMemoryHandleTypeFlagBitsKHR> for ExternalMemoryHandleType {
+    #[inline]
+    fn into(self) -> vk::ExternalMemoryHandleTypeFlagBitsKHR {
+        match self {
+            ExternalMemoryHandleType::OpaqueFd => {
+                vk::EXTERNAL_MEMORY_HANDLE_TYPE_OPAQUE_FD_BIT_KHR
+            },
+            ExternalMemoryHandleType::DmaBuf => vk::EXTERNAL_MEMORY_HANDLE_TYPE_DMA_BUF_BIT_EXT,
+        }
+    }
+}
+
+/// Error that can happen when importing memory.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MemoryImportError {
+    /// Not enough memory.
+    OomError(OomError),
+
+    /// The handle is not valid for the given handle type.
+    InvalidExternalHandle,
+
+    /// The extensions required by the handle type are not enabled on the device. See
+    /// `ExternalMemoryHandleType::is_supported`.
+    MissingExtension,
+}
+
+impl error::Error for MemoryImportError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            MemoryImportError::OomError(_) => "not enough memory",
+            MemoryImportError::InvalidExternalHandle => {
+                "the handle is not valid for the given handle type"
+            },
+            MemoryImportError::MissingExtension => {
+                "the extensions required by the handle type were not enabled"
+            },
+        }
+    }
+
+    #[inline]
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            MemoryImportError::OomError(ref err) => Some(err),
+            _ => None
+        }
+    }
+}
+
+impl fmt::Display for MemoryImportError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+impl From<OomError> for MemoryImportError {
+    #[inline]
+    fn from(err: OomError) -> MemoryImportError {
+        MemoryImportError::OomError(err)
+    }
+}
+
+impl From<Error> for MemoryImportError {
+    #[inline]
+    fn from(err: Error) -> MemoryImportError {
+        match err {
+            e @ Error::OutOfHostMemory | e @ Error::OutOfDeviceMemory => {
+                MemoryImportError::OomError(e.into())
+            },
+            Error::InvalidExternalHandle => MemoryImportError::InvalidExternalHandle,
+            _ => panic!("unexpected error: {:?}", err)
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use OomError;
     use memory::DeviceMemory;
+    use memory::ExternalMemoryHandleType;
+    use memory::MemoryImportError;
+    use super::align_to_atoms;
 
     #[test]
     fn create() {
@@ -401,4 +664,30 @@ mod tests {
 
         panic!()
     }
+
+    #[test]
+    fn import_fd_missing_extension() {
+        let (device, _) = gfx_dev_and_queue!();
+        let mem_ty = device.physical_device().memory_types().next().unwrap();
+
+        let handle_type = ExternalMemoryHandleType::OpaqueFd;
+        match unsafe { DeviceMemory::import_fd(&device, mem_ty, 256, handle_type, -1) } {
+            Err(MemoryImportError::MissingExtension) => (),
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn atom_alignment() {
+        // Already aligned.
+        assert_eq!(align_to_atoms(64 .. 128, 64, 256), 64 .. 128);
+        // The start is aligned down and the end up.
+        assert_eq!(align_to_atoms(70 .. 130, 64, 256), 64 .. 192);
+        assert_eq!(align_to_atoms(1 .. 2, 64, 256), 0 .. 64);
+        // The end is capped at the size of the memory.
+        assert_eq!(align_to_atoms(200 .. 250, 64, 250), 192 .. 250);
+        assert_eq!(align_to_atoms(0 .. 100, 256, 100), 0 .. 100);
+        // An atom size of 1 leaves the range untouched.
+        assert_eq!(align_to_atoms(3 .. 7, 1, 256), 3 .. 7);
+    }
 }
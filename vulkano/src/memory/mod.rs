@@ -93,7 +93,9 @@ use vk;
 
 pub use self::device_memory::CpuAccess;
 pub use self::device_memory::DeviceMemory;
+pub use self::device_memory::ExternalMemoryHandleType;
 pub use self::device_memory::MappedDeviceMemory;
+pub use self::device_memory::MemoryImportError;
 pub use self::pool::MemoryPool;
 
 mod device_memory;
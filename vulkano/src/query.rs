@@ -14,17 +14,23 @@
 //! pool and the slot id within that query pool.
 
 use std::error;
+use std::ffi::CStr;
 use std::fmt;
 use std::mem;
+use std::os::raw::c_char;
+use std::os::raw::c_void;
 use std::ptr;
 use std::sync::Arc;
+use std::time::Duration;
 
 use device::Device;
+use instance::QueueFamily;
 
 use check_errors;
 use Error;
 use OomError;
 use SafeDeref;
+use Success;
 use VulkanObject;
 use VulkanPointers;
 use vk;
@@ -33,6 +39,8 @@ pub struct UnsafeQueryPool<P = Arc<Device>> where P: SafeDeref<Target = Device>
     pool: vk::QueryPool,
     device: P,
     num_slots: u32,
+    // The type of queries that the pool contains.
+    ty: vk::QueryType,
 }
 
 impl<P> UnsafeQueryPool<P> where P: SafeDeref<Target = Device> {
@@ -52,10 +60,18 @@ impl<P> UnsafeQueryPool<P> where P: SafeDeref<Target = Device> {
             },
         };
 
-        let pool = unsafe {
+        unsafe { UnsafeQueryPool::create(device, vk_ty, statistics, ptr::null(), num_slots) }
+    }
+
+    // Builds a new query pool from the raw values of the `VkQueryPoolCreateInfo`.
+    unsafe fn create(device: P, vk_ty: vk::QueryType, statistics: vk::QueryPipelineStatisticFlags,
+                     next: *const c_void, num_slots: u32)
+                     -> Result<UnsafeQueryPool<P>, QueryPoolCreationError>
+    {
+        let pool = {
             let infos = vk::QueryPoolCreateInfo {
                 sType: vk::STRUCTURE_TYPE_QUERY_POOL_CREATE_INFO,
-                pNext: ptr::null(),
+                pNext: next,
                 flags: 0,   // reserved
                 queryType: vk_ty,
                 queryCount: num_slots,
@@ -73,6 +89,7 @@ impl<P> UnsafeQueryPool<P> where P: SafeDeref<Target = Device> {
             pool: pool,
             device: device,
             num_slots: num_slots,
+            ty: vk_ty,
         })
     }
 
@@ -82,6 +99,12 @@ impl<P> UnsafeQueryPool<P> where P: SafeDeref<Target = Device> {
         self.num_slots
     }
 
+    /// Returns true if the pool was created with `QueryType::Timestamp`.
+    #[inline]
+    pub fn is_timestamp(&self) -> bool {
+        self.ty == vk::QUERY_TYPE_TIMESTAMP
+    }
+
     /// Returns the device used to create the pool.
     #[inline]
     pub fn device(&self) -> &P {
@@ -170,6 +193,67 @@ impl Into<vk::QueryPipelineStatisticFlags> for QueryPipelineStatisticFlags {
     }
 }
 
+/// How the results of queries are written when they are retrieved.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct QueryResultFlags {
+    /// The results are written as 64-bits integers instead of 32-bits integers.
+    pub result_64: bool,
+    /// Wait for the results of all the queries to be available before writing them.
+    pub wait: bool,
+    /// Write after the results of each query an integer that is non-zero if the results are
+    /// available.
+    pub with_availability: bool,
+    /// Write the current results of the queries that aren't available yet. Not allowed for
+    /// timestamp queries.
+    pub partial: bool,
+}
+
+impl QueryResultFlags {
+    #[inline]
+    pub fn none() -> QueryResultFlags {
+        QueryResultFlags {
+            result_64: false,
+            wait: false,
+            with_availability: false,
+            partial: false,
+        }
+    }
+
+    /// Returns the size in bytes of a single integer written with these flags.
+    #[inline]
+    pub fn element_size(&self) -> usize {
+        if self.result_64 { 8 } else { 4 }
+    }
+}
+
+impl Into<vk::QueryResultFlags> for QueryResultFlags {
+    fn into(self) -> vk::QueryResultFlags {
+        let mut result = 0;
+        if self.result_64 {
+            result |= vk::QUERY_RESULT_64_BIT;
+        }
+        if self.wait {
+            result |= vk::QUERY_RESULT_WAIT_BIT;
+        }
+        if self.with_availability {
+            result |= vk::QUERY_RESULT_WITH_AVAILABILITY_BIT;
+        }
+        if self.partial {
+            result |= vk::QUERY_RESULT_PARTIAL_BIT;
+        }
+        result
+    }
+}
+
+unsafe impl<P> VulkanObject for UnsafeQueryPool<P> where P: SafeDeref<Target = Device> {
+    type Object = vk::QueryPool;
+
+    #[inline]
+    fn internal_object(&self) -> vk::QueryPool {
+        self.pool
+    }
+}
+
 impl<P> Drop for UnsafeQueryPool<P> where P: SafeDeref<Target = Device> {
     #[inline]
     fn drop(&mut self) {
@@ -187,6 +271,12 @@ pub enum QueryPoolCreationError {
     OomError(OomError),
     /// A pipeline statistics pool was requested but the corresponding feature wasn't enabled.
     PipelineStatisticsQueryFeatureNotEnabled,
+    /// A performance query pool was requested but the `khr_performance_query` extension wasn't
+    /// enabled.
+    PerformanceQueryExtensionNotEnabled,
+    /// A performance query pool was requested but the `performance_counter_query_pools`
+    /// feature wasn't enabled.
+    PerformanceQueryFeatureNotEnabled,
 }
 
 impl error::Error for QueryPoolCreationError {
@@ -198,6 +288,14 @@ impl error::Error for QueryPoolCreationError {
                 "a pipeline statistics pool was requested but the corresponding feature \
                  wasn't enabled"
             },
+            QueryPoolCreationError::PerformanceQueryExtensionNotEnabled => {
+                "a performance query pool was requested but the `khr_performance_query` \
+                 extension wasn't enabled"
+            },
+            QueryPoolCreationError::PerformanceQueryFeatureNotEnabled => {
+                "a performance query pool was requested but the corresponding feature wasn't \
+                 enabled"
+            },
         }
     }
 
@@ -248,7 +346,9 @@ impl OcclusionQueriesPool {
             inner: match UnsafeQueryPool::new(device.clone(), QueryType::Occlusion, num_slots) {
                 Ok(q) => q,
                 Err(QueryPoolCreationError::OomError(err)) => return Err(err),
-                Err(QueryPoolCreationError::PipelineStatisticsQueryFeatureNotEnabled) => {
+                Err(QueryPoolCreationError::PipelineStatisticsQueryFeatureNotEnabled) |
+                Err(QueryPoolCreationError::PerformanceQueryExtensionNotEnabled) |
+                Err(QueryPoolCreationError::PerformanceQueryFeatureNotEnabled) => {
                     unreachable!()
                 },
             }
@@ -281,9 +381,512 @@ impl OcclusionQueriesPool {
     }
 }
 
+/// Returns the list of performance counters that can be read by performance queries on
+/// queues of the given family.
+///
+/// The index of a counter within this list is the value to pass to `PerformanceQueryPool::new`.
+///
+/// # Panic
+///
+/// - Panics if the physical device doesn't support the `khr_performance_query` extension.
+///
+pub fn performance_counters(queue_family: QueueFamily)
+                            -> Result<Vec<PerformanceCounter>, OomError>
+{
+    unsafe {
+        let physical_device = queue_family.physical_device();
+        let vk_i = physical_device.instance().pointers();
+
+        let mut num = 0;
+        try!(check_errors(vk_i.EnumeratePhysicalDeviceQueueFamilyPerformanceQueryCountersKHR(
+            physical_device.internal_object(), queue_family.id(), &mut num, ptr::null_mut(),
+            ptr::null_mut())));
+
+        let mut counters = (0 .. num).map(|_| vk::PerformanceCounterKHR {
+            sType: vk::STRUCTURE_TYPE_PERFORMANCE_COUNTER_KHR,
+            pNext: ptr::null_mut(),
+            .. mem::zeroed()
+        }).collect::<Vec<_>>();
+        let mut descriptions = (0 .. num).map(|_| vk::PerformanceCounterDescriptionKHR {
+            sType: vk::STRUCTURE_TYPE_PERFORMANCE_COUNTER_DESCRIPTION_KHR,
+            pNext: ptr::null_mut(),
+            .. mem::zeroed()
+        }).collect::<Vec<_>>();
+
+        try!(check_errors(vk_i.EnumeratePhysicalDeviceQueueFamilyPerformanceQueryCountersKHR(
+            physical_device.internal_object(), queue_family.id(), &mut num,
+            counters.as_mut_ptr(), descriptions.as_mut_ptr())));
+        counters.truncate(num as usize);
+        descriptions.truncate(num as usize);
+
+        let to_string = |chars: &[c_char]| {
+            CStr::from_ptr(chars.as_ptr()).to_string_lossy().into_owned()
+        };
+
+        Ok(counters.iter().zip(descriptions.iter()).enumerate().map(|(index, (c, d))| {
+            PerformanceCounter {
+                index: index as u32,
+                unit: PerformanceCounterUnit::from_vk(c.unit),
+                scope: PerformanceCounterScope::from_vk(c.scope),
+                storage: PerformanceCounterStorage::from_vk(c.storage),
+                uuid: c.uuid,
+                name: to_string(&d.name),
+                category: to_string(&d.category),
+                description: to_string(&d.description),
+            }
+        }).collect())
+    }
+}
+
+/// Description of a performance counter of a queue family.
+///
+/// Returned by `performance_counters()`.
+#[derive(Debug, Clone)]
+pub struct PerformanceCounter {
+    index: u32,
+    unit: PerformanceCounterUnit,
+    scope: PerformanceCounterScope,
+    storage: PerformanceCounterStorage,
+    uuid: [u8; 16],
+    name: String,
+    category: String,
+    description: String,
+}
+
+impl PerformanceCounter {
+    /// Returns the index of the counter within the list of counters of the queue family.
+    #[inline]
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// Returns the unit of the values of the counter.
+    #[inline]
+    pub fn unit(&self) -> PerformanceCounterUnit {
+        self.unit
+    }
+
+    /// Returns the range of commands whose execution is measured by the counter.
+    #[inline]
+    pub fn scope(&self) -> PerformanceCounterScope {
+        self.scope
+    }
+
+    /// Returns the type of the values of the counter.
+    #[inline]
+    pub fn storage(&self) -> PerformanceCounterStorage {
+        self.storage
+    }
+
+    /// Returns an identifier of the counter that stays the same across devices and drivers.
+    #[inline]
+    pub fn uuid(&self) -> [u8; 16] {
+        self.uuid
+    }
+
+    /// Returns the name of the counter, for example `"L2 cache misses"`.
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the category of the counter.
+    #[inline]
+    pub fn category(&self) -> &str {
+        &self.category
+    }
+
+    /// Returns a description of what the counter measures.
+    #[inline]
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+}
+
+/// Unit of the values of a performance counter.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PerformanceCounterUnit {
+    Generic,
+    Percentage,
+    Nanoseconds,
+    Bytes,
+    BytesPerSecond,
+    Kelvin,
+    Watts,
+    Volts,
+    Amps,
+    Hertz,
+    Cycles,
+}
+
+impl PerformanceCounterUnit {
+    #[inline]
+    fn from_vk(unit: vk::PerformanceCounterUnitKHR) -> PerformanceCounterUnit {
+        match unit {
+            vk::PERFORMANCE_COUNTER_UNIT_GENERIC_KHR => PerformanceCounterUnit::Generic,
+            vk::PERFORMANCE_COUNTER_UNIT_PERCENTAGE_KHR => PerformanceCounterUnit::Percentage,
+            vk::PERFORMANCE_COUNTER_UNIT_NANOSECONDS_KHR => PerformanceCounterUnit::Nanoseconds,
+            vk::PERFORMANCE_COUNTER_UNIT_BYTES_KHR => PerformanceCounterUnit::Bytes,
+            vk::PERFORMANCE_COUNTER_UNIT_BYTES_PER_SECOND_KHR => {
+                PerformanceCounterUnit::BytesPerSecond
+            },
+            vk::PERFORMANCE_COUNTER_UNIT_KELVIN_KHR => PerformanceCounterUnit::Kelvin,
+            vk::PERFORMANCE_COUNTER_UNIT_WATTS_KHR => PerformanceCounterUnit::Watts,
+            vk::PERFORMANCE_COUNTER_UNIT_VOLTS_KHR => PerformanceCounterUnit::Volts,
+            vk::PERFORMANCE_COUNTER_UNIT_AMPS_KHR => PerformanceCounterUnit::Amps,
+            vk::PERFORMANCE_COUNTER_UNIT_HERTZ_KHR => PerformanceCounterUnit::Hertz,
+            vk::PERFORMANCE_COUNTER_UNIT_CYCLES_KHR => PerformanceCounterUnit::Cycles,
+            _ => panic!("unexpected performance counter unit: {}", unit)
+        }
+    }
+}
+
+/// Range of commands whose execution is measured by a performance counter.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PerformanceCounterScope {
+    /// The query must be started before any other command of the command buffer and ended after
+    /// all of them.
+    CommandBuffer,
+    /// The query must be started and ended outside of a render pass, around whole render passes.
+    RenderPass,
+    /// The query can be started and ended around any command.
+    Command,
+}
+
+impl PerformanceCounterScope {
+    #[inline]
+    fn from_vk(scope: vk::PerformanceCounterScopeKHR) -> PerformanceCounterScope {
+        match scope {
+            vk::PERFORMANCE_COUNTER_SCOPE_COMMAND_BUFFER_KHR => {
+                PerformanceCounterScope::CommandBuffer
+            },
+            vk::PERFORMANCE_COUNTER_SCOPE_RENDER_PASS_KHR => PerformanceCounterScope::RenderPass,
+            vk::PERFORMANCE_COUNTER_SCOPE_COMMAND_KHR => PerformanceCounterScope::Command,
+            _ => panic!("unexpected performance counter scope: {}", scope)
+        }
+    }
+}
+
+/// Type of the values of a performance counter.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PerformanceCounterStorage {
+    Int32,
+    Int64,
+    Uint32,
+    Uint64,
+    Float32,
+    Float64,
+}
+
+impl PerformanceCounterStorage {
+    #[inline]
+    fn from_vk(storage: vk::PerformanceCounterStorageKHR) -> PerformanceCounterStorage {
+        match storage {
+            vk::PERFORMANCE_COUNTER_STORAGE_INT32_KHR => PerformanceCounterStorage::Int32,
+            vk::PERFORMANCE_COUNTER_STORAGE_INT64_KHR => PerformanceCounterStorage::Int64,
+            vk::PERFORMANCE_COUNTER_STORAGE_UINT32_KHR => PerformanceCounterStorage::Uint32,
+            vk::PERFORMANCE_COUNTER_STORAGE_UINT64_KHR => PerformanceCounterStorage::Uint64,
+            vk::PERFORMANCE_COUNTER_STORAGE_FLOAT32_KHR => PerformanceCounterStorage::Float32,
+            vk::PERFORMANCE_COUNTER_STORAGE_FLOAT64_KHR => PerformanceCounterStorage::Float64,
+            _ => panic!("unexpected performance counter storage: {}", storage)
+        }
+    }
+}
+
+/// Value of a performance counter read from a performance query.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum PerformanceCounterResult {
+    Int32(i32),
+    Int64(i64),
+    Uint32(u32),
+    Uint64(u64),
+    Float32(f32),
+    Float64(f64),
+}
+
+/// Query pool whose queries read performance counters.
+///
+/// The commands to measure must be surrounded with `CmdBeginQuery` and `CmdEndQuery` in the same
+/// command buffer, and a `ProfilingLock` must be held from before the command buffer starts being
+/// recorded until it has finished executing. Like any other query, a performance query must be
+/// reset before it is used, and the reset must not be part of the command buffer that begins it.
+///
+/// Depending on the counters, the implementation may need the command buffer to be executed
+/// multiple times in order to collect all of them. See `num_passes()` and
+/// `GpuFuture::then_profile()`.
+pub struct PerformanceQueryPool {
+    inner: Arc<UnsafeQueryPool>,
+    queue_family: u32,
+    counters: Vec<PerformanceCounter>,
+    num_passes: u32,
+}
+
+impl PerformanceQueryPool {
+    /// Builds a new query pool whose queries read the counters of `queue_family` whose indices
+    /// are in `counters`. The indices correspond to the list returned by
+    /// `performance_counters(queue_family)`.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if `counters` is empty or contains an index that is out of range.
+    /// - Panics if the queue family doesn't belong to the physical device of `device`.
+    ///
+    pub fn new(device: &Arc<Device>, queue_family: QueueFamily, counters: &[u32],
+               num_slots: u32)
+               -> Result<Arc<PerformanceQueryPool>, QueryPoolCreationError>
+    {
+        if !device.loaded_extensions().khr_performance_query {
+            return Err(QueryPoolCreationError::PerformanceQueryExtensionNotEnabled);
+        }
+
+        if !device.enabled_features().performance_counter_query_pools {
+            return Err(QueryPoolCreationError::PerformanceQueryFeatureNotEnabled);
+        }
+
+        assert!(!counters.is_empty());
+        assert_eq!(queue_family.physical_device().internal_object(),
+                   device.physical_device().internal_object());
+
+        let available = try!(performance_counters(queue_family));
+        let selected = counters.iter().map(|&index| {
+            assert!((index as usize) < available.len(), "Performance counter index out of range");
+            available[index as usize].clone()
+        }).collect();
+
+        let perf_infos = vk::QueryPoolPerformanceCreateInfoKHR {
+            sType: vk::STRUCTURE_TYPE_QUERY_POOL_PERFORMANCE_CREATE_INFO_KHR,
+            pNext: ptr::null(),
+            queueFamilyIndex: queue_family.id(),
+            counterIndexCount: counters.len() as u32,
+            pCounterIndices: counters.as_ptr(),
+        };
+
+        let (inner, num_passes) = unsafe {
+            let physical_device = queue_family.physical_device();
+            let vk_i = physical_device.instance().pointers();
+            let mut num_passes = 0;
+            vk_i.GetPhysicalDeviceQueueFamilyPerformanceQueryPassesKHR(
+                physical_device.internal_object(), &perf_infos, &mut num_passes);
+
+            let inner = try!(UnsafeQueryPool::create(device.clone(),
+                                                     vk::QUERY_TYPE_PERFORMANCE_QUERY_KHR, 0,
+                                                     &perf_infos as *const _ as *const c_void,
+                                                     num_slots));
+            (inner, num_passes)
+        };
+
+        Ok(Arc::new(PerformanceQueryPool {
+            inner: Arc::new(inner),
+            queue_family: queue_family.id(),
+            counters: selected,
+            num_passes: num_passes,
+        }))
+    }
+
+    /// Returns the underlying query pool. This is what must be passed to `CmdBeginQuery`,
+    /// `CmdEndQuery` and `CmdResetQueryPool`.
+    #[inline]
+    pub fn inner(&self) -> &Arc<UnsafeQueryPool> {
+        &self.inner
+    }
+
+    /// Returns the id of the queue family whose counters are read. The queries can only be used
+    /// in command buffers that belong to this family.
+    #[inline]
+    pub fn queue_family_id(&self) -> u32 {
+        self.queue_family
+    }
+
+    /// Returns the counters read by each query, in the order of the results.
+    #[inline]
+    pub fn counters(&self) -> &[PerformanceCounter] {
+        &self.counters
+    }
+
+    /// Returns the number of times a command buffer that contains a query of this pool must be
+    /// submitted in order to read all the counters.
+    #[inline]
+    pub fn num_passes(&self) -> u32 {
+        self.num_passes
+    }
+
+    /// Returns the number of slots of that query pool.
+    #[inline]
+    pub fn num_slots(&self) -> u32 {
+        self.inner.num_slots()
+    }
+
+    /// Returns the device that was used to create this pool.
+    #[inline]
+    pub fn device(&self) -> &Arc<Device> {
+        self.inner.device()
+    }
+
+    /// Returns the value of each counter for the query of index `query`, in the same order as
+    /// `counters()`.
+    ///
+    /// Returns `None` if the results aren't available yet, which is the case until all the
+    /// passes of the command buffer that contains the query have finished executing.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if `query` is out of range of the pool.
+    ///
+    pub fn results(&self, query: u32)
+                   -> Result<Option<Vec<PerformanceCounterResult>>, OomError>
+    {
+        assert!(query < self.num_slots());
+
+        let raw = unsafe {
+            let mut data: Vec<vk::PerformanceCounterResultKHR> =
+                Vec::with_capacity(self.counters.len());
+            data.set_len(self.counters.len());
+            let size = data.len() * mem::size_of::<vk::PerformanceCounterResultKHR>();
+
+            let device = self.device();
+            let vk = device.pointers();
+            let r = try!(check_errors(vk.GetQueryPoolResults(device.internal_object(),
+                                                             self.inner.internal_object(), query,
+                                                             1, size,
+                                                             data.as_mut_ptr() as *mut c_void,
+                                                             size as vk::DeviceSize, 0)));
+            match r {
+                Success::Success => data,
+                Success::NotReady => return Ok(None),
+                _ => unreachable!()
+            }
+        };
+
+        Ok(Some(self.counters.iter().zip(raw.iter()).map(|(counter, value)| {
+            match counter.storage {
+                PerformanceCounterStorage::Int32 => PerformanceCounterResult::Int32(value.int32()),
+                PerformanceCounterStorage::Int64 => PerformanceCounterResult::Int64(value.int64()),
+                PerformanceCounterStorage::Uint32 => {
+                    PerformanceCounterResult::Uint32(value.uint32())
+                },
+                PerformanceCounterStorage::Uint64 => {
+                    PerformanceCounterResult::Uint64(value.uint64())
+                },
+                PerformanceCounterStorage::Float32 => {
+                    PerformanceCounterResult::Float32(value.float32())
+                },
+                PerformanceCounterStorage::Float64 => {
+                    PerformanceCounterResult::Float64(value.float64())
+                },
+            }
+        }).collect()))
+    }
+}
+
+/// Lock that must be held while command buffers that contain performance queries are recorded
+/// and executed.
+///
+/// The lock is released when the `ProfilingLock` is destroyed.
+pub struct ProfilingLock {
+    device: Arc<Device>,
+}
+
+impl ProfilingLock {
+    /// Acquires the profiling lock of `device`.
+    ///
+    /// Returns an error if the lock couldn't be acquired before `timeout` has elapsed, for
+    /// example because another process is holding it.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if the `khr_performance_query` extension isn't enabled on the device.
+    ///
+    pub fn acquire(device: &Arc<Device>, timeout: Duration)
+                   -> Result<Arc<ProfilingLock>, ProfilingLockError>
+    {
+        assert!(device.loaded_extensions().khr_performance_query);
+
+        let timeout_ns = timeout.as_secs().saturating_mul(1_000_000_000)
+                                          .saturating_add(timeout.subsec_nanos() as u64);
+
+        unsafe {
+            let infos = vk::AcquireProfilingLockInfoKHR {
+                sType: vk::STRUCTURE_TYPE_ACQUIRE_PROFILING_LOCK_INFO_KHR,
+                pNext: ptr::null(),
+                flags: 0,   // reserved
+                timeout: timeout_ns,
+            };
+
+            let vk = device.pointers();
+            let r = try!(check_errors(vk.AcquireProfilingLockKHR(device.internal_object(),
+                                                                 &infos)));
+            match r {
+                Success::Success => Ok(Arc::new(ProfilingLock { device: device.clone() })),
+                Success::Timeout => Err(ProfilingLockError::Timeout),
+                _ => unreachable!()
+            }
+        }
+    }
+
+    /// Returns the device whose profiling lock is held.
+    #[inline]
+    pub fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+}
+
+impl Drop for ProfilingLock {
+    #[inline]
+    fn drop(&mut self) {
+        unsafe {
+            let vk = self.device.pointers();
+            vk.ReleaseProfilingLockKHR(self.device.internal_object());
+        }
+    }
+}
+
+/// Error that can happen when acquiring the profiling lock.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ProfilingLockError {
+    /// Not enough memory.
+    OomError(OomError),
+    /// The lock couldn't be acquired before the timeout has elapsed.
+    Timeout,
+}
+
+impl error::Error for ProfilingLockError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            ProfilingLockError::OomError(_) => "not enough memory available",
+            ProfilingLockError::Timeout => {
+                "the profiling lock couldn't be acquired before the timeout has elapsed"
+            },
+        }
+    }
+
+    #[inline]
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            ProfilingLockError::OomError(ref err) => Some(err),
+            _ => None
+        }
+    }
+}
+
+impl fmt::Display for ProfilingLockError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+impl From<Error> for ProfilingLockError {
+    #[inline]
+    fn from(err: Error) -> ProfilingLockError {
+        ProfilingLockError::OomError(OomError::from(err))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use query::OcclusionQueriesPool;
+    use query::PerformanceQueryPool;
     use query::QueryPipelineStatisticFlags;
     use query::QueryPoolCreationError;
     use query::QueryType;
@@ -305,4 +908,14 @@ mod tests {
             _ => panic!()
         };
     }
+
+    #[test]
+    fn performance_query_extension() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        match PerformanceQueryPool::new(&device, queue.family(), &[0], 1) {
+            Err(QueryPoolCreationError::PerformanceQueryExtensionNotEnabled) => (),
+            _ => panic!()
+        };
+    }
 }
@@ -205,7 +205,9 @@ pub use self::surface::SupportedCompositeAlphaIter;
 pub use self::surface::ColorSpace;
 pub use self::surface::SurfaceCreationError;
 pub use self::swapchain::AcquireError;
+pub use self::swapchain::PresentError;
 pub use self::swapchain::PresentFuture;
+pub use self::swapchain::PresentWaitError;
 pub use self::swapchain::Swapchain;
 pub use self::swapchain::SwapchainAcquireFuture;
 
@@ -7,13 +7,17 @@
 // notice may not be copied, modified, or distributed except
 // according to those terms.
 
+use std::collections::HashMap;
 use std::error;
 use std::fmt;
+use std::hash::BuildHasherDefault;
 use std::mem;
 use std::os::raw::c_ulong;
 use std::ptr;
 use std::sync::Arc;
+use std::sync::Mutex;
 use std::sync::atomic::AtomicBool;
+use fnv::FnvHasher;
 
 use format::Format;
 use image::Usage as ImageUsage;
@@ -43,6 +47,10 @@ pub struct Surface {
     // If true, a swapchain has been associated to this surface, and that any new swapchain
     // creation should be forbidden.
     has_swapchain: AtomicBool,
+
+    // Cache of the results of `is_supported`, indexed by physical device index and queue family
+    // id.
+    supported_queues: Mutex<HashMap<(usize, u32), bool, BuildHasherDefault<FnvHasher>>>,
 }
 
 impl Surface {
@@ -94,6 +102,7 @@ impl Surface {
             instance: instance.clone(),
             surface: surface,
             has_swapchain: AtomicBool::new(false),
+            supported_queues: Mutex::new(HashMap::default()),
         }))
     }
 
@@ -133,6 +142,7 @@ impl Surface {
             instance: instance.clone(),
             surface: surface,
             has_swapchain: AtomicBool::new(false),
+            supported_queues: Mutex::new(HashMap::default()),
         }))
     }
 
@@ -172,6 +182,7 @@ impl Surface {
             instance: instance.clone(),
             surface: surface,
             has_swapchain: AtomicBool::new(false),
+            supported_queues: Mutex::new(HashMap::default()),
         }))
     }
 
@@ -211,6 +222,7 @@ impl Surface {
             instance: instance.clone(),
             surface: surface,
             has_swapchain: AtomicBool::new(false),
+            supported_queues: Mutex::new(HashMap::default()),
         }))
     }
 
@@ -250,6 +262,7 @@ impl Surface {
             instance: instance.clone(),
             surface: surface,
             has_swapchain: AtomicBool::new(false),
+            supported_queues: Mutex::new(HashMap::default()),
         }))
     }
 
@@ -290,6 +303,7 @@ impl Surface {
             instance: instance.clone(),
             surface: surface,
             has_swapchain: AtomicBool::new(false),
+            supported_queues: Mutex::new(HashMap::default()),
         }))
     }
 
@@ -326,6 +340,7 @@ impl Surface {
             instance: instance.clone(),
             surface: surface,
             has_swapchain: AtomicBool::new(false),
+            supported_queues: Mutex::new(HashMap::default()),
         }))
     }
 
@@ -362,12 +377,23 @@ impl Surface {
             instance: instance.clone(),
             surface: surface,
             has_swapchain: AtomicBool::new(false),
+            supported_queues: Mutex::new(HashMap::default()),
         }))
     }
 
     /// Returns true if the given queue family can draw on this surface.
+    ///
+    /// The result is cached, so calling this function multiple times with the same queue family
+    /// only queries the implementation once.
     pub fn is_supported(&self, queue: &QueueFamily) -> Result<bool, OomError> {
-        unsafe {
+        let key = (queue.physical_device().index(), queue.id());
+
+        let mut cache = self.supported_queues.lock().unwrap();
+        if let Some(&supported) = cache.get(&key) {
+            return Ok(supported);
+        }
+
+        let supported = unsafe {
             let vk = self.instance.pointers();
 
             let mut output = mem::uninitialized();
@@ -375,8 +401,11 @@ impl Surface {
                 vk.GetPhysicalDeviceSurfaceSupportKHR(queue.physical_device().internal_object(),
                                                       queue.id(), self.surface, &mut output)
             ));
-            Ok(output != 0)
-        }
+            output != 0
+        };
+
+        cache.insert(key, supported);
+        Ok(supported)
     }
 
     /// Retreives the capabilities of a surface when used by a certain device.
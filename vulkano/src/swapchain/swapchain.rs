@@ -39,6 +39,7 @@ use swapchain::Surface;
 use swapchain::SurfaceTransform;
 use swapchain::SurfaceSwapchainLock;
 use sync::AccessFlagBits;
+use sync::FlushError;
 use sync::GpuFuture;
 use sync::PipelineStages;
 use sync::Semaphore;
@@ -81,6 +82,10 @@ pub struct Swapchain {
 
     // TODO: meh for Mutex
     images: Mutex<Vec<Weak<SwapchainImage>>>,
+
+    // For each image, the identifier of the last present operation of this image that was given
+    // an identifier, or 0 if none.
+    last_present_ids: Mutex<Vec<u64>>,
 }
 
 impl Swapchain {
@@ -231,6 +236,7 @@ impl Swapchain {
             mode: mode,
             clipped: clipped,
             images: Mutex::new(Vec::new()),     // Filled below.
+            last_present_ids: Mutex::new(Vec::new()),       // Filled below.
         });
 
         let images = unsafe {
@@ -254,6 +260,7 @@ impl Swapchain {
         }).collect::<Vec<_>>();
 
         *swapchain.images.lock().unwrap() = images.iter().map(|i| Arc::downgrade(i)).collect();
+        *swapchain.last_present_ids.lock().unwrap() = vec![0; images.len()];
         Ok((swapchain, images))
     }
 
@@ -312,6 +319,13 @@ impl Swapchain {
     ///
     /// The actual behavior depends on the present mode that you passed when creating the
     /// swapchain.
+    ///
+    /// If `before` is itself a `PresentFuture` for another swapchain, both images are presented
+    /// with the same `vkQueuePresentKHR` call. Signaling a fence after the last present then
+    /// gives a single fence for all of them. See `FenceSignalFuture::present_results`.
+    ///
+    /// The queue is not checked against the surface of the swapchain. Use `present_checked`
+    /// to get an error if the family of `queue` can't present to it.
     // TODO: use another API, since taking by Arc is meh
     pub fn present<F>(me: Arc<Self>, before: F, queue: Arc<Queue>, index: usize)
                       -> PresentFuture<F>
@@ -330,10 +344,87 @@ impl Swapchain {
             queue: queue,
             swapchain: me,
             image_id: index as u32,
+            present_id: 0,
+            wait_stages: None,
+            flushed: Mutex::new(false),
             finished: AtomicBool::new(false),
         }
     }
 
+    /// Same as `present`, but returns an error if the family of `queue` doesn't support
+    /// presenting to the surface of this swapchain.
+    pub fn present_checked<F>(me: Arc<Self>, before: F, queue: Arc<Queue>, index: usize)
+                              -> Result<PresentFuture<F>, PresentError>
+        where F: GpuFuture
+    {
+        if !try!(me.surface.is_supported(&queue.family())) {
+            return Err(PresentError::UnsupportedQueue);
+        }
+
+        Ok(Swapchain::present(me, before, queue, index))
+    }
+
+    /// Blocks until the present with the given identifier has been displayed, or until the
+    /// timeout has elapsed.
+    ///
+    /// The identifier is the one passed to `PresentFuture::with_present_id`. Contrary to a fence
+    /// signaled after a present, this waits for the image to actually be visible on the screen,
+    /// which makes it suitable for measuring the latency between input and display.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if the `present_wait` feature isn't enabled on the device.
+    ///
+    pub fn wait_for_present(&self, present_id: u64, timeout: Duration)
+                            -> Result<(), PresentWaitError>
+    {
+        assert!(self.device.enabled_features().present_wait);
+
+        unsafe {
+            let vk = self.device.pointers();
+
+            let timeout_ns = timeout.as_secs().saturating_mul(1_000_000_000)
+                                              .saturating_add(timeout.subsec_nanos() as u64);
+
+            let r = try!(check_errors(vk.WaitForPresentKHR(self.device.internal_object(),
+                                                           self.swapchain, present_id,
+                                                           timeout_ns)));
+
+            match r {
+                Success::Success => Ok(()),
+                Success::Suboptimal => Ok(()),
+                Success::Timeout => Err(PresentWaitError::Timeout),
+                s => panic!("unexpected success value: {:?}", s)
+            }
+        }
+    }
+
+    /// Returns the identifier of the last present of the given image that was submitted with an
+    /// identifier, if any.
+    ///
+    /// The identifier is recorded once `vkQueuePresentKHR` has succeeded for this image, in other
+    /// words when the `PresentFuture` or a future that follows it is flushed. A present that is
+    /// never submitted or that fails doesn't change the identifier.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if `image_index` is out of range.
+    ///
+    #[inline]
+    pub fn last_present_id(&self, image_index: usize) -> Option<u64> {
+        match self.last_present_ids.lock().unwrap()[image_index] {
+            0 => None,
+            id => Some(id),
+        }
+    }
+
+    // Called by `SubmitPresentBuilder` once an image has been presented with an identifier.
+    #[doc(hidden)]
+    #[inline]
+    pub fn record_present_id(&self, image_index: u32, present_id: u64) {
+        self.last_present_ids.lock().unwrap()[image_index as usize] = present_id;
+    }
+
     /// Returns the number of images of the swapchain.
     ///
     /// See the documentation of `Swapchain::new`. 
@@ -408,6 +499,13 @@ unsafe impl VulkanObject for Swapchain {
     }
 }
 
+unsafe impl DeviceOwned for Swapchain {
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+}
+
 impl Drop for Swapchain {
     #[inline]
     fn drop(&mut self) {
@@ -448,14 +546,14 @@ unsafe impl GpuFuture for SwapchainAcquireFuture {
     }
 
     #[inline]
-    unsafe fn build_submission(&self) -> Result<SubmitAnyBuilder, Box<error::Error>> {
+    unsafe fn build_submission(&self) -> Result<SubmitAnyBuilder, FlushError> {
         let mut sem = SubmitSemaphoresWaitBuilder::new();
         sem.add_wait_semaphore(&self.semaphore);
         Ok(SubmitAnyBuilder::SemaphoresWait(sem))
     }
 
     #[inline]
-    fn flush(&self) -> Result<(), Box<error::Error>> {
+    fn flush(&self) -> Result<(), FlushError> {
         Ok(())
     }
 
@@ -495,6 +593,30 @@ unsafe impl GpuFuture for SwapchainAcquireFuture {
             Err(())
         }
     }
+
+    #[inline]
+    fn acquired_swapchain_image(&self, swapchain: &Swapchain) -> Option<usize> {
+        match self.image.upgrade() {
+            Some(ref img) if img.swapchain().internal_object() == swapchain.internal_object() => {
+                Some(self.id)
+            },
+            _ => None,
+        }
+    }
+
+    #[inline]
+    fn wait_semaphore_count(&self) -> usize {
+        1
+    }
+
+    #[inline]
+    fn validate_resources(&self) {
+        // Same as `sync_assert!`, which isn't available in this module.
+        if cfg!(any(debug_assertions, feature = "strict-sync")) {
+            assert!(self.image.upgrade().is_some(), "The image acquired by a \
+                                                     SwapchainAcquireFuture has been destroyed");
+        }
+    }
 }
 
 unsafe impl DeviceOwned for SwapchainAcquireFuture {
@@ -589,6 +711,110 @@ impl From<Error> for AcquireError {
     }
 }
 
+/// Error that can happen when calling `Swapchain::present_checked`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PresentError {
+    /// Not enough memory.
+    OomError(OomError),
+
+    /// The family of the queue doesn't support presenting to the surface of the swapchain.
+    UnsupportedQueue,
+}
+
+impl error::Error for PresentError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            PresentError::OomError(_) => "not enough memory",
+            PresentError::UnsupportedQueue => "the queue family doesn't support presenting to the \
+                                               surface of the swapchain",
+        }
+    }
+
+    #[inline]
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            PresentError::OomError(ref err) => Some(err),
+            _ => None
+        }
+    }
+}
+
+impl fmt::Display for PresentError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+impl From<OomError> for PresentError {
+    #[inline]
+    fn from(err: OomError) -> PresentError {
+        PresentError::OomError(err)
+    }
+}
+
+/// Error that can happen when calling `Swapchain::wait_for_present`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PresentWaitError {
+    /// Not enough memory.
+    OomError(OomError),
+
+    /// The connection to the device has been lost.
+    DeviceLost,
+
+    /// The timeout has elapsed before the image was displayed.
+    Timeout,
+
+    /// The surface is no longer accessible and must be recreated.
+    SurfaceLost,
+
+    /// The surface has changed in a way that makes the swapchain unusable.
+    OutOfDate,
+}
+
+impl error::Error for PresentWaitError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            PresentWaitError::OomError(_) => "not enough memory",
+            PresentWaitError::DeviceLost => "the connection to the device has been lost",
+            PresentWaitError::Timeout => "the image wasn't displayed before the timeout",
+            PresentWaitError::SurfaceLost => "the surface of this swapchain is no longer valid",
+            PresentWaitError::OutOfDate => "the swapchain needs to be recreated",
+        }
+    }
+
+    #[inline]
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            PresentWaitError::OomError(ref err) => Some(err),
+            _ => None
+        }
+    }
+}
+
+impl fmt::Display for PresentWaitError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+impl From<Error> for PresentWaitError {
+    #[inline]
+    fn from(err: Error) -> PresentWaitError {
+        match err {
+            err @ Error::OutOfHostMemory => PresentWaitError::OomError(OomError::from(err)),
+            err @ Error::OutOfDeviceMemory => PresentWaitError::OomError(OomError::from(err)),
+            Error::DeviceLost => PresentWaitError::DeviceLost,
+            Error::SurfaceLost => PresentWaitError::SurfaceLost,
+            Error::OutOfDate => PresentWaitError::OutOfDate,
+            _ => panic!("unexpected error: {:?}", err)
+        }
+    }
+}
+
 /// Represents a swapchain image being presented on the screen.
 #[must_use = "Dropping this object will immediately block the thread until the GPU has finished processing the submission"]
 pub struct PresentFuture<P> where P: GpuFuture {
@@ -596,9 +822,93 @@ pub struct PresentFuture<P> where P: GpuFuture {
     queue: Arc<Queue>,
     swapchain: Arc<Swapchain>,
     image_id: u32,
+    // Identifier of the present operation, or 0 if none.
+    present_id: u64,
+    // If `Some`, the stages of the previous submission that wait upon its semaphores.
+    wait_stages: Option<PipelineStages>,
+    // True if the present has already been submitted.
+    // If flush is called multiple times, we want to block so that only one flushing is executed.
+    // Therefore we use a `Mutex<bool>` and not an `AtomicBool`.
+    flushed: Mutex<bool>,
     finished: AtomicBool,
 }
 
+impl<P> PresentFuture<P> where P: GpuFuture {
+    /// Associates an identifier with the present operation.
+    ///
+    /// After the present has been submitted, you can pass the same identifier to
+    /// `Swapchain::wait_for_present` in order to block until the image is actually displayed.
+    /// Identifiers must be strictly increasing for a given swapchain, for example a frame counter.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if the `present_id` feature isn't enabled on the device.
+    /// - Panics if `present_id` is 0.
+    ///
+    #[inline]
+    pub fn with_present_id(mut self, present_id: u64) -> PresentFuture<P> {
+        assert!(self.swapchain.device.enabled_features().present_id);
+        assert!(present_id != 0);
+        self.present_id = present_id;
+        self
+    }
+
+    /// Sets the stages that wait for the semaphores of the previous futures, when the previous
+    /// future submits command buffers on the same queue as the present.
+    ///
+    /// A present operation doesn't have a stage mask. What it depends on is the command buffer
+    /// that writes the swapchain image, which by default waits for the semaphores (such as the
+    /// acquire semaphore) with all of its stages. If the image is only written by a blit or by a
+    /// render pass, passing `transfer` or `color_attachment_output` lets the other stages of the
+    /// command buffer start before the image is available.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if `stages` is empty.
+    ///
+    /// # Safety
+    ///
+    /// - The stages must be supported by the device.
+    /// - The command buffers of the previous future must not access, outside of `stages`, any
+    ///   resource whose accesses are synchronized by the semaphores it waits upon. This includes
+    ///   the swapchain image and the resources written by other queues.
+    ///
+    #[inline]
+    pub unsafe fn with_wait_stages(mut self, stages: PipelineStages) -> PresentFuture<P> {
+        let raw: vk::PipelineStageFlagBits = stages.into();
+        assert!(raw != 0, "The wait stages of a present can't be empty");
+        self.wait_stages = Some(stages);
+        self
+    }
+
+    /// Returns the identifier passed to `with_present_id`, if any.
+    #[inline]
+    pub fn present_id(&self) -> Option<u64> {
+        if self.present_id == 0 { None } else { Some(self.present_id) }
+    }
+
+    /// Returns the index of the presented image in the list of images returned when creating the
+    /// swapchain.
+    #[inline]
+    pub fn image_id(&self) -> usize {
+        self.image_id as usize
+    }
+
+    /// Returns the image that is presented.
+    ///
+    /// Returns `None` if all the `Arc`s to the image have been dropped.
+    #[inline]
+    pub fn presented_image(&self) -> Option<Arc<SwapchainImage>> {
+        self.swapchain.images.lock().unwrap()[self.image_id as usize].upgrade()
+    }
+
+    /// Returns the swapchain that the image is presented to.
+    #[inline]
+    pub fn swapchain(&self) -> &Arc<Swapchain> {
+        &self.swapchain
+    }
+}
+
 unsafe impl<P> GpuFuture for PresentFuture<P> where P: GpuFuture {
     #[inline]
     fn cleanup_finished(&mut self) {
@@ -606,7 +916,7 @@ unsafe impl<P> GpuFuture for PresentFuture<P> where P: GpuFuture {
     }
 
     #[inline]
-    unsafe fn build_submission(&self) -> Result<SubmitAnyBuilder, Box<error::Error>> {
+    unsafe fn build_submission(&self) -> Result<SubmitAnyBuilder, FlushError> {
         let queue = self.previous.queue().map(|q| q.clone());
 
         // TODO: if the swapchain image layout is not PRESENT, should add a transition command
@@ -615,33 +925,55 @@ unsafe impl<P> GpuFuture for PresentFuture<P> where P: GpuFuture {
         Ok(match try!(self.previous.build_submission()) {
             SubmitAnyBuilder::Empty => {
                 let mut builder = SubmitPresentBuilder::new();
-                builder.add_swapchain(&self.swapchain, self.image_id);
+                builder.add_swapchain_with_present_id(&self.swapchain, self.image_id,
+                                                      self.present_id);
                 SubmitAnyBuilder::QueuePresent(builder)
             },
             SubmitAnyBuilder::SemaphoresWait(sem) => {
                 let mut builder: SubmitPresentBuilder = sem.into();
-                builder.add_swapchain(&self.swapchain, self.image_id);
+                builder.add_swapchain_with_present_id(&self.swapchain, self.image_id,
+                                                      self.present_id);
                 SubmitAnyBuilder::QueuePresent(builder)
             },
-            SubmitAnyBuilder::CommandBuffer(cb) => {
+            SubmitAnyBuilder::CommandBuffer(mut cb) => {
+                if let Some(stages) = self.wait_stages {
+                    cb.set_wait_dest_stages(stages);
+                }
                 try!(cb.submit(&queue.unwrap()));        // FIXME: wrong because build_submission can be called multiple times
                 let mut builder = SubmitPresentBuilder::new();
-                builder.add_swapchain(&self.swapchain, self.image_id);
+                builder.add_swapchain_with_present_id(&self.swapchain, self.image_id,
+                                                      self.present_id);
                 SubmitAnyBuilder::QueuePresent(builder)
             },
-            SubmitAnyBuilder::QueuePresent(present) => {
-                unimplemented!()        // TODO:
-                /*present.submit();
-                let mut builder = SubmitPresentBuilder::new();
-                builder.add_swapchain(self.command_buffer.inner(), self.image_id);
-                SubmitAnyBuilder::CommandBuffer(builder)*/
+            SubmitAnyBuilder::QueuePresent(mut present) => {
+                // The previous future presents to another swapchain. Both presents are merged
+                // in a single `vkQueuePresentKHR`.
+                present.add_swapchain_with_present_id(&self.swapchain, self.image_id,
+                                                      self.present_id);
+                SubmitAnyBuilder::QueuePresent(present)
             },
         })
     }
 
     #[inline]
-    fn flush(&self) -> Result<(), Box<error::Error>> {
-        unimplemented!()
+    fn flush(&self) -> Result<(), FlushError> {
+        unsafe {
+            let mut flushed = self.flushed.lock().unwrap();
+            if *flushed {
+                return Ok(());
+            }
+
+            match try!(self.build_submission()) {
+                SubmitAnyBuilder::QueuePresent(present) => {
+                    try!(present.submit(&self.queue));
+                },
+                _ => unreachable!(),
+            };
+
+            // Only write `true` here in order to try again next time if we failed to submit.
+            *flushed = true;
+            Ok(())
+        }
     }
 
     #[inline]
@@ -669,14 +1001,59 @@ unsafe impl<P> GpuFuture for PresentFuture<P> where P: GpuFuture {
     fn check_buffer_access(&self, buffer: &BufferAccess, exclusive: bool, queue: &Queue)
                            -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
     {
-        unimplemented!()        // TODO: VK specs don't say whether it is legal to do that
+        self.previous.check_buffer_access(buffer, exclusive, queue)
     }
 
     #[inline]
     fn check_image_access(&self, image: &ImageAccess, exclusive: bool, queue: &Queue)
                           -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
     {
-        unimplemented!()        // TODO: VK specs don't say whether it is legal to do that
+        // The presented image belongs to the presentation engine until it is acquired again.
+        // The other resources of the previous future, such as the image of another swapchain
+        // that is presented after this one, can still be accessed.
+        let presented = self.presented_image().map(|img| {
+            ImageAccess::inner(&*img).internal_object()
+        });
+        if presented == Some(image.inner().internal_object()) {
+            return Err(());
+        }
+
+        self.previous.check_image_access(image, exclusive, queue)
+    }
+
+    #[inline]
+    fn acquired_swapchain_image(&self, swapchain: &Swapchain) -> Option<usize> {
+        // The image presented by this future isn't acquired anymore, but the image of another
+        // swapchain acquired by the previous future can still be presented after this one.
+        if swapchain.internal_object() == self.swapchain.internal_object() {
+            return None;
+        }
+
+        self.previous.acquired_swapchain_image(swapchain)
+    }
+
+    #[inline]
+    fn wait_semaphore_count(&self) -> usize {
+        self.previous.wait_semaphore_count()
+    }
+
+    #[inline]
+    fn validate_resources(&self) {
+        if cfg!(any(debug_assertions, feature = "strict-sync")) {
+            assert!(self.presented_image().is_some(), "The image presented by a PresentFuture \
+                                                       has been destroyed");
+        }
+        self.previous.validate_resources();
+    }
+
+    #[inline]
+    fn is_flushed(&self) -> bool {
+        *self.flushed.lock().unwrap()
+    }
+
+    #[inline]
+    fn shared_identities(&self, ids: &mut Vec<usize>) {
+        self.previous.shared_identities(ids);
     }
 }
 
@@ -691,8 +1068,15 @@ impl<P> Drop for PresentFuture<P> where P: GpuFuture {
     fn drop(&mut self) {
         unsafe {
             if !*self.finished.get_mut() {
-                // TODO: handle errors?
-                self.flush().unwrap();
+                match self.flush() {
+                    Ok(()) => (),
+                    // The swapchain must be recreated, which is the responsibility of the user.
+                    // The submissions of the previous future still have to be waited upon.
+                    Err(FlushError::OutOfDate) | Err(FlushError::SurfaceLost) |
+                    Err(FlushError::FullScreenExclusiveLost) => (),
+                    // TODO: handle errors?
+                    Err(err) => panic!("{:?}", err),
+                }
                 // Block until the queue finished.
                 self.queue().unwrap().wait().unwrap();
                 self.previous.signal_finished();
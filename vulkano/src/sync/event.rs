@@ -10,6 +10,7 @@
 use std::mem;
 use std::ptr;
 use std::sync::Arc;
+use std::sync::Mutex;
 
 use device::Device;
 use device::DeviceOwned;
@@ -32,6 +33,9 @@ pub struct Event {
     event: vk::Event,
     // The device.
     device: Arc<Device>,
+    // Vulkan requires host accesses to an event to be externally synchronized. Locked when
+    // setting or resetting the event from the host, so that this can be done through an `Arc`.
+    host_access: Mutex<()>,
 }
 
 impl Event {
@@ -57,6 +61,7 @@ impl Event {
         Ok(Event {
             device: device.clone(),
             event: event,
+            host_access: Mutex::new(()),
         })
     }
     
@@ -88,7 +93,8 @@ impl Event {
 
     /// See the docs of set().
     #[inline]
-    pub fn set_raw(&mut self) -> Result<(), OomError> {
+    pub fn set_raw(&self) -> Result<(), OomError> {
+        let _lock = self.host_access.lock().unwrap();
         unsafe {
             let vk = self.device.pointers();
             try!(check_errors(vk.SetEvent(self.device.internal_object(), self.event)));
@@ -98,20 +104,23 @@ impl Event {
 
     /// Changes the `Event` to the signaled state.
     ///
-    /// If a command buffer is waiting on this event, it is then unblocked.
+    /// If a command buffer is waiting on this event, it is then unblocked. This can be done while
+    /// the event is shared with a command buffer, for example one created with
+    /// `GpuFuture::then_wait_host_event()`.
     ///
     /// # Panic
     ///
     /// - Panics if the device or host ran out of memory.
     ///
     #[inline]
-    pub fn set(&mut self) {
+    pub fn set(&self) {
         self.set_raw().unwrap();
     }
 
     /// See the docs of reset().
     #[inline]
-    pub fn reset_raw(&mut self) -> Result<(), OomError> {
+    pub fn reset_raw(&self) -> Result<(), OomError> {
+        let _lock = self.host_access.lock().unwrap();
         unsafe {
             let vk = self.device.pointers();
             try!(check_errors(vk.ResetEvent(self.device.internal_object(), self.event)));
@@ -126,7 +135,7 @@ impl Event {
     /// - Panics if the device or host ran out of memory.
     ///
     #[inline]
-    pub fn reset(&mut self) {
+    pub fn reset(&self) {
         self.reset_raw().unwrap();
     }
 }
@@ -179,6 +188,18 @@ mod tests {
         assert!(event.signaled().unwrap());
     }
 
+    #[test]
+    fn event_set_shared() {
+        let (device, _) = gfx_dev_and_queue!();
+        let event = Event::new(&device);
+        let other = event.clone();
+
+        other.set();
+        assert!(event.signaled().unwrap());
+        other.reset();
+        assert!(!event.signaled().unwrap());
+    }
+
     #[test]
     fn event_reset() {
         let (device, _) = gfx_dev_and_queue!();
@@ -10,11 +10,14 @@
 use std::error;
 use std::fmt;
 use std::mem;
+use std::os::raw::c_int;
 use std::ptr;
 use std::sync::Arc;
+use std::sync::Mutex;
 use std::sync::atomic::AtomicBool;
 use std::sync::atomic::Ordering;
 use std::time::Duration;
+use std::time::Instant;
 use smallvec::SmallVec;
 
 use device::Device;
@@ -128,6 +131,16 @@ impl<D> Fence<D> where D: SafeDeref<Target = Device> {
         }
     }
 
+    /// Waits until the fence is signaled, or until the deadline has passed.
+    ///
+    /// This is the same as `wait(deadline.remaining())`. If the deadline has already passed, the
+    /// status of the fence is checked without blocking and `Timeout` is returned if it isn't
+    /// signaled.
+    #[inline]
+    pub fn wait_until(&self, deadline: &Deadline) -> Result<(), FenceWaitError> {
+        self.wait(deadline.remaining())
+    }
+
     /// Waits for multiple fences at once.
     ///
     /// # Panic
@@ -214,6 +227,48 @@ impl<D> Fence<D> where D: SafeDeref<Target = Device> {
             }
         }
     }
+
+    /// Imports a fence payload from a POSIX file descriptor exported by another API or process.
+    ///
+    /// If `temporary` is true, the imported payload only replaces the fence's payload until the
+    /// next time the fence is reset. This is required for `SyncFd` handles.
+    ///
+    /// On success, the ownership of `fd` is transferred to the Vulkan implementation.
+    ///
+    /// # Safety
+    ///
+    /// - `fd` must be a valid file descriptor of the given handle type.
+    /// - The fence must not be in use by any pending queue operation.
+    ///
+    // This function takes a `&mut self` because the Vulkan API requires that the fence be
+    // externally synchronized.
+    pub unsafe fn import_fd(&mut self, handle_type: ExternalFenceHandleType, fd: c_int,
+                            temporary: bool)
+                            -> Result<(), FenceImportError>
+    {
+        if !self.device.loaded_extensions().khr_external_fence_fd {
+            return Err(FenceImportError::MissingExtension);
+        }
+
+        if handle_type == ExternalFenceHandleType::SyncFd && !temporary {
+            return Err(FenceImportError::PermanentSyncFd);
+        }
+
+        let infos = vk::ImportFenceFdInfoKHR {
+            sType: vk::STRUCTURE_TYPE_IMPORT_FENCE_FD_INFO_KHR,
+            pNext: ptr::null(),
+            fence: self.fence,
+            flags: if temporary { vk::FENCE_IMPORT_TEMPORARY_BIT_KHR } else { 0 },
+            handleType: handle_type.into(),
+            fd: fd,
+        };
+
+        let vk = self.device.pointers();
+        try!(check_errors(vk.ImportFenceFdKHR(self.device.internal_object(), &infos)));
+        // The status of the new payload is unknown.
+        self.signaled.store(false, Ordering::Relaxed);
+        Ok(())
+    }
 }
 
 unsafe impl DeviceOwned for Fence {
@@ -242,6 +297,134 @@ impl<D> Drop for Fence<D> where D: SafeDeref<Target = Device> {
     }
 }
 
+/// Pool of fences that can be reused instead of creating a new fence for each submission.
+///
+/// Cloning a `FencePool` is cheap and returns a handle to the same pool, which makes it possible
+/// to share it between threads. All the fences of a pool belong to the same device.
+///
+/// Fences can be retrieved from the pool with `acquire()` and given back with `release()`. The
+/// `GpuFuture::then_signal_fence_from_pool()` method does this automatically.
+#[derive(Debug, Clone)]
+pub struct FencePool {
+    device: Arc<Device>,
+    fences: Arc<Mutex<Vec<Fence>>>,
+}
+
+impl FencePool {
+    /// Builds a new empty pool for the given device.
+    #[inline]
+    pub fn new(device: Arc<Device>) -> FencePool {
+        FencePool {
+            device: device,
+            fences: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Returns a fence of the pool, or creates a new one if the pool is empty.
+    ///
+    /// The returned fence is always in the unsignaled state.
+    pub fn acquire(&self) -> Result<Fence, OomError> {
+        let fence = self.fences.lock().unwrap().pop();
+        match fence {
+            Some(mut fence) => {
+                fence.reset();
+                Ok(fence)
+            },
+            None => Fence::new(self.device.clone()),
+        }
+    }
+
+    /// Gives back a fence to the pool.
+    ///
+    /// The fence is reset the next time it is acquired. It must not be in use by a submission
+    /// that hasn't finished.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if the fence doesn't belong to the device of the pool.
+    ///
+    pub fn release(&self, fence: Fence) {
+        assert_eq!(fence.device().internal_object(), self.device.internal_object());
+        self.fences.lock().unwrap().push(fence);
+    }
+
+    /// Returns the number of fences that are available in the pool.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.fences.lock().unwrap().len()
+    }
+}
+
+unsafe impl DeviceOwned for FencePool {
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+}
+
+/// Point in time shared by multiple waits.
+///
+/// When multiple fences have to be waited upon in a sequence, and the whole sequence must not
+/// take longer than a certain amount of time, you can create a single `Deadline` and pass its
+/// `remaining()` time to each call to `Fence::wait` (or use `Fence::wait_until`). Each wait
+/// then consumes a part of the same budget, and the waits that happen after the deadline has
+/// passed return `FenceWaitError::Timeout` immediately unless the fence is already signaled.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use vulkano::sync::Deadline;
+/// use vulkano::sync::Fence;
+///
+/// # let fences: Vec<Fence> = return;
+/// let deadline = Deadline::after(Duration::from_millis(16));
+/// for fence in fences.iter() {
+///     fence.wait_until(&deadline).unwrap();
+/// }
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Deadline {
+    instant: Instant,
+}
+
+impl Deadline {
+    /// Builds a deadline that expires after the given duration, starting now.
+    #[inline]
+    pub fn after(duration: Duration) -> Deadline {
+        Deadline { instant: Instant::now() + duration }
+    }
+
+    /// Builds a deadline that expires at the given point in time.
+    #[inline]
+    pub fn at(instant: Instant) -> Deadline {
+        Deadline { instant: instant }
+    }
+
+    /// Returns the point in time when the deadline expires.
+    #[inline]
+    pub fn instant(&self) -> Instant {
+        self.instant
+    }
+
+    /// Returns the time left before the deadline expires, or zero if it has already expired.
+    #[inline]
+    pub fn remaining(&self) -> Duration {
+        let now = Instant::now();
+        if now >= self.instant {
+            Duration::new(0, 0)
+        } else {
+            self.instant - now
+        }
+    }
+
+    /// Returns true if the deadline has expired.
+    #[inline]
+    pub fn is_expired(&self) -> bool {
+        Instant::now() >= self.instant
+    }
+}
+
 /// Error that can be returned when waiting on a fence.
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum FenceWaitError {
@@ -293,10 +476,103 @@ impl From<Error> for FenceWaitError {
     }
 }
 
+/// Type of a handle that can be imported into a fence.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ExternalFenceHandleType {
+    /// An opaque file descriptor, exported by Vulkan or a compatible API.
+    OpaqueFd,
+    /// A Linux sync file descriptor, for example one exported by a media driver.
+    SyncFd,
+}
+
+impl Into<vk::ExternalFenceHandleTypeFlagBitsKHR> for ExternalFenceHandleType {
+    #[inline]
+    fn into(self) -> vk::ExternalFenceHandleTypeFlagBitsKHR {
+        match self {
+            ExternalFenceHandleType::OpaqueFd => vk::EXTERNAL_FENCE_HANDLE_TYPE_OPAQUE_FD_BIT_KHR,
+            ExternalFenceHandleType::SyncFd => vk::EXTERNAL_FENCE_HANDLE_TYPE_SYNC_FD_BIT_KHR,
+        }
+    }
+}
+
+/// Error that can happen when importing a fence payload.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FenceImportError {
+    /// Not enough memory.
+    OomError(OomError),
+
+    /// The handle is not valid for the given handle type.
+    InvalidExternalHandle,
+
+    /// The `khr_external_fence_fd` extension was not enabled on the device.
+    MissingExtension,
+
+    /// Sync file descriptors can only be imported temporarily.
+    PermanentSyncFd,
+}
+
+impl error::Error for FenceImportError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            FenceImportError::OomError(_) => "not enough memory",
+            FenceImportError::InvalidExternalHandle => {
+                "the handle is not valid for the given handle type"
+            },
+            FenceImportError::MissingExtension => {
+                "the `khr_external_fence_fd` extension was not enabled"
+            },
+            FenceImportError::PermanentSyncFd => {
+                "sync file descriptors can only be imported temporarily"
+            },
+        }
+    }
+
+    #[inline]
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            FenceImportError::OomError(ref err) => Some(err),
+            _ => None
+        }
+    }
+}
+
+impl fmt::Display for FenceImportError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+impl From<OomError> for FenceImportError {
+    #[inline]
+    fn from(err: OomError) -> FenceImportError {
+        FenceImportError::OomError(err)
+    }
+}
+
+impl From<Error> for FenceImportError {
+    #[inline]
+    fn from(err: Error) -> FenceImportError {
+        match err {
+            e @ Error::OutOfHostMemory | e @ Error::OutOfDeviceMemory => {
+                FenceImportError::OomError(e.into())
+            },
+            Error::InvalidExternalHandle => FenceImportError::InvalidExternalHandle,
+            _ => panic!("unexpected error: {:?}", err)
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::time::Duration;
+    use std::time::Instant;
+    use sync::Deadline;
     use sync::Fence;
+    use sync::FencePool;
+    use sync::FenceWaitError;
+    use VulkanObject;
 
     #[test]
     fn fence_create() {
@@ -314,6 +590,22 @@ mod tests {
         assert!(fence.ready().unwrap());
     }
 
+    #[test]
+    fn fence_pool_reuse() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let pool = FencePool::new(device.clone());
+        let fence = Fence::signaled(device.clone()).unwrap();
+        let raw = fence.internal_object();
+        pool.release(fence);
+        assert_eq!(pool.len(), 1);
+
+        let fence = pool.clone().acquire().unwrap();
+        assert_eq!(fence.internal_object(), raw);
+        assert!(!fence.ready().unwrap());
+        assert_eq!(pool.len(), 0);
+    }
+
     #[test]
     fn fence_signaled_wait() {
         let (device, _) = gfx_dev_and_queue!();
@@ -322,6 +614,21 @@ mod tests {
         fence.wait(Duration::new(0, 10)).unwrap();
     }
 
+    #[test]
+    fn fence_wait_expired_deadline() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let deadline = Deadline::at(Instant::now());
+        assert!(deadline.is_expired());
+        assert_eq!(deadline.remaining(), Duration::new(0, 0));
+
+        let signaled = Fence::signaled(device.clone()).unwrap();
+        signaled.wait_until(&deadline).unwrap();
+
+        let unsignaled = Fence::new(device.clone()).unwrap();
+        assert_eq!(unsignaled.wait_until(&deadline), Err(FenceWaitError::Timeout));
+    }
+
     #[test]
     fn fence_reset() {
         let (device, _) = gfx_dev_and_queue!();
@@ -0,0 +1,176 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::sync::Arc;
+
+use buffer::BufferAccess;
+use command_buffer::submit::SubmitAnyBuilder;
+use device::Device;
+use device::DeviceOwned;
+use device::Queue;
+use image::ImageAccess;
+use instance::QueueFamily;
+use swapchain::Swapchain;
+use sync::AccessFlagBits;
+use sync::FlushError;
+use sync::GpuFuture;
+use sync::PipelineStages;
+
+/// Builds a new access mask future.
+#[inline]
+pub fn with_access_mask<F>(future: F, mask: AccessFlagBits) -> AccessMaskFuture<F>
+    where F: GpuFuture
+{
+    AccessMaskFuture {
+        previous: future,
+        mask: mask,
+    }
+}
+
+/// Removes some access flags from the accesses that a previous future reports.
+///
+/// Created with `GpuFuture::with_access_mask()`.
+#[must_use]
+pub struct AccessMaskFuture<F> where F: GpuFuture {
+    previous: F,
+    mask: AccessFlagBits,
+}
+
+impl<F> AccessMaskFuture<F> where F: GpuFuture {
+    /// Returns the access flags that are kept.
+    #[inline]
+    pub fn mask(&self) -> AccessFlagBits {
+        self.mask
+    }
+
+    #[inline]
+    fn apply(&self, result: Result<Option<(PipelineStages, AccessFlagBits)>, ()>)
+             -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        result.map(|scope| scope.map(|(stages, access)| (stages, access & self.mask)))
+    }
+}
+
+unsafe impl<F> GpuFuture for AccessMaskFuture<F> where F: GpuFuture {
+    #[inline]
+    fn cleanup_finished(&mut self) {
+        self.previous.cleanup_finished();
+    }
+
+    #[inline]
+    unsafe fn build_submission(&self) -> Result<SubmitAnyBuilder, FlushError> {
+        self.previous.build_submission()
+    }
+
+    #[inline]
+    fn flush(&self) -> Result<(), FlushError> {
+        self.previous.flush()
+    }
+
+    #[inline]
+    unsafe fn signal_finished(&self) {
+        self.previous.signal_finished();
+    }
+
+    #[inline]
+    fn queue_change_allowed(&self) -> bool {
+        self.previous.queue_change_allowed()
+    }
+
+    #[inline]
+    fn queue(&self) -> Option<&Arc<Queue>> {
+        self.previous.queue()
+    }
+
+    #[inline]
+    fn queue_family(&self) -> Option<QueueFamily> {
+        self.previous.queue_family()
+    }
+
+    #[inline]
+    fn check_buffer_access(&self, buffer: &BufferAccess, exclusive: bool, queue: &Queue)
+                           -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        self.apply(self.previous.check_buffer_access(buffer, exclusive, queue))
+    }
+
+    #[inline]
+    fn check_image_access(&self, image: &ImageAccess, exclusive: bool, queue: &Queue)
+                          -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        self.apply(self.previous.check_image_access(image, exclusive, queue))
+    }
+
+    #[inline]
+    fn acquired_swapchain_image(&self, swapchain: &Swapchain) -> Option<usize> {
+        self.previous.acquired_swapchain_image(swapchain)
+    }
+
+    #[inline]
+    fn validate_resources(&self) {
+        self.previous.validate_resources();
+    }
+
+    #[inline]
+    fn wait_semaphore_count(&self) -> usize {
+        self.previous.wait_semaphore_count()
+    }
+
+    #[inline]
+    fn signals_fence(&self) -> bool {
+        self.previous.signals_fence()
+    }
+
+    #[inline]
+    fn is_flushed(&self) -> bool {
+        self.previous.is_flushed()
+    }
+
+    #[inline]
+    fn shared_identities(&self, ids: &mut Vec<usize>) {
+        self.previous.shared_identities(ids);
+    }
+}
+
+unsafe impl<F> DeviceOwned for AccessMaskFuture<F> where F: GpuFuture {
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        self.previous.device()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use buffer::BufferUsage;
+    use buffer::CpuAccessibleBuffer;
+    use command_buffer::CommandBuffer;
+    use sync::AccessFlagBits;
+    use sync::GpuFuture;
+    use sync::PipelineStages;
+
+    #[test]
+    fn masks_access() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let buffer = CpuAccessibleBuffer::from_data(&device, &BufferUsage::all(),
+                                                    Some(queue.family()), 0u32).unwrap();
+
+        let reads = AccessFlagBits { shader_read: true, uniform_read: true,
+                                     .. AccessFlagBits::none() };
+        let stages = PipelineStages { vertex_shader: true, .. PipelineStages::none() };
+        let cb = empty_command_buffer!(device, queue);
+        let future = cb.execute(queue.clone())
+            .with_addressed_buffer(buffer.clone(), false, stages, reads)
+            .with_access_mask(AccessFlagBits { uniform_read: true, .. AccessFlagBits::none() });
+
+        let (_, access) = future.check_buffer_access(&buffer, false, &queue).unwrap().unwrap();
+        assert!(access.uniform_read);
+        assert!(!access.shader_read);
+    }
+}
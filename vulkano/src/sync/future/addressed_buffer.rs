@@ -0,0 +1,231 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::sync::Arc;
+
+use buffer::BufferAccess;
+use command_buffer::submit::SubmitAnyBuilder;
+use device::Device;
+use device::DeviceOwned;
+use device::Queue;
+use image::ImageAccess;
+use swapchain::Swapchain;
+use sync::AccessFlagBits;
+use sync::FlushError;
+use sync::GpuFuture;
+use sync::PipelineStages;
+
+use VulkanObject;
+use instance::QueueFamily;
+
+/// Builds a new addressed buffer future.
+///
+/// # Panic
+///
+/// - Panics if the future isn't bound to a queue.
+/// - Panics if the buffer and the future don't belong to the same device.
+/// - Panics if the buffer is already in use by a submission that the future doesn't know about.
+///
+pub fn with_addressed_buffer<F, B>(future: F, buffer: B, exclusive: bool, stages: PipelineStages,
+                                   access: AccessFlagBits) -> AddressedBufferFuture<F, B>
+    where F: GpuFuture, B: BufferAccess
+{
+    assert_eq!(buffer.inner().buffer.device().internal_object(),
+               future.device().internal_object());
+
+    {
+        let queue = future.queue().unwrap();
+        if future.check_buffer_access(&buffer, exclusive, queue).is_ok() {
+            unsafe { buffer.increase_gpu_lock(); }
+        } else {
+            assert!(buffer.try_gpu_lock(exclusive, queue), "The buffer is already in use");
+        }
+    }
+
+    AddressedBufferFuture {
+        previous: future,
+        buffer: buffer,
+        exclusive: exclusive,
+        stages: stages,
+        access: access,
+    }
+}
+
+/// Future that declares that the operations of the previous future access a buffer, even
+/// though the buffer isn't bound to them through a descriptor set.
+///
+/// Created with `GpuFuture::with_addressed_buffer()`.
+#[must_use]
+pub struct AddressedBufferFuture<F, B> where F: GpuFuture {
+    previous: F,
+    buffer: B,
+    exclusive: bool,
+    stages: PipelineStages,
+    access: AccessFlagBits,
+}
+
+impl<F, B> AddressedBufferFuture<F, B> where F: GpuFuture, B: BufferAccess {
+    /// Returns the buffer that the previous future accesses.
+    #[inline]
+    pub fn buffer(&self) -> &B {
+        &self.buffer
+    }
+}
+
+unsafe impl<F, B> GpuFuture for AddressedBufferFuture<F, B> where F: GpuFuture, B: BufferAccess {
+    #[inline]
+    fn cleanup_finished(&mut self) {
+        self.previous.cleanup_finished();
+    }
+
+    #[inline]
+    unsafe fn build_submission(&self) -> Result<SubmitAnyBuilder, FlushError> {
+        self.previous.build_submission()
+    }
+
+    #[inline]
+    fn flush(&self) -> Result<(), FlushError> {
+        self.previous.flush()
+    }
+
+    #[inline]
+    unsafe fn signal_finished(&self) {
+        self.previous.signal_finished();
+    }
+
+    #[inline]
+    fn queue_change_allowed(&self) -> bool {
+        self.previous.queue_change_allowed()
+    }
+
+    #[inline]
+    fn queue(&self) -> Option<&Arc<Queue>> {
+        self.previous.queue()
+    }
+
+    #[inline]
+    fn queue_family(&self) -> Option<QueueFamily> {
+        self.previous.queue_family()
+    }
+
+    #[inline]
+    fn check_buffer_access(&self, buffer: &BufferAccess, exclusive: bool, queue: &Queue)
+                           -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        let previous = self.previous.check_buffer_access(buffer, exclusive, queue);
+
+        if !self.buffer.conflicts_buffer(0, self.buffer.size(), buffer, 0, buffer.size()) {
+            return previous;
+        }
+
+        // The buffer was only registered for shared access.
+        if exclusive && !self.exclusive {
+            return Err(());
+        }
+
+        match previous {
+            Ok(Some((stages, access))) => Ok(Some((stages | self.stages, access | self.access))),
+            _ => Ok(Some((self.stages, self.access))),
+        }
+    }
+
+    #[inline]
+    fn check_image_access(&self, image: &ImageAccess, exclusive: bool, queue: &Queue)
+                          -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        self.previous.check_image_access(image, exclusive, queue)
+    }
+
+    #[inline]
+    fn acquired_swapchain_image(&self, swapchain: &Swapchain) -> Option<usize> {
+        self.previous.acquired_swapchain_image(swapchain)
+    }
+
+    #[inline]
+    fn validate_resources(&self) {
+        self.previous.validate_resources();
+    }
+
+    #[inline]
+    fn wait_semaphore_count(&self) -> usize {
+        self.previous.wait_semaphore_count()
+    }
+
+    #[inline]
+    fn signals_fence(&self) -> bool {
+        self.previous.signals_fence()
+    }
+
+    #[inline]
+    fn is_flushed(&self) -> bool {
+        self.previous.is_flushed()
+    }
+
+    #[inline]
+    fn shared_identities(&self, ids: &mut Vec<usize>) {
+        self.previous.shared_identities(ids);
+    }
+}
+
+unsafe impl<F, B> DeviceOwned for AddressedBufferFuture<F, B> where F: GpuFuture {
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        self.previous.device()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use buffer::Buffer;
+    use buffer::BufferUsage;
+    use buffer::DeviceLocalBuffer;
+    use command_buffer::CommandBuffer;
+    use sync::AccessFlagBits;
+    use sync::GpuFuture;
+    use sync::PipelineStages;
+
+    #[test]
+    fn reports_access() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let buffer = DeviceLocalBuffer::<[u32]>::array(&device, 4, &BufferUsage::all(),
+                                                       Some(queue.family())).unwrap();
+
+        let cb = empty_command_buffer!(device, queue);
+
+        let stages = PipelineStages { compute_shader: true, .. PipelineStages::none() };
+        let access = AccessFlagBits { shader_write: true, .. AccessFlagBits::none() };
+        let future = cb.execute(queue.clone())
+                       .with_addressed_buffer(buffer.clone().access(), true, stages, access);
+
+        let (stages, access) = future.check_buffer_access(&buffer.access(), true, &queue)
+                                     .unwrap().unwrap();
+        assert!(stages.compute_shader);
+        assert!(access.shader_write);
+    }
+
+    #[test]
+    fn shared_denies_exclusive() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let buffer = DeviceLocalBuffer::<[u32]>::array(&device, 4, &BufferUsage::all(),
+                                                       Some(queue.family())).unwrap();
+
+        let cb = empty_command_buffer!(device, queue);
+
+        let stages = PipelineStages { compute_shader: true, .. PipelineStages::none() };
+        let access = AccessFlagBits { shader_read: true, .. AccessFlagBits::none() };
+        let future = cb.execute(queue.clone())
+                       .with_addressed_buffer(buffer.clone().access(), false, stages, access);
+
+        assert!(future.check_buffer_access(&buffer.clone().access(), false, &queue)
+                      .unwrap().is_some());
+        assert!(future.check_buffer_access(&buffer.access(), true, &queue).is_err());
+    }
+}
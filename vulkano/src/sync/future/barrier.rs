@@ -0,0 +1,372 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::error::Error;
+use std::ops::Range;
+use std::sync::Arc;
+
+use buffer::BufferAccess;
+use command_buffer::CommandBuffer;
+use command_buffer::CommandBufferExecFuture;
+use command_buffer::commands_raw::CmdPipelineBarrier;
+use command_buffer::submit::SubmitAnyBuilder;
+use device::Device;
+use device::DeviceOwned;
+use device::Queue;
+use image::ImageAccess;
+use image::Layout;
+use swapchain::Swapchain;
+use sync::AccessFlagBits;
+use sync::FlushError;
+use sync::GpuFuture;
+use sync::PipelineStages;
+use sync::future::one_shot::OneShotBuilder;
+use sync::future::one_shot::OneShotCommandBuffer;
+use sync::future::one_shot::OneShotResources;
+use OomError;
+use VulkanObject;
+use instance::QueueFamily;
+use vk;
+
+/// Description of a pipeline barrier to insert between two futures.
+///
+/// The barrier makes the accesses `source_access` performed by the stages `source_stages` before
+/// the barrier available to the accesses `dest_access` performed by the stages `dest_stages`
+/// after it. This applies to all the resources. In addition, the layouts of some images can be
+/// transitioned with `image_transition()`.
+///
+/// Passed to `GpuFuture::then_barrier()`.
+#[derive(Clone)]
+pub struct PipelineBarrierBuilder {
+    source_stages: PipelineStages,
+    source_access: AccessFlagBits,
+    dest_stages: PipelineStages,
+    dest_access: AccessFlagBits,
+    transitions: Vec<ImageTransition>,
+}
+
+// Layout transition of a subresource range of an image.
+#[derive(Clone)]
+struct ImageTransition {
+    image: Arc<ImageAccess + Send + Sync>,
+    mipmaps: Range<u32>,
+    layers: Range<u32>,
+    old_layout: Layout,
+    new_layout: Layout,
+}
+
+impl PipelineBarrierBuilder {
+    /// Builds a barrier between the given source and destination scopes, without any layout
+    /// transition.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if `source_stages` or `dest_stages` is empty.
+    ///
+    pub fn new(source_stages: PipelineStages, source_access: AccessFlagBits,
+               dest_stages: PipelineStages, dest_access: AccessFlagBits)
+               -> PipelineBarrierBuilder
+    {
+        let raw_source: vk::PipelineStageFlagBits = source_stages.into();
+        let raw_dest: vk::PipelineStageFlagBits = dest_stages.into();
+        assert!(raw_source != 0 && raw_dest != 0, "The stages of a pipeline barrier can't be \
+                                                   empty");
+
+        PipelineBarrierBuilder {
+            source_stages: source_stages,
+            source_access: source_access,
+            dest_stages: dest_stages,
+            dest_access: dest_access,
+            transitions: Vec::new(),
+        }
+    }
+
+    /// Adds a transition of the mipmap levels `mipmaps` and the array layers `layers` of `image`
+    /// from `old_layout` to `new_layout`.
+    ///
+    /// The image is locked for exclusive access when the barrier is submitted.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if `mipmaps` or `layers` is empty.
+    ///
+    pub fn image_transition<I>(mut self, image: I, mipmaps: Range<u32>, layers: Range<u32>,
+                               old_layout: Layout, new_layout: Layout) -> PipelineBarrierBuilder
+        where I: ImageAccess + Send + Sync + 'static
+    {
+        assert!(mipmaps.start < mipmaps.end);
+        assert!(layers.start < layers.end);
+
+        self.transitions.push(ImageTransition {
+            image: Arc::new(image),
+            mipmaps: mipmaps,
+            layers: layers,
+            old_layout: old_layout,
+            new_layout: new_layout,
+        });
+        self
+    }
+
+    /// Returns the stages and accesses of the destination scope of the barrier.
+    #[inline]
+    pub fn dest_scope(&self) -> (PipelineStages, AccessFlagBits) {
+        (self.dest_stages, self.dest_access)
+    }
+
+    /// Returns the number of image layout transitions.
+    #[inline]
+    pub fn num_image_transitions(&self) -> usize {
+        self.transitions.len()
+    }
+
+    // Builds the command that performs the barrier.
+    //
+    // A barrier without any access and without any transition is only an execution dependency,
+    // as built by `GpuFuture::then_execution_barrier()`.
+    unsafe fn command(&self) -> CmdPipelineBarrier {
+        let mut cmd = CmdPipelineBarrier::new();
+
+        let raw_source_access: vk::AccessFlagBits = self.source_access.into();
+        let raw_dest_access: vk::AccessFlagBits = self.dest_access.into();
+        if raw_source_access == 0 && raw_dest_access == 0 && self.transitions.is_empty() {
+            cmd.add_execution_dependency(self.source_stages, self.dest_stages, false);
+            return cmd;
+        }
+
+        cmd.add_memory_barrier(self.source_stages, self.source_access, self.dest_stages,
+                               self.dest_access, false);
+        for transition in self.transitions.iter() {
+            cmd.add_image_memory_barrier(&transition.image, transition.mipmaps.clone(),
+                                         transition.layers.clone(), self.source_stages,
+                                         self.source_access, self.dest_stages,
+                                         self.dest_access, false, None,
+                                         transition.old_layout, transition.new_layout);
+        }
+        cmd
+    }
+}
+
+// Starts building a command buffer that contains `barrier`, for the queue family of `queue`.
+#[inline]
+pub unsafe fn command_buffer_builder(queue: &Queue, barrier: &PipelineBarrierBuilder)
+                                     -> Result<OneShotBuilder, OomError>
+{
+    Ok(try!(OneShotBuilder::new(queue)).add(&barrier.command()))
+}
+
+/// Builds a new barrier future.
+///
+/// # Panic
+///
+/// - Panics if the future isn't bound to a queue.
+/// - Panics if one of the transitioned images is already in use by another future.
+///
+/// # Safety
+///
+/// See the documentation of `GpuFuture::then_barrier()`.
+///
+pub unsafe fn then_barrier<F>(future: F, barrier: PipelineBarrierBuilder)
+                              -> Result<BarrierFuture<F>, OomError>
+    where F: GpuFuture
+{
+    let queue = future.queue().expect("The future isn't bound to a queue").clone();
+
+    let (dest_stages, dest_access) = barrier.dest_scope();
+    let builder = try!(command_buffer_builder(&queue, &barrier));
+    let command_buffer = try!(builder.build(BarrierResources { barrier: barrier }));
+
+    Ok(BarrierFuture {
+        inner: command_buffer.execute_after(future, queue),
+        dest_stages: dest_stages,
+        dest_access: dest_access,
+    })
+}
+
+/// Represents a pipeline barrier executed by the GPU after a previous future.
+///
+/// Created with `GpuFuture::then_barrier()`. Any access to a resource that the previous future
+/// reports is reported by this future as an access by the destination scope of the barrier.
+#[must_use = "Dropping this object will immediately block the thread until the GPU has finished processing the submission"]
+pub struct BarrierFuture<F> where F: GpuFuture {
+    inner: CommandBufferExecFuture<F, OneShotCommandBuffer<BarrierResources>>,
+    // Stages and accesses of the destination scope of the barrier.
+    dest_stages: PipelineStages,
+    dest_access: AccessFlagBits,
+}
+
+impl<F> BarrierFuture<F> where F: GpuFuture {
+    /// Returns the stages and accesses of the destination scope of the barrier.
+    #[inline]
+    pub fn dest_scope(&self) -> (PipelineStages, AccessFlagBits) {
+        (self.dest_stages, self.dest_access)
+    }
+
+    #[inline]
+    fn apply(&self, result: Result<Option<(PipelineStages, AccessFlagBits)>, ()>)
+             -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        let scope = self.dest_scope();
+        result.map(|access| access.map(|_| scope))
+    }
+}
+
+unsafe impl<F> GpuFuture for BarrierFuture<F> where F: GpuFuture {
+    #[inline]
+    fn cleanup_finished(&mut self) {
+        self.inner.cleanup_finished();
+    }
+
+    #[inline]
+    unsafe fn build_submission(&self) -> Result<SubmitAnyBuilder, FlushError> {
+        self.inner.build_submission()
+    }
+
+    #[inline]
+    fn flush(&self) -> Result<(), FlushError> {
+        self.inner.flush()
+    }
+
+    #[inline]
+    unsafe fn signal_finished(&self) {
+        self.inner.signal_finished();
+    }
+
+    #[inline]
+    fn queue_change_allowed(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    fn queue(&self) -> Option<&Arc<Queue>> {
+        self.inner.queue()
+    }
+
+    #[inline]
+    fn queue_family(&self) -> Option<QueueFamily> {
+        self.inner.queue_family()
+    }
+
+    #[inline]
+    fn check_buffer_access(&self, buffer: &BufferAccess, exclusive: bool, queue: &Queue)
+                           -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        self.apply(self.inner.check_buffer_access(buffer, exclusive, queue))
+    }
+
+    #[inline]
+    fn check_image_access(&self, image: &ImageAccess, exclusive: bool, queue: &Queue)
+                          -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        self.apply(self.inner.check_image_access(image, exclusive, queue))
+    }
+
+    #[inline]
+    fn acquired_swapchain_image(&self, swapchain: &Swapchain) -> Option<usize> {
+        self.inner.acquired_swapchain_image(swapchain)
+    }
+
+    #[inline]
+    fn validate_resources(&self) {
+        self.inner.validate_resources();
+    }
+
+    #[inline]
+    fn wait_semaphore_count(&self) -> usize {
+        self.inner.wait_semaphore_count()
+    }
+
+    #[inline]
+    fn is_flushed(&self) -> bool {
+        self.inner.is_flushed()
+    }
+
+    #[inline]
+    fn shared_identities(&self, ids: &mut Vec<usize>) {
+        self.inner.shared_identities(ids);
+    }
+}
+
+unsafe impl<F> DeviceOwned for BarrierFuture<F> where F: GpuFuture {
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        self.inner.device()
+    }
+}
+
+// Resources of the command buffer that only contains the barrier.
+struct BarrierResources {
+    barrier: PipelineBarrierBuilder,
+}
+
+unsafe impl OneShotResources for BarrierResources {
+    fn submit_check(&self, future: &GpuFuture, queue: &Queue) -> Result<(), Box<Error>> {
+        for transition in self.barrier.transitions.iter() {
+            if future.check_image_access(&transition.image, true, queue).is_ok() {
+                unsafe { transition.image.increase_gpu_lock(); }
+            } else {
+                assert!(transition.image.try_gpu_lock(true, queue), "An image transitioned by a \
+                                                                     barrier is already in use");
+            }
+        }
+
+        Ok(())
+    }
+
+    #[inline]
+    fn check_image_access(&self, image: &ImageAccess, _: bool, _: &Queue)
+                          -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        let raw = image.inner().internal_object();
+        if self.barrier.transitions.iter().any(|t| t.image.inner().internal_object() == raw) {
+            Ok(Some(self.barrier.dest_scope()))
+        } else {
+            Err(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use buffer::BufferUsage;
+    use buffer::CpuAccessibleBuffer;
+    use command_buffer::CommandBuffer;
+    use sync::AccessFlagBits;
+    use sync::GpuFuture;
+    use sync::PipelineBarrierBuilder;
+    use sync::PipelineStages;
+
+    #[test]
+    fn reports_dest_scope() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let buffer = CpuAccessibleBuffer::from_data(&device, &BufferUsage::all(),
+                                                    Some(queue.family()), 0u32).unwrap();
+        let other = CpuAccessibleBuffer::from_data(&device, &BufferUsage::all(),
+                                                   Some(queue.family()), 0u32).unwrap();
+
+        let transfer = PipelineStages { transfer: true, .. PipelineStages::none() };
+        let compute = PipelineStages { compute_shader: true, .. PipelineStages::none() };
+        let write = AccessFlagBits { transfer_write: true, .. AccessFlagBits::none() };
+        let read = AccessFlagBits { shader_read: true, .. AccessFlagBits::none() };
+
+        let cb = empty_command_buffer!(device, queue);
+        let barrier = PipelineBarrierBuilder::new(transfer, write, compute, read);
+        let future = unsafe {
+            cb.execute(queue.clone())
+              .with_addressed_buffer(buffer.clone(), true, transfer, write)
+              .then_barrier(barrier).unwrap()
+        };
+
+        let (stages, access) = future.check_buffer_access(&buffer, true, &queue).unwrap().unwrap();
+        assert!(stages.compute_shader && !stages.transfer);
+        assert!(access.shader_read && !access.transfer_write);
+        assert!(future.check_buffer_access(&other, false, &queue).is_err());
+        future.then_signal_fence_and_flush().unwrap();
+    }
+}
@@ -0,0 +1,257 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::error;
+use std::error::Error;
+use std::fmt;
+
+use command_buffer::commands_raw::CmdBlitImage;
+use command_buffer::commands_raw::CmdBlitImageError;
+use command_buffer::commands_raw::CmdPipelineBarrier;
+use device::Queue;
+use image::ImageAccess;
+use image::Layout;
+use sampler::Filter;
+use sync::AccessFlagBits;
+use sync::GpuFuture;
+use sync::PipelineStages;
+use sync::future::one_shot::OneShotBuilder;
+use sync::future::one_shot::OneShotCommandBuffer;
+use sync::future::one_shot::OneShotResources;
+use OomError;
+use VulkanObject;
+
+/// Command buffer that blits a region of an image into a region of another image, surrounded
+/// by the barriers and layout transitions that the blit requires.
+///
+/// Created with `GpuFuture::then_blit()`.
+pub type BlitCommandBuffer<S, D> = OneShotCommandBuffer<BlitResources<S, D>>;
+
+/// Resources of a `BlitCommandBuffer`.
+pub struct BlitResources<S, D> {
+    // Owns the images.
+    blit: CmdBlitImage<S, D>,
+}
+
+impl<S, D> OneShotCommandBuffer<BlitResources<S, D>> where S: ImageAccess, D: ImageAccess {
+    /// Builds a command buffer that blits `source_region` of the first mipmap and layer of
+    /// `source` into `destination_region` of `destination`, for the queue family of `queue`.
+    ///
+    /// Each region is given as two opposite corners. The images are transitioned from their
+    /// default layout to the transfer layouts before the blit, and back afterwards. The first
+    /// barrier waits for the accesses of `future` to the images, or for nothing if `future`
+    /// doesn't access them. The second one makes the result of the blit available to all the
+    /// commands that follow.
+    pub fn new(future: &GpuFuture, queue: &Queue, source: S, source_region: [[i32; 3]; 2],
+               destination: D, destination_region: [[i32; 3]; 2], filter: Filter)
+               -> Result<BlitCommandBuffer<S, D>, BlitError>
+    {
+        if !queue.family().supports_graphics() {
+            return Err(BlitError::NotSupportedByQueueFamily);
+        }
+
+        let blit = try!(CmdBlitImage::new(source, source_region[0], source_region[1], 0, 0,
+                                          destination, destination_region[0],
+                                          destination_region[1], 0, 0, 1, filter));
+
+        let source_scope = match future.check_image_access(blit.source(), false, queue) {
+            Ok(Some(scope)) => scope,
+            _ => (PipelineStages { top_of_pipe: true, .. PipelineStages::none() },
+                  AccessFlagBits::none()),
+        };
+
+        let destination_scope = match future.check_image_access(blit.destination(), true,
+                                                                queue)
+        {
+            Ok(Some(scope)) => scope,
+            _ => (PipelineStages { top_of_pipe: true, .. PipelineStages::none() },
+                  AccessFlagBits::none()),
+        };
+
+        let transfer = PipelineStages { transfer: true, .. PipelineStages::none() };
+        let transfer_read = AccessFlagBits { transfer_read: true, .. AccessFlagBits::none() };
+        let transfer_write = AccessFlagBits { transfer_write: true, .. AccessFlagBits::none() };
+        let (after_stages, after_access) = after_blit_scope();
+
+        let builder = unsafe {
+            let mut barrier = CmdPipelineBarrier::new();
+            barrier.add_image_memory_barrier(blit.source(), 0 .. 1, 0 .. 1, source_scope.0,
+                                             source_scope.1, transfer, transfer_read, false,
+                                             None, blit.source().default_layout(),
+                                             Layout::TransferSrcOptimal);
+            barrier.add_image_memory_barrier(blit.destination(), 0 .. 1, 0 .. 1,
+                                             destination_scope.0, destination_scope.1, transfer,
+                                             transfer_write, false, None,
+                                             blit.destination().default_layout(),
+                                             Layout::TransferDstOptimal);
+            try!(OneShotBuilder::new(queue)).add(&barrier)
+        };
+
+        let builder = builder.add(&blit);
+
+        let builder = unsafe {
+            let mut barrier = CmdPipelineBarrier::new();
+            barrier.add_image_memory_barrier(blit.source(), 0 .. 1, 0 .. 1, transfer,
+                                             transfer_read, after_stages, after_access, false,
+                                             None, Layout::TransferSrcOptimal,
+                                             blit.source().default_layout());
+            barrier.add_image_memory_barrier(blit.destination(), 0 .. 1, 0 .. 1, transfer,
+                                             transfer_write, after_stages, after_access, false,
+                                             None, Layout::TransferDstOptimal,
+                                             blit.destination().default_layout());
+            builder.add(&barrier)
+        };
+
+        Ok(try!(builder.build(BlitResources { blit: blit })))
+    }
+
+    /// Returns the image that is read by the blit.
+    #[inline]
+    pub fn source(&self) -> &S {
+        self.resources().blit.source()
+    }
+
+    /// Returns the image that is written by the blit.
+    #[inline]
+    pub fn destination(&self) -> &D {
+        self.resources().blit.destination()
+    }
+}
+
+// Destination scope of the barrier that follows the blit. As we don't know what the following
+// commands are going to do with the images, the blit is made visible to everything.
+#[inline]
+fn after_blit_scope() -> (PipelineStages, AccessFlagBits) {
+    (PipelineStages { all_commands: true, .. PipelineStages::none() },
+     AccessFlagBits { memory_read: true, memory_write: true, .. AccessFlagBits::none() })
+}
+
+unsafe impl<S, D> OneShotResources for BlitResources<S, D>
+    where S: ImageAccess + Send + Sync, D: ImageAccess + Send + Sync
+{
+    fn submit_check(&self, future: &GpuFuture, queue: &Queue) -> Result<(), Box<Error>> {
+        let source = self.blit.source();
+        if future.check_image_access(source, false, queue).is_ok() {
+            unsafe { source.increase_gpu_lock(); }
+        } else {
+            assert!(source.try_gpu_lock(false, queue), "The source of a blit is already in use");
+        }
+
+        let destination = self.blit.destination();
+        if future.check_image_access(destination, true, queue).is_ok() {
+            unsafe { destination.increase_gpu_lock(); }
+        } else {
+            assert!(destination.try_gpu_lock(true, queue), "The destination of a blit is \
+                                                            already in use");
+        }
+
+        Ok(())
+    }
+
+    #[inline]
+    fn check_image_access(&self, image: &ImageAccess, _: bool, _: &Queue)
+                          -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        let raw = image.inner().internal_object();
+
+        if self.blit.source().inner().internal_object() == raw ||
+           self.blit.destination().inner().internal_object() == raw
+        {
+            Ok(Some(after_blit_scope()))
+        } else {
+            Err(())
+        }
+    }
+}
+
+/// Error that can happen when calling `GpuFuture::then_blit()`.
+#[derive(Copy, Clone, Debug)]
+pub enum BlitError {
+    /// Not enough memory to create the command buffer.
+    OomError(OomError),
+
+    /// The queue family doesn't support graphics operations, which blits require.
+    NotSupportedByQueueFamily,
+
+    /// The images can't be blitted.
+    BlitImageError(CmdBlitImageError),
+}
+
+impl error::Error for BlitError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            BlitError::OomError(_) => "not enough memory",
+            BlitError::NotSupportedByQueueFamily => {
+                "the queue family doesn't support graphics operations"
+            },
+            BlitError::BlitImageError(_) => "the images can't be blitted",
+        }
+    }
+
+    #[inline]
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            BlitError::OomError(ref err) => Some(err),
+            BlitError::BlitImageError(ref err) => Some(err),
+            _ => None
+        }
+    }
+}
+
+impl fmt::Display for BlitError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+impl From<OomError> for BlitError {
+    #[inline]
+    fn from(err: OomError) -> BlitError {
+        BlitError::OomError(err)
+    }
+}
+
+impl From<CmdBlitImageError> for BlitError {
+    #[inline]
+    fn from(err: CmdBlitImageError) -> BlitError {
+        BlitError::BlitImageError(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use command_buffer::CommandBuffer;
+    use command_buffer::commands_raw::CmdBlitImageError;
+    use format::R8G8B8A8Unorm;
+    use image::Dimensions;
+    use image::ImmutableImage;
+    use sampler::Filter;
+    use sync::BlitError;
+    use sync::GpuFuture;
+
+    #[test]
+    fn same_image() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let image = ImmutableImage::new(&device, Dimensions::Dim2d { width: 32, height: 32 },
+                                        R8G8B8A8Unorm, Some(queue.family())).unwrap();
+
+        let cb = empty_command_buffer!(device, queue);
+
+        let region = [[0, 0, 0], [32, 32, 1]];
+        match cb.execute(queue.clone()).then_blit(queue.clone(), image.clone(), region,
+                                                  image.clone(), region, Filter::Linear)
+        {
+            Err(BlitError::BlitImageError(CmdBlitImageError::OverlappingRanges)) => (),
+            _ => panic!(),
+        }
+    }
+}
@@ -0,0 +1,308 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::error;
+use std::fmt;
+use std::sync::Arc;
+use std::sync::Condvar;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+
+use buffer::BufferAccess;
+use command_buffer::submit::SubmitAnyBuilder;
+use device::Device;
+use device::DeviceOwned;
+use device::Queue;
+use image::ImageAccess;
+use instance::QueueFamily;
+use swapchain::Swapchain;
+use sync::AccessFlagBits;
+use sync::FlushError;
+use sync::GpuFuture;
+use sync::PipelineStages;
+
+/// Limits the total amount of memory referenced by the futures that are alive.
+///
+/// Each future created with `GpuFuture::with_budget()` reserves a number of bytes of the budget,
+/// which is given back when the future is destroyed. Since futures keep their resources alive
+/// until they are destroyed, this bounds the memory held by in-flight work, for example when
+/// streaming assets faster than the GPU can upload them.
+///
+/// The number of bytes is provided by the user and is only an estimation. Vulkano doesn't
+/// measure the memory that the futures actually reference.
+pub struct SubmissionBudget {
+    limit: usize,
+    // Number of bytes reserved by the futures that are alive.
+    used: Mutex<usize>,
+    // Notified every time bytes are given back.
+    released: Condvar,
+}
+
+impl SubmissionBudget {
+    /// Builds a new budget of `limit` bytes.
+    #[inline]
+    pub fn new(limit: usize) -> Arc<SubmissionBudget> {
+        Arc::new(SubmissionBudget {
+            limit: limit,
+            used: Mutex::new(0),
+            released: Condvar::new(),
+        })
+    }
+
+    /// Returns the number of bytes of the budget.
+    #[inline]
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Returns the number of bytes currently reserved by futures.
+    #[inline]
+    pub fn used(&self) -> usize {
+        *self.used.lock().unwrap()
+    }
+
+    // Reserves `bytes`, blocking until enough bytes are given back or until `timeout` has
+    // elapsed. Returns false on timeout.
+    fn reserve(&self, bytes: usize, timeout: Duration) -> bool {
+        let start = Instant::now();
+        let mut used = self.used.lock().unwrap();
+
+        // A reservation larger than the limit would never fit, so it is granted once nothing
+        // else is reserved.
+        while *used != 0 && *used + bytes > self.limit {
+            let elapsed = start.elapsed();
+            if elapsed >= timeout {
+                return false;
+            }
+
+            used = self.released.wait_timeout(used, timeout - elapsed).unwrap().0;
+        }
+
+        *used += bytes;
+        true
+    }
+
+    // Gives back `bytes`.
+    fn release(&self, bytes: usize) {
+        let mut used = self.used.lock().unwrap();
+        debug_assert!(*used >= bytes);
+        *used -= bytes;
+        self.released.notify_all();
+    }
+}
+
+/// Builds a new budgeted future.
+pub fn with_budget<F>(future: F, budget: &Arc<SubmissionBudget>, bytes: usize,
+                      timeout: Duration) -> Result<BudgetedFuture<F>, WouldExceedBudget<F>>
+    where F: GpuFuture
+{
+    if !budget.reserve(bytes, timeout) {
+        return Err(WouldExceedBudget(future));
+    }
+
+    Ok(BudgetedFuture {
+        previous: future,
+        reservation: Reservation {
+            budget: budget.clone(),
+            bytes: bytes,
+        },
+    })
+}
+
+/// A previous future that holds a part of a `SubmissionBudget` until it is destroyed.
+///
+/// Created with `GpuFuture::with_budget()`.
+#[must_use]
+pub struct BudgetedFuture<F> where F: GpuFuture {
+    previous: F,
+    // Declared after `previous` so that the bytes are only given back once the previous future,
+    // and therefore its resources, have been destroyed.
+    reservation: Reservation,
+}
+
+struct Reservation {
+    budget: Arc<SubmissionBudget>,
+    bytes: usize,
+}
+
+impl Drop for Reservation {
+    #[inline]
+    fn drop(&mut self) {
+        self.budget.release(self.bytes);
+    }
+}
+
+impl<F> BudgetedFuture<F> where F: GpuFuture {
+    /// Returns the budget that this future is part of.
+    #[inline]
+    pub fn budget(&self) -> &Arc<SubmissionBudget> {
+        &self.reservation.budget
+    }
+
+    /// Returns the number of bytes that this future reserved.
+    #[inline]
+    pub fn reserved_bytes(&self) -> usize {
+        self.reservation.bytes
+    }
+}
+
+unsafe impl<F> GpuFuture for BudgetedFuture<F> where F: GpuFuture {
+    #[inline]
+    fn cleanup_finished(&mut self) {
+        self.previous.cleanup_finished();
+    }
+
+    #[inline]
+    unsafe fn build_submission(&self) -> Result<SubmitAnyBuilder, FlushError> {
+        self.previous.build_submission()
+    }
+
+    #[inline]
+    fn flush(&self) -> Result<(), FlushError> {
+        self.previous.flush()
+    }
+
+    #[inline]
+    unsafe fn signal_finished(&self) {
+        self.previous.signal_finished();
+    }
+
+    #[inline]
+    fn queue_change_allowed(&self) -> bool {
+        self.previous.queue_change_allowed()
+    }
+
+    #[inline]
+    fn queue(&self) -> Option<&Arc<Queue>> {
+        self.previous.queue()
+    }
+
+    #[inline]
+    fn queue_family(&self) -> Option<QueueFamily> {
+        self.previous.queue_family()
+    }
+
+    #[inline]
+    fn check_buffer_access(&self, buffer: &BufferAccess, exclusive: bool, queue: &Queue)
+                           -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        self.previous.check_buffer_access(buffer, exclusive, queue)
+    }
+
+    #[inline]
+    fn check_image_access(&self, image: &ImageAccess, exclusive: bool, queue: &Queue)
+                          -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        self.previous.check_image_access(image, exclusive, queue)
+    }
+
+    #[inline]
+    fn acquired_swapchain_image(&self, swapchain: &Swapchain) -> Option<usize> {
+        self.previous.acquired_swapchain_image(swapchain)
+    }
+
+    #[inline]
+    fn validate_resources(&self) {
+        self.previous.validate_resources();
+    }
+
+    #[inline]
+    fn wait_semaphore_count(&self) -> usize {
+        self.previous.wait_semaphore_count()
+    }
+
+    #[inline]
+    fn signals_fence(&self) -> bool {
+        self.previous.signals_fence()
+    }
+
+    #[inline]
+    fn is_flushed(&self) -> bool {
+        self.previous.is_flushed()
+    }
+
+    #[inline]
+    fn shared_identities(&self, ids: &mut Vec<usize>) {
+        self.previous.shared_identities(ids);
+    }
+}
+
+unsafe impl<F> DeviceOwned for BudgetedFuture<F> where F: GpuFuture {
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        self.previous.device()
+    }
+}
+
+/// Error returned by `GpuFuture::with_budget()` when the bytes couldn't be reserved before the
+/// timeout. Contains the future that was passed.
+pub struct WouldExceedBudget<F>(pub F);
+
+impl<F> fmt::Debug for WouldExceedBudget<F> {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "WouldExceedBudget")
+    }
+}
+
+impl<F> error::Error for WouldExceedBudget<F> {
+    #[inline]
+    fn description(&self) -> &str {
+        "the future would exceed the submission budget"
+    }
+}
+
+impl<F> fmt::Display for WouldExceedBudget<F> {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use sync::DummyFuture;
+    use sync::GpuFuture;
+    use sync::SubmissionBudget;
+
+    #[test]
+    fn released_on_drop() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let budget = SubmissionBudget::new(1024);
+
+        let first = DummyFuture::new(device.clone())
+            .with_budget(&budget, 768, Duration::from_secs(0)).unwrap();
+        assert_eq!(budget.used(), 768);
+
+        let second = DummyFuture::new(device.clone())
+            .with_budget(&budget, 512, Duration::from_secs(0));
+        assert!(second.is_err());
+
+        drop(first);
+        assert_eq!(budget.used(), 0);
+
+        let _second = DummyFuture::new(device.clone())
+            .with_budget(&budget, 512, Duration::from_secs(0)).unwrap();
+        assert_eq!(budget.used(), 512);
+    }
+
+    #[test]
+    fn larger_than_limit() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let budget = SubmissionBudget::new(16);
+        let _future = DummyFuture::new(device.clone())
+            .with_budget(&budget, 64, Duration::from_secs(0)).unwrap();
+        assert_eq!(budget.used(), 64);
+    }
+}
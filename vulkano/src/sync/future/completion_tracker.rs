@@ -0,0 +1,168 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::sync::mpsc;
+use std::sync::mpsc::Receiver;
+use std::sync::mpsc::Sender;
+use std::thread;
+use std::time::Duration;
+
+use sync::Deadline;
+use sync::FenceSignalFuture;
+use sync::GpuFuture;
+
+/// Tracks the order in which multiple `FenceSignalFuture`s finish.
+///
+/// Submissions that are made individually can finish in a different order than the one they
+/// were submitted in, for example if they are submitted to different queues. A
+/// `CompletionTracker` owns the futures and reports the index of each of them (in the order they
+/// were added with `push()`) as soon as it is observed to be finished. This allows processing
+/// results as soon as they are available instead of in submission order.
+///
+/// Completion is detected by polling the fences. Futures that are observed to be finished during
+/// the same call to `poll()` are reported in the order they were added.
+///
+/// Dropping a `CompletionTracker` blocks until all the futures it still owns are finished.
+pub struct CompletionTracker<F> where F: GpuFuture {
+    // The futures, indexed by the value returned by `push()`. `None` once finished.
+    futures: Vec<Option<FenceSignalFuture<F>>>,
+    // Number of elements of `futures` that are `Some`.
+    num_pending: usize,
+    sender: Sender<usize>,
+    receiver: Receiver<usize>,
+    // Indices of the finished futures, in the order they have been observed to be finished.
+    order: Vec<usize>,
+    // Number of elements of `order` that have been returned by `poll()` or `wait_next()`.
+    num_reported: usize,
+}
+
+impl<F> CompletionTracker<F> where F: GpuFuture {
+    /// Builds a new empty tracker.
+    pub fn new() -> CompletionTracker<F> {
+        let (tx, rx) = mpsc::channel();
+
+        CompletionTracker {
+            futures: Vec::new(),
+            num_pending: 0,
+            sender: tx,
+            receiver: rx,
+            order: Vec::new(),
+            num_reported: 0,
+        }
+    }
+
+    /// Adds a future to track. Returns its index, which is the value that is later reported when
+    /// the future finishes.
+    ///
+    /// The future is flushed if it wasn't already.
+    pub fn push(&mut self, future: FenceSignalFuture<F>) -> usize {
+        let index = self.futures.len();
+        let future = future.notify_on_complete(self.sender.clone(), index);
+        // Errors are ignored here. They will be reported again when blocking.
+        let _ = future.flush();
+        self.futures.push(Some(future));
+        self.num_pending += 1;
+        index
+    }
+
+    /// Checks which futures have finished, without blocking. Returns the indices of the ones
+    /// that haven't been returned by `poll()` or `wait_next()` yet, and destroys them.
+    pub fn poll(&mut self) -> Vec<usize> {
+        self.update();
+        let finished = self.order[self.num_reported ..].to_owned();
+        self.num_reported = self.order.len();
+        finished
+    }
+
+    // Checks which futures have finished and appends them to `order`.
+    fn update(&mut self) {
+        for slot in self.futures.iter_mut() {
+            if let Some(ref mut future) = *slot {
+                let _ = future.flush();
+                future.cleanup_finished();
+            }
+        }
+
+        while let Ok(index) = self.receiver.try_recv() {
+            debug_assert!(self.futures[index].is_some());
+            self.futures[index] = None;
+            self.num_pending -= 1;
+            self.order.push(index);
+        }
+    }
+
+    /// Returns the index of the next finished future that hasn't been returned by `poll()` or
+    /// `wait_next()` yet. Blocks until a future finishes or until the deadline expires if there
+    /// is none.
+    ///
+    /// Returns `None` if the deadline expired or if there is nothing left to wait for.
+    pub fn wait_next(&mut self, deadline: &Deadline) -> Option<usize> {
+        loop {
+            if self.num_reported == self.order.len() {
+                self.update();
+            }
+
+            if self.num_reported < self.order.len() {
+                self.num_reported += 1;
+                return Some(self.order[self.num_reported - 1]);
+            }
+
+            if self.num_pending == 0 || deadline.is_expired() {
+                return None;
+            }
+
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    /// Returns the indices of all the futures that have been observed to be finished, in the
+    /// order they finished.
+    #[inline]
+    pub fn completion_order(&self) -> &[usize] {
+        &self.order
+    }
+
+    /// Returns the number of futures that haven't been observed to be finished yet.
+    ///
+    /// > **Note**: If the device is lost, the futures that were pending are never reported as
+    /// > finished.
+    #[inline]
+    pub fn num_pending(&self) -> usize {
+        self.num_pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use command_buffer::CommandBuffer;
+    use sync::CompletionTracker;
+    use sync::Deadline;
+    use sync::GpuFuture;
+
+    #[test]
+    fn reports_all() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let mut tracker = CompletionTracker::new();
+        for _ in 0 .. 3 {
+            let cb = empty_command_buffer!(device, queue);
+            tracker.push(cb.execute(queue.clone()).then_signal_fence());
+        }
+
+        let deadline = Deadline::after(Duration::from_secs(5));
+        while tracker.num_pending() != 0 {
+            tracker.wait_next(&deadline).unwrap();
+        }
+
+        let mut order = tracker.completion_order().to_owned();
+        order.sort();
+        assert_eq!(order, vec![0, 1, 2]);
+    }
+}
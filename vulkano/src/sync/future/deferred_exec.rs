@@ -0,0 +1,321 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::cell::UnsafeCell;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use buffer::BufferAccess;
+use command_buffer::CommandBuffer;
+use command_buffer::CommandBufferExecFuture;
+use command_buffer::submit::SubmitAnyBuilder;
+use device::Device;
+use device::DeviceOwned;
+use device::Queue;
+use image::ImageAccess;
+use swapchain::Swapchain;
+use sync::AccessFlagBits;
+use sync::FlushError;
+use sync::GpuFuture;
+use sync::PipelineStages;
+
+use VulkanObject;
+use instance::QueueFamily;
+
+/// Builds a new deferred execution future.
+///
+/// # Panic
+///
+/// - Panics if the device of the command buffer is not the same as the device of the future.
+///
+#[inline]
+pub fn then_execute_with<F, Cb, S>(future: F, select: S, command_buffer: Cb)
+                                   -> DeferredExecFuture<F, Cb, S>
+    where F: GpuFuture, Cb: CommandBuffer + 'static, S: FnOnce(&Arc<Device>) -> Arc<Queue>
+{
+    assert_eq!(command_buffer.device().internal_object(), future.device().internal_object());
+
+    let imposed = if future.queue_change_allowed() {
+        None
+    } else {
+        future.queue().cloned()
+    };
+
+    DeferredExecFuture {
+        device: future.device().clone(),
+        imposed: imposed,
+        pending: Mutex::new(Some((future, command_buffer, select))),
+        resolved: UnsafeCell::new(None),
+    }
+}
+
+/// Represents a command buffer being executed by the GPU, on a queue that is chosen the first
+/// time it is needed.
+///
+/// Created with `GpuFuture::then_execute_with()`. Once the queue has been chosen, this future
+/// behaves exactly like a `CommandBufferExecFuture`.
+///
+/// Querying the queue with `queue()` doesn't choose it. Until the queue is chosen, `queue()`
+/// only returns the queue imposed by the previous future, if any. Call `resolve_queue()` first
+/// in order to chain functions that require a queue, such as `then_signal_fence()`.
+#[must_use = "Dropping this object will immediately block the thread until the GPU has finished processing the submission"]
+pub struct DeferredExecFuture<F, Cb, S> where F: GpuFuture, Cb: CommandBuffer {
+    // The device of the future.
+    device: Arc<Device>,
+    // The queue of the previous future if it doesn't allow changing its queue.
+    imposed: Option<Arc<Queue>>,
+    // The previous future, the command buffer and the selection closure. `None` once the queue
+    // has been chosen.
+    pending: Mutex<Option<(F, Cb, S)>>,
+    // The future that executes the command buffer. Only written once, while `pending` is locked
+    // and before any reference to its content is handed out. See `resolve()`.
+    resolved: UnsafeCell<Option<CommandBufferExecFuture<F, Cb>>>,
+}
+
+// The content of `resolved` is only written once, while holding the lock of `pending`. After that
+// it is only ever accessed through shared references.
+unsafe impl<F, Cb, S> Sync for DeferredExecFuture<F, Cb, S>
+    where F: GpuFuture + Send + Sync, Cb: CommandBuffer + Send + Sync, S: Send
+{
+}
+
+impl<F, Cb, S> DeferredExecFuture<F, Cb, S>
+    where F: GpuFuture, Cb: CommandBuffer + 'static, S: FnOnce(&Arc<Device>) -> Arc<Queue>
+{
+    /// Returns true if the queue has already been chosen.
+    #[inline]
+    pub fn is_resolved(&self) -> bool {
+        self.pending.lock().unwrap().is_none()
+    }
+
+    /// Chooses the queue if this hasn't been done yet, then returns it.
+    ///
+    /// If the previous future doesn't allow changing its queue, its queue is used and the
+    /// closure isn't called.
+    #[inline]
+    pub fn resolve_queue(&self) -> &Arc<Queue> {
+        // A `CommandBufferExecFuture` is always bound to a queue.
+        self.resolve().queue().unwrap()
+    }
+
+    // Returns the future that executes the command buffer if the queue has already been chosen.
+    fn resolved(&self) -> Option<&CommandBufferExecFuture<F, Cb>> {
+        let pending = self.pending.lock().unwrap();
+        if pending.is_some() {
+            return None;
+        }
+
+        // `pending` is `None`, therefore `resolved` has been written and will never be written
+        // again.
+        unsafe { (*self.resolved.get()).as_ref() }
+    }
+
+    // Chooses the queue if this hasn't been done yet, then returns the future that executes the
+    // command buffer.
+    fn resolve(&self) -> &CommandBufferExecFuture<F, Cb> {
+        let mut pending = self.pending.lock().unwrap();
+
+        if let Some((previous, command_buffer, select)) = pending.take() {
+            let queue = match self.imposed {
+                Some(ref queue) => queue.clone(),
+                None => select(&self.device),
+            };
+
+            // This performs the same checks as `then_execute()`, including the resource locking.
+            let future = command_buffer.execute_after(previous, queue);
+            unsafe { *self.resolved.get() = Some(future); }
+        }
+
+        // `pending` is `None`, therefore `resolved` has been written and will never be written
+        // again.
+        unsafe { (*self.resolved.get()).as_ref().unwrap() }
+    }
+}
+
+unsafe impl<F, Cb, S> GpuFuture for DeferredExecFuture<F, Cb, S>
+    where F: GpuFuture, Cb: CommandBuffer + 'static, S: FnOnce(&Arc<Device>) -> Arc<Queue>
+{
+    #[inline]
+    fn cleanup_finished(&mut self) {
+        if let Some((ref mut previous, _, _)) = *self.pending.get_mut().unwrap() {
+            previous.cleanup_finished();
+            return;
+        }
+
+        // We have a `&mut self`, so there's no other reference to the content of `resolved`.
+        unsafe {
+            if let Some(ref mut future) = *self.resolved.get() {
+                future.cleanup_finished();
+            }
+        }
+    }
+
+    #[inline]
+    unsafe fn build_submission(&self) -> Result<SubmitAnyBuilder, FlushError> {
+        self.resolve().build_submission()
+    }
+
+    #[inline]
+    fn flush(&self) -> Result<(), FlushError> {
+        self.resolve().flush()
+    }
+
+    #[inline]
+    unsafe fn signal_finished(&self) {
+        self.resolve().signal_finished()
+    }
+
+    #[inline]
+    fn queue_change_allowed(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    fn queue(&self) -> Option<&Arc<Queue>> {
+        match self.resolved() {
+            Some(future) => future.queue(),
+            None => self.imposed.as_ref(),
+        }
+    }
+
+    #[inline]
+    fn queue_family(&self) -> Option<QueueFamily> {
+        match self.resolved() {
+            Some(future) => future.queue_family(),
+            None => self.imposed.as_ref().map(|q| q.family()),
+        }
+    }
+
+    // The access checks resolve the queue as well, as granting an access must go along with
+    // locking the resources of the command buffer, which requires the queue.
+    #[inline]
+    fn check_buffer_access(&self, buffer: &BufferAccess, exclusive: bool, queue: &Queue)
+                           -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        self.resolve().check_buffer_access(buffer, exclusive, queue)
+    }
+
+    #[inline]
+    fn check_image_access(&self, image: &ImageAccess, exclusive: bool, queue: &Queue)
+                          -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        self.resolve().check_image_access(image, exclusive, queue)
+    }
+
+    #[inline]
+    fn acquired_swapchain_image(&self, swapchain: &Swapchain) -> Option<usize> {
+        self.resolve().acquired_swapchain_image(swapchain)
+    }
+
+    #[inline]
+    fn validate_resources(&self) {
+        // Validating shouldn't choose the queue, so we don't resolve the future here.
+        let pending = self.pending.lock().unwrap();
+        match *pending {
+            Some((ref previous, _, _)) => previous.validate_resources(),
+            None => unsafe { (*self.resolved.get()).as_ref().unwrap().validate_resources() },
+        }
+    }
+
+    #[inline]
+    fn wait_semaphore_count(&self) -> usize {
+        self.resolve().wait_semaphore_count()
+    }
+
+    #[inline]
+    fn shared_identities(&self, ids: &mut Vec<usize>) {
+        // Doesn't choose the queue, contrary to the access checks.
+        if let Some((ref previous, _, _)) = *self.pending.lock().unwrap() {
+            previous.shared_identities(ids);
+            return;
+        }
+
+        if let Some(future) = self.resolved() {
+            future.shared_identities(ids);
+        }
+    }
+}
+
+unsafe impl<F, Cb, S> DeviceOwned for DeferredExecFuture<F, Cb, S>
+    where F: GpuFuture, Cb: CommandBuffer
+{
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::atomic::Ordering;
+
+    use command_buffer::CommandBuffer;
+    use sync::DummyFuture;
+    use sync::GpuFuture;
+
+    #[test]
+    fn selected_on_first_use() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let cb = empty_command_buffer!(device, queue);
+
+        let called = Arc::new(AtomicBool::new(false));
+        let future = {
+            let called = called.clone();
+            let queue = queue.clone();
+            DummyFuture::new(device.clone()).then_execute_with(move |_| {
+                called.store(true, Ordering::SeqCst);
+                queue
+            }, cb)
+        };
+
+        assert!(!future.is_resolved());
+        assert!(!called.load(Ordering::SeqCst));
+
+        future.flush().unwrap();
+        assert!(future.is_resolved());
+        assert!(called.load(Ordering::SeqCst));
+        assert!(future.queue().unwrap().is_same(&queue));
+    }
+
+    #[test]
+    fn queue_doesnt_resolve() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let cb1 = empty_command_buffer!(device, queue);
+        let cb2 = empty_command_buffer!(device, queue);
+        let cb3 = empty_command_buffer!(device, queue);
+
+        let called = Arc::new(AtomicBool::new(false));
+        let future = {
+            let called = called.clone();
+            let queue = queue.clone();
+            DummyFuture::new(device.clone()).then_execute_with(move |_| {
+                called.store(true, Ordering::SeqCst);
+                queue
+            }, cb1)
+        };
+
+        assert!(future.queue().is_none());
+        assert!(future.queue_family().is_none());
+        assert!(!future.is_resolved());
+        assert!(!called.load(Ordering::SeqCst));
+
+        assert!(future.resolve_queue().is_same(&queue));
+        assert!(called.load(Ordering::SeqCst));
+        assert!(future.queue().unwrap().is_same(&queue));
+
+        // The queue imposed by the previous future is known without resolving.
+        let future = cb2.execute(queue.clone()).then_execute_with(|_| panic!(), cb3);
+        assert!(future.queue().unwrap().is_same(&queue));
+        assert!(!future.is_resolved());
+    }
+}
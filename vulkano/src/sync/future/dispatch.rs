@@ -0,0 +1,286 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::error;
+use std::error::Error;
+use std::fmt;
+
+use buffer::BufferAccess;
+use command_buffer::commands_raw::CmdBindDescriptorSets;
+use command_buffer::commands_raw::CmdBindDescriptorSetsError;
+use command_buffer::commands_raw::CmdBindPipeline;
+use command_buffer::commands_raw::CmdDispatchRaw;
+use command_buffer::commands_raw::CmdDispatchRawError;
+use command_buffer::commands_raw::CmdPipelineBarrier;
+use descriptor::descriptor_set::DescriptorSetsCollection;
+use device::Queue;
+use pipeline::ComputePipelineAbstract;
+use sync::AccessFlagBits;
+use sync::GpuFuture;
+use sync::PipelineStages;
+use sync::future::one_shot::OneShotBuilder;
+use sync::future::one_shot::OneShotCommandBuffer;
+use sync::future::one_shot::OneShotResources;
+use OomError;
+use VulkanObject;
+
+/// Command buffer that executes a compute shader, after a barrier that makes it wait for the
+/// accesses of the previous future to the buffers of its descriptor sets.
+///
+/// Created with `GpuFuture::then_dispatch()`.
+pub type DispatchCommandBuffer<P, S> = OneShotCommandBuffer<DispatchResources<P, S>>;
+
+/// Resources of a `DispatchCommandBuffer`.
+pub struct DispatchResources<P, S> {
+    pipeline: P,
+    sets: S,
+}
+
+impl<P, S> OneShotCommandBuffer<DispatchResources<P, S>>
+    where P: ComputePipelineAbstract + Clone, S: DescriptorSetsCollection + Clone
+{
+    /// Builds a command buffer that dispatches `pipeline` with `group_counts` work groups, for
+    /// the queue family of `queue`.
+    ///
+    /// All the buffers of `sets` are considered to be both read and written by the compute
+    /// shader. For each of them that `future` accesses, the source stages and accesses are
+    /// merged into a single memory barrier whose destination is `shader_read` and
+    /// `shader_write` in the `compute_shader` stage.
+    ///
+    /// Descriptor sets that contain images are rejected, as the layout transitions that they
+    /// may require aren't handled.
+    pub fn new(future: &GpuFuture, queue: &Queue, pipeline: P, sets: S, group_counts: [u32; 3])
+               -> Result<DispatchCommandBuffer<P, S>, DispatchError>
+    {
+        if !queue.family().supports_compute() {
+            return Err(DispatchError::NotSupportedByQueueFamily);
+        }
+
+        if sets.images_list().next().is_some() {
+            return Err(DispatchError::ImagesNotSupported);
+        }
+
+        let dispatch = try!(unsafe { CmdDispatchRaw::new(queue.device().clone(), group_counts) });
+        let bind_sets = try!(CmdBindDescriptorSets::new(false, pipeline.clone(), sets.clone()));
+        let bind_pipeline = CmdBindPipeline::bind_compute_pipeline(pipeline.clone());
+
+        let mut source = None;
+        for buffer in sets.buffers_list() {
+            if let Ok(Some((stages, access))) = future.check_buffer_access(buffer, true, queue) {
+                source = Some(match source {
+                    Some((s, a)) => (stages | s, access | a),
+                    None => (stages, access),
+                });
+            }
+        }
+
+        let builder = try!(unsafe { OneShotBuilder::new(queue) });
+
+        let builder = match source {
+            Some((stages, access)) => unsafe {
+                let mut barrier = CmdPipelineBarrier::new();
+                barrier.add_memory_barrier(stages, access,
+                                           PipelineStages { compute_shader: true,
+                                                            .. PipelineStages::none() },
+                                           AccessFlagBits { shader_read: true,
+                                                            shader_write: true,
+                                                            .. AccessFlagBits::none() },
+                                           false);
+                builder.add(&barrier)
+            },
+            None => builder,
+        };
+
+        let builder = builder.add(&bind_pipeline).add(&bind_sets).add(&dispatch);
+
+        Ok(try!(builder.build(DispatchResources {
+            pipeline: pipeline,
+            sets: sets,
+        })))
+    }
+
+    /// Returns the compute pipeline that is dispatched.
+    #[inline]
+    pub fn pipeline(&self) -> &P {
+        &self.resources().pipeline
+    }
+}
+
+unsafe impl<P, S> OneShotResources for DispatchResources<P, S>
+    where P: ComputePipelineAbstract + Send + Sync,
+          S: DescriptorSetsCollection + Send + Sync
+{
+    fn submit_check(&self, future: &GpuFuture, queue: &Queue) -> Result<(), Box<Error>> {
+        for buffer in self.sets.buffers_list() {
+            if future.check_buffer_access(buffer, true, queue).is_ok() {
+                unsafe { buffer.increase_gpu_lock(); }
+            } else {
+                assert!(buffer.try_gpu_lock(true, queue), "A buffer of a dispatch is already \
+                                                           in use");
+            }
+        }
+
+        Ok(())
+    }
+
+    #[inline]
+    fn check_buffer_access(&self, buffer: &BufferAccess, _: bool, _: &Queue)
+                           -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        let raw = buffer.inner().buffer.internal_object();
+
+        if self.sets.buffers_list().any(|b| b.inner().buffer.internal_object() == raw) {
+            Ok(Some((PipelineStages { compute_shader: true, .. PipelineStages::none() },
+                     AccessFlagBits { shader_read: true, shader_write: true,
+                                      .. AccessFlagBits::none() })))
+        } else {
+            Err(())
+        }
+    }
+}
+
+/// Error that can happen when calling `GpuFuture::then_dispatch()`.
+#[derive(Copy, Clone, Debug)]
+pub enum DispatchError {
+    /// Not enough memory to create the command buffer.
+    OomError(OomError),
+
+    /// The queue family doesn't support compute operations.
+    NotSupportedByQueueFamily,
+
+    /// The descriptor sets contain images, which aren't supported.
+    ImagesNotSupported,
+
+    /// The descriptor sets aren't compatible with the pipeline.
+    BindDescriptorSetsError(CmdBindDescriptorSetsError),
+
+    /// The number of work groups is larger than the hardware limits.
+    DispatchRawError(CmdDispatchRawError),
+}
+
+impl error::Error for DispatchError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            DispatchError::OomError(_) => "not enough memory",
+            DispatchError::NotSupportedByQueueFamily => {
+                "the queue family doesn't support compute operations"
+            },
+            DispatchError::ImagesNotSupported => {
+                "the descriptor sets of the dispatch contain images"
+            },
+            DispatchError::BindDescriptorSetsError(_) => "error while binding descriptor sets",
+            DispatchError::DispatchRawError(_) => {
+                "the dispatch dimensions are larger than the hardware limits"
+            },
+        }
+    }
+
+    #[inline]
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            DispatchError::OomError(ref err) => Some(err),
+            DispatchError::BindDescriptorSetsError(ref err) => Some(err),
+            DispatchError::DispatchRawError(ref err) => Some(err),
+            _ => None
+        }
+    }
+}
+
+impl fmt::Display for DispatchError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+impl From<OomError> for DispatchError {
+    #[inline]
+    fn from(err: OomError) -> DispatchError {
+        DispatchError::OomError(err)
+    }
+}
+
+impl From<CmdBindDescriptorSetsError> for DispatchError {
+    #[inline]
+    fn from(err: CmdBindDescriptorSetsError) -> DispatchError {
+        DispatchError::BindDescriptorSetsError(err)
+    }
+}
+
+impl From<CmdDispatchRawError> for DispatchError {
+    #[inline]
+    fn from(err: CmdDispatchRawError) -> DispatchError {
+        DispatchError::DispatchRawError(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CString;
+    use std::sync::Arc;
+
+    use command_buffer::CommandBuffer;
+    use command_buffer::commands_raw::CmdDispatchRawError;
+    use descriptor::pipeline_layout::EmptyPipelineDesc;
+    use pipeline::ComputePipeline;
+    use pipeline::shader::ShaderModule;
+    use sync::DispatchError;
+    use sync::GpuFuture;
+
+    #[test]
+    fn dispatch() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let cs = unsafe { ShaderModule::new(device.clone(), &EMPTY_CS).unwrap() };
+        let pipeline = Arc::new(ComputePipeline::new(&device, &unsafe {
+            cs.compute_shader_entry_point::<(), _>(&CString::new("main").unwrap(),
+                                                   EmptyPipelineDesc)
+        }, &()).unwrap());
+
+        let cb = empty_command_buffer!(device, queue);
+        cb.execute(queue.clone())
+          .then_dispatch(queue.clone(), pipeline, (), [1, 1, 1]).unwrap()
+          .then_signal_fence_and_flush().unwrap();
+    }
+
+    #[test]
+    fn too_many_groups() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let max = device.physical_device().limits().max_compute_work_group_count();
+        let too_many = match max[0].checked_add(1) {
+            Some(n) => n,
+            None => return,
+        };
+
+        let cs = unsafe { ShaderModule::new(device.clone(), &EMPTY_CS).unwrap() };
+        let pipeline = Arc::new(ComputePipeline::new(&device, &unsafe {
+            cs.compute_shader_entry_point::<(), _>(&CString::new("main").unwrap(),
+                                                   EmptyPipelineDesc)
+        }, &()).unwrap());
+
+        let cb = empty_command_buffer!(device, queue);
+        match cb.execute(queue.clone()).then_dispatch(queue.clone(), pipeline, (),
+                                                      [too_many, 1, 1])
+        {
+            Err(DispatchError::DispatchRawError(CmdDispatchRawError::DimensionsTooLarge)) => (),
+            _ => panic!(),
+        }
+    }
+
+    // A compute shader with a 1x1x1 work group and an empty `main` function.
+    const EMPTY_CS: [u8; 140] = [3, 2, 35, 7, 0, 0, 1, 0, 0, 0, 0, 0, 5, 0, 0, 0, 0, 0, 0, 0, 17, 0,
+                                 2, 0, 1, 0, 0, 0, 14, 0, 3, 0, 0, 0, 0, 0, 1, 0, 0, 0, 15, 0, 5, 0,
+                                 5, 0, 0, 0, 1, 0, 0, 0, 109, 97, 105, 110, 0, 0, 0, 0, 16, 0, 6, 0,
+                                 1, 0, 0, 0, 17, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 19, 0,
+                                 2, 0, 2, 0, 0, 0, 33, 0, 3, 0, 3, 0, 0, 0, 2, 0, 0, 0, 54, 0, 5, 0,
+                                 2, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 248, 0, 2, 0, 4, 0,
+                                 0, 0, 253, 0, 1, 0, 56, 0, 1, 0];
+}
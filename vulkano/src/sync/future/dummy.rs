@@ -7,7 +7,6 @@
 // notice may not be copied, modified, or distributed except
 // according to those terms.
 
-use std::error::Error;
 use std::sync::Arc;
 
 use buffer::BufferAccess;
@@ -17,10 +16,23 @@ use device::DeviceOwned;
 use device::Queue;
 use image::ImageAccess;
 use sync::AccessFlagBits;
+use sync::FlushError;
 use sync::GpuFuture;
+use sync::GpuFutureMut;
 use sync::PipelineStages;
+use vk;
+
+/// Builds a future that represents "now", from which chains of futures can start.
+///
+/// This is the same as `DummyFuture::new(device)`.
+#[inline]
+pub fn now(device: Arc<Device>) -> DummyFuture {
+    DummyFuture::new(device)
+}
 
 /// A dummy future that represents "now".
+///
+/// Created with `now()`.
 #[must_use]
 pub struct DummyFuture {
     device: Arc<Device>,
@@ -42,12 +54,22 @@ unsafe impl GpuFuture for DummyFuture {
     }
 
     #[inline]
-    unsafe fn build_submission(&self) -> Result<SubmitAnyBuilder, Box<Error>> {
+    fn pending_fences(&self, fences: &mut Vec<vk::Fence>) -> bool {
+        true
+    }
+
+    #[inline]
+    fn empty_submission_is_finished(&self) -> bool {
+        true
+    }
+
+    #[inline]
+    unsafe fn build_submission(&self) -> Result<SubmitAnyBuilder, FlushError> {
         Ok(SubmitAnyBuilder::Empty)
     }
 
     #[inline]
-    fn flush(&self) -> Result<(), Box<Error>> {
+    fn flush(&self) -> Result<(), FlushError> {
         Ok(())
     }
 
@@ -80,9 +102,45 @@ unsafe impl GpuFuture for DummyFuture {
     }
 }
 
+unsafe impl GpuFutureMut for DummyFuture {
+    #[inline]
+    fn check_buffer_access_mut(&mut self, buffer: &BufferAccess, exclusive: bool, queue: &Queue)
+                               -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        self.check_buffer_access(buffer, exclusive, queue)
+    }
+
+    #[inline]
+    fn check_image_access_mut(&mut self, image: &ImageAccess, exclusive: bool, queue: &Queue)
+                              -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        self.check_image_access(image, exclusive, queue)
+    }
+}
+
 unsafe impl DeviceOwned for DummyFuture {
     #[inline]
     fn device(&self) -> &Arc<Device> {
         &self.device
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use command_buffer::submit::SubmitAnyBuilder;
+    use sync::GpuFuture;
+    use sync::now;
+
+    #[test]
+    fn now_is_empty() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let future = now(device);
+        assert!(future.queue().is_none());
+        assert!(future.queue_change_allowed());
+        match unsafe { future.build_submission() } {
+            Ok(SubmitAnyBuilder::Empty) => (),
+            _ => panic!()
+        };
+    }
+}
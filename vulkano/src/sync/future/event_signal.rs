@@ -0,0 +1,216 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::sync::Arc;
+
+use buffer::BufferAccess;
+use command_buffer::CommandBuffer;
+use command_buffer::CommandBufferExecFuture;
+use command_buffer::commands_raw::CmdSetEvent;
+use command_buffer::submit::SubmitAnyBuilder;
+use device::Device;
+use device::DeviceOwned;
+use device::Queue;
+use image::ImageAccess;
+use swapchain::Swapchain;
+use sync::AccessFlagBits;
+use sync::Event;
+use sync::FlushError;
+use sync::GpuFuture;
+use sync::PipelineStages;
+use sync::future::one_shot::OneShotBuilder;
+use sync::future::one_shot::OneShotCommandBuffer;
+use OomError;
+use instance::QueueFamily;
+
+/// Builds a new event signal future.
+///
+/// # Panic
+///
+/// - Panics if the future isn't bound to a queue.
+/// - Panics if `stages` is empty or includes `host`.
+///
+/// # Safety
+///
+/// See the documentation of `GpuFuture::then_signal_event()`.
+///
+pub unsafe fn then_signal_event<F>(future: F, stages: PipelineStages)
+                                   -> Result<EventSignalFuture<F>, OomError>
+    where F: GpuFuture
+{
+    let queue = future.queue().expect("The future isn't bound to a queue").clone();
+    let event = Arc::new(try!(Event::raw(queue.device())));
+
+    let cmd = CmdSetEvent::set(event.clone(), stages);
+    let command_buffer = try!(try!(OneShotBuilder::new(&queue)).add(&cmd).build(event.clone()));
+
+    Ok(EventSignalFuture {
+        inner: command_buffer.execute_after(future, queue),
+        event: event,
+        stages: stages,
+    })
+}
+
+/// Represents an event being set by the GPU after a previous future.
+///
+/// Created with `GpuFuture::then_signal_event()`. The event is set once the operations of the
+/// previous future have finished executing the stages passed at creation. Later submissions on
+/// the same queue can wait for it with `GpuFuture::then_wait_event()`, and the host can query
+/// it with `Event::signaled()`.
+#[must_use = "Dropping this object will immediately block the thread until the GPU has finished processing the submission"]
+pub struct EventSignalFuture<F> where F: GpuFuture {
+    // The command buffer only sets the event, which it keeps alive.
+    inner: CommandBufferExecFuture<F, OneShotCommandBuffer<Arc<Event>>>,
+    event: Arc<Event>,
+    // Stages after which the event is set.
+    stages: PipelineStages,
+}
+
+impl<F> EventSignalFuture<F> where F: GpuFuture {
+    /// Returns the event that is set by this future.
+    ///
+    /// The event is never reset by vulkano. Call `Event::reset()` once it has been set and the
+    /// operations that wait for it are finished, before signaling it again.
+    #[inline]
+    pub fn event(&self) -> &Arc<Event> {
+        &self.event
+    }
+
+    /// Returns the stages after which the event is set.
+    ///
+    /// These stages are the source stages to pass to `GpuFuture::then_wait_event()`.
+    #[inline]
+    pub fn stages(&self) -> PipelineStages {
+        self.stages
+    }
+}
+
+unsafe impl<F> GpuFuture for EventSignalFuture<F> where F: GpuFuture {
+    #[inline]
+    fn cleanup_finished(&mut self) {
+        self.inner.cleanup_finished();
+    }
+
+    #[inline]
+    unsafe fn build_submission(&self) -> Result<SubmitAnyBuilder, FlushError> {
+        self.inner.build_submission()
+    }
+
+    #[inline]
+    fn flush(&self) -> Result<(), FlushError> {
+        self.inner.flush()
+    }
+
+    #[inline]
+    unsafe fn signal_finished(&self) {
+        self.inner.signal_finished();
+    }
+
+    #[inline]
+    fn queue_change_allowed(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    fn queue(&self) -> Option<&Arc<Queue>> {
+        self.inner.queue()
+    }
+
+    #[inline]
+    fn queue_family(&self) -> Option<QueueFamily> {
+        self.inner.queue_family()
+    }
+
+    #[inline]
+    fn check_buffer_access(&self, buffer: &BufferAccess, exclusive: bool, queue: &Queue)
+                           -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        self.inner.check_buffer_access(buffer, exclusive, queue)
+    }
+
+    #[inline]
+    fn check_image_access(&self, image: &ImageAccess, exclusive: bool, queue: &Queue)
+                          -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        self.inner.check_image_access(image, exclusive, queue)
+    }
+
+    #[inline]
+    fn acquired_swapchain_image(&self, swapchain: &Swapchain) -> Option<usize> {
+        self.inner.acquired_swapchain_image(swapchain)
+    }
+
+    #[inline]
+    fn validate_resources(&self) {
+        self.inner.validate_resources();
+    }
+
+    #[inline]
+    fn wait_semaphore_count(&self) -> usize {
+        self.inner.wait_semaphore_count()
+    }
+
+    #[inline]
+    fn is_flushed(&self) -> bool {
+        self.inner.is_flushed()
+    }
+
+    #[inline]
+    fn shared_identities(&self, ids: &mut Vec<usize>) {
+        self.inner.shared_identities(ids);
+    }
+}
+
+unsafe impl<F> DeviceOwned for EventSignalFuture<F> where F: GpuFuture {
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        self.inner.device()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use command_buffer::CommandBuffer;
+    use sync::AccessFlagBits;
+    use sync::GpuFuture;
+    use sync::PipelineStages;
+
+    #[test]
+    fn signal_then_wait() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let compute = PipelineStages { compute_shader: true, .. PipelineStages::none() };
+        let transfer = PipelineStages { transfer: true, .. PipelineStages::none() };
+
+        let cb = empty_command_buffer!(device, queue);
+        let future = unsafe { cb.execute(queue.clone()).then_signal_event(compute).unwrap() };
+        let event = future.event().clone();
+        assert!(!event.signaled().unwrap());
+
+        let future = unsafe {
+            future.then_wait_event(event.clone(), compute, transfer,
+                                   AccessFlagBits { shader_write: true, .. AccessFlagBits::none() },
+                                   AccessFlagBits { transfer_read: true, .. AccessFlagBits::none() })
+                  .unwrap()
+        };
+        future.then_signal_fence_and_flush().unwrap().wait(Duration::from_secs(5)).unwrap();
+        assert!(event.signaled().unwrap());
+    }
+
+    #[test]
+    #[should_panic]
+    fn host_stage() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let host = PipelineStages { host: true, .. PipelineStages::none() };
+        let cb = empty_command_buffer!(device, queue);
+        let _ = unsafe { cb.execute(queue.clone()).then_signal_event(host) };
+    }
+}
@@ -0,0 +1,88 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::sync::Arc;
+
+use command_buffer::commands_raw::CmdWaitEvents;
+use device::Queue;
+use sync::AccessFlagBits;
+use sync::Event;
+use sync::PipelineStages;
+use sync::future::one_shot::OneShotBuilder;
+use sync::future::one_shot::OneShotCommandBuffer;
+use sync::future::one_shot::OneShotResources;
+use OomError;
+
+/// Command buffer that waits for an event to be set by a previous submission on the same queue,
+/// or by the host.
+///
+/// Created with `GpuFuture::then_wait_event()` or `GpuFuture::then_wait_host_event()`.
+pub type EventWaitCommandBuffer = OneShotCommandBuffer<EventWaitResources>;
+
+/// Command buffer that waits for an event to be set by the host.
+///
+/// Created with `GpuFuture::then_wait_host_event()`.
+pub type HostEventWaitCommandBuffer = EventWaitCommandBuffer;
+
+/// Resources of an `EventWaitCommandBuffer`.
+pub struct EventWaitResources {
+    event: Arc<Event>,
+}
+
+impl OneShotCommandBuffer<EventWaitResources> {
+    /// Builds a command buffer that waits for `event`, for the queue family of `queue`.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if `source_stages` or `dest_stages` is empty.
+    ///
+    /// # Safety
+    ///
+    /// See the documentation of `GpuFuture::then_wait_event()`.
+    ///
+    pub unsafe fn new(queue: &Queue, event: Arc<Event>, source_stages: PipelineStages,
+                      dest_stages: PipelineStages, source_access: AccessFlagBits,
+                      dest_access: AccessFlagBits)
+                      -> Result<EventWaitCommandBuffer, OomError>
+    {
+        let mut cmd = CmdWaitEvents::new(event.clone(), source_stages, dest_stages);
+        cmd.add_memory_barrier(source_access, dest_access);
+        try!(OneShotBuilder::new(queue)).add(&cmd).build(EventWaitResources { event: event })
+    }
+
+    /// Builds a command buffer that waits for `event` to be set by the host, for the queue
+    /// family of `queue`.
+    ///
+    /// The host writes performed before setting the event are made visible to all the accesses
+    /// of `dest_stages`.
+    ///
+    /// # Safety
+    ///
+    /// See the documentation of `GpuFuture::then_wait_host_event()`.
+    ///
+    #[inline]
+    pub unsafe fn from_host(queue: &Queue, event: Arc<Event>, dest_stages: PipelineStages)
+                            -> Result<EventWaitCommandBuffer, OomError>
+    {
+        let host = PipelineStages { host: true, .. PipelineStages::none() };
+        EventWaitCommandBuffer::new(queue, event, host, dest_stages,
+                                    AccessFlagBits { host_write: true, .. AccessFlagBits::none() },
+                                    AccessFlagBits { memory_read: true, memory_write: true,
+                                                     .. AccessFlagBits::none() })
+    }
+
+    /// Returns the event that the command buffer waits upon.
+    #[inline]
+    pub fn event(&self) -> &Arc<Event> {
+        &self.resources().event
+    }
+}
+
+unsafe impl OneShotResources for EventWaitResources {
+}
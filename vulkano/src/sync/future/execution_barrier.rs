@@ -0,0 +1,66 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use device::Queue;
+use sync::AccessFlagBits;
+use sync::PipelineBarrierBuilder;
+use sync::PipelineStages;
+use sync::future::barrier::command_buffer_builder;
+use sync::future::one_shot::OneShotCommandBuffer;
+use OomError;
+
+/// Command buffer that only contains an execution dependency, without any memory barrier.
+///
+/// Created with `GpuFuture::then_execution_barrier()`.
+pub type ExecutionBarrierCommandBuffer = OneShotCommandBuffer<()>;
+
+impl OneShotCommandBuffer<()> {
+    /// Builds a command buffer that makes `dest_stages` of the following commands wait for
+    /// `source_stages` of the previous commands, for the queue family of `queue`.
+    ///
+    /// This is the command buffer of a `PipelineBarrierBuilder` without any access and without
+    /// any image transition.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if `source_stages` or `dest_stages` is empty.
+    ///
+    /// # Safety
+    ///
+    /// See the documentation of `GpuFuture::then_execution_barrier()`.
+    ///
+    pub unsafe fn new(queue: &Queue, source_stages: PipelineStages, dest_stages: PipelineStages)
+                      -> Result<ExecutionBarrierCommandBuffer, OomError>
+    {
+        let barrier = PipelineBarrierBuilder::new(source_stages, AccessFlagBits::none(),
+                                                  dest_stages, AccessFlagBits::none());
+        try!(command_buffer_builder(queue, &barrier)).build(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use command_buffer::CommandBuffer;
+    use sync::GpuFuture;
+    use sync::PipelineStages;
+
+    #[test]
+    fn execution_barrier() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let compute = PipelineStages { compute_shader: true, .. PipelineStages::none() };
+        let transfer = PipelineStages { transfer: true, .. PipelineStages::none() };
+
+        let cb = empty_command_buffer!(device, queue);
+        let future = unsafe {
+            cb.execute(queue.clone()).then_execution_barrier(compute, transfer).unwrap()
+        };
+        future.then_signal_fence_and_flush().unwrap();
+    }
+}
@@ -0,0 +1,140 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::sync::Arc;
+
+use buffer::BufferAccess;
+use command_buffer::submit::SubmitAnyBuilder;
+use command_buffer::submit::SubmitSemaphoresWaitBuilder;
+use device::Device;
+use device::DeviceOwned;
+use device::Queue;
+use image::ImageAccess;
+use sync::AccessFlagBits;
+use sync::FlushError;
+use sync::GpuFuture;
+use sync::PipelineStages;
+use sync::Semaphore;
+
+use VulkanObject;
+
+/// Represents the moment when an image written by an external producer becomes available.
+///
+/// This is the equivalent of `SwapchainAcquireFuture` for images whose memory was imported from
+/// another API or process, for example a video decoder, with `ExternalImage::import_fd`. The
+/// producer signals a semaphore once it has finished writing the image, and this semaphore is
+/// imported into a vulkano `Semaphore` with `Semaphore::import_fd`. Any operation chained after this future waits on the semaphore
+/// before accessing the image.
+///
+/// # Example
+///
+/// ```ignore
+/// let imported_image = unsafe {
+///     ExternalImage::import_fd(&device, dimensions, format, usage,
+///                              ExternalMemoryHandleType::DmaBuf, image_fd).unwrap()
+/// };
+///
+/// let semaphore = Semaphore::new(device.clone()).unwrap();
+/// unsafe { semaphore.import_fd(ExternalSemaphoreHandleType::SyncFd, semaphore_fd, true).unwrap(); }
+///
+/// let future = ExternalAcquireFuture::new(semaphore, imported_image.clone())
+///     .then_execute(queue.clone(), sampling_command_buffer);
+/// ```
+#[must_use]
+pub struct ExternalAcquireFuture<I> {
+    semaphore: Semaphore,
+    image: I,
+}
+
+impl<I> ExternalAcquireFuture<I> where I: ImageAccess {
+    /// Builds a future that waits on `semaphore` before granting access to `image`.
+    ///
+    /// The semaphore must be signaled by the external producer after it is done writing the
+    /// image, and the image must be in the layout expected by the operations that use it.
+    #[inline]
+    pub fn new(semaphore: Semaphore, image: I) -> ExternalAcquireFuture<I> {
+        assert_eq!(semaphore.device().internal_object(),
+                   image.inner().device().internal_object());
+
+        ExternalAcquireFuture {
+            semaphore: semaphore,
+            image: image,
+        }
+    }
+
+    /// Returns the image that is acquired.
+    #[inline]
+    pub fn image(&self) -> &I {
+        &self.image
+    }
+}
+
+unsafe impl<I> GpuFuture for ExternalAcquireFuture<I> where I: ImageAccess {
+    #[inline]
+    fn cleanup_finished(&mut self) {
+    }
+
+    #[inline]
+    unsafe fn build_submission(&self) -> Result<SubmitAnyBuilder, FlushError> {
+        let mut sem = SubmitSemaphoresWaitBuilder::new();
+        sem.add_wait_semaphore(&self.semaphore);
+        Ok(SubmitAnyBuilder::SemaphoresWait(sem))
+    }
+
+    #[inline]
+    fn flush(&self) -> Result<(), FlushError> {
+        Ok(())
+    }
+
+    #[inline]
+    unsafe fn signal_finished(&self) {
+    }
+
+    #[inline]
+    fn queue_change_allowed(&self) -> bool {
+        true
+    }
+
+    #[inline]
+    fn queue(&self) -> Option<&Arc<Queue>> {
+        None
+    }
+
+    #[inline]
+    fn check_buffer_access(&self, _: &BufferAccess, _: bool, _: &Queue)
+                           -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        Err(())
+    }
+
+    #[inline]
+    fn check_image_access(&self, image: &ImageAccess, _: bool, _: &Queue)
+                          -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        // The external producer is the only other user of the image, and the semaphore wait
+        // orders our accesses after its writes. No additional barrier is needed.
+        if self.image.inner().internal_object() == image.inner().internal_object() {
+            Ok(None)
+        } else {
+            Err(())
+        }
+    }
+
+    #[inline]
+    fn wait_semaphore_count(&self) -> usize {
+        1
+    }
+}
+
+unsafe impl<I> DeviceOwned for ExternalAcquireFuture<I> {
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        self.semaphore.device()
+    }
+}
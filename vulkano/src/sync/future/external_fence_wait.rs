@@ -0,0 +1,219 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::os::raw::c_int;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use buffer::BufferAccess;
+use command_buffer::submit::SubmitAnyBuilder;
+use device::Device;
+use device::DeviceOwned;
+use device::Queue;
+use image::ImageAccess;
+use instance::QueueFamily;
+use swapchain::Swapchain;
+use sync::AccessFlagBits;
+use sync::ExternalFenceHandleType;
+use sync::Fence;
+use sync::FenceImportError;
+use sync::FlushError;
+use sync::GpuFuture;
+use sync::PipelineStages;
+
+/// Builds a new external fence wait future.
+#[inline]
+pub unsafe fn then_wait_external_fence<F>(future: F, fd: c_int,
+                                          handle_type: ExternalFenceHandleType)
+                                          -> Result<ExternalFenceWaitFuture<F>, FenceImportError>
+    where F: GpuFuture
+{
+    let mut fence = try!(Fence::new(future.device().clone()));
+    // A temporary import is valid for all the handle types, and the fence is never reset.
+    try!(fence.import_fd(handle_type, fd, true));
+
+    Ok(ExternalFenceWaitFuture {
+        previous: future,
+        fence: fence,
+        timeout: Duration::from_secs(600),
+        waited: AtomicBool::new(false),
+    })
+}
+
+/// Represents the moment when a fence signaled by an external producer is signaled, after a
+/// previous event.
+///
+/// Contrary to semaphores, fences can't be waited upon by the GPU. Instead the submissions that
+/// are chained after this future are only built and submitted once the CPU has observed the
+/// fence as signaled, which blocks the thread that flushes them. The previous future itself
+/// doesn't depend on the fence and is submitted as usual by `flush()`.
+///
+/// Building the submission blocks for at most the timeout set with `with_timeout()`, which is
+/// 600 seconds by default. If the fence isn't signaled by then, `FlushError::Timeout` is returned
+/// and nothing is submitted, so that flushing can be tried again later. Use `is_signaled()` to
+/// know whether flushing would block.
+#[must_use]
+pub struct ExternalFenceWaitFuture<F> {
+    previous: F,
+    fence: Fence,
+    // Maximum duration of the wait for the fence when building the submission.
+    timeout: Duration,
+    // True if the fence has been observed as signaled.
+    waited: AtomicBool,
+}
+
+impl<F> ExternalFenceWaitFuture<F> where F: GpuFuture {
+    /// Sets the maximum duration that building the submission blocks for while waiting for the
+    /// external fence.
+    #[inline]
+    pub fn with_timeout(mut self, timeout: Duration) -> ExternalFenceWaitFuture<F> {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Returns true if the external fence is signaled. Doesn't block.
+    #[inline]
+    pub fn is_signaled(&self) -> Result<bool, FlushError> {
+        if self.waited.load(Ordering::SeqCst) {
+            return Ok(true);
+        }
+
+        let signaled = try!(self.fence.ready());
+        if signaled {
+            self.waited.store(true, Ordering::SeqCst);
+        }
+        Ok(signaled)
+    }
+
+    // Blocks until the external fence is signaled, or returns `FlushError::Timeout` if the
+    // timeout is reached first.
+    fn wait(&self) -> Result<(), FlushError> {
+        if self.waited.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+
+        try!(self.fence.wait(self.timeout));
+        self.waited.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+}
+
+unsafe impl<F> GpuFuture for ExternalFenceWaitFuture<F> where F: GpuFuture {
+    #[inline]
+    fn cleanup_finished(&mut self) {
+        self.previous.cleanup_finished();
+    }
+
+    #[inline]
+    unsafe fn build_submission(&self) -> Result<SubmitAnyBuilder, FlushError> {
+        // The submission that is being built is the one that must wait for the fence.
+        try!(self.wait());
+        self.previous.build_submission()
+    }
+
+    #[inline]
+    fn flush(&self) -> Result<(), FlushError> {
+        self.previous.flush()
+    }
+
+    #[inline]
+    unsafe fn signal_finished(&self) {
+        self.previous.signal_finished();
+    }
+
+    #[inline]
+    fn queue_change_allowed(&self) -> bool {
+        self.previous.queue_change_allowed()
+    }
+
+    #[inline]
+    fn queue(&self) -> Option<&Arc<Queue>> {
+        self.previous.queue()
+    }
+
+    #[inline]
+    fn queue_family(&self) -> Option<QueueFamily> {
+        self.previous.queue_family()
+    }
+
+    #[inline]
+    fn check_buffer_access(&self, buffer: &BufferAccess, exclusive: bool, queue: &Queue)
+                           -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        self.previous.check_buffer_access(buffer, exclusive, queue)
+    }
+
+    #[inline]
+    fn check_image_access(&self, image: &ImageAccess, exclusive: bool, queue: &Queue)
+                          -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        self.previous.check_image_access(image, exclusive, queue)
+    }
+
+    #[inline]
+    fn acquired_swapchain_image(&self, swapchain: &Swapchain) -> Option<usize> {
+        self.previous.acquired_swapchain_image(swapchain)
+    }
+
+    #[inline]
+    fn validate_resources(&self) {
+        self.previous.validate_resources();
+    }
+
+    #[inline]
+    fn wait_semaphore_count(&self) -> usize {
+        self.previous.wait_semaphore_count()
+    }
+
+    #[inline]
+    fn signals_fence(&self) -> bool {
+        self.previous.signals_fence()
+    }
+
+    #[inline]
+    fn is_flushed(&self) -> bool {
+        self.previous.is_flushed()
+    }
+
+    #[inline]
+    fn shared_identities(&self, ids: &mut Vec<usize>) {
+        self.previous.shared_identities(ids);
+    }
+}
+
+unsafe impl<F> DeviceOwned for ExternalFenceWaitFuture<F> where F: DeviceOwned {
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        self.previous.device()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sync::DummyFuture;
+    use sync::ExternalFenceHandleType;
+    use sync::FenceImportError;
+    use sync::GpuFuture;
+
+    #[test]
+    fn missing_extension() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let result = unsafe {
+            DummyFuture::new(device).then_wait_external_fence(-1, ExternalFenceHandleType::SyncFd)
+        };
+
+        match result {
+            Err(FenceImportError::MissingExtension) => (),
+            _ => panic!()
+        }
+    }
+}
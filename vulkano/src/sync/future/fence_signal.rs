@@ -7,36 +7,217 @@
 // notice may not be copied, modified, or distributed except
 // according to those terms.
 
-use std::error::Error;
+use std::cmp;
+use std::error;
+use std::fmt;
 use std::mem;
 use std::sync::Arc;
 use std::sync::Mutex;
 use std::sync::MutexGuard;
+use std::sync::mpsc::Sender;
 use std::time::Duration;
+use std::time::Instant;
+#[cfg(feature = "async")]
+use std::future::Future;
+#[cfg(feature = "async")]
+use std::pin::Pin;
+#[cfg(feature = "async")]
+use std::sync::atomic::AtomicBool;
+#[cfg(feature = "async")]
+use std::sync::atomic::Ordering;
+#[cfg(feature = "async")]
+use std::task::Context;
+#[cfg(feature = "async")]
+use std::task::Poll;
+#[cfg(feature = "async")]
+use std::task::Waker;
+#[cfg(feature = "async")]
+use std::thread;
 
 use buffer::BufferAccess;
 use command_buffer::submit::SubmitAnyBuilder;
 use command_buffer::submit::SubmitCommandBufferBuilder;
+use command_buffer::submit::SubmitPresentBuilder;
+use command_buffer::submit::SwapchainPresentResult;
 use device::Device;
 use device::DeviceOwned;
 use device::Queue;
 use image::ImageAccess;
+use query::UnsafeQueryPool;
+use swapchain::Swapchain;
 use sync::AccessFlagBits;
 use sync::Fence;
+use sync::FencePool;
+use sync::FenceWaitError;
+use sync::FlushError;
 use sync::GpuFuture;
+use sync::GpuFutureMut;
 use sync::PipelineStages;
+use sync::Semaphore;
+use sync::future::timestamps;
+use sync::future::timestamps::TimestampFuture;
+use sync::future::timestamps::TimestampQueries;
+
+use OomError;
+use VulkanObject;
+use instance::QueueFamily;
+use vk;
+
+// Duration in milliseconds of each wait on the fence in `wait_pumping()`.
+const PUMP_SLICE_MS: u64 = 2;
 
 /// Builds a new fence signal future.
 #[inline]
 pub fn then_signal_fence<F>(future: F) -> FenceSignalFuture<F> where F: GpuFuture {
+    let fence = Fence::new(future.device().clone()).unwrap();
+    then_signal_fence_with(future, fence)
+}
+
+/// Builds a new fence signal future that signals an existing fence.
+///
+/// # Panic
+///
+/// - Panics if the fence and the future don't belong to the same device.
+/// - Panics if the future already signals a fence.
+///
+pub fn then_signal_fence_with<F>(future: F, mut fence: Fence) -> FenceSignalFuture<F>
+    where F: GpuFuture
+{
     let device = future.device().clone();
 
-    assert!(future.queue().is_some());        // TODO: document
+    // Checked first, as a cleaned `FenceSignalFuture` has no queue and would fail the check below.
+    assert!(!future.signals_fence(), "Signaling a fence after a future that already signals a \
+                                      fence");
+    let queue = future.queue().expect("The future isn't bound to a queue").clone();
+    assert_eq!(fence.device().internal_object(), device.internal_object());
+
+    // The fence is owned by us, so it can't be in use by a pending submission.
+    fence.reset();
 
-    let fence = Fence::new(device.clone()).unwrap();
     FenceSignalFuture {
         device: device,
+        queue: queue,
+        fence_pool: None,
         state: Mutex::new(FenceSignalFutureState::Pending(future, fence)),
+        on_complete: Mutex::new(Vec::new()),
+        present_semaphore: None,
+        finished_fence: Mutex::new(None),
+        present_results: Mutex::new(Vec::new()),
+        max_partial_flush_retries: None,
+        partial_flush: Mutex::new(PartialFlushDiagnostics { retries: 0, last_error: None }),
+        wait_timeout: Mutex::new(Some(Duration::from_secs(600))),
+        timestamps: None,
+    }
+}
+
+/// Builds a new fence signal future that takes its fence from a pool.
+///
+/// # Panic
+///
+/// - Panics if the pool and the future don't belong to the same device.
+/// - Panics if the future already signals a fence.
+///
+pub fn then_signal_fence_from_pool<F>(future: F, pool: &FencePool)
+                                      -> Result<FenceSignalFuture<F>, OomError>
+    where F: GpuFuture
+{
+    assert_eq!(pool.device().internal_object(), future.device().internal_object());
+    let fence = try!(pool.acquire());
+    let mut future = then_signal_fence_with(future, fence);
+    future.fence_pool = Some(pool.clone());
+    Ok(future)
+}
+
+/// Builds a new fence signal future whose submission is surrounded with timestamps.
+///
+/// # Panic
+///
+/// - Panics if the future isn't bound to a queue, or if the queue family of this queue doesn't
+///   support timestamps.
+/// - Panics if `start` or `end` is out of range of the pool, or if they are equal.
+/// - Panics if the pool wasn't created with `QueryType::Timestamp`.
+/// - Panics if the pool doesn't belong to the same device as the future.
+/// - Panics if the future already signals a fence.
+///
+pub fn then_signal_fence_with_timestamps<F>(future: F, pool: Arc<UnsafeQueryPool>, start: u32,
+                                            end: u32)
+                                            -> Result<FenceSignalFuture<TimestampFuture<F>>,
+                                                      OomError>
+    where F: GpuFuture
+{
+    let (future, queries) = try!(timestamps::with_timestamps(future, pool, start, end));
+    let mut future = then_signal_fence(future);
+    future.timestamps = Some(queries);
+    Ok(future)
+}
+
+/// How a `FenceSignalFuture` signals its fence after a swapchain present.
+///
+/// Presenting and signaling a fence can't be done with a single Vulkan command. Therefore when
+/// the previous future ends with a present, the fence signal future has to perform multiple
+/// submissions. If one of the submissions fails, flushing the future again resumes from the
+/// submission that failed.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PresentFenceStrategy {
+    /// Submits the present, then submits an empty batch that signals the fence.
+    ///
+    /// This is the default strategy. It only requires two submissions.
+    Direct,
+
+    /// Submits an empty batch that signals a semaphore, then submits the present while waiting
+    /// on this semaphore, and finally submits an empty batch that signals the fence.
+    ///
+    /// This requires an additional semaphore and an additional submission, but each step can
+    /// fail and be retried independently. In particular, an error returned by the first
+    /// submission leaves the future in a state where nothing has been submitted yet.
+    Semaphore,
+}
+
+/// Current state of a `FenceSignalFuture`, as returned by `FenceSignalFuture::status()`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FenceSignalFutureStatus {
+    /// Nothing has been submitted yet.
+    Pending,
+
+    /// Some of the submissions needed to signal the fence have been submitted, but a later one
+    /// failed. Flushing the future again resumes from the submission that failed. See
+    /// `PresentFenceStrategy` and `FenceSignalFuture::partial_flush_retries()`.
+    PartiallyFlushed,
+
+    /// Everything has been submitted. The GPU may or may not have finished.
+    Flushed,
+
+    /// The GPU has finished and the previous future has been destroyed. Dropping the future
+    /// doesn't block.
+    Cleaned,
+
+    /// A function panicked while the future was being modified.
+    Poisoned,
+}
+
+/// Error returned by `FenceSignalFuture::try_replace()` when the current future isn't finished.
+///
+/// Contains the future that was passed to `try_replace()`.
+pub struct StillPending<F>(pub F);
+
+impl<F> fmt::Debug for StillPending<F> {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "StillPending")
+    }
+}
+
+impl<F> error::Error for StillPending<F> {
+    #[inline]
+    fn description(&self) -> &str {
+        "the GPU hasn't finished executing the current future"
+    }
+}
+
+impl<F> fmt::Display for StillPending<F> {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
     }
 }
 
@@ -47,6 +228,35 @@ pub struct FenceSignalFuture<F> where F: GpuFuture {
     state: Mutex<FenceSignalFutureState<F>>,
     // The device of the future.
     device: Arc<Device>,
+    // The queue of the previous future, which is also the queue the fence is submitted to.
+    queue: Arc<Queue>,
+    // If `Some`, the fence is given back to this pool once the future is cleaned.
+    fence_pool: Option<FencePool>,
+    // Functions to call once the future has been observed to be finished.
+    on_complete: Mutex<Vec<Box<FnMut() + Send>>>,
+    // If `Some`, the `PresentFenceStrategy::Semaphore` strategy is used with this semaphore.
+    present_semaphore: Option<Semaphore>,
+    // The fence of the submission, kept after the previous future has been cleaned so that it
+    // can be retrieved with `into_fence()`.
+    finished_fence: Mutex<Option<Fence>>,
+    // Result of each present of the submission, if it ends with presents.
+    present_results: Mutex<Vec<SwapchainPresentResult>>,
+    // If `Some`, the maximum number of times a partially flushed future can be flushed again.
+    max_partial_flush_retries: Option<usize>,
+    // What happened to the flushes that only partially succeeded.
+    partial_flush: Mutex<PartialFlushDiagnostics>,
+    // Timeout of the blocking waits of `build_submission()` and of the destructor. `None` means
+    // no timeout. Only modified while `state` is locked.
+    wait_timeout: Mutex<Option<Duration>>,
+    // If `Some`, the submission is surrounded with timestamps written to these queries.
+    timestamps: Option<TimestampQueries>,
+}
+
+struct PartialFlushDiagnostics {
+    // Number of flushes that started from a partially flushed state.
+    retries: usize,
+    // Message of the last error that left the future partially flushed.
+    last_error: Option<String>,
 }
 
 // This future can be in three different states: pending (ie. newly-created), submitted (ie. the
@@ -60,9 +270,14 @@ enum FenceSignalFutureState<F> {
     // steps, and when the first step succeeded while the second step failed.
     //
     // Note that if there's ever a submit operation that needs three steps we will need to rework
-    // this code, as it was designed for two-step operations only.
+    // this code, as it was designed for two-step operations only. The only exception is the
+    // `PresentFenceStrategy::Semaphore` strategy, which has its own state below.
     PartiallyFlushed(F, Fence),
 
+    // Only used with `PresentFenceStrategy::Semaphore`. The submission that signals the present
+    // semaphore has succeeded, but the present itself hasn't been submitted yet.
+    PresentSemaphoreSignaled(F, Fence),
+
     // Submitted to the queue.
     Flushed(F, Fence),
 
@@ -74,30 +289,403 @@ enum FenceSignalFutureState<F> {
 }
 
 impl<F> FenceSignalFuture<F> where F: GpuFuture {
+    /// Chooses how the fence is signaled if the previous future ends with a swapchain present.
+    ///
+    /// The default is `PresentFenceStrategy::Direct`. See the documentation of
+    /// `PresentFenceStrategy` for the tradeoffs.
+    ///
+    /// This must be called before the future is flushed, otherwise it has no effect until the
+    /// next flush attempt.
+    pub fn present_strategy(mut self, strategy: PresentFenceStrategy)
+                            -> Result<FenceSignalFuture<F>, OomError>
+    {
+        self.present_semaphore = match strategy {
+            PresentFenceStrategy::Direct => None,
+            PresentFenceStrategy::Semaphore => {
+                match self.present_semaphore.take() {
+                    Some(sem) => Some(sem),
+                    None => Some(try!(Semaphore::new(self.device.clone()))),
+                }
+            },
+        };
+
+        Ok(self)
+    }
+
+    /// Sets the maximum number of times the future can be flushed again after a flush that only
+    /// partially succeeded.
+    ///
+    /// Once the limit is reached, flushing returns `FlushError::RetriesExceeded` without
+    /// submitting anything. There is no limit by default. See also `partial_flush_retries()`.
+    #[inline]
+    pub fn max_partial_flush_retries(mut self, max: usize) -> FenceSignalFuture<F> {
+        self.max_partial_flush_retries = Some(max);
+        self
+    }
+
+    /// Sets the maximum duration of the blocking waits for the fence that happen when a future
+    /// chained after this one is flushed, and when this future is dropped. `None` means that
+    /// these waits never time out. The default is 10 minutes.
+    ///
+    /// Reaching the timeout makes flushing the following future return `FlushError::Timeout`,
+    /// and makes the destructor panic, as the previous future can't be destroyed while the GPU
+    /// may still be using its resources.
+    ///
+    /// The timeout can be changed at any time, for example to wait forever for a long compute
+    /// job once it's been flushed.
+    pub fn set_wait_timeout(&self, timeout: Option<Duration>) {
+        let _state = self.state.lock().unwrap();
+        *self.wait_timeout.lock().unwrap() = timeout;
+    }
+
+    /// Returns the time it took the GPU to execute the submission, as measured by the timestamps
+    /// of `then_signal_fence_with_timestamps()`.
+    ///
+    /// Returns `None` if the future wasn't created with `then_signal_fence_with_timestamps()`,
+    /// or if the fence isn't signaled yet. The timestamps are read each time this method is
+    /// called, so the queries must not be reused until then.
+    pub fn elapsed(&self) -> Option<Duration> {
+        let queries = match self.timestamps {
+            Some(ref queries) => queries,
+            None => return None,
+        };
+
+        match *self.state.lock().unwrap() {
+            FenceSignalFutureState::Flushed(_, ref fence) => {
+                match fence.ready() {
+                    Ok(true) => (),
+                    _ => return None,
+                }
+            },
+            FenceSignalFutureState::Cleaned => (),
+            _ => return None,
+        }
+
+        queries.elapsed()
+    }
+
+    /// Returns the timeout set with `set_wait_timeout()`.
+    #[inline]
+    pub fn wait_timeout(&self) -> Option<Duration> {
+        *self.wait_timeout.lock().unwrap()
+    }
+
+    // Waits for `fence` with the timeout set with `set_wait_timeout()`. The state must be locked.
+    fn blocking_wait(&self, fence: &Fence) -> Result<(), FenceWaitError> {
+        match *self.wait_timeout.lock().unwrap() {
+            Some(timeout) => fence.wait(timeout),
+            // This corresponds to `UINT64_MAX` nanoseconds, which Vulkan treats as no timeout.
+            None => fence.wait(Duration::new(u64::max_value(), 0)),
+        }
+    }
+
+    /// Returns the number of flushes that resumed from the `PartiallyFlushed` state.
+    ///
+    /// A steadily increasing number means that the submission that follows a present keeps
+    /// failing. The error that caused it is returned by `last_partial_flush_error()`.
+    #[inline]
+    pub fn partial_flush_retries(&self) -> usize {
+        self.partial_flush.lock().unwrap().retries
+    }
+
+    /// Returns the message of the last error that left the future in the `PartiallyFlushed`
+    /// state, if any.
+    #[inline]
+    pub fn last_partial_flush_error(&self) -> Option<String> {
+        self.partial_flush.lock().unwrap().last_error.clone()
+    }
+
+    /// Registers a value that will be sent through `sender` once the GPU has finished executing
+    /// this future.
+    ///
+    /// The value is sent exactly once, the first time that the future is observed to be
+    /// finished. This happens either in `cleanup_finished()` or when the future is dropped. If
+    /// the device is lost before the future is finished, the value is never sent.
+    ///
+    /// Errors that happen when sending (ie. if the receiver has been destroyed) are ignored.
+    pub fn notify_on_complete<T>(self, sender: Sender<T>, value: T) -> FenceSignalFuture<F>
+        where T: Send + 'static
+    {
+        let mut notification = Some((sender, value));
+        self.on_complete.lock().unwrap().push(Box::new(move || {
+            if let Some((sender, value)) = notification.take() {
+                let _ = sender.send(value);
+            }
+        }));
+        self
+    }
+
+    /// Destroys the future and returns its fence so that it can be reused for another
+    /// submission, for example with `GpuFuture::then_signal_fence_with()`.
+    ///
+    /// This only succeeds if the GPU has finished executing the submission, which is checked
+    /// without blocking. Otherwise the future is returned unchanged in the `Err`. The returned
+    /// fence is in the unsignaled state.
+    ///
+    /// This is the building block for reusing the parts of a per-frame chain of futures that don't
+    /// need to be recreated. The chain itself holds per-submission state and can't be submitted
+    /// twice, but the queues are cheap to clone and the fence can be recycled with this method.
+    ///
+    /// A future created with `then_signal_fence_from_pool()` gives its fence back to the pool
+    /// when it is cleaned, so this always fails for such a future.
+    pub fn into_fence(self) -> Result<Fence, FenceSignalFuture<F>> {
+        self.cleanup_finished_impl();
+        let fence = self.finished_fence.lock().unwrap().take();
+        match fence {
+            Some(mut fence) => {
+                fence.reset();
+                Ok(fence)
+            },
+            None => Err(self),
+        }
+    }
+
+    /// Installs `future` as the new previous future, if the GPU has finished executing the
+    /// current one.
+    ///
+    /// This only succeeds if the state is `FenceSignalFutureStatus::Cleaned`, after a cleanup
+    /// that is performed without blocking. The future then becomes pending again, and signals
+    /// the same fence after `future`. Since this takes a `&self`, it can be used on a future that
+    /// is shared with an `Arc` in order to recycle a per-slot future, for example for
+    /// double-buffered resources, without allocating a new one.
+    ///
+    /// All the holders of the `Arc` observe the swap. From then on, their calls to `wait()`,
+    /// `flush()`, `queue()` or `check_buffer_access()` and the futures that they build on top of
+    /// this one refer to `future`, even though they may have obtained their clone when it still
+    /// represented the previous work. Only share the future with code that expects it to be
+    /// recycled.
+    ///
+    /// If the state isn't `Cleaned`, `future` is returned in the error.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if `future` doesn't belong to the same device as this future.
+    /// - Panics if `future` already signals a fence.
+    /// - Panics if `future` isn't bound to the same queue as the current future.
+    ///
+    pub fn try_replace(&self, future: F) -> Result<(), StillPending<F>> {
+        assert!(!future.signals_fence(), "Signaling a fence after a future that already signals a \
+                                          fence");
+        assert!(future.queue().map(|q| q.is_same(&self.queue)).unwrap_or(false),
+                "The new future isn't bound to the queue of the current future");
+        assert_eq!(future.device().internal_object(), self.device.internal_object());
+
+        self.cleanup_finished_impl();
+
+        let mut state = self.state.lock().unwrap();
+        match *state {
+            FenceSignalFutureState::Cleaned => (),
+            _ => return Err(StillPending(future)),
+        }
+
+        let fence = match self.finished_fence.lock().unwrap().take() {
+            Some(mut fence) => {
+                fence.reset();
+                fence
+            },
+            None => match self.fence_pool {
+                Some(ref pool) => pool.acquire().unwrap(),
+                None => Fence::new(self.device.clone()).unwrap(),
+            },
+        };
+
+        *state = FenceSignalFutureState::Pending(future, fence);
+        self.present_results.lock().unwrap().clear();
+        *self.partial_flush.lock().unwrap() = PartialFlushDiagnostics {
+            retries: 0,
+            last_error: None,
+        };
+        Ok(())
+    }
+
+    /// Flushes the future, then blocks the current thread until the GPU has finished executing
+    /// it or until `timeout` has elapsed, calling `pump` regularly in the meantime.
+    ///
+    /// The fence is waited upon in short slices, and `pump` is called between two slices. This
+    /// is meant for single-threaded applications that need to keep processing the events of
+    /// their windows while waiting for the GPU.
+    ///
+    /// On success, the future is cleaned up as with `cleanup_finished()`. If the timeout is
+    /// reached, `FlushError::Timeout` is returned and the future is left untouched.
+    pub fn wait_pumping<P>(&self, timeout: Duration, mut pump: P) -> Result<(), FlushError>
+        where P: FnMut()
+    {
+        try!(self.flush());
+
+        let start = Instant::now();
+        loop {
+            {
+                let state = self.state.lock().unwrap();
+                let fence = match *state {
+                    FenceSignalFutureState::Flushed(_, ref fence) => fence,
+                    FenceSignalFutureState::Cleaned | FenceSignalFutureState::Poisonned => {
+                        return Ok(());
+                    },
+                    // Flushing succeeded.
+                    _ => unreachable!(),
+                };
+
+                let elapsed = start.elapsed();
+                if elapsed >= timeout {
+                    return Err(FlushError::Timeout);
+                }
+
+                let slice = cmp::min(timeout - elapsed, Duration::from_millis(PUMP_SLICE_MS));
+                match fence.wait(slice) {
+                    Ok(()) => break,
+                    Err(FenceWaitError::Timeout) => (),
+                    Err(err) => return Err(FlushError::from(err)),
+                }
+            }
+
+            // The state is unlocked, so that `pump` can use the future.
+            pump();
+        }
+
+        self.cleanup_finished_impl();
+        Ok(())
+    }
+
+    /// Flushes the future, then blocks the current thread until the GPU has finished executing
+    /// it or until `timeout` has elapsed.
+    ///
+    /// On success, the future is cleaned up as with `cleanup_finished()`. If the timeout is
+    /// reached, `FlushError::Timeout` is returned and the future is left untouched.
+    #[inline]
+    pub fn wait(&self, timeout: Duration) -> Result<(), FlushError> {
+        self.wait_pumping(timeout, || ())
+    }
+
+    /// Returns true if the fence has been signaled, which means that the GPU has finished
+    /// executing the future. Doesn't flush the future and doesn't block.
+    ///
+    /// Contrary to `cleanup_finished()`, this doesn't change the state of the future and doesn't
+    /// release the previous future. This makes it possible to poll many futures and to only
+    /// clean up some of them. Calling `cleanup_finished()` afterwards still cleans up the future
+    /// as usual.
+    pub fn is_signaled(&self) -> Result<bool, OomError> {
+        match *self.state.lock().unwrap() {
+            FenceSignalFutureState::Flushed(_, ref fence) => fence.ready(),
+            FenceSignalFutureState::Cleaned | FenceSignalFutureState::Poisonned => Ok(true),
+            _ => Ok(false),
+        }
+    }
+
+    /// Returns the result of each present of the submission, if the previous future ends with
+    /// one or more `PresentFuture`s. The results are in the order in which the presents were
+    /// chained, and the list is empty if nothing has been presented yet.
+    ///
+    /// Presenting several swapchains at once, for example one per monitor, is done by building
+    /// a `PresentFuture` on top of another one. The presents are then submitted together and
+    /// followed with a single fence signal. A swapchain that is out of date or whose surface is
+    /// lost doesn't prevent the other images from being presented nor the fence from being
+    /// signaled, and flushing still succeeds. Check the results in order to know which
+    /// swapchains must be recreated.
+    pub fn present_results(&self) -> Vec<SwapchainPresentResult> {
+        self.present_results.lock().unwrap().clone()
+    }
+
+    /// Returns the current state of the future.
+    ///
+    /// This doesn't check whether the GPU has finished. A flushed future only becomes
+    /// `FenceSignalFutureStatus::Cleaned` after `cleanup_finished()` has observed that it is
+    /// finished.
+    pub fn status(&self) -> FenceSignalFutureStatus {
+        match *self.state.lock().unwrap() {
+            FenceSignalFutureState::Pending(_, _) => FenceSignalFutureStatus::Pending,
+            FenceSignalFutureState::PartiallyFlushed(_, _) => {
+                FenceSignalFutureStatus::PartiallyFlushed
+            },
+            FenceSignalFutureState::PresentSemaphoreSignaled(_, _) => {
+                FenceSignalFutureStatus::PartiallyFlushed
+            },
+            FenceSignalFutureState::Flushed(_, _) => FenceSignalFutureStatus::Flushed,
+            FenceSignalFutureState::Cleaned => FenceSignalFutureStatus::Cleaned,
+            FenceSignalFutureState::Poisonned => FenceSignalFutureStatus::Poisoned,
+        }
+    }
+
+    // Returns true if the previous future has been cleaned up, which means that dropping this
+    // future doesn't block.
+    fn is_cleaned(&self) -> bool {
+        match *self.state.lock().unwrap() {
+            FenceSignalFutureState::Cleaned | FenceSignalFutureState::Poisonned => true,
+            _ => false,
+        }
+    }
+
+    // Calls all the functions registered with `notify_on_complete`, then removes them.
+    fn notify_completion(&self) {
+        let mut on_complete = self.on_complete.lock().unwrap();
+        for mut callback in on_complete.drain(..) {
+            callback();
+        }
+    }
+
     // Implementation of `cleanup_finished`, but takes a `&self` instead of a `&mut self`.
     // This is an external function so that we can also call it from an `Arc<FenceSignalFuture>`.
     #[inline]
     fn cleanup_finished_impl(&self) {
+        let _ = self.take_finished();
+    }
+
+    // If the GPU has finished executing the submission, switches the state to `Cleaned` and
+    // returns the previous future, which has been signaled as finished. Doesn't block.
+    //
+    // Returns `Ok(None)` if the future isn't flushed or isn't finished yet. If the device is
+    // lost, the future is cleaned up as well, as the GPU can no longer be using the resources,
+    // but the previous future is destroyed and an error is returned.
+    fn take_finished(&self) -> Result<Option<F>, FlushError> {
         let mut state = self.state.lock().unwrap();
 
-        match *state {
+        let device_lost = match *state {
             FenceSignalFutureState::Flushed(_, ref fence) => {
                 match fence.wait(Duration::from_secs(0)) {
-                    Ok(()) => (),
-                    Err(_) => return,
+                    Ok(()) => false,
+                    Err(FenceWaitError::DeviceLostError) => true,
+                    Err(FenceWaitError::Timeout) => return Ok(None),
+                    Err(err) => return Err(FlushError::from(err)),
                 }
             },
-            _ => return,
+            _ => return Ok(None),
         };
 
         // This code can only be reached if we're already flushed and waiting on the fence
         // succeeded.
-        *state = FenceSignalFutureState::Cleaned;
+        let previous = match mem::replace(&mut *state, FenceSignalFutureState::Cleaned) {
+            FenceSignalFutureState::Flushed(previous, fence) => unsafe {
+                // Signaling the previous future so that it doesn't block when dropped.
+                previous.signal_finished();
+                match self.fence_pool {
+                    Some(ref pool) => pool.release(fence),
+                    None => *self.finished_fence.lock().unwrap() = Some(fence),
+                }
+                previous
+            },
+            _ => unreachable!(),
+        };
+
+        if device_lost {
+            return Err(FlushError::DeviceLost);
+        }
+
+        self.notify_completion();
+        Ok(Some(previous))
+    }
+
+    // Blocks until the fence of a flushed future is signaled, without cleaning up the future.
+    // The state stays locked during the whole wait.
+    #[cfg(feature = "async")]
+    fn wait_signaled(&self) {
+        let state = self.state.lock().unwrap();
+        if let FenceSignalFutureState::Flushed(_, ref fence) = *state {
+            while let Err(FenceWaitError::Timeout) = self.blocking_wait(fence) {}
+        }
     }
 
     // Implementation of `flush`. You must lock the state and pass the mutex guard here.
     fn flush_impl(&self, state: &mut MutexGuard<FenceSignalFutureState<F>>)
-                  -> Result<(), Box<Error>>
+                  -> Result<(), FlushError>
     {
         unsafe {
             // In this function we temporarily replace the current state with `Poisonned` at the
@@ -105,12 +693,15 @@ impl<F> FenceSignalFuture<F> where F: GpuFuture {
             // returning (even in case of error).
             let old_state = mem::replace(&mut **state, FenceSignalFutureState::Poisonned);
 
-            let (previous, fence, partially_flushed) = match old_state {
+            let (previous, fence, partially_flushed, semaphore_signaled) = match old_state {
                 FenceSignalFutureState::Pending(prev, fence) => {
-                    (prev, fence, false)
+                    (prev, fence, false, false)
                 },
                 FenceSignalFutureState::PartiallyFlushed(prev, fence) => {
-                    (prev, fence, true)
+                    (prev, fence, true, true)
+                },
+                FenceSignalFutureState::PresentSemaphoreSignaled(prev, fence) => {
+                    (prev, fence, false, true)
                 },
                 other => {
                     // We were already flushed in the past, or we're already poisonned. Don't do
@@ -120,28 +711,70 @@ impl<F> FenceSignalFuture<F> where F: GpuFuture {
                 },
             };
 
+            if partially_flushed || semaphore_signaled {
+                let mut diagnostics = self.partial_flush.lock().unwrap();
+                if let Some(max) = self.max_partial_flush_retries {
+                    if diagnostics.retries >= max {
+                        **state = if partially_flushed {
+                            FenceSignalFutureState::PartiallyFlushed(previous, fence)
+                        } else {
+                            FenceSignalFutureState::PresentSemaphoreSignaled(previous, fence)
+                        };
+                        return Err(FlushError::RetriesExceeded);
+                    }
+                }
+                diagnostics.retries += 1;
+            }
+
+            previous.validate_resources();
+
             // TODO: meh for unwrap
             let queue = previous.queue().unwrap().clone();
 
             // There are three possible outcomes for the flush operation: success, partial success
             // in which case `result` will contain `Err(OutcomeErr::Partial)`, or total failure
-            // in which case `result` will contain `Err(OutcomeErr::Full)`.
-            enum OutcomeErr<E> { Partial(E), Full(E) }
+            // in which case `result` will contain `Err(OutcomeErr::Full)`. With the semaphore
+            // present strategy, `Err(OutcomeErr::SemaphoreSignaled)` means that only the
+            // semaphore signal has been submitted.
+            enum OutcomeErr<E> { Partial(E), SemaphoreSignaled(E), Full(E) }
+
+            // Submits the presents. A present that fails because of its swapchain, for example
+            // because it is out of date, still counts as being in the queue, so it doesn't
+            // prevent the fence from being signaled. Its result is only recorded.
+            let submit_present = |present: SubmitPresentBuilder| {
+                present.submit_with_results(&queue).map(|results| {
+                    *self.present_results.lock().unwrap() = results.into_iter().collect();
+                })
+            };
+
             let result = match try!(previous.build_submission()) {
+                SubmitAnyBuilder::Empty if previous.empty_submission_is_finished() => {
+                    sync_assert!(!partially_flushed);
+                    // There's nothing to wait for, so we skip the submission that would only
+                    // signal the fence and clean up right away. The fence is left unsignaled.
+                    previous.signal_finished();
+                    match self.fence_pool {
+                        Some(ref pool) => pool.release(fence),
+                        None => *self.finished_fence.lock().unwrap() = Some(fence),
+                    }
+                    **state = FenceSignalFutureState::Cleaned;
+                    self.notify_completion();
+                    return Ok(());
+                },
                 SubmitAnyBuilder::Empty => {
-                    debug_assert!(!partially_flushed);
+                    sync_assert!(!partially_flushed);
                     let mut b = SubmitCommandBufferBuilder::new();
                     b.set_fence_signal(&fence);
                     b.submit(&queue).map_err(|err| OutcomeErr::Full(err.into()))
                 },
                 SubmitAnyBuilder::SemaphoresWait(sem) => {
-                    debug_assert!(!partially_flushed);
+                    sync_assert!(!partially_flushed);
                     let b: SubmitCommandBufferBuilder = sem.into();
-                    debug_assert!(!b.has_fence());
+                    sync_assert!(!b.has_fence());
                     b.submit(&queue).map_err(|err| OutcomeErr::Full(err.into()))
                 },
                 SubmitAnyBuilder::CommandBuffer(mut cb_builder) => {
-                    debug_assert!(!partially_flushed);
+                    sync_assert!(!partially_flushed);
                     // The assert below could technically be a debug assertion as it is part of the
                     // safety contract of the trait. However it is easy to get this wrong if you
                     // write a custom implementation, and if so the consequences would be
@@ -151,11 +784,28 @@ impl<F> FenceSignalFuture<F> where F: GpuFuture {
                     cb_builder.set_fence_signal(&fence);
                     cb_builder.submit(&queue).map_err(|err| OutcomeErr::Full(err.into()))
                 },
-                SubmitAnyBuilder::QueuePresent(present) => {
+                SubmitAnyBuilder::QueuePresent(mut present) => {
                     let intermediary_result = if partially_flushed {
                         Ok(())
+                    } else if let Some(ref semaphore) = self.present_semaphore {
+                        let signal_result = if semaphore_signaled {
+                            Ok(())
+                        } else {
+                            let mut b = SubmitCommandBufferBuilder::new();
+                            b.add_signal_semaphore(semaphore);
+                            b.submit(&queue)
+                        };
+
+                        match signal_result {
+                            Ok(()) => {
+                                present.add_wait_semaphore(semaphore);
+                                submit_present(present)
+                                    .map_err(|err| OutcomeErr::SemaphoreSignaled(err.into()))
+                            },
+                            Err(err) => Err(OutcomeErr::Full(err.into())),
+                        }
                     } else {
-                        present.submit(&queue)
+                        submit_present(present).map_err(|err| OutcomeErr::Full(err.into()))
                     };
                     match intermediary_result {
                         Ok(()) => {
@@ -163,23 +813,28 @@ impl<F> FenceSignalFuture<F> where F: GpuFuture {
                             b.set_fence_signal(&fence);
                             b.submit(&queue).map_err(|err| OutcomeErr::Partial(err.into()))
                         },
-                        Err(err) => {
-                            Err(OutcomeErr::Full(err.into()))
-                        }
+                        Err(err) => Err(err),
                     }
                 },
             };
 
             // Restore the state before returning.
+            let result: Result<(), OutcomeErr<FlushError>> = result;
             match result {
                 Ok(()) => {
                     **state = FenceSignalFutureState::Flushed(previous, fence);
                     Ok(())
                 },
                 Err(OutcomeErr::Partial(err)) => {
+                    self.partial_flush.lock().unwrap().last_error = Some(err.to_string());
                     **state = FenceSignalFutureState::PartiallyFlushed(previous, fence);
                     Err(err)
                 },
+                Err(OutcomeErr::SemaphoreSignaled(err)) => {
+                    self.partial_flush.lock().unwrap().last_error = Some(err.to_string());
+                    **state = FenceSignalFutureState::PresentSemaphoreSignaled(previous, fence);
+                    Err(err)
+                },
                 Err(OutcomeErr::Full(err)) => {
                     **state = FenceSignalFutureState::Pending(previous, fence);
                     Err(err)
@@ -195,6 +850,7 @@ impl<F> FenceSignalFutureState<F> {
         match *self {
             FenceSignalFutureState::Pending(ref prev, _) => Some(prev),
             FenceSignalFutureState::PartiallyFlushed(ref prev, _) => Some(prev),
+            FenceSignalFutureState::PresentSemaphoreSignaled(ref prev, _) => Some(prev),
             FenceSignalFutureState::Flushed(ref prev, _) => Some(prev),
             FenceSignalFutureState::Cleaned => None,
             FenceSignalFutureState::Poisonned => None,
@@ -209,24 +865,25 @@ unsafe impl<F> GpuFuture for FenceSignalFuture<F> where F: GpuFuture {
     }
 
     #[inline]
-    unsafe fn build_submission(&self) -> Result<SubmitAnyBuilder, Box<Error>> {
+    unsafe fn build_submission(&self) -> Result<SubmitAnyBuilder, FlushError> {
         let mut state = self.state.lock().unwrap();
         try!(self.flush_impl(&mut state));
 
         match *state {
             FenceSignalFutureState::Flushed(_, ref fence) => {
-                try!(fence.wait(Duration::from_secs(600)));     // TODO: arbitrary timeout?
+                try!(self.blocking_wait(fence).map_err(FlushError::from));
             },
             FenceSignalFutureState::Cleaned | FenceSignalFutureState::Poisonned => (),
             FenceSignalFutureState::Pending(_, _)  => unreachable!(),
             FenceSignalFutureState::PartiallyFlushed(_, _) => unreachable!(),
+            FenceSignalFutureState::PresentSemaphoreSignaled(_, _) => unreachable!(),
         }
 
         Ok(SubmitAnyBuilder::Empty)
     }
 
     #[inline]
-    fn flush(&self) -> Result<(), Box<Error>> {
+    fn flush(&self) -> Result<(), FlushError> {
         let mut state = self.state.lock().unwrap();
         self.flush_impl(&mut state)
     }
@@ -250,8 +907,16 @@ unsafe impl<F> GpuFuture for FenceSignalFuture<F> where F: GpuFuture {
 
     #[inline]
     fn queue(&self) -> Option<&Arc<Queue>> {
-        // FIXME: reimplement correctly ; either find a solution or change the API to take &mut self
-        None
+        match *self.state.lock().unwrap() {
+            FenceSignalFutureState::Cleaned | FenceSignalFutureState::Poisonned => None,
+            _ => Some(&self.queue),
+        }
+    }
+
+    #[inline]
+    fn queue_family(&self) -> Option<QueueFamily> {
+        // Unlike `queue()`, the family is still reported once the future has been cleaned up.
+        Some(self.queue.family())
     }
 
     #[inline]
@@ -275,6 +940,79 @@ unsafe impl<F> GpuFuture for FenceSignalFuture<F> where F: GpuFuture {
             Err(())
         }
     }
+
+    #[inline]
+    fn acquired_swapchain_image(&self, swapchain: &Swapchain) -> Option<usize> {
+        let state = self.state.lock().unwrap();
+        state.get_prev().and_then(|previous| previous.acquired_swapchain_image(swapchain))
+    }
+
+    #[inline]
+    fn validate_resources(&self) {
+        let state = self.state.lock().unwrap();
+        if let Some(previous) = state.get_prev() {
+            previous.validate_resources();
+        }
+    }
+
+    #[inline]
+    fn signals_fence(&self) -> bool {
+        true
+    }
+
+    #[inline]
+    fn is_flushed(&self) -> bool {
+        match *self.state.lock().unwrap() {
+            FenceSignalFutureState::Flushed(_, _) | FenceSignalFutureState::Cleaned => true,
+            _ => false,
+        }
+    }
+
+    #[inline]
+    fn pending_fences(&self, fences: &mut Vec<vk::Fence>) -> bool {
+        // Cleaning up only ever looks at the fence, and does nothing if we aren't flushed yet.
+        if let FenceSignalFutureState::Flushed(_, ref fence) = *self.state.lock().unwrap() {
+            fences.push(fence.internal_object());
+        }
+        true
+    }
+
+    #[inline]
+    fn empty_submission_is_finished(&self) -> bool {
+        // `build_submission()` waits for the fence.
+        true
+    }
+
+    #[inline]
+    fn shared_identities(&self, ids: &mut Vec<usize>) {
+        let state = self.state.lock().unwrap();
+        if let Some(previous) = state.get_prev() {
+            previous.shared_identities(ids);
+        }
+    }
+}
+
+// Same as the access checks of `GpuFuture`, without locking `state`.
+unsafe impl<F> GpuFutureMut for FenceSignalFuture<F> where F: GpuFuture {
+    #[inline]
+    fn check_buffer_access_mut(&mut self, buffer: &BufferAccess, exclusive: bool, queue: &Queue)
+                               -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        match self.state.get_mut().unwrap().get_prev() {
+            Some(previous) => previous.check_buffer_access(buffer, exclusive, queue),
+            None => Err(()),
+        }
+    }
+
+    #[inline]
+    fn check_image_access_mut(&mut self, image: &ImageAccess, exclusive: bool, queue: &Queue)
+                              -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        match self.state.get_mut().unwrap().get_prev() {
+            Some(previous) => previous.check_image_access(image, exclusive, queue),
+            None => Err(()),
+        }
+    }
 }
 
 unsafe impl<F> DeviceOwned for FenceSignalFuture<F> where F: GpuFuture {
@@ -289,14 +1027,25 @@ impl<F> Drop for FenceSignalFuture<F> where F: GpuFuture {
         let mut state = self.state.lock().unwrap();
 
         // We ignore any possible error while submitting for now. Problems are handled below.
-        let _ = self.flush_impl(&mut state);
+        let flush_result = self.flush_impl(&mut state);
 
         match mem::replace(&mut *state, FenceSignalFutureState::Cleaned) {
             FenceSignalFutureState::Flushed(previous, fence) => {
                 // This is a normal situation. Submitting worked.
-                // TODO: arbitrary timeout?
-                // TODO: handle errors?
-                fence.wait(Duration::from_secs(600)).unwrap();
+                match self.blocking_wait(&fence) {
+                    Ok(()) => {
+                        self.notify_completion();
+                        if let Some(ref pool) = self.fence_pool {
+                            pool.release(fence);
+                        }
+                    },
+                    // The device is gone, so the GPU can't be using the resources anymore. We
+                    // don't want to panic here, as dropping is likely to happen while the user is
+                    // tearing things down after a device loss.
+                    Err(FenceWaitError::DeviceLostError) => (),
+                    // TODO: handle errors?
+                    Err(err) => panic!("{:?}", err),
+                }
                 unsafe { previous.signal_finished(); }
             },
             FenceSignalFutureState::Cleaned => {
@@ -305,10 +1054,23 @@ impl<F> Drop for FenceSignalFuture<F> where F: GpuFuture {
             FenceSignalFutureState::Poisonned => {
                 // The previous future was already dropped and blocked the current queue.
             },
-            FenceSignalFutureState::Pending(_, _) |
-            FenceSignalFutureState::PartiallyFlushed(_, _) => {
-                // Flushing produced an error. There's nothing more we can do except drop the 
+            FenceSignalFutureState::Pending(previous, _) |
+            FenceSignalFutureState::PartiallyFlushed(previous, _) => {
+                // Flushing produced an error. There's nothing more we can do except drop the
                 // previous future and let it block the current queue.
+                //
+                // If the device is lost, the destructor of the previous future would try to
+                // submit it again and panic. Nothing will ever be executed, so it is finished.
+                if let Err(FlushError::DeviceLost) = flush_result {
+                    unsafe { previous.signal_finished(); }
+                }
+            },
+            FenceSignalFutureState::PresentSemaphoreSignaled(previous, _) => {
+                // Same as above, but the present semaphore may still be signaled by the GPU. We
+                // have to wait for the queue before destroying it.
+                if let Some(queue) = previous.queue() {
+                    let _ = queue.wait();
+                }
             },
         }
     }
@@ -321,14 +1083,14 @@ unsafe impl<F> GpuFuture for Arc<FenceSignalFuture<F>> where F: GpuFuture {
     }
 
     #[inline]
-    unsafe fn build_submission(&self) -> Result<SubmitAnyBuilder, Box<Error>> {
+    unsafe fn build_submission(&self) -> Result<SubmitAnyBuilder, FlushError> {
         // Note that this is sound because we always return `SubmitAnyBuilder::Empty`. See the
         // documentation of `build_submission`.
         (**self).build_submission()
     }
 
     #[inline]
-    fn flush(&self) -> Result<(), Box<Error>> {
+    fn flush(&self) -> Result<(), FlushError> {
         (**self).flush()
     }
 
@@ -347,6 +1109,11 @@ unsafe impl<F> GpuFuture for Arc<FenceSignalFuture<F>> where F: GpuFuture {
         (**self).queue()
     }
 
+    #[inline]
+    fn queue_family(&self) -> Option<QueueFamily> {
+        (**self).queue_family()
+    }
+
     #[inline]
     fn check_buffer_access(&self, buffer: &BufferAccess, exclusive: bool, queue: &Queue)
                           -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
@@ -360,4 +1127,624 @@ unsafe impl<F> GpuFuture for Arc<FenceSignalFuture<F>> where F: GpuFuture {
     {
         (**self).check_image_access(image, exclusive, queue)
     }
+
+    #[inline]
+    fn acquired_swapchain_image(&self, swapchain: &Swapchain) -> Option<usize> {
+        (**self).acquired_swapchain_image(swapchain)
+    }
+
+    #[inline]
+    fn validate_resources(&self) {
+        (**self).validate_resources();
+    }
+
+    #[inline]
+    fn signals_fence(&self) -> bool {
+        (**self).signals_fence()
+    }
+
+    #[inline]
+    fn is_flushed(&self) -> bool {
+        (**self).is_flushed()
+    }
+
+    #[inline]
+    fn pending_fences(&self, fences: &mut Vec<vk::Fence>) -> bool {
+        (**self).pending_fences(fences)
+    }
+
+    #[inline]
+    fn shared_identities(&self, ids: &mut Vec<usize>) {
+        ids.push(&**self as *const FenceSignalFuture<F> as usize);
+        (**self).shared_identities(ids);
+    }
+
+    #[inline]
+    fn empty_submission_is_finished(&self) -> bool {
+        (**self).empty_submission_is_finished()
+    }
+}
+
+impl<F> FenceSignalFuture<F> where F: GpuFuture + Send + 'static {
+    /// Builds a handle to a shared future whose destructor never blocks.
+    ///
+    /// When a `FenceSignalFuture` is shared with an `Arc`, the owner that drops the last `Arc`
+    /// blocks until the GPU has finished executing the submission. Handles created with this
+    /// function don't hold this responsibility: if a detached handle turns out to be the last
+    /// owner of the future, it flushes the future and hands it over to its queue instead, as with
+    /// `GpuFuture::leak_on_drop()`. Therefore only the regular `Arc`s (the primary owners) can
+    /// block.
+    ///
+    /// > **Note**: If the last two owners are dropped simultaneously from different threads, it
+    /// > is possible that the future is destroyed by a detached handle in the normal way, which
+    /// > blocks.
+    #[inline]
+    pub fn detach(this: &Arc<FenceSignalFuture<F>>) -> DetachedFenceSignalFuture<F> {
+        DetachedFenceSignalFuture {
+            inner: Some(this.clone()),
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl<F> FenceSignalFuture<F> where F: GpuFuture + Send + 'static {
+    /// Turns this future into a `std::future::Future` that can be awaited from an asynchronous
+    /// task instead of blocking a thread.
+    ///
+    /// The future is flushed when it is first polled. Its output is the previous future, on
+    /// which `signal_finished()` has been called, so that dropping it releases the resources
+    /// without blocking.
+    ///
+    /// Waiting on a Vulkan fence can't be integrated in an event loop. Therefore, if the GPU
+    /// hasn't finished when the future is first polled, a thread is spawned that blocks on the
+    /// fence and wakes the task once it is signaled.
+    ///
+    /// Only available if the `async` feature of the crate is enabled.
+    #[inline]
+    pub fn into_async(self) -> FenceSignalFutureAsync<F> {
+        FenceSignalFutureAsync {
+            inner: Arc::new(self),
+            waker: Arc::new(Mutex::new(None)),
+            signaled: Arc::new(AtomicBool::new(false)),
+            waiting: false,
+        }
+    }
+}
+
+/// Adapter that implements `std::future::Future` for a `FenceSignalFuture`.
+///
+/// Created with `FenceSignalFuture::into_async()`. Only available if the `async` feature of the
+/// crate is enabled.
+#[cfg(feature = "async")]
+#[must_use = "Futures do nothing unless they are polled"]
+pub struct FenceSignalFutureAsync<F> where F: GpuFuture + Send + 'static {
+    // Shared with the thread that waits on the fence.
+    inner: Arc<FenceSignalFuture<F>>,
+    // Waker of the last task that polled the future.
+    waker: Arc<Mutex<Option<Waker>>>,
+    // Set by the waiting thread once the fence is signaled.
+    signaled: Arc<AtomicBool>,
+    // True if the waiting thread has been spawned.
+    waiting: bool,
+}
+
+// The inner future is never pinned, as it is behind an `Arc`.
+#[cfg(feature = "async")]
+impl<F> Unpin for FenceSignalFutureAsync<F> where F: GpuFuture + Send + 'static {}
+
+#[cfg(feature = "async")]
+impl<F> Future for FenceSignalFutureAsync<F> where F: GpuFuture + Send + 'static {
+    type Output = Result<F, FlushError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<F, FlushError>> {
+        let this = self.get_mut();
+
+        if !this.waiting {
+            if let Err(err) = this.inner.flush() {
+                return Poll::Ready(Err(err));
+            }
+        } else {
+            // The waiting thread keeps the state locked, so it must not be touched before the
+            // thread is done. The waker is stored before checking the flag, so that the thread
+            // can't set the flag in between without seeing the new waker.
+            *this.waker.lock().unwrap() = Some(cx.waker().clone());
+            if !this.signaled.load(Ordering::SeqCst) {
+                return Poll::Pending;
+            }
+        }
+
+        match this.inner.take_finished() {
+            Ok(Some(previous)) => return Poll::Ready(Ok(previous)),
+            Ok(None) => (),
+            Err(err) => return Poll::Ready(Err(err)),
+        }
+
+        if this.waiting {
+            // The fence is signaled, but the state hasn't been observed as such yet.
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+
+        *this.waker.lock().unwrap() = Some(cx.waker().clone());
+        this.waiting = true;
+
+        let inner = this.inner.clone();
+        let waker = this.waker.clone();
+        let signaled = this.signaled.clone();
+        thread::spawn(move || {
+            inner.wait_signaled();
+            signaled.store(true, Ordering::SeqCst);
+            if let Some(waker) = waker.lock().unwrap().take() {
+                waker.wake();
+            }
+        });
+
+        Poll::Pending
+    }
+}
+
+/// Handle to a shared `FenceSignalFuture` whose destructor never blocks.
+///
+/// See `FenceSignalFuture::detach()`.
+pub struct DetachedFenceSignalFuture<F> where F: GpuFuture + Send + 'static {
+    // Always `Some`, except during the destructor.
+    inner: Option<Arc<FenceSignalFuture<F>>>,
+}
+
+impl<F> DetachedFenceSignalFuture<F> where F: GpuFuture + Send + 'static {
+    #[inline]
+    fn inner(&self) -> &Arc<FenceSignalFuture<F>> {
+        self.inner.as_ref().unwrap()
+    }
+}
+
+impl<F> Clone for DetachedFenceSignalFuture<F> where F: GpuFuture + Send + 'static {
+    #[inline]
+    fn clone(&self) -> DetachedFenceSignalFuture<F> {
+        DetachedFenceSignalFuture {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+unsafe impl<F> GpuFuture for DetachedFenceSignalFuture<F> where F: GpuFuture + Send + 'static {
+    #[inline]
+    fn cleanup_finished(&mut self) {
+        self.inner().cleanup_finished_impl()
+    }
+
+    #[inline]
+    unsafe fn build_submission(&self) -> Result<SubmitAnyBuilder, FlushError> {
+        self.inner().build_submission()
+    }
+
+    #[inline]
+    fn flush(&self) -> Result<(), FlushError> {
+        self.inner().flush()
+    }
+
+    #[inline]
+    unsafe fn signal_finished(&self) {
+        self.inner().signal_finished()
+    }
+
+    #[inline]
+    fn queue_change_allowed(&self) -> bool {
+        self.inner().queue_change_allowed()
+    }
+
+    #[inline]
+    fn queue(&self) -> Option<&Arc<Queue>> {
+        self.inner().queue()
+    }
+
+    #[inline]
+    fn queue_family(&self) -> Option<QueueFamily> {
+        self.inner().queue_family()
+    }
+
+    #[inline]
+    fn check_buffer_access(&self, buffer: &BufferAccess, exclusive: bool, queue: &Queue)
+                          -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        self.inner().check_buffer_access(buffer, exclusive, queue)
+    }
+
+    #[inline]
+    fn check_image_access(&self, image: &ImageAccess, exclusive: bool, queue: &Queue)
+                          -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        self.inner().check_image_access(image, exclusive, queue)
+    }
+
+    #[inline]
+    fn acquired_swapchain_image(&self, swapchain: &Swapchain) -> Option<usize> {
+        self.inner().acquired_swapchain_image(swapchain)
+    }
+
+    #[inline]
+    fn validate_resources(&self) {
+        self.inner().validate_resources();
+    }
+
+    #[inline]
+    fn signals_fence(&self) -> bool {
+        self.inner().signals_fence()
+    }
+
+    #[inline]
+    fn is_flushed(&self) -> bool {
+        self.inner().is_flushed()
+    }
+
+    #[inline]
+    fn shared_identities(&self, ids: &mut Vec<usize>) {
+        self.inner().shared_identities(ids)
+    }
+
+    #[inline]
+    fn empty_submission_is_finished(&self) -> bool {
+        self.inner().empty_submission_is_finished()
+    }
+}
+
+unsafe impl<F> DeviceOwned for DetachedFenceSignalFuture<F> where F: GpuFuture + Send + 'static {
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        &self.inner().device
+    }
+}
+
+impl<F> Drop for DetachedFenceSignalFuture<F> where F: GpuFuture + Send + 'static {
+    fn drop(&mut self) {
+        let future = match Arc::try_unwrap(self.inner.take().unwrap()) {
+            Ok(future) => future,
+            // Another owner is still alive and will handle the destruction.
+            Err(_) => return,
+        };
+
+        future.cleanup_finished_impl();
+        if future.is_cleaned() {
+            return;
+        }
+
+        drop(future.leak_on_drop());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::mpsc;
+    use std::time::Duration;
+    use std::time::Instant;
+    use buffer::BufferUsage;
+    use buffer::CpuAccessibleBuffer;
+    use command_buffer::CommandBuffer;
+    use sync::AccessFlagBits;
+    use sync::FencePool;
+    use sync::FenceSignalFuture;
+    use sync::FenceSignalFutureStatus;
+    use sync::FlushError;
+    use sync::GpuFuture;
+    use sync::GpuFutureMut;
+    use sync::PipelineStages;
+    use sync::StillPending;
+
+    #[test]
+    fn notify_on_complete() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let cb = empty_command_buffer!(device, queue);
+
+        let (tx, rx) = mpsc::channel();
+        let future = cb.execute(queue.clone())
+                       .then_signal_fence_and_flush().unwrap()
+                       .notify_on_complete(tx, 12);
+        drop(future);
+
+        assert_eq!(rx.try_recv(), Ok(12));
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn detached_drop_last() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let cb = empty_command_buffer!(device, queue);
+
+        let (tx, rx) = mpsc::channel();
+        let future = Arc::new(cb.execute(queue.clone())
+                                .then_signal_fence_and_flush().unwrap()
+                                .notify_on_complete(tx, 5));
+        let detached = FenceSignalFuture::detach(&future);
+
+        // The detached handle is the last owner, so the future is handed over to the queue.
+        drop(future);
+        drop(detached);
+
+        queue.wait().unwrap();
+        assert_eq!(rx.try_recv(), Ok(5));
+    }
+
+    #[test]
+    fn status() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let cb = empty_command_buffer!(device, queue);
+        let mut future = cb.execute(queue.clone()).then_signal_fence();
+        assert_eq!(future.status(), FenceSignalFutureStatus::Pending);
+
+        future.flush().unwrap();
+        assert_eq!(future.status(), FenceSignalFutureStatus::Flushed);
+
+        queue.wait().unwrap();
+        future.cleanup_finished();
+        assert_eq!(future.status(), FenceSignalFutureStatus::Cleaned);
+    }
+
+    #[test]
+    fn reuse_fence() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let cb = empty_command_buffer!(device, queue);
+        let future = cb.execute(queue.clone()).then_signal_fence_and_flush().unwrap();
+        queue.wait().unwrap();
+        let fence = match future.into_fence() {
+            Ok(fence) => fence,
+            Err(_) => panic!(),
+        };
+        assert!(!fence.ready().unwrap());
+
+        let cb = empty_command_buffer!(device, queue);
+        let future = cb.execute(queue.clone()).then_signal_fence_with(fence);
+        future.flush().unwrap();
+        queue.wait().unwrap();
+        assert!(future.into_fence().is_ok());
+    }
+
+    #[test]
+    #[should_panic(expected = "Signaling a fence after a future that already signals a fence")]
+    fn double_fence() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let cb = empty_command_buffer!(device, queue);
+        let _ = cb.execute(queue.clone()).then_signal_fence().then_signal_fence();
+    }
+
+    #[test]
+    fn check_access_mut() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let buffer = CpuAccessibleBuffer::from_data(&device, &BufferUsage::all(),
+                                                    Some(queue.family()), 0u32).unwrap();
+        let other = CpuAccessibleBuffer::from_data(&device, &BufferUsage::all(),
+                                                   Some(queue.family()), 0u32).unwrap();
+
+        let transfer = PipelineStages { transfer: true, .. PipelineStages::none() };
+        let write = AccessFlagBits { transfer_write: true, .. AccessFlagBits::none() };
+
+        let cb = empty_command_buffer!(device, queue);
+        let mut future = cb.execute(queue.clone())
+                           .with_addressed_buffer(buffer.clone(), true, transfer, write)
+                           .then_signal_fence();
+
+        let (stages, access) = future.check_buffer_access_mut(&buffer, true, &queue)
+                                     .unwrap().unwrap();
+        assert!(stages.transfer);
+        assert!(access.transfer_write);
+        assert!(future.check_buffer_access_mut(&other, true, &queue).is_err());
+        future.flush().unwrap();
+    }
+
+    #[test]
+    fn try_replace() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let cb = empty_command_buffer!(device, queue);
+        let future = Arc::new(cb.execute(queue.clone()).then_signal_fence());
+
+        let cb = empty_command_buffer!(device, queue);
+        let next = match future.try_replace(cb.execute(queue.clone())) {
+            Ok(()) => panic!(),
+            Err(StillPending(next)) => next,
+        };
+
+        future.flush().unwrap();
+        queue.wait().unwrap();
+        assert!(future.try_replace(next).is_ok());
+        assert_eq!(future.status(), FenceSignalFutureStatus::Pending);
+        future.flush().unwrap();
+    }
+
+    #[test]
+    fn queue() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let cb = empty_command_buffer!(device, queue);
+        let future = cb.execute(queue.clone()).then_signal_fence();
+        assert!(future.queue().unwrap().is_same(&queue));
+
+        future.flush().unwrap();
+        assert!(future.queue().unwrap().is_same(&queue));
+
+        future.wait_pumping(Duration::from_secs(5), || ()).unwrap();
+        assert!(future.queue().is_none());
+    }
+
+    #[test]
+    fn queue_family_after_cleanup() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let cb = empty_command_buffer!(device, queue);
+        let future = cb.execute(queue.clone()).then_signal_fence_and_flush().unwrap();
+        assert_eq!(future.queue_family().unwrap().id(), queue.family().id());
+
+        future.wait(Duration::from_secs(5)).unwrap();
+        assert!(future.queue().is_none());
+        assert_eq!(future.queue_family().unwrap().id(), queue.family().id());
+    }
+
+    #[test]
+    fn fence_pool() {
+        let (device, queue) = gfx_dev_and_queue!();
+        let pool = FencePool::new(device.clone());
+
+        for _ in 0 .. 3 {
+            let cb = empty_command_buffer!(device, queue);
+            let future = cb.execute(queue.clone()).then_signal_fence_from_pool(&pool).unwrap();
+            assert_eq!(pool.len(), 0);
+            future.wait(Duration::from_secs(5)).unwrap();
+            assert_eq!(pool.len(), 1);
+        }
+    }
+
+    #[test]
+    fn wait_pumping() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let cb = empty_command_buffer!(device, queue);
+        let future = cb.execute(queue.clone()).then_signal_fence();
+
+        future.wait_pumping(Duration::from_secs(5), || ()).unwrap();
+        assert_eq!(future.status(), FenceSignalFutureStatus::Cleaned);
+    }
+
+    #[test]
+    fn wait_and_is_signaled() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let cb = empty_command_buffer!(device, queue);
+        let future = cb.execute(queue.clone()).then_signal_fence();
+        assert!(!future.is_signaled().unwrap());
+        assert!(future.present_results().is_empty());
+
+        future.wait(Duration::from_secs(5)).unwrap();
+        assert!(future.is_signaled().unwrap());
+        assert_eq!(future.status(), FenceSignalFutureStatus::Cleaned);
+    }
+
+    #[test]
+    fn is_signaled_keeps_state() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let cb = empty_command_buffer!(device, queue);
+        let mut future = cb.execute(queue.clone()).then_signal_fence_and_flush().unwrap();
+
+        let start = Instant::now();
+        while !future.is_signaled().unwrap() {
+            assert!(start.elapsed() < Duration::from_secs(5));
+        }
+        assert_eq!(future.status(), FenceSignalFutureStatus::Flushed);
+
+        future.cleanup_finished();
+        assert_eq!(future.status(), FenceSignalFutureStatus::Cleaned);
+    }
+
+    #[test]
+    fn wait_timeout() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let cb = empty_command_buffer!(device, queue);
+        let future = cb.execute(queue.clone()).then_signal_fence_and_flush().unwrap();
+        assert_eq!(future.wait_timeout(), Some(Duration::from_secs(600)));
+
+        future.set_wait_timeout(None);
+        assert_eq!(future.wait_timeout(), None);
+
+        // Flushing the next future waits for the fence without a timeout.
+        let cb = empty_command_buffer!(device, queue);
+        future.then_execute(queue.clone(), cb).then_signal_fence_and_flush().unwrap();
+    }
+
+    #[test]
+    fn flush_with_timeout() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let cb = empty_command_buffer!(device, queue);
+        let future = cb.execute(queue.clone())
+            .then_signal_fence_and_flush_with_timeout(Duration::from_millis(50)).unwrap();
+        assert_eq!(future.wait_timeout(), Some(Duration::from_millis(50)));
+    }
+
+    #[test]
+    fn already_flushed() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let cb = empty_command_buffer!(device, queue);
+        let future = cb.execute(queue.clone());
+        future.flush().unwrap();
+        assert!(future.is_flushed());
+
+        match future.then_signal_fence_and_flush() {
+            Err(FlushError::AlreadyFlushed) => (),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn already_flushed_with_timeout() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let cb = empty_command_buffer!(device, queue);
+        let future = cb.execute(queue.clone());
+        future.flush().unwrap();
+
+        match future.then_signal_fence_and_flush_with_timeout(Duration::from_millis(50)) {
+            Err(FlushError::AlreadyFlushed) => (),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn no_partial_flush() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let cb = empty_command_buffer!(device, queue);
+        let future = cb.execute(queue.clone()).then_signal_fence().max_partial_flush_retries(0);
+        future.flush().unwrap();
+        assert_eq!(future.partial_flush_retries(), 0);
+        assert!(future.last_partial_flush_error().is_none());
+    }
+
+    // Minimal executor that parks the current thread until the future wakes it.
+    #[cfg(feature = "async")]
+    fn block_on<T>(mut future: T) -> T::Output where T: ::std::future::Future + Unpin {
+        use std::pin::Pin;
+        use std::task::Context;
+        use std::task::Poll;
+        use std::task::Wake;
+        use std::thread;
+        use std::thread::Thread;
+
+        struct ThreadWaker(Thread);
+        impl Wake for ThreadWaker {
+            fn wake(self: Arc<Self>) {
+                self.0.unpark();
+            }
+        }
+
+        let waker = Arc::new(ThreadWaker(thread::current())).into();
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            match Pin::new(&mut future).poll(&mut cx) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn into_async() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let cb = empty_command_buffer!(device, queue);
+
+        let future = cb.execute(queue.clone()).then_signal_fence().into_async();
+        let previous = block_on(future).unwrap();
+        assert!(previous.queue().is_some());
+        // The previous future has been signaled as finished, so dropping it doesn't block.
+        drop(previous);
+    }
 }
@@ -0,0 +1,105 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::sync::mpsc;
+use std::sync::mpsc::Receiver;
+
+use sync::FenceSignalFuture;
+use sync::GpuFuture;
+
+/// Owns the future of a frame that is in flight, and the resources it uses.
+///
+/// This is meant to be used when there are multiple frames in flight. Each frame slot holds a
+/// `FrameToken`, and when the slot comes around again you call `reclaim()` to know whether the
+/// GPU has finished processing the frame.
+///
+/// Dropping a `FrameToken` whose frame isn't finished blocks until it is, just like dropping a
+/// `FenceSignalFuture`. Use `reclaim()` if you don't want to block.
+#[must_use = "Dropping this object will immediately block the thread until the GPU has finished processing the submission"]
+pub struct FrameToken<F> where F: GpuFuture {
+    // The future of the frame. `None` if the frame has been reclaimed.
+    future: Option<FenceSignalFuture<F>>,
+    // Receives a message once the future is observed to be finished.
+    completion: Receiver<()>,
+}
+
+impl<F> FrameToken<F> where F: GpuFuture {
+    /// Builds a new `FrameToken` that owns the given future.
+    ///
+    /// The future should already be flushed, otherwise it will be flushed the first time you
+    /// call `reclaim()`.
+    pub fn new(future: FenceSignalFuture<F>) -> FrameToken<F> {
+        let (tx, rx) = mpsc::channel();
+
+        FrameToken {
+            future: Some(future.notify_on_complete(tx, ())),
+            completion: rx,
+        }
+    }
+
+    /// Checks whether the GPU has finished processing the frame, without blocking. If so,
+    /// destroys the future and releases the resources it uses.
+    ///
+    /// Returns true if the frame has been reclaimed, either now or during a previous call.
+    pub fn reclaim(&mut self) -> bool {
+        let finished = match self.future {
+            Some(ref mut future) => {
+                // Errors are ignored here. They will be reported again when blocking.
+                let _ = future.flush();
+                future.cleanup_finished();
+                self.completion.try_recv().is_ok()
+            },
+            None => return true,
+        };
+
+        if finished {
+            self.future = None;
+        }
+
+        finished
+    }
+
+    /// Blocks until the GPU has finished processing the frame, then destroys the future and
+    /// releases the resources it uses.
+    ///
+    /// Does nothing if the frame has already been reclaimed.
+    #[inline]
+    pub fn force(&mut self) {
+        // Dropping the future blocks until it is finished.
+        self.future = None;
+    }
+
+    /// Returns true if the frame has been reclaimed.
+    #[inline]
+    pub fn is_reclaimed(&self) -> bool {
+        self.future.is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use command_buffer::CommandBuffer;
+    use sync::FrameToken;
+    use sync::GpuFuture;
+
+    #[test]
+    fn force_then_reclaim() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let cb = empty_command_buffer!(device, queue);
+
+        let future = cb.execute(queue.clone()).then_signal_fence_and_flush().unwrap();
+        let mut token = FrameToken::new(future);
+        assert!(!token.is_reclaimed());
+
+        token.force();
+        assert!(token.is_reclaimed());
+        assert!(token.reclaim());
+    }
+}
@@ -0,0 +1,163 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::error::Error;
+use std::ops::Range;
+use std::sync::Arc;
+
+use buffer::BufferAccess;
+use buffer::CpuAccessibleBuffer;
+use command_buffer::commands_raw::CmdPipelineBarrier;
+use device::Queue;
+use memory::Content;
+use memory::pool::MemoryPool;
+use sync::AccessFlagBits;
+use sync::GpuFuture;
+use sync::PipelineStages;
+use sync::future::one_shot::OneShotBuilder;
+use sync::future::one_shot::OneShotCommandBuffer;
+use sync::future::one_shot::OneShotResources;
+use OomError;
+use VulkanObject;
+
+/// Command buffer that makes the host writes to a range of a buffer visible to the commands
+/// that follow it.
+///
+/// Created with `GpuFuture::then_after_host_write()`.
+pub type HostWriteBarrierCommandBuffer<T, A> =
+    OneShotCommandBuffer<HostWriteBarrierResources<T, A>>;
+
+/// Resources of a `HostWriteBarrierCommandBuffer`.
+pub struct HostWriteBarrierResources<T: ?Sized, A> where A: MemoryPool {
+    buffer: Arc<CpuAccessibleBuffer<T, A>>,
+    range: Range<usize>,
+}
+
+impl<T: ?Sized, A> OneShotCommandBuffer<HostWriteBarrierResources<T, A>>
+    where T: Content + Send + Sync + 'static, A: MemoryPool
+{
+    /// Flushes the range `written_range` of the bytes of `buffer` if its memory isn't
+    /// host-coherent, then builds a command buffer for the queue family of `queue` that contains
+    /// a barrier from the host writes to this range to all the accesses of the commands that
+    /// follow.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if the range is empty or out of range of the buffer.
+    ///
+    pub fn new(queue: &Queue, buffer: Arc<CpuAccessibleBuffer<T, A>>, written_range: Range<usize>)
+               -> Result<HostWriteBarrierCommandBuffer<T, A>, OomError>
+    {
+        assert!(written_range.start < written_range.end);
+        assert!(written_range.end <= buffer.size());
+
+        try!(buffer.flush_range(written_range.clone()));
+
+        let (after_stages, after_access) = after_host_write_scope();
+
+        let builder = unsafe {
+            let mut barrier = CmdPipelineBarrier::new();
+            barrier.add_buffer_memory_barrier(&buffer,
+                                              PipelineStages { host: true,
+                                                               .. PipelineStages::none() },
+                                              AccessFlagBits { host_write: true,
+                                                               .. AccessFlagBits::none() },
+                                              after_stages, after_access, false, None,
+                                              written_range.start,
+                                              written_range.end - written_range.start);
+            try!(OneShotBuilder::new(queue)).add(&barrier)
+        };
+
+        builder.build(HostWriteBarrierResources {
+            buffer: buffer,
+            range: written_range,
+        })
+    }
+
+    /// Returns the buffer that was written by the host.
+    #[inline]
+    pub fn buffer(&self) -> &Arc<CpuAccessibleBuffer<T, A>> {
+        &self.resources().buffer
+    }
+
+    /// Returns the range of bytes of the buffer that was written by the host.
+    #[inline]
+    pub fn written_range(&self) -> Range<usize> {
+        self.resources().range.clone()
+    }
+}
+
+// Destination scope of the barrier. We don't know which commands read or overwrite the data.
+#[inline]
+fn after_host_write_scope() -> (PipelineStages, AccessFlagBits) {
+    (PipelineStages { all_commands: true, .. PipelineStages::none() },
+     AccessFlagBits { memory_read: true, memory_write: true, .. AccessFlagBits::none() })
+}
+
+unsafe impl<T: ?Sized, A> OneShotResources for HostWriteBarrierResources<T, A>
+    where T: Content + Send + Sync + 'static, A: MemoryPool
+{
+    fn submit_check(&self, future: &GpuFuture, queue: &Queue) -> Result<(), Box<Error>> {
+        if future.check_buffer_access(&self.buffer, false, queue).is_ok() {
+            unsafe { self.buffer.increase_gpu_lock(); }
+        } else {
+            assert!(self.buffer.try_gpu_lock(false, queue), "The buffer written by the host is \
+                                                             already in use");
+        }
+
+        Ok(())
+    }
+
+    #[inline]
+    fn check_buffer_access(&self, buffer: &BufferAccess, _: bool, _: &Queue)
+                           -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        let raw = buffer.inner().buffer.internal_object();
+
+        if raw == self.buffer.inner().buffer.internal_object() {
+            Ok(Some(after_host_write_scope()))
+        } else {
+            Err(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use buffer::BufferUsage;
+    use buffer::CpuAccessibleBuffer;
+    use command_buffer::CommandBuffer;
+    use sync::GpuFuture;
+
+    #[test]
+    fn after_host_write() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let buffer = CpuAccessibleBuffer::from_iter(&device, &BufferUsage::all(),
+                                                    Some(queue.family()), 0 .. 16u32).unwrap();
+        buffer.write().unwrap()[3] = 12;
+
+        let cb = empty_command_buffer!(device, queue);
+        cb.execute(queue.clone())
+          .then_after_host_write(buffer, 12 .. 16).unwrap()
+          .then_signal_fence_and_flush().unwrap();
+    }
+
+    #[test]
+    #[should_panic]
+    fn out_of_range() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let buffer = CpuAccessibleBuffer::from_iter(&device, &BufferUsage::all(),
+                                                    Some(queue.family()), 0 .. 4u32).unwrap();
+
+        let cb = empty_command_buffer!(device, queue);
+        let _ = cb.execute(queue.clone()).then_after_host_write(buffer, 8 .. 32);
+    }
+}
@@ -0,0 +1,96 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::error::Error;
+use std::ops::Range;
+
+use command_buffer::commands_raw::CmdPipelineBarrier;
+use device::Queue;
+use image::ImageAccess;
+use image::Layout;
+use sync::AccessFlagBits;
+use sync::GpuFuture;
+use sync::PipelineStages;
+use sync::future::one_shot::OneShotBuilder;
+use sync::future::one_shot::OneShotCommandBuffer;
+use sync::future::one_shot::OneShotResources;
+use OomError;
+use VulkanObject;
+
+/// Command buffer that contains a single image memory barrier.
+///
+/// Created with `GpuFuture::then_image_barrier()`.
+pub type ImageBarrierCommandBuffer<I> = OneShotCommandBuffer<ImageBarrierResources<I>>;
+
+/// Resources of an `ImageBarrierCommandBuffer`.
+pub struct ImageBarrierResources<I> {
+    image: I,
+    // Stages and accesses of the destination scope of the barrier.
+    dest_stages: PipelineStages,
+    dest_access: AccessFlagBits,
+}
+
+impl<I> OneShotCommandBuffer<ImageBarrierResources<I>> where I: ImageAccess {
+    /// Builds a command buffer containing the barrier, for the queue family of `queue`.
+    ///
+    /// # Safety
+    ///
+    /// See the documentation of `GpuFuture::then_image_barrier()`.
+    ///
+    pub unsafe fn new(queue: &Queue, image: I, mipmaps: Range<u32>, layers: Range<u32>,
+                      source_stages: PipelineStages, dest_stages: PipelineStages,
+                      source_access: AccessFlagBits, dest_access: AccessFlagBits,
+                      old_layout: Layout, new_layout: Layout)
+                      -> Result<ImageBarrierCommandBuffer<I>, OomError>
+    {
+        let builder = {
+            let mut barrier = CmdPipelineBarrier::new();
+            barrier.add_image_memory_barrier(&image, mipmaps, layers, source_stages,
+                                             source_access, dest_stages, dest_access, false,
+                                             None, old_layout, new_layout);
+            try!(OneShotBuilder::new(queue)).add(&barrier)
+        };
+
+        builder.build(ImageBarrierResources {
+            image: image,
+            dest_stages: dest_stages,
+            dest_access: dest_access,
+        })
+    }
+
+    /// Returns the image that the barrier applies to.
+    #[inline]
+    pub fn image(&self) -> &I {
+        &self.resources().image
+    }
+}
+
+unsafe impl<I> OneShotResources for ImageBarrierResources<I> where I: ImageAccess {
+    fn submit_check(&self, future: &GpuFuture, queue: &Queue) -> Result<(), Box<Error>> {
+        if future.check_image_access(&self.image, true, queue).is_ok() {
+            unsafe { self.image.increase_gpu_lock(); }
+        } else {
+            assert!(self.image.try_gpu_lock(true, queue), "The image of an image barrier is \
+                                                           already in use");
+        }
+
+        Ok(())
+    }
+
+    #[inline]
+    fn check_image_access(&self, image: &ImageAccess, _: bool, _: &Queue)
+                          -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        if self.image.inner().internal_object() == image.inner().internal_object() {
+            Ok(Some((self.dest_stages, self.dest_access)))
+        } else {
+            Err(())
+        }
+    }
+}
@@ -7,22 +7,38 @@
 // notice may not be copied, modified, or distributed except
 // according to those terms.
 
-use std::error::Error;
+use std::error;
+use std::fmt;
 use std::sync::Arc;
 
 use buffer::BufferAccess;
 use command_buffer::submit::SubmitAnyBuilder;
+use command_buffer::submit::SubmitCommandBufferBuilder;
+use command_buffer::submit::SubmitPresentBuilder;
+use command_buffer::submit::SubmitSemaphoresWaitBuilder;
 use device::Device;
 use device::DeviceOwned;
 use device::Queue;
 use image::ImageAccess;
+use swapchain::Swapchain;
 use sync::AccessFlagBits;
+use sync::FlushError;
 use sync::GpuFuture;
 use sync::PipelineStages;
 
 use VulkanObject;
+use VulkanPointers;
+use vk;
 
 /// Joins two futures together.
+///
+/// # Panic
+///
+/// - Panics if the futures don't belong to the same device.
+/// - Panics if both futures can't change their queue and are bound to different queues.
+/// - Panics if both futures contain the same shared future. A future can't be joined with
+///   itself, as all its accesses would be reported twice.
+///
 // TODO: handle errors
 #[inline]
 pub fn join<F, S>(first: F, second: S) -> JoinFuture<F, S>
@@ -34,9 +50,18 @@ pub fn join<F, S>(first: F, second: S) -> JoinFuture<F, S>
         assert!(first.queue().unwrap().is_same(second.queue().unwrap()));
     }
 
+    let mut first_ids = Vec::new();
+    first.shared_identities(&mut first_ids);
+    let mut second_ids = Vec::new();
+    second.shared_identities(&mut second_ids);
+    assert!(!first_ids.iter().any(|id| second_ids.contains(id)),
+            "Can't join a future with itself");
+
     JoinFuture {
         first: first,
         second: second,
+        max_wait_semaphores: None,
+        preferred_queue: None,
     }
 }
 
@@ -45,13 +70,75 @@ pub fn join<F, S>(first: F, second: S) -> JoinFuture<F, S>
 pub struct JoinFuture<A, B> {
     first: A,
     second: B,
+    // If `Some`, building the submission fails if it would wait on more semaphores than this.
+    max_wait_semaphores: Option<usize>,
+    // Queue returned by `queue()` when both futures allow changing their queue, or when neither
+    // of them is bound to a queue.
+    preferred_queue: Option<Arc<Queue>>,
+}
+
+impl<A, B> JoinFuture<A, B> where A: GpuFuture, B: GpuFuture {
+    /// Sets the maximum number of semaphores that the submission built by this future is allowed
+    /// to wait upon.
+    ///
+    /// If `wait_semaphore_count()` exceeds this value when the future is flushed or submitted as
+    /// part of another future, a `TooManyWaitSemaphoresError` is returned instead of building
+    /// the submission. No work is submitted in that case.
+    #[inline]
+    pub fn with_max_wait_semaphores(mut self, max: usize) -> JoinFuture<A, B> {
+        self.max_wait_semaphores = Some(max);
+        self
+    }
+
+    /// Returns the maximum set with `with_max_wait_semaphores()`, if any.
+    #[inline]
+    pub fn max_wait_semaphores(&self) -> Option<usize> {
+        self.max_wait_semaphores
+    }
+
+    /// Returns true if the two joined futures are bound to different queues.
+    ///
+    /// Two futures bound to different queues can only be joined if one of them allows changing
+    /// its queue, in which case `queue()` returns the queue of the other one. The work of the
+    /// first one is still executed on its own queue, so the submission of the joined future
+    /// depends on another queue, usually through a semaphore. Returns false if one of the futures
+    /// isn't bound to any queue.
+    #[inline]
+    pub fn spans_multiple_queues(&self) -> bool {
+        match (self.first.queue(), self.second.queue()) {
+            (Some(q1), Some(q2)) => !q1.is_same(q2),
+            _ => false,
+        }
+    }
+
+    /// Sets the queue that `queue()` returns when the choice is free.
+    ///
+    /// If both joined futures allow changing their queue, the work that follows this future can
+    /// be submitted on any queue, and `queue()` returns `queue`. This can be used to balance the
+    /// load between multiple queues. Otherwise the preference is ignored, as the queue is imposed
+    /// by one of the futures.
+    ///
+    /// The preference is also used when neither future is bound to a queue. Joining two futures
+    /// such as two `DummyFuture`s otherwise produces a future whose `queue()` is `None`, and
+    /// functions like `then_execute_same_queue()` panic on it.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if `queue` doesn't belong to the same device as the futures.
+    ///
+    #[inline]
+    pub fn prefer_queue(mut self, queue: &Arc<Queue>) -> JoinFuture<A, B> {
+        assert_eq!(queue.device().internal_object(), self.device().internal_object());
+        self.preferred_queue = Some(queue.clone());
+        self
+    }
 }
 
 unsafe impl<A, B> DeviceOwned for JoinFuture<A, B> where A: DeviceOwned, B: DeviceOwned {
     #[inline]
     fn device(&self) -> &Arc<Device> {
         let device = self.first.device();
-        debug_assert_eq!(self.second.device().internal_object(), device.internal_object());
+        sync_assert_eq!(self.second.device().internal_object(), device.internal_object());
         device
     }
 }
@@ -59,12 +146,16 @@ unsafe impl<A, B> DeviceOwned for JoinFuture<A, B> where A: DeviceOwned, B: Devi
 unsafe impl<A, B> GpuFuture for JoinFuture<A, B> where A: GpuFuture, B: GpuFuture {
     #[inline]
     fn cleanup_finished(&mut self) {
+        if !needs_cleanup(self) {
+            return;
+        }
+
         self.first.cleanup_finished();
         self.second.cleanup_finished();
     }
 
     #[inline]
-    fn flush(&self) -> Result<(), Box<Error>> {
+    fn flush(&self) -> Result<(), FlushError> {
         // Since each future remembers whether it has been flushed, there's no safety issue here
         // if we call this function multiple times.
         try!(self.first.flush());
@@ -73,7 +164,16 @@ unsafe impl<A, B> GpuFuture for JoinFuture<A, B> where A: GpuFuture, B: GpuFutur
     }
 
     #[inline]
-    unsafe fn build_submission(&self) -> Result<SubmitAnyBuilder, Box<Error>> {
+    unsafe fn build_submission(&self) -> Result<SubmitAnyBuilder, FlushError> {
+        // Checked before building the submissions of the two branches, as this can submit some
+        // of their work.
+        if let Some(max) = self.max_wait_semaphores {
+            let count = self.wait_semaphore_count();
+            if count > max {
+                return Err(FlushError::from(TooManyWaitSemaphoresError { count: count, max: max }));
+            }
+        }
+
         let first = try!(self.first.build_submission());
         let second = try!(self.second.build_submission());
 
@@ -111,10 +211,12 @@ unsafe impl<A, B> GpuFuture for JoinFuture<A, B> where A: GpuFuture, B: GpuFutur
                 SubmitAnyBuilder::Empty
             },
             (SubmitAnyBuilder::CommandBuffer(a), SubmitAnyBuilder::QueuePresent(b)) => {
-                unimplemented!()
+                try!(a.submit(&self.first.queue().clone().unwrap()));
+                SubmitAnyBuilder::QueuePresent(b)
             },
             (SubmitAnyBuilder::QueuePresent(a), SubmitAnyBuilder::CommandBuffer(b)) => {
-                unimplemented!()
+                try!(b.submit(&self.second.queue().clone().unwrap()));
+                SubmitAnyBuilder::QueuePresent(a)
             },
         })
     }
@@ -132,6 +234,12 @@ unsafe impl<A, B> GpuFuture for JoinFuture<A, B> where A: GpuFuture, B: GpuFutur
 
     #[inline]
     fn queue(&self) -> Option<&Arc<Queue>> {
+        if let Some(ref preferred) = self.preferred_queue {
+            if self.queue_change_allowed() {
+                return Some(preferred);
+            }
+        }
+
         match (self.first.queue(), self.second.queue()) {
             (Some(q1), Some(q2)) => if q1.is_same(&q2) {
                 Some(q1)
@@ -144,7 +252,7 @@ unsafe impl<A, B> GpuFuture for JoinFuture<A, B> where A: GpuFuture, B: GpuFutur
             },
             (Some(q), None) => Some(q),
             (None, Some(q)) => Some(q),
-            (None, None) => None,
+            (None, None) => self.preferred_queue.as_ref(),
         }
     }
 
@@ -154,18 +262,11 @@ unsafe impl<A, B> GpuFuture for JoinFuture<A, B> where A: GpuFuture, B: GpuFutur
     {
         let first = self.first.check_buffer_access(buffer, exclusive, queue);
         let second = self.second.check_buffer_access(buffer, exclusive, queue);
-        debug_assert!(!exclusive || !(first.is_ok() && second.is_ok()), "Two futures gave \
-                                                                         exclusive access to the \
-                                                                         same resource");
-        match (first, second) {
-            (Ok(v), Err(_)) | (Err(_), Ok(v)) => Ok(v),
-            (Err(()), Err(())) => Err(()),
-            (Ok(None), Ok(None)) => Ok(None),
-            (Ok(Some(a)), Ok(None)) | (Ok(None), Ok(Some(a))) => Ok(Some(a)),
-            (Ok(Some((a1, a2))), Ok(Some((b1, b2)))) => {
-                Ok(Some((a1 | b1, a2 | b2)))
-            },
+        if buffer.inner().buffer.exclusive_sharing() {
+            check_queue_family(&self.first, &first, queue);
+            check_queue_family(&self.second, &second, queue);
         }
+        merge_access(first, second, exclusive)
     }
 
     #[inline]
@@ -174,17 +275,780 @@ unsafe impl<A, B> GpuFuture for JoinFuture<A, B> where A: GpuFuture, B: GpuFutur
     {
         let first = self.first.check_image_access(image, exclusive, queue);
         let second = self.second.check_image_access(image, exclusive, queue);
-        debug_assert!(!exclusive || !(first.is_ok() && second.is_ok()), "Two futures gave \
-                                                                         exclusive access to the \
-                                                                         same resource");
-        match (first, second) {
-            (Ok(v), Err(_)) | (Err(_), Ok(v)) => Ok(v),
-            (Err(()), Err(())) => Err(()),
-            (Ok(None), Ok(None)) => Ok(None),
-            (Ok(Some(a)), Ok(None)) | (Ok(None), Ok(Some(a))) => Ok(Some(a)),
-            (Ok(Some((a1, a2))), Ok(Some((b1, b2)))) => {
-                Ok(Some((a1 | b1, a2 | b2)))
-            },
+        if image.inner().exclusive_sharing() {
+            check_queue_family(&self.first, &first, queue);
+            check_queue_family(&self.second, &second, queue);
+        }
+        merge_access(first, second, exclusive)
+    }
+
+    #[inline]
+    fn acquired_swapchain_image(&self, swapchain: &Swapchain) -> Option<usize> {
+        self.first.acquired_swapchain_image(swapchain)
+            .or_else(|| self.second.acquired_swapchain_image(swapchain))
+    }
+
+    #[inline]
+    fn validate_resources(&self) {
+        self.first.validate_resources();
+        self.second.validate_resources();
+    }
+
+    #[inline]
+    fn wait_semaphore_count(&self) -> usize {
+        self.first.wait_semaphore_count() + self.second.wait_semaphore_count()
+    }
+
+    #[inline]
+    fn is_flushed(&self) -> bool {
+        self.first.is_flushed() && self.second.is_flushed()
+    }
+
+    #[inline]
+    fn pending_fences(&self, fences: &mut Vec<vk::Fence>) -> bool {
+        // Both sides must be queried, even if the first one returns false.
+        let first = self.first.pending_fences(fences);
+        let second = self.second.pending_fences(fences);
+        first && second
+    }
+
+    #[inline]
+    fn shared_identities(&self, ids: &mut Vec<usize>) {
+        self.first.shared_identities(ids);
+        self.second.shared_identities(ids);
+    }
+
+    #[inline]
+    fn empty_submission_is_finished(&self) -> bool {
+        self.first.empty_submission_is_finished() && self.second.empty_submission_is_finished()
+    }
+}
+
+/// Error that can happen when submitting a `JoinFuture` whose maximum number of wait semaphores
+/// has been set.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TooManyWaitSemaphoresError {
+    /// Number of semaphores that the submission would have waited upon.
+    pub count: usize,
+    /// Maximum that was set with `with_max_wait_semaphores()`.
+    pub max: usize,
+}
+
+impl error::Error for TooManyWaitSemaphoresError {
+    #[inline]
+    fn description(&self) -> &str {
+        "the joined future waits on more semaphores than the maximum"
+    }
+}
+
+impl fmt::Display for TooManyWaitSemaphoresError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+/// Joins an arbitrary number of futures together.
+///
+/// This is equivalent to calling `join()` repeatedly, except that the result has a simple type
+/// and that the submissions of all the futures are merged at once.
+///
+/// # Panic
+///
+/// - Panics if the iterator is empty.
+/// - Panics if the futures don't all belong to the same device.
+/// - Panics if two futures that don't allow changing their queue are bound to different queues.
+/// - Panics if two futures contain the same shared future. See `join()`.
+///
+pub fn join_all<I>(futures: I) -> JoinAllFuture
+    where I: IntoIterator<Item = Box<GpuFuture>>
+{
+    let futures: Vec<_> = futures.into_iter().collect();
+    assert!(!futures.is_empty(), "Joining an empty list of futures");
+
+    let device = futures[0].device().internal_object();
+    let mut bound_queue: Option<&Arc<Queue>> = None;
+    let mut ids = Vec::new();
+    for future in futures.iter() {
+        assert_eq!(future.device().internal_object(), device);
+
+        let mut future_ids = Vec::new();
+        future.shared_identities(&mut future_ids);
+        assert!(!future_ids.iter().any(|id| ids.contains(id)), "Can't join a future with itself");
+        ids.extend(future_ids);
+
+        if !future.queue_change_allowed() {
+            if let Some(queue) = future.queue() {
+                if let Some(bound) = bound_queue {
+                    assert!(bound.is_same(queue));
+                }
+                bound_queue = Some(queue);
+            }
+        }
+    }
+
+    JoinAllFuture {
+        futures: futures,
+    }
+}
+
+/// Any number of futures joined into one.
+///
+/// Created with `join_all()`.
+#[must_use]
+pub struct JoinAllFuture {
+    futures: Vec<Box<GpuFuture>>,
+}
+
+impl JoinAllFuture {
+    /// Returns the number of futures that were joined.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.futures.len()
+    }
+}
+
+unsafe impl DeviceOwned for JoinAllFuture {
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        self.futures[0].device()
+    }
+}
+
+unsafe impl GpuFuture for JoinAllFuture {
+    #[inline]
+    fn cleanup_finished(&mut self) {
+        if !needs_cleanup(self) {
+            return;
+        }
+
+        for future in self.futures.iter_mut() {
+            future.cleanup_finished();
+        }
+    }
+
+    #[inline]
+    fn flush(&self) -> Result<(), FlushError> {
+        for future in self.futures.iter() {
+            try!(future.flush());
+        }
+        Ok(())
+    }
+
+    unsafe fn build_submission(&self) -> Result<SubmitAnyBuilder, FlushError> {
+        // The semaphore waits and the command buffers of all the futures are merged into a
+        // single builder each. The presents can't be merged, as they may belong to different
+        // queues.
+        let mut waits: Option<SubmitSemaphoresWaitBuilder> = None;
+        let mut command_buffers: Option<(SubmitCommandBufferBuilder, &Arc<Queue>)> = None;
+        let mut presents: Vec<(SubmitPresentBuilder, &Arc<Queue>)> = Vec::new();
+
+        for future in self.futures.iter() {
+            let submission = try!(future.build_submission());
+
+            match submission {
+                SubmitAnyBuilder::Empty => (),
+                SubmitAnyBuilder::SemaphoresWait(b) => {
+                    match waits {
+                        Some(ref mut a) => a.merge(b),
+                        None => waits = Some(b),
+                    }
+                },
+                SubmitAnyBuilder::CommandBuffer(b) => {
+                    command_buffers = Some(match command_buffers.take() {
+                        Some((a, queue)) => (a.merge(b), queue),
+                        None => (b, future.queue().unwrap()),
+                    });
+                },
+                SubmitAnyBuilder::QueuePresent(b) => {
+                    presents.push((b, future.queue().unwrap()));
+                },
+            }
+        }
+
+        // Same rules as `JoinFuture`: semaphore waits are kept over everything else, and a
+        // present is kept over command buffers unless there are multiple presents.
+        if waits.is_none() && presents.len() == 1 {
+            if let Some((cb, queue)) = command_buffers.take() {
+                try!(cb.submit(queue));
+            }
+            let (present, _) = presents.pop().unwrap();
+            return Ok(SubmitAnyBuilder::QueuePresent(present));
+        }
+
+        for (present, queue) in presents {
+            try!(present.submit(queue));
+        }
+
+        if let Some(waits) = waits {
+            if let Some((cb, queue)) = command_buffers {
+                try!(cb.submit(queue));
+            }
+            return Ok(SubmitAnyBuilder::SemaphoresWait(waits));
+        }
+
+        Ok(match command_buffers {
+            Some((cb, _)) => SubmitAnyBuilder::CommandBuffer(cb),
+            None => SubmitAnyBuilder::Empty,
+        })
+    }
+
+    #[inline]
+    unsafe fn signal_finished(&self) {
+        for future in self.futures.iter() {
+            future.signal_finished();
+        }
+    }
+
+    #[inline]
+    fn queue_change_allowed(&self) -> bool {
+        self.futures.iter().all(|f| f.queue_change_allowed())
+    }
+
+    #[inline]
+    fn queue(&self) -> Option<&Arc<Queue>> {
+        // A queue imposed by one of the futures has the priority.
+        self.futures.iter()
+            .filter(|f| !f.queue_change_allowed())
+            .filter_map(|f| f.queue())
+            .next()
+            .or_else(|| self.futures.iter().filter_map(|f| f.queue()).next())
+    }
+
+    #[inline]
+    fn check_buffer_access(&self, buffer: &BufferAccess, exclusive: bool, queue: &Queue)
+                           -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        let mut result = Err(());
+        for future in self.futures.iter() {
+            let access = future.check_buffer_access(buffer, exclusive, queue);
+            if buffer.inner().buffer.exclusive_sharing() {
+                check_queue_family(&**future, &access, queue);
+            }
+            result = merge_access(result, access, exclusive);
         }
+        result
+    }
+
+    #[inline]
+    fn check_image_access(&self, image: &ImageAccess, exclusive: bool, queue: &Queue)
+                          -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        let mut result = Err(());
+        for future in self.futures.iter() {
+            let access = future.check_image_access(image, exclusive, queue);
+            if image.inner().exclusive_sharing() {
+                check_queue_family(&**future, &access, queue);
+            }
+            result = merge_access(result, access, exclusive);
+        }
+        result
+    }
+
+    #[inline]
+    fn acquired_swapchain_image(&self, swapchain: &Swapchain) -> Option<usize> {
+        self.futures.iter().filter_map(|f| f.acquired_swapchain_image(swapchain)).next()
+    }
+
+    #[inline]
+    fn validate_resources(&self) {
+        for future in self.futures.iter() {
+            future.validate_resources();
+        }
+    }
+
+    #[inline]
+    fn wait_semaphore_count(&self) -> usize {
+        self.futures.iter().map(|f| f.wait_semaphore_count()).sum()
+    }
+
+    #[inline]
+    fn is_flushed(&self) -> bool {
+        self.futures.iter().all(|f| f.is_flushed())
+    }
+
+    #[inline]
+    fn pending_fences(&self, fences: &mut Vec<vk::Fence>) -> bool {
+        self.futures.iter().fold(true, |all, f| f.pending_fences(fences) && all)
+    }
+
+    #[inline]
+    fn shared_identities(&self, ids: &mut Vec<usize>) {
+        for future in self.futures.iter() {
+            future.shared_identities(ids);
+        }
+    }
+
+    #[inline]
+    fn empty_submission_is_finished(&self) -> bool {
+        self.futures.iter().all(|f| f.empty_submission_is_finished())
+    }
+}
+
+// Returns false if calling `cleanup_finished()` on the futures contained in `future` is known
+// to do nothing.
+//
+// Instead of letting each future poll its own fence, we poll all of them at once with a single
+// `vkWaitForFences` that returns as soon as any of them is signaled. If none is, there's nothing
+// to clean up.
+fn needs_cleanup<F>(future: &F) -> bool where F: GpuFuture {
+    let mut fences = Vec::new();
+    if !future.pending_fences(&mut fences) {
+        return true;
+    }
+
+    match fences.len() {
+        0 => false,
+        // Polling a single fence costs the same as letting its future do it.
+        1 => true,
+        num => unsafe {
+            let device = future.device();
+            let vk = device.pointers();
+            let r = vk.WaitForFences(device.internal_object(), num as u32, fences.as_ptr(),
+                                     vk::FALSE, 0);
+            // Errors are reported by the futures themselves when they poll their fence.
+            r != vk::TIMEOUT
+        },
+    }
+}
+
+// Combines the accesses that two joined futures report for the same resource.
+#[inline]
+fn merge_access(a: Result<Option<(PipelineStages, AccessFlagBits)>, ()>,
+                b: Result<Option<(PipelineStages, AccessFlagBits)>, ()>, exclusive: bool)
+                -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+{
+    sync_assert!(!exclusive || !(a.is_ok() && b.is_ok()), "Two futures gave exclusive access to \
+                                                           the same resource");
+    match (a, b) {
+        (Ok(v), Err(_)) | (Err(_), Ok(v)) => Ok(v),
+        (Err(()), Err(())) => Err(()),
+        (Ok(None), Ok(None)) => Ok(None),
+        (Ok(Some(a)), Ok(None)) | (Ok(None), Ok(Some(a))) => Ok(Some(a)),
+        (Ok(Some((a1, a2))), Ok(Some((b1, b2)))) => {
+            sync_assert!(a2.checked_or(b2).is_ok(), "The two futures accessed the same \
+                                                     resource in incompatible ways");
+            Ok(Some((a1 | b1, a2 | b2)))
+        },
+    }
+}
+
+// Panics if `future` granted access to a resource in exclusive sharing mode while belonging to a
+// queue family different from the one of `queue`. Like the other checks of the `sync` module, this
+// is only done in debug builds or with the `strict-sync` feature.
+//
+// Using an exclusive resource from another queue family requires a queue family ownership
+// transfer, which vulkano doesn't insert automatically yet.
+// TODO: insert the release and acquire barriers automatically instead
+#[inline]
+fn check_queue_family<F: ?Sized, T>(future: &F, access: &Result<T, ()>, queue: &Queue)
+    where F: GpuFuture
+{
+    if access.is_err() {
+        return;
+    }
+
+    if let Some(family) = future.queue_family() {
+        sync_assert_eq!(family.id(), queue.family().id(),
+                        "A resource in exclusive sharing mode is used by a queue family \
+                         different from the one that previously used it. You must either create \
+                         the resource with concurrent sharing, or manually perform a queue \
+                         family ownership transfer");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use buffer::BufferAccess;
+    use command_buffer::CommandBuffer;
+    use command_buffer::submit::SubmitAnyBuilder;
+    use command_buffer::submit::SubmitCommandBufferBuilder;
+    use command_buffer::submit::SubmitPresentBuilder;
+    use device::Device;
+    use device::DeviceExtensions;
+    use device::DeviceOwned;
+    use device::Queue;
+    use features::Features;
+    use format::R8G8B8A8Unorm;
+    use image::Dimensions;
+    use image::ImageAccess;
+    use image::ImmutableImage;
+    use instance::PhysicalDevice;
+    use sync::AccessFlagBits;
+    use sync::DummyFuture;
+    use sync::FlushError;
+    use sync::GpuFuture;
+    use sync::PipelineStages;
+    use sync::TooManyWaitSemaphoresError;
+    use sync::join_all;
+
+    // Future that pretends to have written every resource on its queue.
+    struct AccessAllFuture {
+        queue: Arc<Queue>,
+    }
+
+    unsafe impl GpuFuture for AccessAllFuture {
+        fn cleanup_finished(&mut self) {}
+        unsafe fn build_submission(&self) -> Result<SubmitAnyBuilder, FlushError> {
+            Ok(SubmitAnyBuilder::Empty)
+        }
+        fn flush(&self) -> Result<(), FlushError> { Ok(()) }
+        unsafe fn signal_finished(&self) {}
+        fn queue_change_allowed(&self) -> bool { false }
+        fn queue(&self) -> Option<&Arc<Queue>> { Some(&self.queue) }
+        fn check_buffer_access(&self, _: &BufferAccess, _: bool, _: &Queue)
+                               -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+        {
+            Ok(None)
+        }
+        fn check_image_access(&self, _: &ImageAccess, _: bool, _: &Queue)
+                              -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+        {
+            Ok(None)
+        }
+    }
+
+    unsafe impl DeviceOwned for AccessAllFuture {
+        fn device(&self) -> &Arc<Device> {
+            self.queue.device()
+        }
+    }
+
+    // Future bound to a queue, but that allows the work that follows it to be submitted on
+    // another queue.
+    struct MovableFuture {
+        queue: Arc<Queue>,
+    }
+
+    unsafe impl GpuFuture for MovableFuture {
+        fn cleanup_finished(&mut self) {}
+        unsafe fn build_submission(&self) -> Result<SubmitAnyBuilder, FlushError> {
+            Ok(SubmitAnyBuilder::Empty)
+        }
+        fn flush(&self) -> Result<(), FlushError> { Ok(()) }
+        unsafe fn signal_finished(&self) {}
+        fn queue_change_allowed(&self) -> bool { true }
+        fn queue(&self) -> Option<&Arc<Queue>> { Some(&self.queue) }
+        fn check_buffer_access(&self, _: &BufferAccess, _: bool, _: &Queue)
+                               -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+        {
+            Err(())
+        }
+        fn check_image_access(&self, _: &ImageAccess, _: bool, _: &Queue)
+                              -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+        {
+            Err(())
+        }
+    }
+
+    unsafe impl DeviceOwned for MovableFuture {
+        fn device(&self) -> &Arc<Device> {
+            self.queue.device()
+        }
+    }
+
+    // Future whose submission is a present, like a `PresentFuture`. Swapchains can't be created
+    // in the tests, so the present is empty and must not be submitted.
+    struct PresentLikeFuture {
+        queue: Arc<Queue>,
+    }
+
+    unsafe impl GpuFuture for PresentLikeFuture {
+        fn cleanup_finished(&mut self) {}
+        unsafe fn build_submission(&self) -> Result<SubmitAnyBuilder, FlushError> {
+            Ok(SubmitAnyBuilder::QueuePresent(SubmitPresentBuilder::new()))
+        }
+        fn flush(&self) -> Result<(), FlushError> { Ok(()) }
+        unsafe fn signal_finished(&self) {}
+        fn queue_change_allowed(&self) -> bool { false }
+        fn queue(&self) -> Option<&Arc<Queue>> { Some(&self.queue) }
+        fn check_buffer_access(&self, _: &BufferAccess, _: bool, _: &Queue)
+                               -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+        {
+            Err(())
+        }
+        fn check_image_access(&self, _: &ImageAccess, _: bool, _: &Queue)
+                              -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+        {
+            Err(())
+        }
+    }
+
+    unsafe impl DeviceOwned for PresentLikeFuture {
+        fn device(&self) -> &Arc<Device> {
+            self.queue.device()
+        }
+    }
+
+    #[test]
+    fn join_all_semaphores() {
+        let (_, queue) = gfx_dev_and_queue!();
+
+        let futures = (0 .. 5).map(|_| {
+            Box::new(AccessAllFuture { queue: queue.clone() }.then_signal_semaphore())
+                as Box<GpuFuture>
+        });
+        let joined = join_all(futures);
+        assert_eq!(joined.wait_semaphore_count(), 5);
+
+        match unsafe { joined.build_submission() }.unwrap() {
+            SubmitAnyBuilder::SemaphoresWait(sem) => {
+                let builder: SubmitCommandBufferBuilder = sem.into();
+                assert_eq!(builder.wait_dest_stages().len(), 5);
+            },
+            _ => panic!()
+        };
+    }
+
+    #[test]
+    fn command_buffer_and_present() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let cb1 = empty_command_buffer!(device, queue);
+        let cb2 = empty_command_buffer!(device, queue);
+
+        let joined = cb1.execute(queue.clone()).join(PresentLikeFuture { queue: queue.clone() });
+        match unsafe { joined.build_submission() }.unwrap() {
+            SubmitAnyBuilder::QueuePresent(_) => (),
+            _ => panic!()
+        };
+
+        let joined = PresentLikeFuture { queue: queue.clone() }.join(cb2.execute(queue.clone()));
+        match unsafe { joined.build_submission() }.unwrap() {
+            SubmitAnyBuilder::QueuePresent(_) => (),
+            _ => panic!()
+        };
+    }
+
+    #[test]
+    #[should_panic]
+    fn exclusive_image_cross_family() {
+        let instance = instance!();
+
+        let physical = match PhysicalDevice::enumerate(&instance).next() {
+            Some(p) => p,
+            None => return
+        };
+
+        let graphics = match physical.queue_families().find(|q| q.supports_graphics()) {
+            Some(q) => q,
+            None => return
+        };
+
+        let transfer = match physical.queue_families().find(|q| q.id() != graphics.id()) {
+            Some(q) => q,
+            None => return
+        };
+
+        let (device, mut queues) = match Device::new(&physical, &Features::none(),
+                                                     &DeviceExtensions::none(),
+                                                     [(graphics, 0.5), (transfer, 0.5)]
+                                                         .iter().cloned())
+        {
+            Ok(r) => r,
+            Err(_) => return
+        };
+
+        let graphics_queue = queues.next().unwrap();
+        let transfer_queue = queues.next().unwrap();
+
+        // Image that is uploaded by the transfer queue, in exclusive sharing mode.
+        let image = ImmutableImage::new(&device, Dimensions::Dim2d { width: 32, height: 32 },
+                                        R8G8B8A8Unorm, Some(transfer_queue.family())).unwrap();
+
+        let upload = AccessAllFuture { queue: transfer_queue };
+        let joined = upload.join(DummyFuture::new(device.clone()));
+
+        // Sampling from the graphics queue without an ownership transfer.
+        let _ = joined.check_image_access(&image, false, &graphics_queue);
+    }
+
+    #[test]
+    fn wait_semaphore_count() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let joined = AccessAllFuture { queue: queue.clone() }.then_signal_semaphore()
+            .join(AccessAllFuture { queue: queue.clone() }.then_signal_semaphore())
+            .join(DummyFuture::new(device.clone()));
+        assert_eq!(joined.wait_semaphore_count(), 2);
+    }
+
+    #[test]
+    fn max_wait_semaphores_exceeded() {
+        let (_, queue) = gfx_dev_and_queue!();
+
+        let joined = AccessAllFuture { queue: queue.clone() }.then_signal_semaphore()
+            .join(AccessAllFuture { queue: queue.clone() }.then_signal_semaphore())
+            .with_max_wait_semaphores(1);
+
+        match unsafe { joined.build_submission() } {
+            Err(FlushError::TooManyWaitSemaphores(TooManyWaitSemaphoresError { count: 2,
+                                                                               max: 1 })) => (),
+            _ => panic!()
+        };
+    }
+
+    #[test]
+    fn prefer_queue() {
+        let instance = instance!();
+
+        let physical = match PhysicalDevice::enumerate(&instance).next() {
+            Some(p) => p,
+            None => return
+        };
+
+        let family = match physical.queue_families().find(|q| q.queues_count() >= 2) {
+            Some(q) => q,
+            None => return
+        };
+
+        let (device, mut queues) = match Device::new(&physical, &Features::none(),
+                                                     &DeviceExtensions::none(),
+                                                     [(family, 0.5), (family, 0.5)]
+                                                         .iter().cloned())
+        {
+            Ok(r) => r,
+            Err(_) => return
+        };
+
+        let queue1 = queues.next().unwrap();
+        let queue2 = queues.next().unwrap();
+        assert!(!queue1.is_same(&queue2));
+
+        let joined = DummyFuture::new(device.clone()).join(DummyFuture::new(device.clone()));
+        assert!(joined.queue().is_none());
+        let joined = joined.prefer_queue(&queue2);
+        assert!(joined.queue().unwrap().is_same(&queue2));
+
+        let joined = MovableFuture { queue: queue1.clone() }
+            .join(MovableFuture { queue: queue1.clone() });
+        assert!(joined.queue().unwrap().is_same(&queue1));
+        let joined = joined.prefer_queue(&queue2);
+        assert!(joined.queue().unwrap().is_same(&queue2));
+
+        // The preference is ignored when a branch imposes its queue.
+        let joined = AccessAllFuture { queue: queue1.clone() }
+            .join(MovableFuture { queue: queue1.clone() })
+            .prefer_queue(&queue2);
+        assert!(joined.queue().unwrap().is_same(&queue1));
+    }
+
+    #[test]
+    fn prefer_queue_unbound() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let joined = DummyFuture::new(device.clone()).join(DummyFuture::new(device.clone()))
+                                                     .prefer_queue(&queue);
+        assert!(joined.queue().unwrap().is_same(&queue));
+    }
+
+    #[test]
+    fn batched_cleanup() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let cb1 = empty_command_buffer!(device, queue);
+        let cb2 = empty_command_buffer!(device, queue);
+        let first = cb1.execute(queue.clone()).then_signal_fence_and_flush().unwrap();
+        let second = cb2.execute(queue.clone()).then_signal_fence_and_flush().unwrap();
+        let mut joined = first.join(second);
+
+        let mut fences = Vec::new();
+        assert!(joined.pending_fences(&mut fences));
+        assert_eq!(fences.len(), 2);
+
+        // Once both futures are cleaned up, there's no fence left to poll.
+        queue.wait().unwrap();
+        joined.cleanup_finished();
+        let mut fences = Vec::new();
+        assert!(joined.pending_fences(&mut fences));
+        assert!(fences.is_empty());
+
+        // A future that doesn't report its fences disables the batching.
+        let joined = joined.join(AccessAllFuture { queue: queue.clone() });
+        assert!(!joined.pending_fences(&mut Vec::new()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Can't join a future with itself")]
+    fn self_join() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let cb = empty_command_buffer!(device, queue);
+        let future = Arc::new(cb.execute(queue.clone()).then_signal_fence());
+        let _ = future.clone().join(future);
+    }
+
+    #[test]
+    #[should_panic(expected = "Can't join a future with itself")]
+    fn self_join_through_wrapper() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let cb = empty_command_buffer!(device, queue);
+        let future = Arc::new(cb.execute(queue.clone()).then_signal_fence());
+        let _ = future.clone().join(future.then_signal_semaphore().then_signal_fence());
+    }
+
+    #[test]
+    #[should_panic(expected = "Can't join a future with itself")]
+    fn self_join_all() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let cb = empty_command_buffer!(device, queue);
+        let future = Arc::new(cb.execute(queue.clone()).then_signal_fence());
+        let cb = empty_command_buffer!(device, queue);
+        let _ = join_all(vec![Box::new(future.clone()) as Box<GpuFuture>,
+                              Box::new(future.then_execute(queue.clone(), cb)) as Box<_>]);
+    }
+
+    #[test]
+    fn spans_multiple_queues() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let joined = DummyFuture::new(device.clone())
+            .join(AccessAllFuture { queue: queue.clone() });
+        assert!(!joined.spans_multiple_queues());
+
+        let joined = AccessAllFuture { queue: queue.clone() }.then_signal_semaphore()
+            .join(AccessAllFuture { queue: queue.clone() });
+        assert!(!joined.spans_multiple_queues());
+    }
+
+    #[test]
+    fn spans_multiple_queues_cross_family() {
+        let instance = instance!();
+
+        let physical = match PhysicalDevice::enumerate(&instance).next() {
+            Some(p) => p,
+            None => return
+        };
+
+        let graphics = match physical.queue_families().find(|q| q.supports_graphics()) {
+            Some(q) => q,
+            None => return
+        };
+
+        let transfer = match physical.queue_families().find(|q| q.id() != graphics.id()) {
+            Some(q) => q,
+            None => return
+        };
+
+        let (_, mut queues) = match Device::new(&physical, &Features::none(),
+                                                &DeviceExtensions::none(),
+                                                [(graphics, 0.5), (transfer, 0.5)]
+                                                    .iter().cloned())
+        {
+            Ok(r) => r,
+            Err(_) => return
+        };
+
+        let graphics_queue = queues.next().unwrap();
+        let transfer_queue = queues.next().unwrap();
+
+        let joined = AccessAllFuture { queue: transfer_queue }.then_signal_semaphore()
+            .join(AccessAllFuture { queue: graphics_queue.clone() });
+        assert!(joined.spans_multiple_queues());
+        assert!(joined.queue().unwrap().is_same(&graphics_queue));
     }
 }
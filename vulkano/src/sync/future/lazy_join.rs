@@ -0,0 +1,324 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::cell::UnsafeCell;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use buffer::BufferAccess;
+use command_buffer::submit::SubmitAnyBuilder;
+use device::Device;
+use device::DeviceOwned;
+use device::Queue;
+use image::ImageAccess;
+use instance::QueueFamily;
+use swapchain::Swapchain;
+use sync::AccessFlagBits;
+use sync::FlushError;
+use sync::GpuFuture;
+use sync::JoinFuture;
+use sync::PipelineStages;
+
+use super::join::join;
+
+/// Builds a new lazy join future.
+#[inline]
+pub fn join_lazy<A, S, M>(first: A, make_second: M) -> LazyJoinFuture<A, S, M>
+    where A: GpuFuture, S: GpuFuture, M: FnOnce() -> Option<S>
+{
+    let imposed = if first.queue_change_allowed() {
+        None
+    } else {
+        first.queue().cloned()
+    };
+
+    LazyJoinFuture {
+        device: first.device().clone(),
+        imposed: imposed,
+        pending: Mutex::new(Some((first, make_second))),
+        resolved: UnsafeCell::new(None),
+    }
+}
+
+/// A future joined with another future that is only built the first time it is needed.
+///
+/// Created with `GpuFuture::join_lazy()`. Once the second future has been built, this future
+/// behaves exactly like a `JoinFuture`, or like the first future if the closure didn't return
+/// anything.
+///
+/// `queue()` and `queue_family()` don't call the closure. Until it has been called, they only
+/// return the queue imposed by the first future, if any.
+#[must_use]
+pub struct LazyJoinFuture<A, S, M> {
+    // The device of the future.
+    device: Arc<Device>,
+    // The queue of the first future if it doesn't allow changing its queue.
+    imposed: Option<Arc<Queue>>,
+    // The first future and the closure that builds the second one. `None` once the closure has
+    // been called.
+    pending: Mutex<Option<(A, M)>>,
+    // The result of the closure. Only written once, while `pending` is locked and before any
+    // reference to its content is handed out. See `resolve()`.
+    resolved: UnsafeCell<Option<LazyJoinState<A, S>>>,
+}
+
+enum LazyJoinState<A, S> {
+    // The closure returned a future.
+    Joined(JoinFuture<A, S>),
+    // The closure returned `None`.
+    Alone(A),
+}
+
+// The content of `resolved` is only written once, while holding the lock of `pending`. After that
+// it is only ever accessed through shared references.
+unsafe impl<A, S, M> Sync for LazyJoinFuture<A, S, M>
+    where A: GpuFuture + Send + Sync, S: GpuFuture + Send + Sync, M: Send
+{
+}
+
+impl<A, S, M> LazyJoinFuture<A, S, M>
+    where A: GpuFuture, S: GpuFuture, M: FnOnce() -> Option<S>
+{
+    /// Returns true if the closure has already been called.
+    #[inline]
+    pub fn is_resolved(&self) -> bool {
+        self.pending.lock().unwrap().is_none()
+    }
+
+    /// Returns true if the closure has already been called and returned a future.
+    #[inline]
+    pub fn is_joined(&self) -> bool {
+        let pending = self.pending.lock().unwrap();
+        if pending.is_some() {
+            return false;
+        }
+
+        match unsafe { (*self.resolved.get()).as_ref().unwrap() } {
+            &LazyJoinState::Joined(_) => true,
+            &LazyJoinState::Alone(_) => false,
+        }
+    }
+
+    // Returns the future that this future behaves like, or `None` if the closure hasn't been
+    // called yet.
+    fn resolved(&self) -> Option<&GpuFuture> {
+        let pending = self.pending.lock().unwrap();
+        if pending.is_some() {
+            return None;
+        }
+
+        // `pending` is `None`, therefore `resolved` has been written and will never be written
+        // again.
+        match unsafe { (*self.resolved.get()).as_ref().unwrap() } {
+            &LazyJoinState::Joined(ref future) => Some(future),
+            &LazyJoinState::Alone(ref future) => Some(future),
+        }
+    }
+
+    // Calls the closure if this hasn't been done yet, then returns the future that this future
+    // behaves like.
+    fn resolve(&self) -> &GpuFuture {
+        let mut pending = self.pending.lock().unwrap();
+
+        if let Some((first, make_second)) = pending.take() {
+            // This performs the same checks as `join()`.
+            let state = match make_second() {
+                Some(second) => LazyJoinState::Joined(join(first, second)),
+                None => LazyJoinState::Alone(first),
+            };
+
+            unsafe { *self.resolved.get() = Some(state); }
+        }
+
+        // `pending` is `None`, therefore `resolved` has been written and will never be written
+        // again.
+        match unsafe { (*self.resolved.get()).as_ref().unwrap() } {
+            &LazyJoinState::Joined(ref future) => future,
+            &LazyJoinState::Alone(ref future) => future,
+        }
+    }
+}
+
+unsafe impl<A, S, M> GpuFuture for LazyJoinFuture<A, S, M>
+    where A: GpuFuture, S: GpuFuture, M: FnOnce() -> Option<S>
+{
+    #[inline]
+    fn cleanup_finished(&mut self) {
+        if let Some((ref mut first, _)) = *self.pending.get_mut().unwrap() {
+            first.cleanup_finished();
+            return;
+        }
+
+        // We have a `&mut self`, so there's no other reference to the content of `resolved`.
+        unsafe {
+            match *self.resolved.get() {
+                Some(LazyJoinState::Joined(ref mut future)) => future.cleanup_finished(),
+                Some(LazyJoinState::Alone(ref mut future)) => future.cleanup_finished(),
+                None => (),
+            }
+        }
+    }
+
+    #[inline]
+    unsafe fn build_submission(&self) -> Result<SubmitAnyBuilder, FlushError> {
+        self.resolve().build_submission()
+    }
+
+    #[inline]
+    fn flush(&self) -> Result<(), FlushError> {
+        self.resolve().flush()
+    }
+
+    #[inline]
+    unsafe fn signal_finished(&self) {
+        self.resolve().signal_finished()
+    }
+
+    #[inline]
+    fn queue_change_allowed(&self) -> bool {
+        self.resolve().queue_change_allowed()
+    }
+
+    #[inline]
+    fn queue(&self) -> Option<&Arc<Queue>> {
+        match self.resolved() {
+            Some(future) => future.queue(),
+            None => self.imposed.as_ref(),
+        }
+    }
+
+    #[inline]
+    fn queue_family(&self) -> Option<QueueFamily> {
+        match self.resolved() {
+            Some(future) => future.queue_family(),
+            None => self.imposed.as_ref().map(|q| q.family()),
+        }
+    }
+
+    // The access checks call the closure as well, as the second future may access the resource.
+    #[inline]
+    fn check_buffer_access(&self, buffer: &BufferAccess, exclusive: bool, queue: &Queue)
+                           -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        self.resolve().check_buffer_access(buffer, exclusive, queue)
+    }
+
+    #[inline]
+    fn check_image_access(&self, image: &ImageAccess, exclusive: bool, queue: &Queue)
+                          -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        self.resolve().check_image_access(image, exclusive, queue)
+    }
+
+    #[inline]
+    fn acquired_swapchain_image(&self, swapchain: &Swapchain) -> Option<usize> {
+        self.resolve().acquired_swapchain_image(swapchain)
+    }
+
+    #[inline]
+    fn validate_resources(&self) {
+        // Validating shouldn't build the second future, so we don't resolve the future here.
+        let pending = self.pending.lock().unwrap();
+        match *pending {
+            Some((ref first, _)) => first.validate_resources(),
+            None => match unsafe { (*self.resolved.get()).as_ref().unwrap() } {
+                &LazyJoinState::Joined(ref future) => future.validate_resources(),
+                &LazyJoinState::Alone(ref future) => future.validate_resources(),
+            },
+        }
+    }
+
+    #[inline]
+    fn wait_semaphore_count(&self) -> usize {
+        self.resolve().wait_semaphore_count()
+    }
+
+    #[inline]
+    fn shared_identities(&self, ids: &mut Vec<usize>) {
+        // Doesn't call the closure. The future that it returns is checked against the first one
+        // by `join()` once it's called.
+        if let Some((ref first, _)) = *self.pending.lock().unwrap() {
+            first.shared_identities(ids);
+            return;
+        }
+
+        if let Some(future) = self.resolved() {
+            future.shared_identities(ids);
+        }
+    }
+}
+
+unsafe impl<A, S, M> DeviceOwned for LazyJoinFuture<A, S, M> {
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::atomic::Ordering;
+
+    use command_buffer::CommandBuffer;
+    use sync::DummyFuture;
+    use sync::GpuFuture;
+
+    #[test]
+    fn skipped_branch() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let cb = empty_command_buffer!(device, queue);
+
+        let called = Arc::new(AtomicBool::new(false));
+        let future = {
+            let called = called.clone();
+            cb.execute(queue.clone()).join_lazy(move || {
+                called.store(true, Ordering::SeqCst);
+                None::<DummyFuture>
+            })
+        };
+
+        assert!(!future.is_resolved());
+        assert!(!called.load(Ordering::SeqCst));
+
+        future.flush().unwrap();
+        assert!(future.is_resolved());
+        assert!(!future.is_joined());
+        assert!(called.load(Ordering::SeqCst));
+        assert!(future.queue().unwrap().is_same(&queue));
+    }
+
+    #[test]
+    fn joined_branch() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let cb = empty_command_buffer!(device, queue);
+
+        let dummy_device = device.clone();
+        let future = cb.execute(queue.clone())
+                       .join_lazy(move || Some(DummyFuture::new(dummy_device)));
+
+        future.flush().unwrap();
+        assert!(future.is_joined());
+    }
+
+    #[test]
+    fn queue_doesnt_resolve() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let cb = empty_command_buffer!(device, queue);
+
+        let future = cb.execute(queue.clone()).join_lazy(|| -> Option<DummyFuture> { panic!() });
+        assert!(future.queue().unwrap().is_same(&queue));
+        assert_eq!(future.queue_family().unwrap().id(), queue.family().id());
+        assert!(!future.is_resolved());
+    }
+}
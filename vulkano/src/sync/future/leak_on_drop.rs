@@ -0,0 +1,234 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+
+use buffer::BufferAccess;
+use command_buffer::submit::SubmitAnyBuilder;
+use command_buffer::submit::SubmitCommandBufferBuilder;
+use device::Device;
+use device::DeviceOwned;
+use device::Queue;
+use image::ImageAccess;
+use instance::QueueFamily;
+use swapchain::Swapchain;
+use sync::AccessFlagBits;
+use sync::Fence;
+use sync::FlushError;
+use sync::GpuFuture;
+use sync::PipelineStages;
+use sync::future::fence_signal::then_signal_fence_with;
+
+/// Builds a new leak-on-drop future.
+#[inline]
+pub fn leak_on_drop<F>(future: F) -> LeakOnDropFuture<F> where F: GpuFuture + Send + 'static {
+    LeakOnDropFuture {
+        previous: Some(future),
+        finished: AtomicBool::new(false),
+    }
+}
+
+/// Wraps around a future so that dropping it never blocks.
+///
+/// See the documentation of `GpuFuture::leak_on_drop()`.
+pub struct LeakOnDropFuture<F> where F: GpuFuture + Send + 'static {
+    // Always `Some`, except during the destructor.
+    previous: Option<F>,
+    finished: AtomicBool,
+}
+
+impl<F> LeakOnDropFuture<F> where F: GpuFuture + Send + 'static {
+    #[inline]
+    fn previous(&self) -> &F {
+        self.previous.as_ref().unwrap()
+    }
+}
+
+unsafe impl<F> GpuFuture for LeakOnDropFuture<F> where F: GpuFuture + Send + 'static {
+    #[inline]
+    fn cleanup_finished(&mut self) {
+        let previous = self.previous.as_mut().unwrap();
+        previous.cleanup_finished();
+
+        if let Some(queue) = previous.queue() {
+            queue.cleanup_leaked();
+        }
+    }
+
+    #[inline]
+    unsafe fn build_submission(&self) -> Result<SubmitAnyBuilder, FlushError> {
+        self.previous().build_submission()
+    }
+
+    #[inline]
+    fn flush(&self) -> Result<(), FlushError> {
+        self.previous().flush()
+    }
+
+    #[inline]
+    unsafe fn signal_finished(&self) {
+        self.finished.store(true, Ordering::SeqCst);
+        self.previous().signal_finished();
+    }
+
+    #[inline]
+    fn queue_change_allowed(&self) -> bool {
+        self.previous().queue_change_allowed()
+    }
+
+    #[inline]
+    fn queue(&self) -> Option<&Arc<Queue>> {
+        self.previous().queue()
+    }
+
+    #[inline]
+    fn queue_family(&self) -> Option<QueueFamily> {
+        self.previous().queue_family()
+    }
+
+    #[inline]
+    fn check_buffer_access(&self, buffer: &BufferAccess, exclusive: bool, queue: &Queue)
+                           -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        self.previous().check_buffer_access(buffer, exclusive, queue)
+    }
+
+    #[inline]
+    fn check_image_access(&self, image: &ImageAccess, exclusive: bool, queue: &Queue)
+                          -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        self.previous().check_image_access(image, exclusive, queue)
+    }
+
+    #[inline]
+    fn acquired_swapchain_image(&self, swapchain: &Swapchain) -> Option<usize> {
+        self.previous().acquired_swapchain_image(swapchain)
+    }
+
+    #[inline]
+    fn validate_resources(&self) {
+        self.previous().validate_resources();
+    }
+
+    #[inline]
+    fn wait_semaphore_count(&self) -> usize {
+        self.previous().wait_semaphore_count()
+    }
+
+    #[inline]
+    fn signals_fence(&self) -> bool {
+        self.previous().signals_fence()
+    }
+
+    #[inline]
+    fn is_flushed(&self) -> bool {
+        self.previous().is_flushed()
+    }
+
+    #[inline]
+    fn shared_identities(&self, ids: &mut Vec<usize>) {
+        self.previous().shared_identities(ids);
+    }
+}
+
+unsafe impl<F> DeviceOwned for LeakOnDropFuture<F> where F: GpuFuture + Send + 'static {
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        self.previous().device()
+    }
+}
+
+impl<F> Drop for LeakOnDropFuture<F> where F: GpuFuture + Send + 'static {
+    fn drop(&mut self) {
+        let previous = self.previous.take().unwrap();
+
+        if *self.finished.get_mut() {
+            return;
+        }
+
+        // If anything goes wrong below, `previous` is dropped normally and blocks if necessary.
+        let queue = match previous.queue() {
+            Some(queue) => queue.clone(),
+            None => return,
+        };
+
+        let fence = match Fence::new(queue.device().clone()) {
+            Ok(fence) => fence,
+            Err(_) => return,
+        };
+
+        // The fence must be part of the submission that `previous` builds. A fence signaled by a
+        // later submission only waits for the work of this queue, while a join may also submit
+        // work on other queues and wait for it with a semaphore.
+        if !previous.signals_fence() {
+            let future = then_signal_fence_with(previous, fence);
+            if future.flush().is_ok() {
+                queue.keep_alive_until_signaled(future);
+            }
+            return;
+        }
+
+        if previous.flush().is_err() {
+            return;
+        }
+
+        // The fence of `previous` is signaled at the end of its submission. A fence signal
+        // operation waits for all the previous submissions on the same queue, therefore an empty
+        // submission is enough to signal our fence after it.
+        unsafe {
+            let result = {
+                let mut builder = SubmitCommandBufferBuilder::new();
+                builder.set_fence_signal(&fence);
+                builder.submit(&queue)
+            };
+
+            if result.is_err() {
+                return;
+            }
+
+            // Safe because `previous` has been flushed and the fence is signaled after it.
+            queue.keep_alive_until_finished(Box::new(previous), fence);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use command_buffer::CommandBuffer;
+    use sync::GpuFuture;
+
+    #[test]
+    fn drop_then_wait() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let cb = empty_command_buffer!(device, queue);
+
+        let future = cb.execute(queue.clone()).leak_on_drop();
+        future.flush().unwrap();
+        drop(future);
+
+        // Waiting for the queue destroys the command buffer that was kept alive.
+        queue.wait().unwrap();
+    }
+
+    #[test]
+    fn drop_signaled_then_wait() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let cb = empty_command_buffer!(device, queue);
+
+        // The future already signals a fence, so the fence of the leak is signaled after it.
+        let future = cb.execute(queue.clone()).then_signal_fence().leak_on_drop();
+        drop(future);
+
+        queue.wait().unwrap();
+    }
+}
@@ -0,0 +1,236 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use buffer::BufferAccess;
+use command_buffer::submit::SubmitAnyBuilder;
+use command_buffer::submit::SubmitCommandBufferBuilder;
+use device::Device;
+use device::DeviceOwned;
+use device::Queue;
+use image::ImageAccess;
+use instance::QueueFamily;
+use swapchain::Swapchain;
+use sync::AccessFlagBits;
+use sync::Fence;
+use sync::FlushError;
+use sync::GpuFuture;
+use sync::PipelineStages;
+
+/// Builds a new max inflight future.
+///
+/// # Panic
+///
+/// - Panics if `max` is 0.
+/// - Panics if `future` isn't bound to a queue.
+///
+#[inline]
+pub fn max_inflight<F>(future: F, max: usize) -> MaxInflightFuture<F> where F: GpuFuture {
+    assert!(max >= 1);
+    assert!(future.queue().is_some());        // TODO: document
+
+    let device = future.device().clone();
+
+    MaxInflightFuture {
+        previous: future,
+        max: max,
+        fence: Arc::new(Fence::new(device).unwrap()),
+        flushed: Mutex::new(false),
+        finished: AtomicBool::new(false),
+    }
+}
+
+/// Submits a previous future only once fewer than a certain number of submissions made through
+/// this type are in progress on the queue.
+#[must_use = "Dropping this object will immediately block the thread until the GPU has finished processing the submission"]
+pub struct MaxInflightFuture<F> where F: GpuFuture {
+    previous: F,
+    max: usize,
+    // Fence signaled at the end of the submission. Also registered in the queue.
+    fence: Arc<Fence>,
+    // True if the submission has already been submitted.
+    // If flush is called multiple times, we want to block so that only one flushing is executed.
+    // Therefore we use a `Mutex<bool>` and not an `AtomicBool`.
+    flushed: Mutex<bool>,
+    finished: AtomicBool,
+}
+
+unsafe impl<F> GpuFuture for MaxInflightFuture<F> where F: GpuFuture {
+    #[inline]
+    fn cleanup_finished(&mut self) {
+        self.previous.cleanup_finished();
+
+        if *self.flushed.get_mut().unwrap() && !*self.finished.get_mut() &&
+            self.fence.ready().unwrap_or(false)
+        {
+            unsafe { self.signal_finished(); }
+        }
+    }
+
+    #[inline]
+    unsafe fn build_submission(&self) -> Result<SubmitAnyBuilder, FlushError> {
+        // The submission has to go through `flush()` so that the fence is signaled.
+        try!(self.flush());
+        Ok(SubmitAnyBuilder::Empty)
+    }
+
+    fn flush(&self) -> Result<(), FlushError> {
+        unsafe {
+            let mut flushed = self.flushed.lock().unwrap();
+            if *flushed {
+                return Ok(());
+            }
+
+            let queue = self.previous.queue().unwrap().clone();
+
+            try!(queue.wait_inflight(self.max));
+
+            match try!(self.previous.build_submission()) {
+                SubmitAnyBuilder::Empty => {
+                    let mut builder = SubmitCommandBufferBuilder::new();
+                    builder.set_fence_signal(&self.fence);
+                    try!(builder.submit(&queue));
+                },
+                SubmitAnyBuilder::SemaphoresWait(sem) => {
+                    let mut builder: SubmitCommandBufferBuilder = sem.into();
+                    builder.set_fence_signal(&self.fence);
+                    try!(builder.submit(&queue));
+                },
+                SubmitAnyBuilder::CommandBuffer(mut builder) => {
+                    assert!(!builder.has_fence());
+                    builder.set_fence_signal(&self.fence);
+                    try!(builder.submit(&queue));
+                },
+                SubmitAnyBuilder::QueuePresent(present) => {
+                    try!(present.submit(&queue));
+                    let mut builder = SubmitCommandBufferBuilder::new();
+                    builder.set_fence_signal(&self.fence);
+                    try!(builder.submit(&queue));       // FIXME: problematic because if we return an error and flush() is called again, then we'll submit the present twice
+                },
+            };
+
+            queue.add_inflight_fence(self.fence.clone());
+
+            // Only write `true` here in order to try again next time if an error occurs.
+            *flushed = true;
+            Ok(())
+        }
+    }
+
+    #[inline]
+    unsafe fn signal_finished(&self) {
+        self.finished.store(true, Ordering::SeqCst);
+        self.previous.signal_finished();
+    }
+
+    #[inline]
+    fn queue_change_allowed(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    fn queue(&self) -> Option<&Arc<Queue>> {
+        self.previous.queue()
+    }
+
+    #[inline]
+    fn queue_family(&self) -> Option<QueueFamily> {
+        self.previous.queue_family()
+    }
+
+    #[inline]
+    fn check_buffer_access(&self, buffer: &BufferAccess, exclusive: bool, queue: &Queue)
+                           -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        self.previous.check_buffer_access(buffer, exclusive, queue)
+    }
+
+    #[inline]
+    fn check_image_access(&self, image: &ImageAccess, exclusive: bool, queue: &Queue)
+                          -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        self.previous.check_image_access(image, exclusive, queue)
+    }
+
+    #[inline]
+    fn acquired_swapchain_image(&self, swapchain: &Swapchain) -> Option<usize> {
+        self.previous.acquired_swapchain_image(swapchain)
+    }
+
+    #[inline]
+    fn validate_resources(&self) {
+        self.previous.validate_resources();
+    }
+
+    #[inline]
+    fn wait_semaphore_count(&self) -> usize {
+        // Once flushed, the semaphores of `previous` have already been waited upon.
+        if *self.flushed.lock().unwrap() {
+            0
+        } else {
+            self.previous.wait_semaphore_count()
+        }
+    }
+
+    #[inline]
+    fn is_flushed(&self) -> bool {
+        *self.flushed.lock().unwrap()
+    }
+
+    #[inline]
+    fn shared_identities(&self, ids: &mut Vec<usize>) {
+        self.previous.shared_identities(ids);
+    }
+}
+
+unsafe impl<F> DeviceOwned for MaxInflightFuture<F> where F: GpuFuture {
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        self.previous.device()
+    }
+}
+
+impl<F> Drop for MaxInflightFuture<F> where F: GpuFuture {
+    fn drop(&mut self) {
+        unsafe {
+            if !*self.finished.get_mut() {
+                // TODO: handle errors?
+                self.flush().unwrap();
+                // Block until the submission is finished.
+                // TODO: arbitrary timeout?
+                self.fence.wait(Duration::from_secs(600)).unwrap();
+                self.previous.signal_finished();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use command_buffer::CommandBuffer;
+    use sync::GpuFuture;
+
+    #[test]
+    fn forwards_wait_semaphore_count() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let cb = empty_command_buffer!(device, queue);
+
+        let future = cb.execute(queue.clone()).then_signal_semaphore().max_inflight(2);
+        assert_eq!(future.wait_semaphore_count(), 1);
+
+        future.flush().unwrap();
+        assert_eq!(future.wait_semaphore_count(), 0);
+    }
+}
@@ -7,35 +7,162 @@
 // notice may not be copied, modified, or distributed except
 // according to those terms.
 
-use std::error::Error;
+use std::error;
+use std::fmt;
+use std::ops::Range;
+use std::os::raw::c_int;
 use std::sync::Arc;
+use std::time::Duration;
 
 use buffer::BufferAccess;
+use buffer::CpuAccessibleBuffer;
+use buffer::DeviceLocalBuffer;
+use buffer::TypedBufferAccess;
 use command_buffer::CommandBuffer;
+use command_buffer::CommandBufferExecError;
 use command_buffer::CommandBufferExecFuture;
 use command_buffer::submit::SubmitAnyBuilder;
+use command_buffer::submit::SubmitCommandBufferError;
+use command_buffer::submit::SubmitPresentError;
+use descriptor::descriptor_set::DescriptorSetsCollection;
+use device::Device;
 use device::DeviceOwned;
 use device::Queue;
 use image::ImageAccess;
+use instance::QueueFamily;
+use image::Layout;
+use memory::Content;
+use memory::pool::MemoryPool;
+use pipeline::ComputePipelineAbstract;
+use query::PerformanceQueryPool;
+use query::ProfilingLock;
+use query::QueryResultFlags;
+use query::UnsafeQueryPool;
+use sampler::Filter;
 use swapchain::Swapchain;
 use swapchain::PresentFuture;
+use swapchain::PresentError;
+use swapchain::PresentWaitError;
 use sync::AccessFlagBits;
+use sync::Event;
+use sync::ExternalFenceHandleType;
+use sync::Fence;
+use sync::FenceImportError;
+use sync::FencePool;
+use sync::FenceWaitError;
 use sync::PipelineStages;
+use sync::SemaphorePool;
+use OomError;
+use vk;
 
+pub use self::access_mask::AccessMaskFuture;
+pub use self::addressed_buffer::AddressedBufferFuture;
+pub use self::barrier::BarrierFuture;
+pub use self::barrier::PipelineBarrierBuilder;
+pub use self::blit::BlitCommandBuffer;
+pub use self::blit::BlitError;
+pub use self::budget::BudgetedFuture;
+pub use self::budget::SubmissionBudget;
+pub use self::budget::WouldExceedBudget;
+pub use self::completion_tracker::CompletionTracker;
+pub use self::deferred_exec::DeferredExecFuture;
+pub use self::dispatch::DispatchCommandBuffer;
+pub use self::dispatch::DispatchError;
 pub use self::dummy::DummyFuture;
+pub use self::dummy::now;
+pub use self::event_signal::EventSignalFuture;
+pub use self::event_wait::EventWaitCommandBuffer;
+pub use self::execution_barrier::ExecutionBarrierCommandBuffer;
+pub use self::external_acquire::ExternalAcquireFuture;
+pub use self::external_fence_wait::ExternalFenceWaitFuture;
+pub use self::fence_signal::DetachedFenceSignalFuture;
 pub use self::fence_signal::FenceSignalFuture;
+#[cfg(feature = "async")]
+pub use self::fence_signal::FenceSignalFutureAsync;
+pub use self::fence_signal::FenceSignalFutureStatus;
+pub use self::fence_signal::PresentFenceStrategy;
+pub use self::fence_signal::StillPending;
+pub use self::frame_token::FrameToken;
+pub use self::event_wait::HostEventWaitCommandBuffer;
+pub use self::host_write::HostWriteBarrierCommandBuffer;
+pub use self::image_barrier::ImageBarrierCommandBuffer;
+pub use self::join::JoinAllFuture;
 pub use self::join::JoinFuture;
+pub use self::join::TooManyWaitSemaphoresError;
+pub use self::join::join_all;
+pub use self::lazy_join::LazyJoinFuture;
+pub use self::leak_on_drop::LeakOnDropFuture;
+pub use self::max_inflight::MaxInflightFuture;
+pub use self::named_semaphores::NamedSemaphores;
+pub use self::one_shot::OneShotCommandBuffer;
+pub use self::one_shot::OneShotResources;
+pub use self::performance_query::PerformanceQueryFuture;
+pub use self::prior_present::WaitPriorPresentFuture;
+pub use self::query_copy::QueryResultsCopyCommandBuffer;
+pub use self::query_copy::QueryResultsCopyError;
+pub use self::query_reset::QueryPoolResetCommandBuffer;
+pub use self::queue_pinned::QueueMismatch;
+pub use self::queue_pinned::QueuePinnedFuture;
+pub use self::readback::BufferReadback;
+pub use self::readback::ReadbackCommandBuffer;
+pub use self::readback::ReadbackError;
+pub use self::resource_state::ResourceStateCache;
+pub use self::resource_state::ResourceStateFuture;
 pub use self::semaphore_signal::SemaphoreSignalFuture;
+pub use self::submit_hook::SubmitHookFuture;
+pub use self::throttle::ThrottleFuture;
+pub use self::timestamps::TimestampFuture;
+pub use self::upload::UploadCommandBuffer;
+pub use self::upload::UploadError;
+pub use self::wait_all::wait_all_queues;
 
+mod access_mask;
+mod addressed_buffer;
+mod barrier;
+mod blit;
+mod budget;
+mod completion_tracker;
+mod deferred_exec;
+mod dispatch;
 mod dummy;
+mod event_signal;
+mod event_wait;
+mod execution_barrier;
+mod external_acquire;
+mod external_fence_wait;
 mod fence_signal;
+mod frame_token;
+mod host_write;
+mod image_barrier;
 mod join;
+mod lazy_join;
+mod leak_on_drop;
+mod max_inflight;
+mod named_semaphores;
+mod one_shot;
+mod performance_query;
+mod prior_present;
+mod query_copy;
+mod query_reset;
+mod queue_pinned;
+mod readback;
+mod resource_state;
 mod semaphore_signal;
+mod submit_hook;
+mod throttle;
+mod timestamps;
+mod upload;
+mod wait_all;
 
 /// Represents an event that will happen on the GPU in the future.
 ///
 /// See the documentation of the `sync` module for explanations about futures.
 // TODO: consider switching all methods to take `&mut self` for optimization purposes
+//
+// `Arc<FenceSignalFuture<F>>` implements this trait so that a future can be shared between
+// threads, and `CommandBuffer::submit_check()` receives the previous future as a `&GpuFuture`.
+// The access checks, which are called for each resource of each submission, have a `&mut self`
+// variant in `GpuFutureMut` for the futures that aren't shared.
 pub unsafe trait GpuFuture: DeviceOwned {
     /// If possible, checks whether the submission has finished. If so, gives up ownership of the
     /// resources used by these submissions.
@@ -62,16 +189,18 @@ pub unsafe trait GpuFuture: DeviceOwned {
     /// Once the caller has submitted the submission and has determined that the GPU has finished
     /// executing it, it should call `signal_finished`. Failure to do so will incur a large runtime
     /// overhead, as the future will have to block to make sure that it is finished.
-    // TODO: better error type
-    unsafe fn build_submission(&self) -> Result<SubmitAnyBuilder, Box<Error>>;
+    unsafe fn build_submission(&self) -> Result<SubmitAnyBuilder, FlushError>;
 
     /// Flushes the future and submits to the GPU the actions that will permit this future to
     /// occur.
     ///
     /// The implementation must remember that it was flushed. If the function is called multiple
     /// times, only the first time must result in a flush.
-    // TODO: better error type
-    fn flush(&self) -> Result<(), Box<Error>>;
+    ///
+    /// If the future ends with a present, the errors of the present are returned as their own
+    /// variants of `FlushError`. In particular `FlushError::OutOfDate` means that the swapchain
+    /// must be recreated.
+    fn flush(&self) -> Result<(), FlushError>;
 
     /// Sets the future to its "complete" state, meaning that it can safely be destroyed.
     ///
@@ -86,6 +215,21 @@ pub unsafe trait GpuFuture: DeviceOwned {
     /// the `GpuFuture` trait yourself for a type outside of vulkano.
     fn queue(&self) -> Option<&Arc<Queue>>;
 
+    /// Returns the queue family of the queue that executes the work of this future. Returns
+    /// `None` if unknown or irrelevant.
+    ///
+    /// This is the information needed to know whether a resource in exclusive sharing mode
+    /// requires a queue family ownership transfer before being used by another queue.
+    ///
+    /// The default implementation returns the family of `queue()`. Futures that remember their
+    /// queue after `queue()` stops returning it, such as a `FenceSignalFuture` that has been
+    /// cleaned up, should override this method, and futures that wrap around other futures
+    /// should forward the call to them.
+    #[inline]
+    fn queue_family(&self) -> Option<QueueFamily> {
+        self.queue().map(|q| q.family())
+    }
+
     /// Returns `true` if elements submitted after this future can be submitted to a different
     /// queue than the other returned by `queue()`.
     fn queue_change_allowed(&self) -> bool;
@@ -115,7 +259,129 @@ pub unsafe trait GpuFuture: DeviceOwned {
     fn check_image_access(&self, image: &ImageAccess, exclusive: bool, queue: &Queue)
                          -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>;
 
+    /// Returns the index of the image of `swapchain` that was acquired by this future or by one
+    /// of the futures it depends on, if any.
+    ///
+    /// The default implementation returns `None`. Futures that wrap around other futures should
+    /// forward the call to them.
+    #[inline]
+    fn acquired_swapchain_image(&self, swapchain: &Swapchain) -> Option<usize> {
+        None
+    }
+
+    /// Returns the number of semaphores that the submission built by this future will wait
+    /// upon.
+    ///
+    /// Futures that signal a semaphore or that acquire an image count for one each. Joining
+    /// futures adds up their counts, since their wait operations are merged into the same
+    /// submission. Futures that submit their work themselves, such as `FenceSignalFuture`,
+    /// count for zero as far as the following submissions are concerned.
+    ///
+    /// The default implementation returns 0. Futures that wrap around other futures should
+    /// forward the call to them.
+    #[inline]
+    fn wait_semaphore_count(&self) -> usize {
+        0
+    }
+
+    /// Returns true if this future is a fence signal operation, possibly behind wrappers that
+    /// don't add any work to the submission.
+    ///
+    /// This is used by `then_signal_fence()` to reject signaling a fence right after another
+    /// fence, which would only produce a second, useless fence.
+    ///
+    /// The default implementation returns false. Futures that wrap around other futures without
+    /// submitting anything else should forward the call to them.
+    #[inline]
+    fn signals_fence(&self) -> bool {
+        false
+    }
+
+    /// Returns true if this future is known to have been flushed already, which means that
+    /// flushing it again doesn't submit anything.
+    ///
+    /// This is used by `then_signal_fence_and_flush()` to reject signaling a fence after a
+    /// future whose work has already been submitted, as the fence would be signaled by an empty
+    /// submission of its own.
+    ///
+    /// The default implementation returns false. Futures that remember whether they have been
+    /// flushed should return true once they are, and futures that wrap around other futures
+    /// without submitting anything else should forward the call to them.
+    #[inline]
+    fn is_flushed(&self) -> bool {
+        false
+    }
+
+    /// Appends to `fences` the fences that `cleanup_finished()` would poll and that may not be
+    /// signaled yet, and returns true if polling them is the only thing that `cleanup_finished()`
+    /// does.
+    ///
+    /// This is used by the joined futures to poll the fences of all the futures that they contain
+    /// with a single call to `vkWaitForFences`, and to skip their cleanup entirely if none of
+    /// them is signaled.
+    ///
+    /// The default implementation returns false, which means that `cleanup_finished()` must
+    /// always be called.
+    #[inline]
+    fn pending_fences(&self, fences: &mut Vec<vk::Fence>) -> bool {
+        false
+    }
+
+    /// Appends to `ids` a value that identifies each future contained in this one that can be
+    /// shared, such as an `Arc<FenceSignalFuture>`.
+    ///
+    /// This is used by `join()` and `join_all()` to detect that the same future would be joined
+    /// with itself. This is never correct: the joined future would report the accesses of the
+    /// shared future twice, which looks like two futures having exclusive access to the same
+    /// resource.
+    ///
+    /// The default implementation appends nothing. Futures that wrap around other futures
+    /// should forward the call to them.
+    #[inline]
+    fn shared_identities(&self, ids: &mut Vec<usize>) {
+    }
+
+    /// Returns true if the GPU is known to have nothing left to execute for this future once
+    /// `build_submission()` has returned `SubmitAnyBuilder::Empty`.
+    ///
+    /// This is used by `FenceSignalFuture` to skip submitting an empty batch just to signal its
+    /// fence. It's not the case for every future: some futures submit their work themselves and
+    /// return an empty submission while the GPU is still executing it.
+    ///
+    /// The default implementation returns false. Futures that wrap around other futures without
+    /// submitting anything else should forward the call to them.
+    #[inline]
+    fn empty_submission_is_finished(&self) -> bool {
+        false
+    }
+
+    /// Checks that the resources tracked by this future and by the futures it depends on are
+    /// still alive.
+    ///
+    /// The futures provided by vulkano keep their resources alive with `Arc`s, but some of them
+    /// only hold a weak reference, like the image of a `SwapchainAcquireFuture`. A custom
+    /// implementation of this trait that holds weak references or raw handles should override
+    /// this method and panic if one of them is dangling, so that the mistake is caught before the
+    /// GPU uses a destroyed resource. `FenceSignalFuture` calls it every time it is flushed.
+    ///
+    /// The checks of vulkano's futures are part of the strict mode (see the documentation of the
+    /// `sync` module). The default implementation does nothing. Futures that wrap around other
+    /// futures should forward the call to them.
+    #[inline]
+    fn validate_resources(&self) {
+    }
+
     /// Joins this future with another one, representing the moment when both events have happened.
+    ///
+    /// Use `wait_semaphore_count()` on the result to know how many semaphores the joined
+    /// submission will wait upon, and `JoinFuture::with_max_wait_semaphores()` to make the
+    /// submission fail if there are too many of them.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if both futures contain the same shared future, for example two clones of the
+    ///   same `Arc<FenceSignalFuture>`. See `shared_identities()`.
+    ///
     // TODO: handle errors
     fn join<F>(self, other: F) -> JoinFuture<Self, F>
         where Self: Sized, F: GpuFuture
@@ -123,6 +389,68 @@ pub unsafe trait GpuFuture: DeviceOwned {
         join::join(self, other)
     }
 
+    /// Joins this future with the future returned by `make_second`, which is only called the
+    /// first time the result is needed.
+    ///
+    /// The closure is called when the returned future is flushed or submitted, or when a
+    /// following future checks its accesses. If it returns `None`, the returned future behaves
+    /// exactly like this one. This avoids building futures for branches that turn out to be
+    /// skipped, such as a shadow pass that isn't rendered this frame.
+    ///
+    /// # Panic
+    ///
+    /// - Panics, when the closure is called, in the same situations as `join()`.
+    ///
+    #[inline]
+    fn join_lazy<S, M>(self, make_second: M) -> LazyJoinFuture<Self, S, M>
+        where Self: Sized, S: GpuFuture, M: FnOnce() -> Option<S>
+    {
+        lazy_join::join_lazy(self, make_second)
+    }
+
+    /// Declares that the operations of this future access `buffer`, so that the following
+    /// operations synchronize with these accesses.
+    ///
+    /// This is meant for buffers that the shaders access through their device address (with the
+    /// `VK_KHR_buffer_device_address` extension) instead of through a descriptor set. Such
+    /// accesses are invisible to the command buffers, therefore without this function the
+    /// futures that follow wouldn't add any barrier for them. The returned future reports an
+    /// access in `stages` with `access` when `check_buffer_access()` is called with the buffer,
+    /// in addition to the accesses of this future.
+    ///
+    /// The buffer is locked with the given exclusivity and is kept alive as long as the returned
+    /// future. If `exclusive` is false, the returned future denies the requests for an exclusive
+    /// access to the buffer.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if this future isn't bound to a queue.
+    /// - Panics if the buffer doesn't belong to the same device as this future.
+    /// - Panics if the buffer is already in use by a submission that this future doesn't know
+    ///   about.
+    ///
+    #[inline]
+    fn with_addressed_buffer<B>(self, buffer: B, exclusive: bool, stages: PipelineStages,
+                                access: AccessFlagBits) -> AddressedBufferFuture<Self, B>
+        where Self: Sized, B: BufferAccess
+    {
+        addressed_buffer::with_addressed_buffer(self, buffer, exclusive, stages, access)
+    }
+
+    /// Records the accesses that this future reports in `cache`, so that they can be consulted
+    /// by chains of futures that are built separately.
+    ///
+    /// When this future is asked about a resource, the answer of the previous future is recorded
+    /// in the cache. If the previous future grants the access without knowing how the resource
+    /// was last used, the answer recorded in the cache is returned instead. See
+    /// `ResourceStateCache`.
+    #[inline]
+    fn with_resource_states(self, cache: Arc<ResourceStateCache>) -> ResourceStateFuture<Self>
+        where Self: Sized
+    {
+        resource_state::with_resource_states(self, cache)
+    }
+
     /// Executes a command buffer after this future.
     ///
     /// > **Note**: This is just a shortcut function. The actual implementation is in the
@@ -135,6 +463,48 @@ pub unsafe trait GpuFuture: DeviceOwned {
         command_buffer.execute_after(self, queue)
     }
 
+    /// Executes a command buffer after this future, on a queue chosen by `select` the first time
+    /// that the queue is needed.
+    ///
+    /// This allows choosing the queue depending on the load of the queues at the time the work
+    /// is actually submitted, instead of when the chain of futures is built. The closure is
+    /// called when the returned future is flushed, when something queries the resources it
+    /// accesses, or when `DeferredExecFuture::resolve_queue()` is called. Querying its queue
+    /// doesn't call the closure, therefore functions that require a queue, such as
+    /// `then_signal_fence()`, must be called after `resolve_queue()`.
+    ///
+    /// If this future doesn't allow changing its queue, for example because it already executes
+    /// a command buffer, then its queue is used and `select` is never called.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if the device of the command buffer is not the same as the device of this
+    ///   future.
+    /// - Submitting the returned future panics in the same situations as `then_execute()`.
+    ///
+    #[inline]
+    fn then_execute_with<S, Cb>(self, select: S, command_buffer: Cb)
+                                -> DeferredExecFuture<Self, Cb, S>
+        where Self: Sized, Cb: CommandBuffer + 'static, S: FnOnce(&Arc<Device>) -> Arc<Queue>
+    {
+        deferred_exec::then_execute_with(self, select, command_buffer)
+    }
+
+    /// Executes a command buffer `count` times in a row after this future, in a single
+    /// submission.
+    ///
+    /// State isn't carried between the executions, except what the command buffer itself sets.
+    ///
+    /// > **Note**: This is just a shortcut function. The actual implementation is in the
+    /// > `CommandBuffer` trait.
+    #[inline]
+    fn then_execute_repeated<Cb>(self, queue: Arc<Queue>, command_buffer: Cb, count: u32)
+                                 -> CommandBufferExecFuture<Self, Cb>
+        where Self: Sized, Cb: CommandBuffer + 'static
+    {
+        command_buffer.execute_after_repeated(self, queue, count)
+    }
+
     /// Executes a command buffer after this future, on the same queue as the future.
     ///
     /// > **Note**: This is just a shortcut function. The actual implementation is in the
@@ -147,6 +517,447 @@ pub unsafe trait GpuFuture: DeviceOwned {
         command_buffer.execute_after(self, queue)
     }
 
+    /// Executes a pipeline barrier for a subresource range of an image after this future, on the
+    /// same queue as the future.
+    ///
+    /// The barrier only applies to the mipmap levels in `mipmaps` and the array layers in
+    /// `layers`. This avoids over-synchronizing when an operation only touches a part of the
+    /// image, for example when generating one mipmap level from the previous one.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if this future isn't bound to a queue.
+    /// - Panics if the image is already in use by another future.
+    ///
+    /// # Safety
+    ///
+    /// - `old_layout` must be the current layout of the subresources, and the following
+    ///   operations must expect `new_layout`.
+    /// - If the pipeline stages include geometry or tessellation stages, then the corresponding
+    ///   features must have been enabled in the device.
+    ///
+    unsafe fn then_image_barrier<I>(self, image: I, mipmaps: Range<u32>, layers: Range<u32>,
+                                    source_stages: PipelineStages, dest_stages: PipelineStages,
+                                    source_access: AccessFlagBits, dest_access: AccessFlagBits,
+                                    old_layout: Layout, new_layout: Layout)
+                                    -> Result<CommandBufferExecFuture<Self,
+                                                                      ImageBarrierCommandBuffer<I>>,
+                                              OomError>
+        where Self: Sized, I: ImageAccess + 'static
+    {
+        let queue = self.queue().unwrap().clone();
+        let cb = try!(ImageBarrierCommandBuffer::new(&queue, image, mipmaps, layers,
+                                                     source_stages, dest_stages, source_access,
+                                                     dest_access, old_layout, new_layout));
+        Ok(cb.execute_after(self, queue))
+    }
+
+    /// Blocks the execution of `dest_stages` of the following submissions on the same queue
+    /// until `event` is set by the host with `Event::set()`.
+    ///
+    /// The writes to memory that the host performs before setting the event are made visible to
+    /// the destination stages. This allows submitting work ahead of time and letting the GPU
+    /// start it only once the CPU has finished preparing the resources it uses.
+    ///
+    /// The event isn't reset by the GPU. Call `Event::reset()` before reusing it.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if this future isn't bound to a queue.
+    ///
+    /// # Safety
+    ///
+    /// - The host must set the event shortly after the submission. Implementations may have a
+    ///   limit on how long the GPU can wait for an event, after which the device may be lost.
+    ///   In particular, the event must not be set only after waiting for a fence that is
+    ///   signaled after this future.
+    /// - If `dest_stages` include geometry or tessellation stages, then the corresponding
+    ///   features must have been enabled in the device.
+    ///
+    unsafe fn then_wait_host_event(self, event: Arc<Event>, dest_stages: PipelineStages)
+                                   -> Result<CommandBufferExecFuture<Self,
+                                                                     HostEventWaitCommandBuffer>,
+                                             OomError>
+        where Self: Sized
+    {
+        let queue = self.queue().unwrap().clone();
+        let cb = try!(HostEventWaitCommandBuffer::from_host(&queue, event, dest_stages));
+        Ok(cb.execute_after(self, queue))
+    }
+
+    /// Sets a new event once the operations of this future have finished executing `stages`,
+    /// on the same queue as the future.
+    ///
+    /// Combined with `then_wait_event()`, this expresses a dependency between two operations of
+    /// the same queue without waiting for everything that is submitted in between. For example
+    /// graphics work can be submitted after a compute dispatch without waiting for it, and only
+    /// the operations that read the results of the dispatch wait for the event.
+    ///
+    /// The event is available with `EventSignalFuture::event()`.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if this future isn't bound to a queue.
+    /// - Panics if `stages` is empty or includes `host`.
+    ///
+    /// # Safety
+    ///
+    /// - The stages must be supported by the queue family of the queue of this future.
+    /// - If the stages include geometry or tessellation stages, then the corresponding features
+    ///   must have been enabled in the device.
+    ///
+    #[inline]
+    unsafe fn then_signal_event(self, stages: PipelineStages)
+                                -> Result<EventSignalFuture<Self>, OomError>
+        where Self: Sized
+    {
+        event_signal::then_signal_event(self, stages)
+    }
+
+    /// Blocks the execution of `dest_stages` of the following submissions on the same queue
+    /// until `event` is set by `source_stages` of a previous submission, and makes the accesses
+    /// `source_access` before the event available to the accesses `dest_access` after the wait.
+    ///
+    /// `event` is usually obtained from an `EventSignalFuture` that precedes this future in the
+    /// chain, and `source_stages` from `EventSignalFuture::stages()`.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if this future isn't bound to a queue.
+    /// - Panics if `source_stages` or `dest_stages` is empty.
+    ///
+    /// # Safety
+    ///
+    /// - The event must be set by a command submitted earlier to the same queue, or set by the
+    ///   host shortly after the submission, in which case `source_stages` must include `host`.
+    ///   Otherwise the device may be lost.
+    /// - `source_stages` must include the stages passed when setting the event.
+    /// - The stages must be supported by the queue family of the queue of this future, and the
+    ///   accesses must be supported by these stages.
+    ///
+    unsafe fn then_wait_event(self, event: Arc<Event>, source_stages: PipelineStages,
+                              dest_stages: PipelineStages, source_access: AccessFlagBits,
+                              dest_access: AccessFlagBits)
+                              -> Result<CommandBufferExecFuture<Self, EventWaitCommandBuffer>,
+                                        OomError>
+        where Self: Sized
+    {
+        let queue = self.queue().unwrap().clone();
+        let cb = try!(EventWaitCommandBuffer::new(&queue, event, source_stages, dest_stages,
+                                                  source_access, dest_access));
+        Ok(cb.execute_after(self, queue))
+    }
+
+    /// Makes `dest_stages` of the following submissions on the same queue wait for
+    /// `source_stages` of this future, without making any memory write visible.
+    ///
+    /// This only orders the execution of the two phases, which is the lightest possible barrier.
+    /// It is appropriate when the two phases don't share any data but mustn't overlap. If the
+    /// following commands read what this future writes, a memory barrier is needed instead.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if this future isn't bound to a queue.
+    /// - Panics if `source_stages` or `dest_stages` is empty.
+    ///
+    /// # Safety
+    ///
+    /// - The stages must be supported by the queue family of the queue of this future.
+    /// - If the stages include geometry or tessellation stages, then the corresponding features
+    ///   must have been enabled in the device.
+    ///
+    unsafe fn then_execution_barrier(self, source_stages: PipelineStages,
+                                     dest_stages: PipelineStages)
+                                     -> Result<CommandBufferExecFuture<Self,
+                                                   ExecutionBarrierCommandBuffer>, OomError>
+        where Self: Sized
+    {
+        let queue = self.queue().unwrap().clone();
+        let cb = try!(ExecutionBarrierCommandBuffer::new(&queue, source_stages, dest_stages));
+        Ok(cb.execute_after(self, queue))
+    }
+
+    /// Executes a user-provided pipeline barrier after this future, on the same queue as the
+    /// future.
+    ///
+    /// This is useful when the automatic synchronization is too conservative, or to perform
+    /// specific layout transitions. The barrier is submitted as a one-shot command buffer that
+    /// only contains it. The returned future reports the accesses of this future as accesses by
+    /// the destination scope of the barrier, and the transitioned images as accessed
+    /// exclusively by it.
+    ///
+    /// `then_execution_barrier()` and `then_image_barrier()` are shortcuts for the common cases
+    /// of a barrier without any access and of a single layout transition, which don't change the
+    /// accesses reported for the other resources.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if this future isn't bound to a queue.
+    /// - Panics if one of the transitioned images is already in use by another future.
+    ///
+    /// # Safety
+    ///
+    /// - The source scope of the barrier must cover the accesses of this future that the
+    ///   following operations depend on.
+    /// - The old layouts of the transitions must be the current layouts of the subresources, and
+    ///   the following operations must expect the new layouts.
+    /// - The stages must be supported by the queue family of the queue of this future, and the
+    ///   accesses must be supported by these stages.
+    ///
+    #[inline]
+    unsafe fn then_barrier(self, barrier: PipelineBarrierBuilder)
+                           -> Result<BarrierFuture<Self>, OomError>
+        where Self: Sized
+    {
+        barrier::then_barrier(self, barrier)
+    }
+
+    /// Resets the `count` queries of `pool` starting at `first_query` after this future, on the
+    /// same queue as the future.
+    ///
+    /// The queries must be reset before being used again, for example at the start of every
+    /// frame. The reset waits for all the commands previously submitted to the queue, so that
+    /// the queries are no longer being written and their results are no longer being copied
+    /// with `vkCmdCopyQueryPoolResults` by the GPU.
+    ///
+    /// > **Note**: Results that are read by the host must have been retrieved before the future
+    /// > is flushed, as the GPU doesn't know about these reads.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if this future isn't bound to a queue.
+    /// - Panics if `count` is 0 or if the range of queries is out of range of the pool.
+    ///
+    fn then_reset_query_pool(self, pool: Arc<UnsafeQueryPool>, first_query: u32, count: u32)
+                             -> Result<CommandBufferExecFuture<Self, QueryPoolResetCommandBuffer>,
+                                       OomError>
+        where Self: Sized
+    {
+        let queue = self.queue().unwrap().clone();
+        let cb = try!(QueryPoolResetCommandBuffer::new(&queue, pool, first_query, count));
+        Ok(cb.execute_after(self, queue))
+    }
+
+    /// Submits this future once for each pass required by the performance query pool `pool`,
+    /// so that the counters of the query of index `query` are all collected. Returns a future
+    /// that represents the end of the last pass.
+    ///
+    /// The submission of this future must contain command buffers that begin and end the query
+    /// with `CmdBeginQuery` and `CmdEndQuery`. Vulkan doesn't allow a query to span multiple
+    /// command buffers, which is why this function can't add them itself. These command buffers
+    /// must have been recorded while `lock` was held and must allow simultaneous use if more
+    /// than one pass is needed, which is the case of the command buffers built with
+    /// `AutoCommandBufferBuilder`. The query must have been reset in a previous submission.
+    ///
+    /// The lock is kept alive until all the passes have finished executing. Once this is the
+    /// case, the counters can be read with `results()` on the returned future.
+    ///
+    /// The submission is flushed as soon as the returned future is flushed or chained with
+    /// another future, as the passes can't be merged with a following submission.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if this future isn't bound to a queue.
+    /// - Panics if the queue doesn't belong to the queue family of the pool.
+    /// - Panics if `query` is out of range of the pool.
+    /// - Panics if the lock and the pool don't belong to the same device.
+    ///
+    #[inline]
+    fn then_profile(self, pool: Arc<PerformanceQueryPool>, query: u32, lock: Arc<ProfilingLock>)
+                    -> PerformanceQueryFuture<Self>
+        where Self: Sized
+    {
+        performance_query::then_profile(self, pool, query, lock)
+    }
+
+    /// Copies the results of the `count` queries of `pool` starting at `first_query` into
+    /// `destination` on `queue` after this future, one query every `stride` bytes. Returns a
+    /// future that represents the end of the copy.
+    ///
+    /// The copy happens after all the commands previously submitted to the queue, and the
+    /// commands that follow the returned future see the results. However the copy doesn't wait
+    /// for the queries to be available unless `flags.wait` is true. Without it, the results of
+    /// the unavailable queries are undefined, so you should either set `wait` or set
+    /// `with_availability` and check the availability integer written after each query.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if this future can't be moved to `queue`.
+    /// - Panics if `count` is 0 or if the range of queries is out of range of the pool.
+    /// - Panics if the buffer doesn't belong to the same device as the pool.
+    /// - Panics if the buffer is already in use by a submission that this future doesn't know
+    ///   about.
+    ///
+    fn then_copy_query_results<B>(self, queue: Arc<Queue>, pool: Arc<UnsafeQueryPool>,
+                                  first_query: u32, count: u32, destination: B, stride: usize,
+                                  flags: QueryResultFlags)
+        -> Result<CommandBufferExecFuture<Self, QueryResultsCopyCommandBuffer<B>>,
+                  QueryResultsCopyError>
+        where Self: Sized, B: BufferAccess + Send + Sync + 'static
+    {
+        let cb = try!(QueryResultsCopyCommandBuffer::new(&self, &queue, pool, first_query, count,
+                                                         destination, stride, flags));
+        Ok(cb.execute_after(self, queue))
+    }
+
+    /// Makes the host writes to the range `written_range` of the bytes of `buffer` visible to the
+    /// submissions that follow this future. Returns a future that represents the point after
+    /// which the data can be accessed by the device.
+    ///
+    /// The range is flushed with `vkFlushMappedMemoryRanges` if the memory of the buffer isn't
+    /// host-coherent, and a barrier from the `host` stage with the `host_write` access to any
+    /// access of the commands that follow is added on the queue of this future.
+    ///
+    /// Writes through `CpuAccessibleBuffer::write()` are flushed when the lock is released, in
+    /// which case flushing again is harmless.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if this future isn't bound to a queue.
+    /// - Panics if the range is empty or out of range of the buffer.
+    ///
+    fn then_after_host_write<T: ?Sized, A>(self, buffer: Arc<CpuAccessibleBuffer<T, A>>,
+                                           written_range: Range<usize>)
+        -> Result<CommandBufferExecFuture<Self, HostWriteBarrierCommandBuffer<T, A>>, OomError>
+        where Self: Sized, T: Content + Send + Sync + 'static, A: MemoryPool
+    {
+        let queue = self.queue().unwrap().clone();
+        let cb = try!(HostWriteBarrierCommandBuffer::new(&queue, buffer, written_range));
+        Ok(cb.execute_after(self, queue))
+    }
+
+    /// Uploads `data` to `destination` after this future. Returns a future that represents the
+    /// end of the upload.
+    ///
+    /// The data is first written to a host-visible staging buffer, then copied to `destination`
+    /// on the queue of this future. If this future accesses `destination`, a pipeline barrier is
+    /// added so that the copy waits for these accesses. The following submissions see the copy
+    /// as a write in the `transfer` stage.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if this future isn't bound to a queue.
+    /// - Panics if the length of `data` is not the same as the length of `destination`.
+    ///
+    fn then_upload<T>(self, data: &[T], destination: Arc<DeviceLocalBuffer<[T]>>)
+                      -> Result<CommandBufferExecFuture<Self, UploadCommandBuffer<T>>, UploadError>
+        where Self: Sized, T: Content + Copy + Send + Sync + 'static
+    {
+        let queue = self.queue().unwrap().clone();
+        let cb = try!(UploadCommandBuffer::new(&self, &queue, data, destination));
+        Ok(cb.execute_after(self, queue))
+    }
+
+    /// Copies the content of `source` to a host-visible staging buffer on `queue` after this
+    /// future, and signals a fence once the copy is finished. Returns an object whose `wait()`
+    /// method gives back the content of the buffer.
+    ///
+    /// The barriers between the accesses of this future to `source`, the copy and the reads
+    /// from the host are handled automatically. This makes it easy to read the results of a
+    /// compute shader, for example.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if this future can't be moved to `queue`.
+    /// - Panics if the size of `source` isn't a multiple of the size of `T`.
+    /// - Panics if `source` is already in use by a submission that this future doesn't know
+    ///   about.
+    ///
+    #[inline]
+    fn then_read_buffer<B, T>(self, queue: Arc<Queue>, source: B)
+                              -> Result<BufferReadback<Self, B, T>, ReadbackError>
+        where Self: Sized,
+              B: TypedBufferAccess<Content = [T]> + Send + Sync + 'static,
+              T: Content + Copy + Send + Sync + 'static
+    {
+        readback::read_buffer(self, queue, source)
+    }
+
+    /// Blits `source_region` of `source` into `destination_region` of `destination` on `queue`
+    /// after this future, scaling it if the regions don't have the same size. Returns a future
+    /// that represents the end of the blit.
+    ///
+    /// Each region is given as two opposite corners, and only the first mipmap and array layer
+    /// of the images are used. The layout transitions to and from the transfer layouts and the
+    /// barriers are handled automatically: the blit waits for the accesses of this future to
+    /// the images, and the following commands see the result of the blit. This makes it easy
+    /// to produce a thumbnail of an image, or to scale a render target to a swapchain image.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if this future can't be moved to `queue`.
+    /// - Panics if the images don't belong to the same device.
+    /// - Panics if one of the images is already in use by a submission that this future doesn't
+    ///   know about.
+    ///
+    fn then_blit<S, D>(self, queue: Arc<Queue>, source: S, source_region: [[i32; 3]; 2],
+                       destination: D, destination_region: [[i32; 3]; 2], filter: Filter)
+                       -> Result<CommandBufferExecFuture<Self, BlitCommandBuffer<S, D>>,
+                                 BlitError>
+        where Self: Sized,
+              S: ImageAccess + Send + Sync + 'static,
+              D: ImageAccess + Send + Sync + 'static
+    {
+        let cb = try!(BlitCommandBuffer::new(&self, &queue, source, source_region, destination,
+                                             destination_region, filter));
+        Ok(cb.execute_after(self, queue))
+    }
+
+    /// Executes a compute shader on `queue` after this future. Returns a future that represents
+    /// the end of the dispatch.
+    ///
+    /// The buffers of `descriptor_sets` are considered to be both read and written by the
+    /// shader. If this future accesses any of them, a memory barrier is added so that the
+    /// dispatch waits for these accesses. The following submissions see the dispatch as a
+    /// `shader_read` and `shader_write` access in the `compute_shader` stage, so chaining
+    /// multiple dispatches with this method is always correctly synchronized.
+    ///
+    /// The pipeline mustn't use push constants. Descriptor sets that contain images aren't
+    /// supported and return `DispatchError::ImagesNotSupported`.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if this future can't be moved to `queue`.
+    /// - Panics if one of the buffers is already in use by a submission that this future doesn't
+    ///   know about.
+    ///
+    fn then_dispatch<P, S>(self, queue: Arc<Queue>, pipeline: P, descriptor_sets: S,
+                           group_counts: [u32; 3])
+                           -> Result<CommandBufferExecFuture<Self, DispatchCommandBuffer<P, S>>,
+                                     DispatchError>
+        where Self: Sized,
+              P: ComputePipelineAbstract + Clone + Send + Sync + 'static,
+              S: DescriptorSetsCollection + Clone + Send + Sync + 'static
+    {
+        let cb = try!(DispatchCommandBuffer::new(&self, &queue, pipeline, descriptor_sets,
+                                                 group_counts));
+        Ok(cb.execute_after(self, queue))
+    }
+
+    /// Imports a fence signaled by an external producer, and makes the operations chained after
+    /// this future wait for it.
+    ///
+    /// This is the way to synchronize with GPU work submitted by another API or library that
+    /// hands out a fence file descriptor. The descriptor is imported into a new `Fence` with
+    /// `Fence::import_fd`. Since the GPU can't wait on a fence, the following submissions are
+    /// only built once the CPU has observed the fence as signaled, which blocks until the
+    /// timeout of the returned future. See `ExternalFenceWaitFuture` for more information.
+    ///
+    /// The `khr_external_fence_fd` extension must be enabled on the device.
+    ///
+    /// # Safety
+    ///
+    /// - `fd` must be a valid file descriptor of the given handle type. On success, its
+    ///   ownership is transferred to the Vulkan implementation.
+    ///
+    #[inline]
+    unsafe fn then_wait_external_fence(self, fd: c_int, handle_type: ExternalFenceHandleType)
+                                       -> Result<ExternalFenceWaitFuture<Self>, FenceImportError>
+        where Self: Sized
+    {
+        external_fence_wait::then_wait_external_fence(self, fd, handle_type)
+    }
+
     /// Signals a semaphore after this future. Returns another future that represents the signal.
     ///
     /// Call this function when you want to execute some operations on a queue and want to see the
@@ -156,6 +967,25 @@ pub unsafe trait GpuFuture: DeviceOwned {
         semaphore_signal::then_signal_semaphore(self)
     }
 
+    /// Same as `then_signal_semaphore()`, but takes the semaphore from a pool instead of creating
+    /// a new one.
+    ///
+    /// When the returned future is destroyed, the semaphore is given back to the pool if the
+    /// future that waits on it has signaled that it is finished. A semaphore that has been
+    /// signaled but never waited upon can't be reused, and is destroyed instead.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if the pool doesn't belong to the same device as this future.
+    ///
+    #[inline]
+    fn then_signal_semaphore_from_pool(self, pool: &SemaphorePool)
+                                       -> Result<SemaphoreSignalFuture<Self>, OomError>
+        where Self: Sized
+    {
+        semaphore_signal::then_signal_semaphore_from_pool(self, pool)
+    }
+
     /// Signals a semaphore after this future and flushes it. Returns another future that
     /// represents the moment when the semaphore is signalled.
     ///
@@ -170,7 +1000,7 @@ pub unsafe trait GpuFuture: DeviceOwned {
     /// on two different queues, then you would need two submits anyway and it is always
     /// advantageous to submit A as soon as possible.
     #[inline]
-    fn then_signal_semaphore_and_flush(self) -> Result<SemaphoreSignalFuture<Self>, Box<Error>>
+    fn then_signal_semaphore_and_flush(self) -> Result<SemaphoreSignalFuture<Self>, FlushError>
         where Self: Sized
     {
         let f = self.then_signal_semaphore();
@@ -178,27 +1008,285 @@ pub unsafe trait GpuFuture: DeviceOwned {
         Ok(f)
     }
 
+    /// Signals a semaphore after this future, flushes it, then executes a command buffer on
+    /// `target_queue` that waits for the semaphore.
+    ///
+    /// This is a shortcut for `then_signal_semaphore_and_flush()` followed with
+    /// `then_execute(target_queue, command_buffer)`. The operations of this future are submitted
+    /// immediately, and the command buffer is submitted when the returned future is flushed.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if the device of the command buffer is not the same as the device of the future.
+    ///
+    #[inline]
+    fn handoff_to<Cb>(self, target_queue: Arc<Queue>, command_buffer: Cb)
+                      -> Result<CommandBufferExecFuture<SemaphoreSignalFuture<Self>, Cb>,
+                                FlushError>
+        where Self: Sized, Cb: CommandBuffer + 'static
+    {
+        let f = self.then_signal_semaphore_and_flush()?;
+        Ok(command_buffer.execute_after(f, target_queue))
+    }
+
     /// Signals a fence after this future. Returns another future that represents the signal.
     ///
     /// > **Note**: More often than not you want to immediately flush the future after calling this
     /// > function. If so, consider using `then_signal_fence_and_flush`.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if this future already signals a fence (see `signals_fence()`). Only the second
+    ///   fence would be observed, and the first one would be signaled for nothing. Wait on the
+    ///   existing `FenceSignalFuture` instead.
+    ///
     #[inline]
     fn then_signal_fence(self) -> FenceSignalFuture<Self> where Self: Sized {
         fence_signal::then_signal_fence(self)
     }
 
+    /// Same as `then_signal_fence()`, but signals an existing fence instead of creating a new one.
+    ///
+    /// The fence is reset before being used. Use `FenceSignalFuture::into_fence()` to retrieve
+    /// the fence once the GPU has finished, so that the same fence can be used for every frame.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if the fence doesn't belong to the same device as this future.
+    /// - Panics if this future already signals a fence. See `then_signal_fence()`.
+    ///
+    #[inline]
+    fn then_signal_fence_with(self, fence: Fence) -> FenceSignalFuture<Self> where Self: Sized {
+        fence_signal::then_signal_fence_with(self, fence)
+    }
+
+    /// Same as `then_signal_fence()`, but takes the fence from a pool instead of creating a new
+    /// one.
+    ///
+    /// The fence is given back to the pool once the future has been observed to be finished,
+    /// either by `cleanup_finished()` or by the destructor. Using the same pool for every frame
+    /// avoids creating and destroying a fence per submission.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if the pool doesn't belong to the same device as this future.
+    /// - Panics if this future already signals a fence. See `then_signal_fence()`.
+    ///
+    #[inline]
+    fn then_signal_fence_from_pool(self, pool: &FencePool)
+                                   -> Result<FenceSignalFuture<Self>, OomError>
+        where Self: Sized
+    {
+        fence_signal::then_signal_fence_from_pool(self, pool)
+    }
+
+    /// Same as `then_signal_fence()`, but writes a timestamp to the query `start` of `pool`
+    /// before the command buffers of this future, and a timestamp to the query `end` after
+    /// them.
+    ///
+    /// Once the fence is signaled, `FenceSignalFuture::elapsed()` returns the time that the GPU
+    /// spent between the two timestamps. The pool must have been created with
+    /// `QueryType::Timestamp`.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if this future isn't bound to a queue, or if the queue family of this queue
+    ///   doesn't support timestamps.
+    /// - Panics if `start` or `end` is out of range of the pool, or if they are equal.
+    /// - Panics if the pool wasn't created with `QueryType::Timestamp`.
+    /// - Panics if the pool doesn't belong to the same device as this future.
+    /// - Panics if this future already signals a fence. See `then_signal_fence()`.
+    ///
+    #[inline]
+    fn then_signal_fence_with_timestamps(self, pool: Arc<UnsafeQueryPool>, start: u32, end: u32)
+                                         -> Result<FenceSignalFuture<TimestampFuture<Self>>,
+                                                   OomError>
+        where Self: Sized
+    {
+        fence_signal::then_signal_fence_with_timestamps(self, pool, start, end)
+    }
+
     /// Signals a fence after this future. Returns another future that represents the signal.
     ///
     /// This is a just a shortcut for `then_signal_fence()` followed with `flush()`.
+    ///
+    /// Returns `FlushError::AlreadyFlushed` without allocating a fence if this future has already
+    /// been flushed, as its work would then be tracked by nothing but an empty submission. In
+    /// that situation, call `then_signal_fence()` before flushing the future instead. If you
+    /// really want a fence that is signaled once the queue reaches this point, use
+    /// `then_signal_fence()` followed with `flush()` explicitly.
     #[inline]
-    fn then_signal_fence_and_flush(self) -> Result<FenceSignalFuture<Self>, Box<Error>>
+    fn then_signal_fence_and_flush(self) -> Result<FenceSignalFuture<Self>, FlushError>
         where Self: Sized
     {
+        if self.is_flushed() {
+            return Err(FlushError::AlreadyFlushed);
+        }
+
         let f = self.then_signal_fence();
         f.flush()?;
         Ok(f)
     }
 
+    /// Same as `then_signal_fence_and_flush()`, but sets the timeout of the waits for the fence
+    /// to `timeout` instead of the default of 10 minutes.
+    ///
+    /// See `FenceSignalFuture::set_wait_timeout()` for the waits that the timeout applies to. The
+    /// timeout is only set once the flush succeeded.
+    #[inline]
+    fn then_signal_fence_and_flush_with_timeout(self, timeout: Duration)
+                                                -> Result<FenceSignalFuture<Self>, FlushError>
+        where Self: Sized
+    {
+        let f = try!(self.then_signal_fence_and_flush());
+        f.set_wait_timeout(Some(timeout));
+        Ok(f)
+    }
+
+    /// Limits the number of submissions in progress on the queue of this future.
+    ///
+    /// When this future is flushed, the current thread blocks until fewer than `max` of the
+    /// futures created with `max_inflight` are still being executed on the same queue. A fence
+    /// is signaled at the end of the submission in order to know when it is finished.
+    ///
+    /// This can be used as a back-pressure mechanism in order to bound the memory usage when
+    /// submitting a lot of work.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if `max` is 0.
+    /// - Panics if this future isn't bound to a queue.
+    ///
+    #[inline]
+    fn max_inflight(self, max: usize) -> MaxInflightFuture<Self> where Self: Sized {
+        max_inflight::max_inflight(self, max)
+    }
+
+    /// Reserves `bytes` of `budget` until the returned future is destroyed.
+    ///
+    /// `bytes` should be an estimation of the memory referenced by this future, for example the
+    /// size of the buffers that it uploads. If the reservation would bring the bytes reserved by
+    /// the futures of the budget above its limit, the current thread blocks until other futures
+    /// give back their bytes. If this doesn't happen before `timeout`, the future is returned
+    /// back in a `WouldExceedBudget` error. A reservation larger than the limit is granted once
+    /// no other future holds a part of the budget.
+    ///
+    /// The bytes are given back when the future is destroyed, which for a `FenceSignalFuture` can
+    /// happen as part of `cleanup_finished()` on a future that contains it. If the older futures
+    /// are owned by the current thread, pass a timeout of 0 and clean them up before retrying,
+    /// as nothing else would give the bytes back.
+    #[inline]
+    fn with_budget(self, budget: &Arc<SubmissionBudget>, bytes: usize, timeout: Duration)
+                   -> Result<BudgetedFuture<Self>, WouldExceedBudget<Self>>
+        where Self: Sized
+    {
+        budget::with_budget(self, budget, bytes, timeout)
+    }
+
+    /// Removes the access flags that aren't in `mask` from the accesses reported by this future
+    /// to the submissions that follow it.
+    ///
+    /// This is a debugging tool for finding out which access causes a barrier. For example
+    /// masking out `shader_read` shows whether a hazard disappears once the following
+    /// submissions no longer synchronize with the shader reads of this future. The pipeline
+    /// stages are reported unchanged. Since this relaxes the synchronization, it shouldn't be
+    /// used outside of debugging.
+    #[inline]
+    fn with_access_mask(self, mask: AccessFlagBits) -> AccessMaskFuture<Self> where Self: Sized {
+        access_mask::with_access_mask(self, mask)
+    }
+
+    /// Delays the submission of this future so that consecutive throttled submissions on the same
+    /// queue are spaced by at least `min_interval`.
+    ///
+    /// When this future is flushed, the current thread sleeps if the previous throttled future
+    /// on the same queue was flushed less than `min_interval` ago. This can be used to cap the
+    /// frame rate of a render loop in order to save power.
+    #[inline]
+    fn throttle(self, min_interval: Duration) -> ThrottleFuture<Self> where Self: Sized {
+        throttle::throttle(self, min_interval)
+    }
+
+    /// Delays the submissions that follow this future until the last present of the image
+    /// `image_index` of `swapchain` has completed.
+    ///
+    /// This is meant for custom swapchain flows that access a swapchain image without going
+    /// through `acquire_next_image()`, for example in order to read back what was presented.
+    /// In the usual flow, waiting on the semaphore of a `SwapchainAcquireFuture` already
+    /// guarantees that the presentation engine is done with the image. In order to avoid
+    /// synchronizing twice, nothing is waited for if this future contains the acquisition of
+    /// that same image (see `acquired_swapchain_image()`).
+    ///
+    /// Only the presents that were given an identifier with `PresentFuture::with_present_id()`
+    /// are known. The wait happens on the host with `Swapchain::wait_for_present()` when the
+    /// returned future is flushed, and is skipped if the image has never been presented with
+    /// an identifier.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if `image_index` is out of range.
+    /// - Panics if the `present_wait` feature isn't enabled on the device.
+    /// - Panics if the swapchain and this future don't belong to the same device.
+    ///
+    #[inline]
+    fn then_wait_prior_present(self, swapchain: Arc<Swapchain>, image_index: usize)
+                               -> WaitPriorPresentFuture<Self>
+        where Self: Sized
+    {
+        prior_present::wait_prior_present(self, swapchain, image_index)
+    }
+
+    /// Requires that the submissions that follow this future happen on `queue`.
+    ///
+    /// Returns an error if this future is bound to a different queue and doesn't allow changing
+    /// it (see `queue_change_allowed()`), in which case the future is destroyed. Otherwise, the
+    /// returned future reports `queue` as its queue and doesn't allow changing it anymore.
+    #[inline]
+    fn require_queue(self, queue: &Arc<Queue>) -> Result<QueuePinnedFuture<Self>, QueueMismatch>
+        where Self: Sized
+    {
+        queue_pinned::require_queue(self, queue)
+    }
+
+    /// Turns this future into one whose destructor never blocks.
+    ///
+    /// Normally, destroying a future that hasn't finished yet blocks the current thread until
+    /// the GPU is done with the resources that the future uses. If the returned future is
+    /// destroyed before it is finished, it instead flushes the submission, signals a fence at the
+    /// end of it, and hands the original future over to its queue. The queue keeps the future
+    /// (and therefore all the resources it references) alive until the fence is signaled. The
+    /// future is destroyed the next time `cleanup_finished()` is called on a leak-on-drop future
+    /// of the same queue, or when `Queue::cleanup_leaked()` or `Queue::wait()` is called.
+    ///
+    /// > **Note**: Memory is only released when one of these functions is called. If you
+    /// > never call any of them, every future dropped this way stays alive for as long as the
+    /// > queue exists, along with its buffers, images and command buffers.
+    ///
+    /// If this future isn't bound to a queue, or if flushing it fails, the destructor falls
+    /// back to the normal behavior.
+    #[inline]
+    fn leak_on_drop(self) -> LeakOnDropFuture<Self> where Self: Sized + Send + 'static {
+        leak_on_drop::leak_on_drop(self)
+    }
+
+    /// Calls `hook` on the submission built by this future, right before it is submitted by
+    /// `flush()` or returned by `build_submission()`.
+    ///
+    /// This provides a single place to handle things that apply to every submission, such as
+    /// logging, counting or adding debug annotations, without having to modify each future.
+    /// Since `build_submission()` can be called multiple times, the hook can be called more than
+    /// once before the submission is actually submitted.
+    ///
+    /// > **Note**: The hook only sees the submission that this future hands over. Submissions
+    /// > that a previous future performs on its own, for example when it is flushed separately
+    /// > or when a present follows a command buffer, aren't passed to the hook.
+    #[inline]
+    fn with_submit_hook<H>(self, hook: H) -> SubmitHookFuture<Self, H>
+        where Self: Sized, H: FnMut(&mut SubmitAnyBuilder)
+    {
+        submit_hook::with_submit_hook(self, hook)
+    }
+
     /// Presents a swapchain image after this future.
     ///
     /// You should only ever do this indirectly after a `SwapchainAcquireFuture` of the same image,
@@ -212,6 +1300,75 @@ pub unsafe trait GpuFuture: DeviceOwned {
     {
         Swapchain::present(swapchain, self, queue, image_index)
     }
+
+    /// Same as `then_swapchain_present`, but returns an error if the family of `queue` doesn't
+    /// support presenting to the surface of the swapchain.
+    ///
+    /// > **Note**: This is just a shortcut for the `Swapchain::present_checked()` function.
+    #[inline]
+    fn then_swapchain_present_checked(self, queue: Arc<Queue>, swapchain: Arc<Swapchain>,
+                                      image_index: usize)
+                                      -> Result<PresentFuture<Self>, PresentError>
+        where Self: Sized
+    {
+        Swapchain::present_checked(swapchain, self, queue, image_index)
+    }
+
+    /// Presents the swapchain image that was acquired by this future, or by one of the futures
+    /// it depends on.
+    ///
+    /// This is the same as `then_swapchain_present()`, except that the index of the image is
+    /// determined automatically from the `SwapchainAcquireFuture` that this future is built
+    /// upon. Use `then_swapchain_present()` if you need to present a specific image.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if no image of `swapchain` was acquired by this future.
+    ///
+    #[inline]
+    fn then_swapchain_present_acquired(self, queue: Arc<Queue>, swapchain: Arc<Swapchain>)
+                                       -> PresentFuture<Self>
+        where Self: Sized
+    {
+        let image_index = self.acquired_swapchain_image(&swapchain)
+                              .expect("No image of this swapchain was acquired by this future");
+        self.then_swapchain_present(queue, swapchain, image_index)
+    }
+}
+
+/// Variant of the access checks of `GpuFuture` for a future that isn't shared.
+///
+/// The methods return the same results as `check_buffer_access()` and `check_image_access()`,
+/// but take `&mut self`. Since nothing else can access the future at the same time, they skip the
+/// locks that protect the state of futures like `FenceSignalFuture`, which are otherwise taken
+/// for each resource that is checked.
+///
+/// A future shared with an `Arc` can't be borrowed mutably, and only provides the methods of
+/// `GpuFuture`, which keep locking.
+pub unsafe trait GpuFutureMut: GpuFuture {
+    /// Same as `GpuFuture::check_buffer_access()`.
+    fn check_buffer_access_mut(&mut self, buffer: &BufferAccess, exclusive: bool, queue: &Queue)
+                               -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>;
+
+    /// Same as `GpuFuture::check_image_access()`.
+    fn check_image_access_mut(&mut self, image: &ImageAccess, exclusive: bool, queue: &Queue)
+                              -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>;
+}
+
+unsafe impl<F: ?Sized> GpuFutureMut for Box<F> where F: GpuFutureMut {
+    #[inline]
+    fn check_buffer_access_mut(&mut self, buffer: &BufferAccess, exclusive: bool, queue: &Queue)
+                               -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        (**self).check_buffer_access_mut(buffer, exclusive, queue)
+    }
+
+    #[inline]
+    fn check_image_access_mut(&mut self, image: &ImageAccess, exclusive: bool, queue: &Queue)
+                              -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        (**self).check_image_access_mut(image, exclusive, queue)
+    }
 }
 
 unsafe impl<F: ?Sized> GpuFuture for Box<F> where F: GpuFuture {
@@ -221,12 +1378,12 @@ unsafe impl<F: ?Sized> GpuFuture for Box<F> where F: GpuFuture {
     }
 
     #[inline]
-    unsafe fn build_submission(&self) -> Result<SubmitAnyBuilder, Box<Error>> {
+    unsafe fn build_submission(&self) -> Result<SubmitAnyBuilder, FlushError> {
         (**self).build_submission()
     }
 
     #[inline]
-    fn flush(&self) -> Result<(), Box<Error>> {
+    fn flush(&self) -> Result<(), FlushError> {
         (**self).flush()
     }
 
@@ -245,6 +1402,11 @@ unsafe impl<F: ?Sized> GpuFuture for Box<F> where F: GpuFuture {
         (**self).queue()
     }
 
+    #[inline]
+    fn queue_family(&self) -> Option<QueueFamily> {
+        (**self).queue_family()
+    }
+
     #[inline]
     fn check_buffer_access(&self, buffer: &BufferAccess, exclusive: bool, queue: &Queue)
                           -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
@@ -258,4 +1420,205 @@ unsafe impl<F: ?Sized> GpuFuture for Box<F> where F: GpuFuture {
     {
         (**self).check_image_access(image, exclusive, queue)
     }
+
+    #[inline]
+    fn acquired_swapchain_image(&self, swapchain: &Swapchain) -> Option<usize> {
+        (**self).acquired_swapchain_image(swapchain)
+    }
+
+    #[inline]
+    fn validate_resources(&self) {
+        (**self).validate_resources();
+    }
+
+    #[inline]
+    fn wait_semaphore_count(&self) -> usize {
+        (**self).wait_semaphore_count()
+    }
+
+    #[inline]
+    fn signals_fence(&self) -> bool {
+        (**self).signals_fence()
+    }
+
+    #[inline]
+    fn is_flushed(&self) -> bool {
+        (**self).is_flushed()
+    }
+
+    #[inline]
+    fn pending_fences(&self, fences: &mut Vec<vk::Fence>) -> bool {
+        (**self).pending_fences(fences)
+    }
+
+    #[inline]
+    fn shared_identities(&self, ids: &mut Vec<usize>) {
+        (**self).shared_identities(ids)
+    }
+
+    #[inline]
+    fn empty_submission_is_finished(&self) -> bool {
+        (**self).empty_submission_is_finished()
+    }
+}
+
+/// Error that can happen when flushing a future or waiting for it to be finished.
+///
+/// This is the error returned by `GpuFuture::flush()` and `GpuFuture::build_submission()`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FlushError {
+    /// Not enough memory.
+    OomError(OomError),
+
+    /// The connection to the device has been lost.
+    DeviceLost,
+
+    /// The surface of a presented swapchain is no longer accessible and must be recreated.
+    SurfaceLost,
+
+    /// The surface of a presented swapchain has changed in a way that makes the swapchain
+    /// unusable. The swapchain must be recreated.
+    OutOfDate,
+
+    /// A presented swapchain has lost its full-screen exclusive mode.
+    FullScreenExclusiveLost,
+
+    /// A command buffer of the submission can't be executed in the way it was requested.
+    ResourceAccessError(CommandBufferExecError),
+
+    /// The global priority of the queue isn't permitted by the implementation.
+    NotPermitted,
+
+    /// The submission uses pipeline stages that require a device extension that isn't enabled.
+    UnsupportedPipelineStages,
+
+    /// The submission would wait on more semaphores than the maximum that was set with
+    /// `JoinFuture::with_max_wait_semaphores()`.
+    TooManyWaitSemaphores(TooManyWaitSemaphoresError),
+
+    /// The timeout of the wait has been reached before the GPU finished.
+    Timeout,
+
+    /// The future has already been flushed. Returned by `then_signal_fence_and_flush()`.
+    AlreadyFlushed,
+
+    /// A partially flushed future has been flushed again more times than allowed. See
+    /// `FenceSignalFuture::max_partial_flush_retries()`.
+    RetriesExceeded,
+}
+
+impl error::Error for FlushError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            FlushError::OomError(_) => "not enough memory",
+            FlushError::DeviceLost => "the connection to the device has been lost",
+            FlushError::SurfaceLost => "the surface of a presented swapchain is no longer valid",
+            FlushError::OutOfDate => "a presented swapchain needs to be recreated",
+            FlushError::FullScreenExclusiveLost => {
+                "a presented swapchain has lost its full-screen exclusive mode"
+            },
+            FlushError::ResourceAccessError(_) => "a command buffer of the submission can't be \
+                                                   executed",
+            FlushError::NotPermitted => "the global priority of the queue isn't permitted",
+            FlushError::UnsupportedPipelineStages => {
+                "the submission uses pipeline stages whose extension isn't enabled"
+            },
+            FlushError::TooManyWaitSemaphores(_) => {
+                "the submission waits on more semaphores than the maximum"
+            },
+            FlushError::Timeout => "the timeout has been reached",
+            FlushError::AlreadyFlushed => "the future has already been flushed",
+            FlushError::RetriesExceeded => "the maximum number of flush retries has been reached",
+        }
+    }
+
+    #[inline]
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            FlushError::OomError(ref err) => Some(err),
+            FlushError::ResourceAccessError(ref err) => Some(err),
+            FlushError::TooManyWaitSemaphores(ref err) => Some(err),
+            _ => None
+        }
+    }
+}
+
+impl fmt::Display for FlushError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+impl From<OomError> for FlushError {
+    #[inline]
+    fn from(err: OomError) -> FlushError {
+        FlushError::OomError(err)
+    }
+}
+
+impl From<SubmitCommandBufferError> for FlushError {
+    #[inline]
+    fn from(err: SubmitCommandBufferError) -> FlushError {
+        match err {
+            SubmitCommandBufferError::OomError(err) => FlushError::OomError(err),
+            SubmitCommandBufferError::DeviceLost => FlushError::DeviceLost,
+            SubmitCommandBufferError::NotPermitted => FlushError::NotPermitted,
+            SubmitCommandBufferError::UnsupportedPipelineStages => {
+                FlushError::UnsupportedPipelineStages
+            },
+        }
+    }
+}
+
+impl From<SubmitPresentError> for FlushError {
+    #[inline]
+    fn from(err: SubmitPresentError) -> FlushError {
+        match err {
+            SubmitPresentError::OomError(err) => FlushError::OomError(err),
+            SubmitPresentError::DeviceLost => FlushError::DeviceLost,
+            SubmitPresentError::SurfaceLost => FlushError::SurfaceLost,
+            SubmitPresentError::OutOfDate => FlushError::OutOfDate,
+            SubmitPresentError::FullScreenExclusiveLost => FlushError::FullScreenExclusiveLost,
+        }
+    }
+}
+
+impl From<PresentWaitError> for FlushError {
+    #[inline]
+    fn from(err: PresentWaitError) -> FlushError {
+        match err {
+            PresentWaitError::OomError(err) => FlushError::OomError(err),
+            PresentWaitError::DeviceLost => FlushError::DeviceLost,
+            PresentWaitError::Timeout => FlushError::Timeout,
+            PresentWaitError::SurfaceLost => FlushError::SurfaceLost,
+            PresentWaitError::OutOfDate => FlushError::OutOfDate,
+        }
+    }
+}
+
+impl From<CommandBufferExecError> for FlushError {
+    #[inline]
+    fn from(err: CommandBufferExecError) -> FlushError {
+        FlushError::ResourceAccessError(err)
+    }
+}
+
+impl From<TooManyWaitSemaphoresError> for FlushError {
+    #[inline]
+    fn from(err: TooManyWaitSemaphoresError) -> FlushError {
+        FlushError::TooManyWaitSemaphores(err)
+    }
+}
+
+impl From<FenceWaitError> for FlushError {
+    #[inline]
+    fn from(err: FenceWaitError) -> FlushError {
+        match err {
+            FenceWaitError::OomError(err) => FlushError::OomError(err),
+            FenceWaitError::Timeout => FlushError::Timeout,
+            FenceWaitError::DeviceLostError => FlushError::DeviceLost,
+        }
+    }
 }
@@ -0,0 +1,136 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use sync::GpuFuture;
+
+/// Collection of semaphore signal points identified by a name.
+///
+/// When a frame is made of multiple passes, each pass usually signals a semaphore that one or
+/// more later submissions wait upon. Instead of keeping track of every `SemaphoreSignalFuture`
+/// manually, you can register them here with `signal_as()` and retrieve the future that waits on
+/// the semaphore with `wait_for()` when building the downstream submission.
+///
+/// # Example
+///
+/// ```no_run
+/// use vulkano::sync::GpuFuture;
+/// use vulkano::sync::NamedSemaphores;
+///
+/// # let gbuffer_future: vulkano::sync::DummyFuture = return;
+/// # let shadows_future: vulkano::sync::DummyFuture = return;
+/// let mut signals = NamedSemaphores::new();
+/// signals.signal_as("gbuffer", gbuffer_future);
+/// signals.signal_as("shadows", shadows_future);
+///
+/// // Later, when building the lighting pass.
+/// let gbuffer = signals.wait_for("gbuffer").unwrap();
+/// let shadows = signals.wait_for("shadows").unwrap();
+/// let lighting = gbuffer.join(shadows);
+/// ```
+///
+/// Dropping a `NamedSemaphores` that still contains signal points blocks until the GPU has
+/// finished processing them, just like dropping a `SemaphoreSignalFuture`.
+pub struct NamedSemaphores {
+    signals: HashMap<String, Box<GpuFuture + Send>>,
+}
+
+impl NamedSemaphores {
+    /// Builds an empty collection.
+    #[inline]
+    pub fn new() -> NamedSemaphores {
+        NamedSemaphores {
+            signals: HashMap::new(),
+        }
+    }
+
+    /// Signals a semaphore after `future` and registers it under the given name.
+    ///
+    /// This is the same as calling `future.then_signal_semaphore()`, except that the resulting
+    /// future is stored in the collection.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if a signal point with the same name is already registered.
+    /// - Panics if `future` isn't bound to a queue, as with `then_signal_semaphore()`.
+    ///
+    pub fn signal_as<F>(&mut self, name: &str, future: F)
+        where F: GpuFuture + Send + 'static
+    {
+        assert!(!self.signals.contains_key(name),
+                "A signal point named `{}` is already registered", name);
+        let signal = Box::new(future.then_signal_semaphore()) as Box<_>;
+        self.signals.insert(name.to_owned(), signal);
+    }
+
+    /// Removes the signal point with the given name and returns a future that waits on its
+    /// semaphore.
+    ///
+    /// Returns `None` if no signal point with this name is registered. Since a semaphore can only
+    /// be waited upon once, retrieving the same name twice also returns `None`.
+    #[inline]
+    pub fn wait_for(&mut self, name: &str) -> Option<Box<GpuFuture + Send>> {
+        self.signals.remove(name)
+    }
+
+    /// Returns true if a signal point with the given name is registered.
+    #[inline]
+    pub fn contains(&self, name: &str) -> bool {
+        self.signals.contains_key(name)
+    }
+
+    /// Returns the names of the signal points that haven't been retrieved yet.
+    #[inline]
+    pub fn names<'a>(&'a self) -> Box<Iterator<Item = &'a str> + 'a> {
+        Box::new(self.signals.keys().map(|name| name.as_str()))
+    }
+}
+
+impl fmt::Debug for NamedSemaphores {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_set().entries(self.signals.keys()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use command_buffer::CommandBuffer;
+    use sync::GpuFuture;
+    use sync::NamedSemaphores;
+
+    #[test]
+    fn signal_then_wait() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let cb = empty_command_buffer!(device, queue);
+
+        let mut signals = NamedSemaphores::new();
+        signals.signal_as("pass", cb.execute(queue.clone()));
+        assert!(signals.contains("pass"));
+
+        let wait = signals.wait_for("pass").unwrap();
+        assert!(signals.wait_for("pass").is_none());
+        wait.then_signal_fence_and_flush().unwrap();
+    }
+
+    #[test]
+    #[should_panic]
+    fn duplicate_name() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let cb1 = empty_command_buffer!(device, queue);
+        let cb2 = empty_command_buffer!(device, queue);
+
+        let mut signals = NamedSemaphores::new();
+        signals.signal_as("pass", cb1.execute(queue.clone()));
+        signals.signal_as("pass", cb2.execute(queue.clone()));
+    }
+}
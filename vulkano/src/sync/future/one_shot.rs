@@ -0,0 +1,157 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::error::Error;
+use std::sync::Arc;
+
+use buffer::BufferAccess;
+use command_buffer::CommandBuffer;
+use command_buffer::cb::AddCommand;
+use command_buffer::cb::CommandBufferBuild;
+use command_buffer::cb::Flags;
+use command_buffer::cb::Kind;
+use command_buffer::cb::UnsafeCommandBuffer;
+use command_buffer::cb::UnsafeCommandBufferBuilder;
+use command_buffer::pool::StandardCommandPool;
+use device::Device;
+use device::DeviceOwned;
+use device::Queue;
+use image::ImageAccess;
+use sync::AccessFlagBits;
+use sync::Event;
+use sync::GpuFuture;
+use sync::PipelineStages;
+use OomError;
+
+/// Command buffer built by one of the methods of `GpuFuture`, such as `then_upload()` or
+/// `then_dispatch()`, and submitted once by the future that owns it.
+///
+/// `R` holds the resources used by the commands of the command buffer, which are kept alive for
+/// as long as the command buffer. It also determines how these resources are locked and how the
+/// command buffer reports its accesses to them.
+pub struct OneShotCommandBuffer<R> {
+    inner: UnsafeCommandBuffer<Arc<StandardCommandPool>>,
+    resources: R,
+}
+
+impl<R> OneShotCommandBuffer<R> {
+    /// Returns the resources used by the commands.
+    #[inline]
+    pub fn resources(&self) -> &R {
+        &self.resources
+    }
+}
+
+/// Resources used by the commands of a `OneShotCommandBuffer`.
+///
+/// The methods correspond to the methods of the same name of `CommandBuffer`. By default, the
+/// commands don't access any buffer or image.
+pub unsafe trait OneShotResources {
+    /// See `CommandBuffer::submit_check()`.
+    #[inline]
+    fn submit_check(&self, _: &GpuFuture, _: &Queue) -> Result<(), Box<Error>> {
+        Ok(())
+    }
+
+    /// See `CommandBuffer::check_buffer_access()`.
+    #[inline]
+    fn check_buffer_access(&self, _: &BufferAccess, _: bool, _: &Queue)
+                           -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        Err(())
+    }
+
+    /// See `CommandBuffer::check_image_access()`.
+    #[inline]
+    fn check_image_access(&self, _: &ImageAccess, _: bool, _: &Queue)
+                          -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        Err(())
+    }
+}
+
+unsafe impl OneShotResources for () {
+}
+
+unsafe impl OneShotResources for Arc<Event> {
+}
+
+unsafe impl<R> CommandBuffer for OneShotCommandBuffer<R> where R: OneShotResources {
+    type Pool = Arc<StandardCommandPool>;
+
+    #[inline]
+    fn inner(&self) -> &UnsafeCommandBuffer<Arc<StandardCommandPool>> {
+        &self.inner
+    }
+
+    #[inline]
+    fn submit_check(&self, future: &GpuFuture, queue: &Queue) -> Result<(), Box<Error>> {
+        self.resources.submit_check(future, queue)
+    }
+
+    #[inline]
+    fn check_buffer_access(&self, buffer: &BufferAccess, exclusive: bool, queue: &Queue)
+                           -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        self.resources.check_buffer_access(buffer, exclusive, queue)
+    }
+
+    #[inline]
+    fn check_image_access(&self, image: &ImageAccess, exclusive: bool, queue: &Queue)
+                          -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        self.resources.check_image_access(image, exclusive, queue)
+    }
+}
+
+unsafe impl<R> DeviceOwned for OneShotCommandBuffer<R> {
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        self.inner.device()
+    }
+}
+
+// Builder of a `OneShotCommandBuffer`.
+pub struct OneShotBuilder {
+    inner: UnsafeCommandBufferBuilder<Arc<StandardCommandPool>>,
+}
+
+impl OneShotBuilder {
+    // Starts building a primary command buffer for the queue family of `queue`.
+    //
+    // The command buffer is created with the one time submit flag, as it is only ever submitted
+    // once by the future that owns it. The safety requirements are the ones of
+    // `UnsafeCommandBufferBuilder::new()`.
+    #[inline]
+    pub unsafe fn new(queue: &Queue) -> Result<OneShotBuilder, OomError> {
+        let pool = Device::standard_command_pool(queue.device(), queue.family());
+        let inner = try!(UnsafeCommandBufferBuilder::new(&pool, Kind::primary(),
+                                                         Flags::OneTimeSubmit));
+        Ok(OneShotBuilder { inner: inner })
+    }
+
+    // Appends a command.
+    #[inline]
+    pub fn add<C>(self, cmd: C) -> OneShotBuilder
+        where UnsafeCommandBufferBuilder<Arc<StandardCommandPool>>:
+                  AddCommand<C, Out = UnsafeCommandBufferBuilder<Arc<StandardCommandPool>>>
+    {
+        // Adding a command to an unsafe builder never fails.
+        OneShotBuilder { inner: AddCommand::add(self.inner, cmd).unwrap() }
+    }
+
+    // Finishes building the command buffer, which keeps `resources` alive.
+    #[inline]
+    pub fn build<R>(self, resources: R) -> Result<OneShotCommandBuffer<R>, OomError> {
+        Ok(OneShotCommandBuffer {
+            inner: try!(self.inner.build()),
+            resources: resources,
+        })
+    }
+}
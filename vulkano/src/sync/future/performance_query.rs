@@ -0,0 +1,240 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+
+use buffer::BufferAccess;
+use command_buffer::submit::SubmitAnyBuilder;
+use command_buffer::submit::SubmitCommandBufferBuilder;
+use device::Device;
+use device::DeviceOwned;
+use device::Queue;
+use image::ImageAccess;
+use query::PerformanceCounterResult;
+use query::PerformanceQueryPool;
+use query::ProfilingLock;
+use swapchain::Swapchain;
+use sync::AccessFlagBits;
+use sync::FlushError;
+use sync::GpuFuture;
+use sync::PipelineStages;
+use OomError;
+
+/// Builds a new performance query future.
+#[inline]
+pub fn then_profile<F>(future: F, pool: Arc<PerformanceQueryPool>, query: u32,
+                       lock: Arc<ProfilingLock>) -> PerformanceQueryFuture<F>
+    where F: GpuFuture
+{
+    let queue = future.queue().expect("then_profile() requires a future bound to a queue")
+                      .clone();
+    assert_eq!(queue.family().id(), pool.queue_family_id());
+    assert!(query < pool.num_slots());
+    assert_eq!(&**lock.device() as *const Device, &**pool.device() as *const Device);
+
+    PerformanceQueryFuture {
+        previous: future,
+        queue: queue,
+        pool: pool,
+        query: query,
+        lock: lock,
+        submitted_passes: Mutex::new(0),
+        finished: AtomicBool::new(false),
+    }
+}
+
+/// Represents the execution of command buffers that contain a performance query, once for each
+/// pass required by the query pool.
+///
+/// Created with `GpuFuture::then_profile()`.
+#[must_use = "Dropping this object will immediately block the thread until the GPU has finished processing the submission"]
+pub struct PerformanceQueryFuture<F> where F: GpuFuture {
+    previous: F,
+    queue: Arc<Queue>,
+    pool: Arc<PerformanceQueryPool>,
+    query: u32,
+    // Kept alive until the GPU has finished executing all the passes.
+    lock: Arc<ProfilingLock>,
+    // Number of passes that have been successfully submitted.
+    submitted_passes: Mutex<u32>,
+    finished: AtomicBool,
+}
+
+impl<F> PerformanceQueryFuture<F> where F: GpuFuture {
+    /// Returns the query pool that contains the query.
+    #[inline]
+    pub fn pool(&self) -> &Arc<PerformanceQueryPool> {
+        &self.pool
+    }
+
+    /// Returns the index of the query within the pool.
+    #[inline]
+    pub fn query(&self) -> u32 {
+        self.query
+    }
+
+    /// Returns the profiling lock that is held until the submission has finished.
+    #[inline]
+    pub fn profiling_lock(&self) -> &Arc<ProfilingLock> {
+        &self.lock
+    }
+
+    /// Returns the value of each counter of the query, in the same order as
+    /// `pool().counters()`.
+    ///
+    /// Returns `None` if the GPU hasn't finished executing all the passes yet, including if the
+    /// future hasn't been flushed.
+    #[inline]
+    pub fn results(&self) -> Result<Option<Vec<PerformanceCounterResult>>, OomError> {
+        self.pool.results(self.query)
+    }
+}
+
+unsafe impl<F> GpuFuture for PerformanceQueryFuture<F> where F: GpuFuture {
+    #[inline]
+    fn cleanup_finished(&mut self) {
+        self.previous.cleanup_finished();
+    }
+
+    #[inline]
+    unsafe fn build_submission(&self) -> Result<SubmitAnyBuilder, FlushError> {
+        // The passes can't be merged with the submission of the next future.
+        try!(self.flush());
+        Ok(SubmitAnyBuilder::Empty)
+    }
+
+    fn flush(&self) -> Result<(), FlushError> {
+        unsafe {
+            let mut submitted = self.submitted_passes.lock().unwrap();
+            let num_passes = self.pool.num_passes();
+
+            if *submitted >= num_passes {
+                return Ok(());
+            }
+
+            let mut first_pass = match try!(self.previous.build_submission()) {
+                SubmitAnyBuilder::Empty => SubmitCommandBufferBuilder::new(),
+                SubmitAnyBuilder::SemaphoresWait(sem) => sem.into(),
+                SubmitAnyBuilder::CommandBuffer(builder) => builder,
+                SubmitAnyBuilder::QueuePresent(present) => {
+                    // Submitted right before the first pass, so it was already submitted if
+                    // the first pass was.
+                    if *submitted == 0 {
+                        try!(present.submit(&self.queue));
+                    }
+                    SubmitCommandBufferBuilder::new()
+                },
+            };
+
+            // The other passes execute the same command buffers again. Only the first pass
+            // waits upon the semaphores of the previous future, since the passes are executed
+            // in order on the same queue.
+            let command_buffers = first_pass.clone_command_buffers();
+
+            if *submitted == 0 {
+                first_pass.set_performance_query_pass(0);
+                try!(first_pass.submit(&self.queue));
+                *submitted = 1;
+            }
+
+            // Each pass is only counted once submitted, in order to resume from the pass that
+            // failed next time if an error occurs.
+            while *submitted < num_passes {
+                let mut builder = command_buffers.clone_command_buffers();
+                builder.set_performance_query_pass(*submitted);
+                try!(builder.submit(&self.queue));
+                *submitted += 1;
+            }
+
+            Ok(())
+        }
+    }
+
+    #[inline]
+    unsafe fn signal_finished(&self) {
+        sync_assert_eq!(*self.submitted_passes.lock().unwrap(), self.pool.num_passes());
+        self.finished.store(true, Ordering::SeqCst);
+        self.previous.signal_finished();
+    }
+
+    #[inline]
+    fn queue_change_allowed(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    fn queue(&self) -> Option<&Arc<Queue>> {
+        Some(&self.queue)
+    }
+
+    #[inline]
+    fn check_buffer_access(&self, buffer: &BufferAccess, exclusive: bool, queue: &Queue)
+                           -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        self.previous.check_buffer_access(buffer, exclusive, queue)
+    }
+
+    #[inline]
+    fn check_image_access(&self, image: &ImageAccess, exclusive: bool, queue: &Queue)
+                          -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        self.previous.check_image_access(image, exclusive, queue)
+    }
+
+    #[inline]
+    fn acquired_swapchain_image(&self, swapchain: &Swapchain) -> Option<usize> {
+        self.previous.acquired_swapchain_image(swapchain)
+    }
+
+    #[inline]
+    fn validate_resources(&self) {
+        self.previous.validate_resources();
+    }
+
+    #[inline]
+    fn wait_semaphore_count(&self) -> usize {
+        // The semaphores of the previous future are waited upon by the first pass.
+        0
+    }
+
+    #[inline]
+    fn is_flushed(&self) -> bool {
+        *self.submitted_passes.lock().unwrap() >= self.pool.num_passes()
+    }
+
+    #[inline]
+    fn shared_identities(&self, ids: &mut Vec<usize>) {
+        self.previous.shared_identities(ids);
+    }
+}
+
+unsafe impl<F> DeviceOwned for PerformanceQueryFuture<F> where F: GpuFuture {
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        self.queue.device()
+    }
+}
+
+impl<F> Drop for PerformanceQueryFuture<F> where F: GpuFuture {
+    fn drop(&mut self) {
+        unsafe {
+            if !*self.finished.get_mut() {
+                // TODO: handle errors?
+                self.flush().unwrap();
+                // Block until the queue finished, so that the profiling lock isn't released
+                // while the passes are still executing.
+                self.queue.wait().unwrap();
+                self.previous.signal_finished();
+            }
+        }
+    }
+}
@@ -0,0 +1,192 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use buffer::BufferAccess;
+use command_buffer::submit::SubmitAnyBuilder;
+use device::Device;
+use device::DeviceOwned;
+use device::Queue;
+use image::ImageAccess;
+use swapchain::Swapchain;
+use sync::AccessFlagBits;
+use sync::FlushError;
+use sync::GpuFuture;
+use sync::PipelineStages;
+use VulkanObject;
+use instance::QueueFamily;
+
+/// Builds a new prior present wait future.
+///
+/// # Panic
+///
+/// - Panics if `image_index` is out of range.
+/// - Panics if the `present_wait` feature isn't enabled on the device.
+/// - Panics if the swapchain and the future don't belong to the same device.
+///
+#[inline]
+pub fn wait_prior_present<F>(future: F, swapchain: Arc<Swapchain>, image_index: usize)
+                             -> WaitPriorPresentFuture<F>
+    where F: GpuFuture
+{
+    assert!(image_index < swapchain.num_images() as usize);
+    assert!(swapchain.device().enabled_features().present_wait);
+    assert_eq!(future.device().internal_object(), swapchain.device().internal_object());
+
+    WaitPriorPresentFuture {
+        previous: future,
+        swapchain: swapchain,
+        image_index: image_index,
+        waited: AtomicBool::new(false),
+    }
+}
+
+/// Delays the submission of the futures that follow a previous future until the last present of
+/// a swapchain image has completed.
+///
+/// Created with `GpuFuture::then_wait_prior_present()`.
+#[must_use]
+pub struct WaitPriorPresentFuture<F> where F: GpuFuture {
+    previous: F,
+    swapchain: Arc<Swapchain>,
+    image_index: usize,
+    // True if we already waited for the present. Waiting only happens once, even if the future
+    // is flushed or built multiple times.
+    waited: AtomicBool,
+}
+
+impl<F> WaitPriorPresentFuture<F> where F: GpuFuture {
+    /// Returns the swapchain of the image.
+    #[inline]
+    pub fn swapchain(&self) -> &Arc<Swapchain> {
+        &self.swapchain
+    }
+
+    /// Returns the index of the image in the list of images returned when creating the
+    /// swapchain.
+    #[inline]
+    pub fn image_index(&self) -> usize {
+        self.image_index
+    }
+
+    // Blocks the current thread until the last present of the image has completed, unless the
+    // acquire semaphore of the image is already part of the previous future.
+    fn wait_present(&self) -> Result<(), FlushError> {
+        if self.waited.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+
+        let acquired = self.previous.acquired_swapchain_image(&self.swapchain);
+        if acquired != Some(self.image_index) {
+            if let Some(present_id) = self.swapchain.last_present_id(self.image_index) {
+                // A timeout this large is turned into an infinite wait.
+                try!(self.swapchain.wait_for_present(present_id,
+                                                     Duration::from_secs(u64::max_value())));
+            }
+        }
+
+        // Only write `true` here in order to try again next time if the wait failed.
+        self.waited.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+}
+
+unsafe impl<F> GpuFuture for WaitPriorPresentFuture<F> where F: GpuFuture {
+    #[inline]
+    fn cleanup_finished(&mut self) {
+        self.previous.cleanup_finished();
+    }
+
+    #[inline]
+    unsafe fn build_submission(&self) -> Result<SubmitAnyBuilder, FlushError> {
+        try!(self.wait_present());
+        self.previous.build_submission()
+    }
+
+    #[inline]
+    fn flush(&self) -> Result<(), FlushError> {
+        try!(self.wait_present());
+        self.previous.flush()
+    }
+
+    #[inline]
+    unsafe fn signal_finished(&self) {
+        self.previous.signal_finished();
+    }
+
+    #[inline]
+    fn queue_change_allowed(&self) -> bool {
+        self.previous.queue_change_allowed()
+    }
+
+    #[inline]
+    fn queue(&self) -> Option<&Arc<Queue>> {
+        self.previous.queue()
+    }
+
+    #[inline]
+    fn queue_family(&self) -> Option<QueueFamily> {
+        self.previous.queue_family()
+    }
+
+    #[inline]
+    fn check_buffer_access(&self, buffer: &BufferAccess, exclusive: bool, queue: &Queue)
+                           -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        self.previous.check_buffer_access(buffer, exclusive, queue)
+    }
+
+    #[inline]
+    fn check_image_access(&self, image: &ImageAccess, exclusive: bool, queue: &Queue)
+                          -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        self.previous.check_image_access(image, exclusive, queue)
+    }
+
+    #[inline]
+    fn acquired_swapchain_image(&self, swapchain: &Swapchain) -> Option<usize> {
+        self.previous.acquired_swapchain_image(swapchain)
+    }
+
+    #[inline]
+    fn validate_resources(&self) {
+        self.previous.validate_resources();
+    }
+
+    #[inline]
+    fn wait_semaphore_count(&self) -> usize {
+        self.previous.wait_semaphore_count()
+    }
+
+    #[inline]
+    fn signals_fence(&self) -> bool {
+        self.previous.signals_fence()
+    }
+
+    #[inline]
+    fn is_flushed(&self) -> bool {
+        self.previous.is_flushed()
+    }
+
+    #[inline]
+    fn shared_identities(&self, ids: &mut Vec<usize>) {
+        self.previous.shared_identities(ids);
+    }
+}
+
+unsafe impl<F> DeviceOwned for WaitPriorPresentFuture<F> where F: GpuFuture {
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        self.previous.device()
+    }
+}
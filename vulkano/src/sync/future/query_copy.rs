@@ -0,0 +1,255 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::error;
+use std::error::Error;
+use std::fmt;
+use std::sync::Arc;
+
+use buffer::BufferAccess;
+use command_buffer::commands_raw::CmdCopyQueryPoolResults;
+use command_buffer::commands_raw::CmdCopyQueryPoolResultsError;
+use command_buffer::commands_raw::CmdPipelineBarrier;
+use device::Queue;
+use query::QueryResultFlags;
+use query::UnsafeQueryPool;
+use sync::AccessFlagBits;
+use sync::GpuFuture;
+use sync::PipelineStages;
+use sync::future::one_shot::OneShotBuilder;
+use sync::future::one_shot::OneShotCommandBuffer;
+use sync::future::one_shot::OneShotResources;
+use OomError;
+use VulkanObject;
+
+/// Command buffer that copies the results of a range of queries into a buffer, surrounded by
+/// the barriers that the copy requires.
+///
+/// Created with `GpuFuture::then_copy_query_results()`.
+pub type QueryResultsCopyCommandBuffer<B> = OneShotCommandBuffer<QueryResultsCopyResources<B>>;
+
+/// Resources of a `QueryResultsCopyCommandBuffer`.
+pub struct QueryResultsCopyResources<B> {
+    // Owns the pool and the buffer.
+    copy: CmdCopyQueryPoolResults<B>,
+}
+
+impl<B> OneShotCommandBuffer<QueryResultsCopyResources<B>> where B: BufferAccess {
+    /// Builds a command buffer that copies the results of the `count` queries of `pool` starting
+    /// at `first_query` into `destination`, for the queue family of `queue`.
+    ///
+    /// The copy is preceded with an execution dependency on all the commands previously
+    /// submitted to the queue, which are the ones that write the queries, and with a barrier
+    /// that waits for the accesses of `future` to `destination`. It is followed with a barrier
+    /// that makes the results available to all the commands that follow.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if `count` is 0.
+    /// - Panics if the range of queries is out of range of the pool.
+    /// - Panics if the buffer and the pool don't belong to the same device.
+    ///
+    pub fn new(future: &GpuFuture, queue: &Queue, pool: Arc<UnsafeQueryPool>, first_query: u32,
+               count: u32, destination: B, stride: usize, flags: QueryResultFlags)
+               -> Result<QueryResultsCopyCommandBuffer<B>, QueryResultsCopyError>
+    {
+        let copy = try!(CmdCopyQueryPoolResults::new(pool, first_query, count, destination,
+                                                     stride, flags));
+
+        let destination_scope = match future.check_buffer_access(copy.buffer(), true, queue) {
+            Ok(Some(scope)) => scope,
+            _ => (PipelineStages { top_of_pipe: true, .. PipelineStages::none() },
+                  AccessFlagBits::none()),
+        };
+
+        let transfer = PipelineStages { transfer: true, .. PipelineStages::none() };
+        let transfer_write = AccessFlagBits { transfer_write: true, .. AccessFlagBits::none() };
+        let (after_stages, after_access) = after_copy_scope();
+
+        let builder = unsafe {
+            let mut barrier = CmdPipelineBarrier::new();
+            // Query operations aren't memory accesses, so an execution dependency is enough for
+            // the copy to happen after them. Whether it also waits for the results to be
+            // available is controlled by the `wait` flag.
+            barrier.add_execution_dependency(PipelineStages { all_commands: true,
+                                                              .. PipelineStages::none() },
+                                             transfer, false);
+            barrier.add_buffer_memory_barrier(copy.buffer(), destination_scope.0,
+                                              destination_scope.1, transfer, transfer_write,
+                                              false, None, 0, copy.buffer().size());
+            try!(OneShotBuilder::new(queue)).add(&barrier)
+        };
+
+        let builder = builder.add(&copy);
+
+        let builder = unsafe {
+            let mut barrier = CmdPipelineBarrier::new();
+            barrier.add_buffer_memory_barrier(copy.buffer(), transfer, transfer_write,
+                                              after_stages, after_access, false, None, 0,
+                                              copy.buffer().size());
+            builder.add(&barrier)
+        };
+
+        Ok(try!(builder.build(QueryResultsCopyResources { copy: copy })))
+    }
+
+    /// Returns the query pool whose results are copied.
+    #[inline]
+    pub fn pool(&self) -> &Arc<UnsafeQueryPool> {
+        self.resources().copy.pool()
+    }
+
+    /// Returns the buffer that the results are written to.
+    #[inline]
+    pub fn destination(&self) -> &B {
+        self.resources().copy.buffer()
+    }
+}
+
+// Destination scope of the barrier that follows the copy. The results are typically read by a
+// shader or by an indirect command, but we don't know which one.
+#[inline]
+fn after_copy_scope() -> (PipelineStages, AccessFlagBits) {
+    (PipelineStages { all_commands: true, .. PipelineStages::none() },
+     AccessFlagBits { memory_read: true, memory_write: true, .. AccessFlagBits::none() })
+}
+
+unsafe impl<B> OneShotResources for QueryResultsCopyResources<B>
+    where B: BufferAccess + Send + Sync
+{
+    fn submit_check(&self, future: &GpuFuture, queue: &Queue) -> Result<(), Box<Error>> {
+        let destination = self.copy.buffer();
+        if future.check_buffer_access(destination, true, queue).is_ok() {
+            unsafe { destination.increase_gpu_lock(); }
+        } else {
+            assert!(destination.try_gpu_lock(true, queue), "The destination of a query results \
+                                                            copy is already in use");
+        }
+
+        Ok(())
+    }
+
+    #[inline]
+    fn check_buffer_access(&self, buffer: &BufferAccess, _: bool, _: &Queue)
+                           -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        let raw = buffer.inner().buffer.internal_object();
+
+        if raw == self.copy.buffer().inner().buffer.internal_object() {
+            Ok(Some(after_copy_scope()))
+        } else {
+            Err(())
+        }
+    }
+}
+
+/// Error that can happen when calling `GpuFuture::then_copy_query_results()`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum QueryResultsCopyError {
+    /// Not enough memory to create the command buffer.
+    OomError(OomError),
+
+    /// The results can't be copied to the buffer.
+    CopyError(CmdCopyQueryPoolResultsError),
+}
+
+impl error::Error for QueryResultsCopyError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            QueryResultsCopyError::OomError(_) => "not enough memory",
+            QueryResultsCopyError::CopyError(_) => "the results can't be copied to the buffer",
+        }
+    }
+
+    #[inline]
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            QueryResultsCopyError::OomError(ref err) => Some(err),
+            QueryResultsCopyError::CopyError(ref err) => Some(err),
+        }
+    }
+}
+
+impl fmt::Display for QueryResultsCopyError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+impl From<OomError> for QueryResultsCopyError {
+    #[inline]
+    fn from(err: OomError) -> QueryResultsCopyError {
+        QueryResultsCopyError::OomError(err)
+    }
+}
+
+impl From<CmdCopyQueryPoolResultsError> for QueryResultsCopyError {
+    #[inline]
+    fn from(err: CmdCopyQueryPoolResultsError) -> QueryResultsCopyError {
+        QueryResultsCopyError::CopyError(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use buffer::Buffer;
+    use buffer::BufferUsage;
+    use buffer::DeviceLocalBuffer;
+    use command_buffer::CommandBuffer;
+    use command_buffer::commands_raw::CmdCopyQueryPoolResultsError;
+    use query::QueryResultFlags;
+    use query::QueryType;
+    use query::UnsafeQueryPool;
+    use sync::GpuFuture;
+    use sync::QueryResultsCopyError;
+
+    #[test]
+    fn copy_query_results() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let pool = Arc::new(UnsafeQueryPool::new(device.clone(), QueryType::Timestamp, 4)
+                                .unwrap());
+        let buffer = DeviceLocalBuffer::<[u64]>::array(&device, 4, &BufferUsage::transfer_dest(),
+                                                       Some(queue.family())).unwrap();
+
+        let flags = QueryResultFlags { result_64: true, .. QueryResultFlags::none() };
+
+        let cb = empty_command_buffer!(device, queue);
+        cb.execute(queue.clone())
+          .then_reset_query_pool(pool.clone(), 0, 4).unwrap()
+          .then_copy_query_results(queue.clone(), pool, 0, 4, buffer.access(), 8, flags)
+          .unwrap()
+          .then_signal_fence_and_flush().unwrap();
+    }
+
+    #[test]
+    fn buffer_too_small() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let pool = Arc::new(UnsafeQueryPool::new(device.clone(), QueryType::Timestamp, 4)
+                                .unwrap());
+        let buffer = DeviceLocalBuffer::<[u32]>::array(&device, 2, &BufferUsage::transfer_dest(),
+                                                       Some(queue.family())).unwrap();
+
+        let cb = empty_command_buffer!(device, queue);
+        let future = cb.execute(queue.clone());
+        match future.then_copy_query_results(queue.clone(), pool, 0, 4, buffer.access(), 4,
+                                             QueryResultFlags::none())
+        {
+            Err(QueryResultsCopyError::CopyError(CmdCopyQueryPoolResultsError::BufferTooSmall)) => {
+                ()
+            },
+            _ => panic!(),
+        }
+    }
+}
@@ -0,0 +1,107 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::sync::Arc;
+
+use command_buffer::commands_raw::CmdPipelineBarrier;
+use command_buffer::commands_raw::CmdResetQueryPool;
+use device::Queue;
+use query::UnsafeQueryPool;
+use sync::PipelineStages;
+use sync::future::one_shot::OneShotBuilder;
+use sync::future::one_shot::OneShotCommandBuffer;
+use sync::future::one_shot::OneShotResources;
+use OomError;
+
+/// Command buffer that resets a range of queries of a query pool.
+///
+/// Created with `GpuFuture::then_reset_query_pool()`.
+pub type QueryPoolResetCommandBuffer = OneShotCommandBuffer<QueryPoolResetResources>;
+
+/// Resources of a `QueryPoolResetCommandBuffer`.
+pub struct QueryPoolResetResources {
+    pool: Arc<UnsafeQueryPool>,
+}
+
+impl OneShotCommandBuffer<QueryPoolResetResources> {
+    /// Builds a command buffer that resets the `count` queries of `pool` starting at
+    /// `first_query`, for the queue family of `queue`.
+    ///
+    /// The reset is preceded with an execution dependency on all the commands previously
+    /// submitted to the queue, so that the queries are no longer written to, and their results
+    /// no longer copied, when they are reset.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if `count` is 0.
+    /// - Panics if the range of queries is out of range of the pool.
+    ///
+    pub fn new(queue: &Queue, pool: Arc<UnsafeQueryPool>, first_query: u32, count: u32)
+               -> Result<QueryPoolResetCommandBuffer, OomError>
+    {
+        let reset = CmdResetQueryPool::new(pool.clone(), first_query, count);
+
+        let builder = unsafe {
+            let mut barrier = CmdPipelineBarrier::new();
+            // Query operations aren't memory accesses, so an execution dependency is enough.
+            barrier.add_execution_dependency(PipelineStages { all_commands: true,
+                                                              .. PipelineStages::none() },
+                                             PipelineStages { transfer: true,
+                                                              .. PipelineStages::none() },
+                                             false);
+            try!(OneShotBuilder::new(queue)).add(&barrier)
+        };
+
+        builder.add(&reset).build(QueryPoolResetResources { pool: pool })
+    }
+
+    /// Returns the query pool whose queries are reset.
+    #[inline]
+    pub fn pool(&self) -> &Arc<UnsafeQueryPool> {
+        &self.resources().pool
+    }
+}
+
+unsafe impl OneShotResources for QueryPoolResetResources {
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use command_buffer::CommandBuffer;
+    use query::QueryType;
+    use query::UnsafeQueryPool;
+    use sync::GpuFuture;
+
+    #[test]
+    fn reset_query_pool() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let pool = Arc::new(UnsafeQueryPool::new(device.clone(), QueryType::Timestamp, 4)
+                                .unwrap());
+
+        let cb = empty_command_buffer!(device, queue);
+        cb.execute(queue.clone())
+          .then_reset_query_pool(pool, 0, 4).unwrap()
+          .then_signal_fence_and_flush().unwrap();
+    }
+
+    #[test]
+    #[should_panic]
+    fn out_of_range() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let pool = Arc::new(UnsafeQueryPool::new(device.clone(), QueryType::Timestamp, 4)
+                                .unwrap());
+
+        let cb = empty_command_buffer!(device, queue);
+        let _ = cb.execute(queue.clone()).then_reset_query_pool(pool, 2, 4);
+    }
+}
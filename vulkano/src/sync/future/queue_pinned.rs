@@ -0,0 +1,193 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::error;
+use std::fmt;
+use std::sync::Arc;
+
+use buffer::BufferAccess;
+use command_buffer::submit::SubmitAnyBuilder;
+use device::Device;
+use device::DeviceOwned;
+use device::Queue;
+use image::ImageAccess;
+use swapchain::Swapchain;
+use sync::AccessFlagBits;
+use sync::FlushError;
+use sync::GpuFuture;
+use sync::PipelineStages;
+
+/// Builds a new queue-pinned future.
+#[inline]
+pub fn require_queue<F>(future: F, queue: &Arc<Queue>) -> Result<QueuePinnedFuture<F>, QueueMismatch>
+    where F: GpuFuture
+{
+    if let Some(current) = future.queue() {
+        if !current.is_same(queue) && !future.queue_change_allowed() {
+            return Err(QueueMismatch);
+        }
+    }
+
+    Ok(QueuePinnedFuture {
+        previous: future,
+        queue: queue.clone(),
+    })
+}
+
+/// Future whose subsequent submissions are guaranteed to happen on a specific queue.
+///
+/// Created with `GpuFuture::require_queue()`.
+#[must_use]
+pub struct QueuePinnedFuture<F> where F: GpuFuture {
+    previous: F,
+    queue: Arc<Queue>,
+}
+
+unsafe impl<F> GpuFuture for QueuePinnedFuture<F> where F: GpuFuture {
+    #[inline]
+    fn cleanup_finished(&mut self) {
+        self.previous.cleanup_finished();
+    }
+
+    #[inline]
+    unsafe fn build_submission(&self) -> Result<SubmitAnyBuilder, FlushError> {
+        self.previous.build_submission()
+    }
+
+    #[inline]
+    fn flush(&self) -> Result<(), FlushError> {
+        self.previous.flush()
+    }
+
+    #[inline]
+    unsafe fn signal_finished(&self) {
+        self.previous.signal_finished();
+    }
+
+    #[inline]
+    fn queue_change_allowed(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    fn queue(&self) -> Option<&Arc<Queue>> {
+        Some(&self.queue)
+    }
+
+    #[inline]
+    fn check_buffer_access(&self, buffer: &BufferAccess, exclusive: bool, queue: &Queue)
+                           -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        self.previous.check_buffer_access(buffer, exclusive, queue)
+    }
+
+    #[inline]
+    fn check_image_access(&self, image: &ImageAccess, exclusive: bool, queue: &Queue)
+                          -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        self.previous.check_image_access(image, exclusive, queue)
+    }
+
+    #[inline]
+    fn acquired_swapchain_image(&self, swapchain: &Swapchain) -> Option<usize> {
+        self.previous.acquired_swapchain_image(swapchain)
+    }
+
+    #[inline]
+    fn validate_resources(&self) {
+        self.previous.validate_resources();
+    }
+
+    #[inline]
+    fn wait_semaphore_count(&self) -> usize {
+        self.previous.wait_semaphore_count()
+    }
+
+    #[inline]
+    fn signals_fence(&self) -> bool {
+        self.previous.signals_fence()
+    }
+
+    #[inline]
+    fn is_flushed(&self) -> bool {
+        self.previous.is_flushed()
+    }
+
+    #[inline]
+    fn empty_submission_is_finished(&self) -> bool {
+        self.previous.empty_submission_is_finished()
+    }
+
+    #[inline]
+    fn shared_identities(&self, ids: &mut Vec<usize>) {
+        self.previous.shared_identities(ids);
+    }
+}
+
+unsafe impl<F> DeviceOwned for QueuePinnedFuture<F> where F: GpuFuture {
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        self.previous.device()
+    }
+}
+
+/// Error returned by `GpuFuture::require_queue()` when the future is bound to another queue and
+/// doesn't allow changing it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct QueueMismatch;
+
+impl error::Error for QueueMismatch {
+    #[inline]
+    fn description(&self) -> &str {
+        "the future is bound to a different queue than the one that is required"
+    }
+}
+
+impl fmt::Display for QueueMismatch {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use command_buffer::CommandBuffer;
+    use sync::DummyFuture;
+    use sync::FenceSignalFutureStatus;
+    use sync::GpuFuture;
+
+    #[test]
+    fn pins_unbound_future() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let future = DummyFuture::new(device.clone()).require_queue(&queue).unwrap();
+        assert!(!future.queue_change_allowed());
+        assert!(future.queue().unwrap().is_same(&queue));
+    }
+
+    #[test]
+    fn same_queue() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let cb = empty_command_buffer!(device, queue);
+
+        let future = cb.execute(queue.clone()).require_queue(&queue).unwrap();
+        future.then_signal_fence_and_flush().unwrap();
+    }
+
+    #[test]
+    fn empty_flush_skips_fence() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let future = DummyFuture::new(device.clone()).require_queue(&queue).unwrap()
+            .then_signal_fence_and_flush().unwrap();
+        assert_eq!(future.status(), FenceSignalFutureStatus::Cleaned);
+    }
+}
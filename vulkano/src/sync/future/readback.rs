@@ -0,0 +1,311 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::error;
+use std::error::Error;
+use std::fmt;
+use std::mem;
+use std::sync::Arc;
+use std::time::Duration;
+
+use buffer::BufferAccess;
+use buffer::BufferUsage;
+use buffer::CpuAccessibleBuffer;
+use buffer::TypedBufferAccess;
+use command_buffer::CommandBuffer;
+use command_buffer::CommandBufferExecFuture;
+use command_buffer::commands_raw::CmdCopyBuffer;
+use command_buffer::commands_raw::CmdCopyBufferError;
+use command_buffer::commands_raw::CmdPipelineBarrier;
+use device::Queue;
+use memory::Content;
+use sync::AccessFlagBits;
+use sync::FenceSignalFuture;
+use sync::FlushError;
+use sync::GpuFuture;
+use sync::PipelineStages;
+use sync::future::one_shot::OneShotBuilder;
+use sync::future::one_shot::OneShotCommandBuffer;
+use sync::future::one_shot::OneShotResources;
+use OomError;
+use VulkanObject;
+
+/// Command buffer that copies the content of a buffer to a new host-visible staging buffer.
+///
+/// Created with `GpuFuture::then_read_buffer()`.
+pub type ReadbackCommandBuffer<B, T> = OneShotCommandBuffer<ReadbackResources<B, T>>;
+
+/// Resources of a `ReadbackCommandBuffer`.
+pub struct ReadbackResources<B, T> where T: 'static {
+    // Owns the source and the staging buffer.
+    copy: CmdCopyBuffer<B, Arc<CpuAccessibleBuffer<[T]>>>,
+}
+
+impl<B, T> OneShotCommandBuffer<ReadbackResources<B, T>>
+    where B: TypedBufferAccess<Content = [T]>, T: Content + Copy + Send + Sync + 'static
+{
+    /// Builds a command buffer that copies `source` to a new staging buffer, for the queue
+    /// family of `queue`.
+    ///
+    /// If `future` writes `source`, a pipeline barrier is added before the copy so that the copy
+    /// sees these writes. The copy is followed with a barrier that makes the content of the
+    /// staging buffer visible to the host.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if the size of `source` isn't a multiple of the size of `T`.
+    ///
+    pub fn new(future: &GpuFuture, queue: &Queue, source: B)
+               -> Result<ReadbackCommandBuffer<B, T>, ReadbackError>
+    {
+        assert_eq!(source.size() % mem::size_of::<T>(), 0);
+        let len = source.size() / mem::size_of::<T>();
+
+        // The content of the staging buffer is only read after the copy has overwritten it.
+        let staging = try!(unsafe {
+            CpuAccessibleBuffer::uninitialized_array(queue.device(), len,
+                                                     &BufferUsage::transfer_dest(),
+                                                     Some(queue.family()))
+        });
+
+        let copy = match CmdCopyBuffer::new(source, staging) {
+            Ok(copy) => copy,
+            Err(CmdCopyBufferError::SourceMissingTransferUsage) => {
+                return Err(ReadbackError::SourceMissingTransferUsage);
+            },
+            // The staging buffer is a new buffer with the transfer destination usage.
+            Err(_) => unreachable!(),
+        };
+
+        let transfer = PipelineStages { transfer: true, .. PipelineStages::none() };
+        let transfer_write = AccessFlagBits { transfer_write: true, .. AccessFlagBits::none() };
+
+        let builder = try!(unsafe { OneShotBuilder::new(queue) });
+
+        let builder = match future.check_buffer_access(copy.source(), false, queue) {
+            Ok(Some((stages, access))) => unsafe {
+                let mut barrier = CmdPipelineBarrier::new();
+                barrier.add_buffer_memory_barrier(copy.source(), stages, access, transfer,
+                                                  AccessFlagBits { transfer_read: true,
+                                                                   .. AccessFlagBits::none() },
+                                                  false, None, 0, copy.source().size());
+                builder.add(&barrier)
+            },
+            _ => builder,
+        };
+
+        let builder = builder.add(&copy);
+
+        let builder = unsafe {
+            let mut barrier = CmdPipelineBarrier::new();
+            barrier.add_buffer_memory_barrier(copy.destination(), transfer, transfer_write,
+                                              PipelineStages { host: true,
+                                                               .. PipelineStages::none() },
+                                              AccessFlagBits { host_read: true,
+                                                               .. AccessFlagBits::none() },
+                                              false, None, 0, copy.destination().size());
+            builder.add(&barrier)
+        };
+
+        Ok(try!(builder.build(ReadbackResources { copy: copy })))
+    }
+
+    /// Returns the buffer that is read.
+    #[inline]
+    pub fn source(&self) -> &B {
+        self.resources().copy.source()
+    }
+
+    /// Returns the staging buffer that the content of the source is copied to.
+    #[inline]
+    pub fn staging(&self) -> &Arc<CpuAccessibleBuffer<[T]>> {
+        self.resources().copy.destination()
+    }
+}
+
+unsafe impl<B, T> OneShotResources for ReadbackResources<B, T>
+    where B: TypedBufferAccess<Content = [T]> + Send + Sync,
+          T: Content + Copy + Send + Sync + 'static
+{
+    fn submit_check(&self, future: &GpuFuture, queue: &Queue) -> Result<(), Box<Error>> {
+        assert!(self.copy.destination().try_gpu_lock(true, queue));
+
+        let source = self.copy.source();
+        if future.check_buffer_access(source, false, queue).is_ok() {
+            unsafe { source.increase_gpu_lock(); }
+        } else {
+            assert!(source.try_gpu_lock(false, queue), "The source of a readback is already \
+                                                        in use");
+        }
+
+        Ok(())
+    }
+
+    #[inline]
+    fn check_buffer_access(&self, buffer: &BufferAccess, _: bool, _: &Queue)
+                           -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        let transfer = PipelineStages { transfer: true, .. PipelineStages::none() };
+        let raw = buffer.inner().buffer.internal_object();
+
+        if raw == self.copy.source().inner().buffer.internal_object() {
+            Ok(Some((transfer, AccessFlagBits { transfer_read: true, .. AccessFlagBits::none() })))
+        } else if raw == self.copy.destination().inner().buffer.internal_object() {
+            Ok(Some((transfer, AccessFlagBits { transfer_write: true, .. AccessFlagBits::none() })))
+        } else {
+            Err(())
+        }
+    }
+}
+
+/// The content of a buffer being read back by the host.
+///
+/// Created with `GpuFuture::then_read_buffer()`.
+#[must_use = "Dropping this object will immediately block the thread until the GPU has finished processing the submission"]
+pub struct BufferReadback<F, B, T>
+    where F: GpuFuture,
+          B: TypedBufferAccess<Content = [T]> + Send + Sync + 'static,
+          T: Content + Copy + Send + Sync + 'static
+{
+    future: FenceSignalFuture<CommandBufferExecFuture<F, ReadbackCommandBuffer<B, T>>>,
+    staging: Arc<CpuAccessibleBuffer<[T]>>,
+}
+
+impl<F, B, T> BufferReadback<F, B, T>
+    where F: GpuFuture,
+          B: TypedBufferAccess<Content = [T]> + Send + Sync + 'static,
+          T: Content + Copy + Send + Sync + 'static
+{
+    /// Flushes the copy, so that the GPU starts executing it without waiting for `wait()` to be
+    /// called.
+    #[inline]
+    pub fn flush(&self) -> Result<(), FlushError> {
+        self.future.flush()
+    }
+
+    /// Flushes the copy if necessary, then blocks the current thread until the GPU has finished
+    /// executing it or until `timeout` has elapsed, and returns the content of the buffer.
+    ///
+    /// If the timeout is reached, `FlushError::Timeout` is returned and `wait()` can be called
+    /// again later. A timeout of 0 can be used to poll for the result.
+    pub fn wait(&self, timeout: Duration) -> Result<Vec<T>, FlushError> {
+        try!(self.future.wait_pumping(timeout, || ()));
+
+        // The GPU has finished, therefore the staging buffer isn't locked anymore.
+        let content = self.staging.read().unwrap();
+        Ok(content.to_vec())
+    }
+
+    /// Returns the future that signals a fence once the copy is finished.
+    #[inline]
+    pub fn future(&self)
+                  -> &FenceSignalFuture<CommandBufferExecFuture<F, ReadbackCommandBuffer<B, T>>>
+    {
+        &self.future
+    }
+}
+
+/// Builds a new buffer readback.
+pub fn read_buffer<F, B, T>(future: F, queue: Arc<Queue>, source: B)
+                            -> Result<BufferReadback<F, B, T>, ReadbackError>
+    where F: GpuFuture,
+          B: TypedBufferAccess<Content = [T]> + Send + Sync + 'static,
+          T: Content + Copy + Send + Sync + 'static
+{
+    let cb = try!(ReadbackCommandBuffer::new(&future, &queue, source));
+    let staging = cb.staging().clone();
+
+    Ok(BufferReadback {
+        future: cb.execute_after(future, queue).then_signal_fence(),
+        staging: staging,
+    })
+}
+
+/// Error that can happen when calling `GpuFuture::then_read_buffer()`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ReadbackError {
+    /// Not enough memory to create the staging buffer or the command buffer.
+    OomError(OomError),
+
+    /// The source buffer wasn't created with the `transfer_source` usage.
+    SourceMissingTransferUsage,
+}
+
+impl error::Error for ReadbackError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            ReadbackError::OomError(_) => "not enough memory",
+            ReadbackError::SourceMissingTransferUsage => {
+                "the source buffer is missing the transfer source usage"
+            },
+        }
+    }
+
+    #[inline]
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            ReadbackError::OomError(ref err) => Some(err),
+            _ => None
+        }
+    }
+}
+
+impl fmt::Display for ReadbackError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+impl From<OomError> for ReadbackError {
+    #[inline]
+    fn from(err: OomError) -> ReadbackError {
+        ReadbackError::OomError(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use buffer::BufferUsage;
+    use buffer::CpuAccessibleBuffer;
+    use command_buffer::CommandBuffer;
+    use sync::GpuFuture;
+    use sync::ReadbackError;
+
+    #[test]
+    fn read_buffer() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let source = CpuAccessibleBuffer::from_iter(&device, &BufferUsage::transfer_source(),
+                                                    Some(queue.family()), 0 .. 4u32).unwrap();
+
+        let cb = empty_command_buffer!(device, queue);
+
+        let readback = cb.execute(queue.clone()).then_read_buffer(queue.clone(), source).unwrap();
+        assert_eq!(readback.wait(Duration::from_secs(5)).unwrap(), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn missing_usage() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let source = CpuAccessibleBuffer::from_iter(&device, &BufferUsage::vertex_buffer(),
+                                                    Some(queue.family()), 0 .. 4u32).unwrap();
+
+        let cb = empty_command_buffer!(device, queue);
+
+        match cb.execute(queue.clone()).then_read_buffer(queue.clone(), source) {
+            Err(ReadbackError::SourceMissingTransferUsage) => (),
+            _ => panic!()
+        }
+    }
+}
@@ -0,0 +1,375 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use buffer::BufferAccess;
+use command_buffer::submit::SubmitAnyBuilder;
+use device::Device;
+use device::DeviceOwned;
+use device::Queue;
+use image::ImageAccess;
+use swapchain::Swapchain;
+use sync::AccessFlagBits;
+use sync::FlushError;
+use sync::GpuFuture;
+use sync::PipelineStages;
+use sync::pipeline::write_accesses;
+
+use vk;
+use VulkanObject;
+use instance::QueueFamily;
+
+/// Remembers the last known access to buffers and images, across futures and command buffers
+/// that are built separately.
+///
+/// Futures only know about the accesses of the submissions they contain. When the work of a frame
+/// is split between multiple chains of futures, the chains that come later can't tell how a
+/// resource was last accessed and have to assume the worst. A `ResourceStateCache` is shared
+/// between these chains: the futures wrapped with `GpuFuture::with_resource_states()` record the
+/// accesses that they report, and the code that records barriers can ask the cache whether a
+/// barrier is actually needed.
+///
+/// Resources are identified by their Vulkan handle. An access to a part of a buffer or an image
+/// is considered to be an access to the whole resource, and the layouts of images aren't
+/// tracked.
+pub struct ResourceStateCache {
+    buffers: Mutex<HashMap<vk::Buffer, (PipelineStages, AccessFlagBits)>>,
+    images: Mutex<HashMap<vk::Image, (PipelineStages, AccessFlagBits)>>,
+}
+
+impl ResourceStateCache {
+    /// Builds a new empty cache.
+    #[inline]
+    pub fn new() -> ResourceStateCache {
+        ResourceStateCache {
+            buffers: Mutex::new(HashMap::new()),
+            images: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the stages and accesses of the last recorded access to `buffer`, if any.
+    #[inline]
+    pub fn buffer_state(&self, buffer: &BufferAccess) -> Option<(PipelineStages, AccessFlagBits)> {
+        let key = buffer.inner().buffer.internal_object();
+        self.buffers.lock().unwrap().get(&key).cloned()
+    }
+
+    /// Returns the stages and accesses of the last recorded access to `image`, if any.
+    #[inline]
+    pub fn image_state(&self, image: &ImageAccess) -> Option<(PipelineStages, AccessFlagBits)> {
+        let key = image.inner().internal_object();
+        self.images.lock().unwrap().get(&key).cloned()
+    }
+
+    /// Records that `buffer` has been accessed by `stages` with `access`.
+    ///
+    /// If both the recorded access and the new one only read the buffer, they are merged, as the
+    /// last write is visible to both of them. Otherwise the new access replaces the recorded one.
+    #[inline]
+    pub fn record_buffer(&self, buffer: &BufferAccess, stages: PipelineStages,
+                         access: AccessFlagBits)
+    {
+        let key = buffer.inner().buffer.internal_object();
+        record(&mut self.buffers.lock().unwrap(), key, stages, access);
+    }
+
+    /// Records that `image` has been accessed by `stages` with `access`.
+    ///
+    /// See `record_buffer()`.
+    #[inline]
+    pub fn record_image(&self, image: &ImageAccess, stages: PipelineStages,
+                        access: AccessFlagBits)
+    {
+        let key = image.inner().internal_object();
+        record(&mut self.images.lock().unwrap(), key, stages, access);
+    }
+
+    /// Returns true if a barrier is needed before `stages` access `buffer` with `access`.
+    ///
+    /// A barrier can only be skipped if the access is a read, and if the recorded access is a
+    /// read by the same or more stages and access types. In that situation the last write has
+    /// already been made visible to the new access. If nothing is recorded for the buffer, this
+    /// returns true.
+    #[inline]
+    pub fn buffer_needs_barrier(&self, buffer: &BufferAccess, stages: PipelineStages,
+                                access: AccessFlagBits) -> bool
+    {
+        needs_barrier(self.buffer_state(buffer), stages, access)
+    }
+
+    /// Returns true if a barrier is needed before `stages` access `image` with `access`.
+    ///
+    /// Same as `buffer_needs_barrier()`. A barrier is still needed if the image has to be
+    /// transitioned to another layout, which this cache doesn't know about.
+    #[inline]
+    pub fn image_needs_barrier(&self, image: &ImageAccess, stages: PipelineStages,
+                               access: AccessFlagBits) -> bool
+    {
+        needs_barrier(self.image_state(image), stages, access)
+    }
+
+    /// Forgets everything about `buffer`, for example before it is destroyed.
+    #[inline]
+    pub fn forget_buffer(&self, buffer: &BufferAccess) {
+        let key = buffer.inner().buffer.internal_object();
+        self.buffers.lock().unwrap().remove(&key);
+    }
+
+    /// Forgets everything about `image`, for example before it is destroyed.
+    #[inline]
+    pub fn forget_image(&self, image: &ImageAccess) {
+        let key = image.inner().internal_object();
+        self.images.lock().unwrap().remove(&key);
+    }
+}
+
+// Records an access in `map`, merging it with the recorded one if both are reads.
+fn record<K>(map: &mut HashMap<K, (PipelineStages, AccessFlagBits)>, key: K,
+             stages: PipelineStages, access: AccessFlagBits)
+    where K: Hash + Eq
+{
+    let state = match map.get(&key) {
+        Some(&(prev_stages, prev_access)) if is_read_only(prev_access) &&
+                                              is_read_only(access) => {
+            (prev_stages | stages, prev_access | access)
+        },
+        _ => (stages, access),
+    };
+
+    map.insert(key, state);
+}
+
+fn needs_barrier(state: Option<(PipelineStages, AccessFlagBits)>, stages: PipelineStages,
+                 access: AccessFlagBits) -> bool
+{
+    let (prev_stages, prev_access) = match state {
+        Some(s) => s,
+        None => return true,
+    };
+
+    if !is_read_only(prev_access) || !is_read_only(access) {
+        return true;
+    }
+
+    let prev_stages: vk::PipelineStageFlagBits = prev_stages.into();
+    let stages: vk::PipelineStageFlagBits = stages.into();
+    let prev_access: vk::AccessFlagBits = prev_access.into();
+    let access: vk::AccessFlagBits = access.into();
+
+    (stages & !prev_stages) != 0 || (access & !prev_access) != 0
+}
+
+#[inline]
+fn is_read_only(access: AccessFlagBits) -> bool {
+    let writes: vk::AccessFlagBits = (access & write_accesses()).into();
+    writes == 0
+}
+
+/// Builds a new resource state future.
+#[inline]
+pub fn with_resource_states<F>(future: F, cache: Arc<ResourceStateCache>)
+                               -> ResourceStateFuture<F>
+    where F: GpuFuture
+{
+    ResourceStateFuture {
+        previous: future,
+        cache: cache,
+    }
+}
+
+/// Future that records the accesses reported by a previous future in a `ResourceStateCache`.
+///
+/// Created with `GpuFuture::with_resource_states()`.
+#[must_use]
+pub struct ResourceStateFuture<F> where F: GpuFuture {
+    previous: F,
+    cache: Arc<ResourceStateCache>,
+}
+
+impl<F> ResourceStateFuture<F> where F: GpuFuture {
+    /// Returns the cache that the accesses are recorded in.
+    #[inline]
+    pub fn cache(&self) -> &Arc<ResourceStateCache> {
+        &self.cache
+    }
+}
+
+unsafe impl<F> GpuFuture for ResourceStateFuture<F> where F: GpuFuture {
+    #[inline]
+    fn cleanup_finished(&mut self) {
+        self.previous.cleanup_finished();
+    }
+
+    #[inline]
+    unsafe fn build_submission(&self) -> Result<SubmitAnyBuilder, FlushError> {
+        self.previous.build_submission()
+    }
+
+    #[inline]
+    fn flush(&self) -> Result<(), FlushError> {
+        self.previous.flush()
+    }
+
+    #[inline]
+    unsafe fn signal_finished(&self) {
+        self.previous.signal_finished();
+    }
+
+    #[inline]
+    fn queue_change_allowed(&self) -> bool {
+        self.previous.queue_change_allowed()
+    }
+
+    #[inline]
+    fn queue(&self) -> Option<&Arc<Queue>> {
+        self.previous.queue()
+    }
+
+    #[inline]
+    fn queue_family(&self) -> Option<QueueFamily> {
+        self.previous.queue_family()
+    }
+
+    // If the previous future grants the access without knowing how the resource was last
+    // accessed, the cache is consulted instead. This doesn't change whether the access is
+    // granted, which is what matters for safety.
+    #[inline]
+    fn check_buffer_access(&self, buffer: &BufferAccess, exclusive: bool, queue: &Queue)
+                           -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        match self.previous.check_buffer_access(buffer, exclusive, queue) {
+            Ok(Some((stages, access))) => {
+                self.cache.record_buffer(buffer, stages, access);
+                Ok(Some((stages, access)))
+            },
+            Ok(None) => Ok(self.cache.buffer_state(buffer)),
+            Err(()) => Err(()),
+        }
+    }
+
+    #[inline]
+    fn check_image_access(&self, image: &ImageAccess, exclusive: bool, queue: &Queue)
+                          -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        match self.previous.check_image_access(image, exclusive, queue) {
+            Ok(Some((stages, access))) => {
+                self.cache.record_image(image, stages, access);
+                Ok(Some((stages, access)))
+            },
+            Ok(None) => Ok(self.cache.image_state(image)),
+            Err(()) => Err(()),
+        }
+    }
+
+    #[inline]
+    fn acquired_swapchain_image(&self, swapchain: &Swapchain) -> Option<usize> {
+        self.previous.acquired_swapchain_image(swapchain)
+    }
+
+    #[inline]
+    fn validate_resources(&self) {
+        self.previous.validate_resources();
+    }
+
+    #[inline]
+    fn wait_semaphore_count(&self) -> usize {
+        self.previous.wait_semaphore_count()
+    }
+
+    #[inline]
+    fn signals_fence(&self) -> bool {
+        self.previous.signals_fence()
+    }
+
+    #[inline]
+    fn is_flushed(&self) -> bool {
+        self.previous.is_flushed()
+    }
+
+    #[inline]
+    fn shared_identities(&self, ids: &mut Vec<usize>) {
+        self.previous.shared_identities(ids);
+    }
+}
+
+unsafe impl<F> DeviceOwned for ResourceStateFuture<F> where F: GpuFuture {
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        self.previous.device()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use buffer::Buffer;
+    use buffer::BufferUsage;
+    use buffer::DeviceLocalBuffer;
+    use command_buffer::CommandBuffer;
+    use sync::AccessFlagBits;
+    use sync::GpuFuture;
+    use sync::PipelineStages;
+    use sync::ResourceStateCache;
+
+    #[test]
+    fn elide_read_after_read() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let buffer = DeviceLocalBuffer::<[u32]>::array(&device, 4, &BufferUsage::all(),
+                                                       Some(queue.family())).unwrap();
+        let buffer = buffer.access();
+
+        let cache = ResourceStateCache::new();
+        let vertex = PipelineStages { vertex_shader: true, .. PipelineStages::none() };
+        let fragment = PipelineStages { fragment_shader: true, .. PipelineStages::none() };
+        let read = AccessFlagBits { shader_read: true, .. AccessFlagBits::none() };
+        let write = AccessFlagBits { shader_write: true, .. AccessFlagBits::none() };
+
+        assert!(cache.buffer_needs_barrier(&buffer, vertex, read));
+
+        cache.record_buffer(&buffer, vertex, read);
+        assert!(!cache.buffer_needs_barrier(&buffer, vertex, read));
+        assert!(cache.buffer_needs_barrier(&buffer, fragment, read));
+        assert!(cache.buffer_needs_barrier(&buffer, vertex, write));
+
+        cache.record_buffer(&buffer, fragment, read);
+        assert!(!cache.buffer_needs_barrier(&buffer, vertex | fragment, read));
+
+        cache.record_buffer(&buffer, vertex, write);
+        assert!(cache.buffer_needs_barrier(&buffer, vertex, read));
+    }
+
+    #[test]
+    fn records_reported_accesses() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let buffer = DeviceLocalBuffer::<[u32]>::array(&device, 4, &BufferUsage::all(),
+                                                       Some(queue.family())).unwrap();
+
+        let cb = empty_command_buffer!(device, queue);
+
+        let stages = PipelineStages { compute_shader: true, .. PipelineStages::none() };
+        let access = AccessFlagBits { shader_write: true, .. AccessFlagBits::none() };
+        let cache = Arc::new(ResourceStateCache::new());
+        let future = cb.execute(queue.clone())
+                       .with_addressed_buffer(buffer.clone().access(), true, stages, access)
+                       .with_resource_states(cache.clone());
+
+        assert!(cache.buffer_state(&buffer.clone().access()).is_none());
+        let _ = future.check_buffer_access(&buffer.clone().access(), true, &queue);
+        let (stages, access) = cache.buffer_state(&buffer.access()).unwrap();
+        assert!(stages.compute_shader);
+        assert!(access.shader_write);
+    }
+}
@@ -7,7 +7,6 @@
 // notice may not be copied, modified, or distributed except
 // according to those terms.
 
-use std::error::Error;
 use std::sync::Arc;
 use std::sync::Mutex;
 use std::sync::atomic::AtomicBool;
@@ -21,10 +20,17 @@ use device::Device;
 use device::DeviceOwned;
 use device::Queue;
 use image::ImageAccess;
+use swapchain::Swapchain;
 use sync::AccessFlagBits;
+use sync::FlushError;
 use sync::GpuFuture;
 use sync::PipelineStages;
 use sync::Semaphore;
+use sync::SemaphorePool;
+use OomError;
+use VulkanObject;
+use instance::QueueFamily;
+use vk;
 
 /// Builds a new semaphore signal future.
 #[inline]
@@ -35,17 +41,55 @@ pub fn then_signal_semaphore<F>(future: F) -> SemaphoreSignalFuture<F> where F:
 
     SemaphoreSignalFuture {
         previous: future,
-        semaphore: Semaphore::new(device).unwrap(),
+        semaphore: Some(Semaphore::new(device).unwrap()),
+        semaphore_pool: None,
+        wait_stages: default_wait_stages(),
         wait_submitted: Mutex::new(false),
         finished: AtomicBool::new(false),
     }
 }
 
+/// Builds a new semaphore signal future that takes its semaphore from a pool.
+///
+/// # Panic
+///
+/// - Panics if the pool and the future don't belong to the same device.
+///
+pub fn then_signal_semaphore_from_pool<F>(future: F, pool: &SemaphorePool)
+                                          -> Result<SemaphoreSignalFuture<F>, OomError>
+    where F: GpuFuture
+{
+    assert_eq!(pool.device().internal_object(), future.device().internal_object());
+    assert!(future.queue().is_some());
+
+    Ok(SemaphoreSignalFuture {
+        previous: future,
+        semaphore: Some(try!(pool.acquire())),
+        semaphore_pool: Some(pool.clone()),
+        wait_stages: default_wait_stages(),
+        wait_submitted: Mutex::new(false),
+        finished: AtomicBool::new(false),
+    })
+}
+
+// Stages that wait upon the semaphore if `SemaphoreSignalFuture::wait_stages()` isn't called.
+// We don't know what the next submission does with the results, so everything waits.
+#[inline]
+fn default_wait_stages() -> PipelineStages {
+    PipelineStages { all_commands: true, .. PipelineStages::none() }
+}
+
 /// Represents a semaphore being signaled after a previous event.
 #[must_use = "Dropping this object will immediately block the thread until the GPU has finished processing the submission"]
 pub struct SemaphoreSignalFuture<F> where F: GpuFuture {
     previous: F,
-    semaphore: Semaphore,
+    // Always `Some`, except in the destructor.
+    semaphore: Option<Semaphore>,
+    // If `Some`, the semaphore is given back to this pool when the future is destroyed, provided
+    // that the future that waits on it has finished.
+    semaphore_pool: Option<SemaphorePool>,
+    // Stages of the submission that waits upon the semaphore which actually wait.
+    wait_stages: PipelineStages,
     // True if the signaling command has already been submitted.
     // If flush is called multiple times, we want to block so that only one flushing is executed.
     // Therefore we use a `Mutex<bool>` and not an `AtomicBool`.
@@ -53,6 +97,40 @@ pub struct SemaphoreSignalFuture<F> where F: GpuFuture {
     finished: AtomicBool,
 }
 
+impl<F> SemaphoreSignalFuture<F> where F: GpuFuture {
+    /// Sets the stages of the command buffers submitted after this future that wait upon the
+    /// semaphore. The other stages can start executing before the semaphore is signaled.
+    ///
+    /// The default is `all_commands`. For example, if the result of the previous future is only
+    /// read by fragment shaders, passing `fragment_shader` lets the vertex processing of the
+    /// next submission overlap with the previous future.
+    ///
+    /// The stages are ignored if the semaphore is waited upon by a swapchain present.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if `stages` is empty or includes `host`.
+    ///
+    /// # Safety
+    ///
+    /// - The stages must be supported by the device.
+    /// - The command buffers submitted after this future must not access, outside of `stages`,
+    ///   any resource written by the previous future.
+    ///
+    #[inline]
+    pub unsafe fn wait_stages(mut self, stages: PipelineStages) -> SemaphoreSignalFuture<F> {
+        assert!(!stages.host, "The host stage can't wait upon a semaphore");
+        assert!(Into::<vk::PipelineStageFlags>::into(stages) != 0);
+        self.wait_stages = stages;
+        self
+    }
+
+    #[inline]
+    fn semaphore(&self) -> &Semaphore {
+        self.semaphore.as_ref().unwrap()
+    }
+}
+
 unsafe impl<F> GpuFuture for SemaphoreSignalFuture<F> where F: GpuFuture {
     #[inline]
     fn cleanup_finished(&mut self) {
@@ -60,16 +138,16 @@ unsafe impl<F> GpuFuture for SemaphoreSignalFuture<F> where F: GpuFuture {
     }
 
     #[inline]
-    unsafe fn build_submission(&self) -> Result<SubmitAnyBuilder, Box<Error>> {
+    unsafe fn build_submission(&self) -> Result<SubmitAnyBuilder, FlushError> {
         // Flushing the signaling part, since it must always be submitted before the waiting part.
         try!(self.flush());
 
         let mut sem = SubmitSemaphoresWaitBuilder::new();
-        sem.add_wait_semaphore(&self.semaphore);
+        sem.add_wait_semaphore_with_stages(self.semaphore(), self.wait_stages);
         Ok(SubmitAnyBuilder::SemaphoresWait(sem))
     }
 
-    fn flush(&self) -> Result<(), Box<Error>> {
+    fn flush(&self) -> Result<(), FlushError> {
         unsafe {
             let mut wait_submitted = self.wait_submitted.lock().unwrap();
 
@@ -82,23 +160,23 @@ unsafe impl<F> GpuFuture for SemaphoreSignalFuture<F> where F: GpuFuture {
             match try!(self.previous.build_submission()) {
                 SubmitAnyBuilder::Empty => {
                     let mut builder = SubmitCommandBufferBuilder::new();
-                    builder.add_signal_semaphore(&self.semaphore);
+                    builder.add_signal_semaphore(self.semaphore());
                     try!(builder.submit(&queue));
                 },
                 SubmitAnyBuilder::SemaphoresWait(sem) => {
                     let mut builder: SubmitCommandBufferBuilder = sem.into();
-                    builder.add_signal_semaphore(&self.semaphore);
+                    builder.add_signal_semaphore(self.semaphore());
                     try!(builder.submit(&queue));
                 },
                 SubmitAnyBuilder::CommandBuffer(mut builder) => {
-                    debug_assert_eq!(builder.num_signal_semaphores(), 0);
-                    builder.add_signal_semaphore(&self.semaphore);
+                    sync_assert_eq!(builder.num_signal_semaphores(), 0);
+                    builder.add_signal_semaphore(self.semaphore());
                     try!(builder.submit(&queue));
                 },
                 SubmitAnyBuilder::QueuePresent(present) => {
                     try!(present.submit(&queue));
                     let mut builder = SubmitCommandBufferBuilder::new();
-                    builder.add_signal_semaphore(&self.semaphore);
+                    builder.add_signal_semaphore(self.semaphore());
                     try!(builder.submit(&queue));       // FIXME: problematic because if we return an error and flush() is called again, then we'll submit the present twice
                 },
             };
@@ -111,7 +189,7 @@ unsafe impl<F> GpuFuture for SemaphoreSignalFuture<F> where F: GpuFuture {
 
     #[inline]
     unsafe fn signal_finished(&self) {
-        debug_assert!(*self.wait_submitted.lock().unwrap());
+        sync_assert!(*self.wait_submitted.lock().unwrap());
         self.finished.store(true, Ordering::SeqCst);
         self.previous.signal_finished();
     }
@@ -126,6 +204,11 @@ unsafe impl<F> GpuFuture for SemaphoreSignalFuture<F> where F: GpuFuture {
         self.previous.queue()
     }
 
+    #[inline]
+    fn queue_family(&self) -> Option<QueueFamily> {
+        self.previous.queue_family()
+    }
+
     #[inline]
     fn check_buffer_access(&self, buffer: &BufferAccess, exclusive: bool, queue: &Queue)
                            -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
@@ -139,12 +222,38 @@ unsafe impl<F> GpuFuture for SemaphoreSignalFuture<F> where F: GpuFuture {
     {
         self.previous.check_image_access(image, exclusive, queue).map(|_| None)
     }
+
+    #[inline]
+    fn acquired_swapchain_image(&self, swapchain: &Swapchain) -> Option<usize> {
+        self.previous.acquired_swapchain_image(swapchain)
+    }
+
+    #[inline]
+    fn validate_resources(&self) {
+        self.previous.validate_resources();
+    }
+
+    #[inline]
+    fn wait_semaphore_count(&self) -> usize {
+        // The previous future is submitted along with the signal operation.
+        1
+    }
+
+    #[inline]
+    fn is_flushed(&self) -> bool {
+        *self.wait_submitted.lock().unwrap()
+    }
+
+    #[inline]
+    fn shared_identities(&self, ids: &mut Vec<usize>) {
+        self.previous.shared_identities(ids);
+    }
 }
 
 unsafe impl<F> DeviceOwned for SemaphoreSignalFuture<F> where F: GpuFuture {
     #[inline]
     fn device(&self) -> &Arc<Device> {
-        self.semaphore.device()
+        self.semaphore().device()
     }
 }
 
@@ -157,7 +266,88 @@ impl<F> Drop for SemaphoreSignalFuture<F> where F: GpuFuture {
                 // Block until the queue finished.
                 self.queue().unwrap().wait().unwrap();
                 self.previous.signal_finished();
+                // The semaphore is signaled but nothing waited on it, so it can't be reused.
+                return;
+            }
+
+            // `finished` is only set once the future that waits on the semaphore has finished
+            // executing, so the semaphore is unsignaled and unused.
+            if let Some(pool) = self.semaphore_pool.take() {
+                pool.release(self.semaphore.take().unwrap());
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use command_buffer::CommandBuffer;
+    use command_buffer::submit::SubmitAnyBuilder;
+    use command_buffer::submit::SubmitCommandBufferBuilder;
+    use sync::GpuFuture;
+    use sync::PipelineStages;
+    use sync::SemaphorePool;
+
+    #[test]
+    fn handoff() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let cb1 = empty_command_buffer!(device, queue);
+        let cb2 = empty_command_buffer!(device, queue);
+
+        let future = cb1.execute(queue.clone()).handoff_to(queue.clone(), cb2).unwrap();
+        assert!(future.queue().unwrap().is_same(&queue));
+        future.then_signal_fence_and_flush().unwrap();
+    }
+
+    #[test]
+    fn semaphore_pool() {
+        let (device, queue) = gfx_dev_and_queue!();
+        let pool = SemaphorePool::new(device.clone());
+
+        for _ in 0 .. 3 {
+            let cb1 = empty_command_buffer!(device, queue);
+            let cb2 = empty_command_buffer!(device, queue);
+
+            let future = cb1.execute(queue.clone())
+                .then_signal_semaphore_from_pool(&pool).unwrap()
+                .then_execute(queue.clone(), cb2)
+                .then_signal_fence_and_flush().unwrap();
+            assert_eq!(pool.len(), 0);
+            future.wait(Duration::from_secs(5)).unwrap();
+            drop(future);
+            assert_eq!(pool.len(), 1);
+        }
+    }
+
+    #[test]
+    fn unwaited_semaphore_not_recycled() {
+        let (device, queue) = gfx_dev_and_queue!();
+        let pool = SemaphorePool::new(device.clone());
+
+        let cb = empty_command_buffer!(device, queue);
+        drop(cb.execute(queue.clone()).then_signal_semaphore_from_pool(&pool).unwrap());
+        assert_eq!(pool.len(), 0);
+    }
+
+    #[test]
+    fn wait_stages() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let cb = empty_command_buffer!(device, queue);
+        let stages = PipelineStages { fragment_shader: true, .. PipelineStages::none() };
+        let future = unsafe {
+            cb.execute(queue.clone()).then_signal_semaphore().wait_stages(stages)
+        };
+
+        let builder: SubmitCommandBufferBuilder = match unsafe { future.build_submission() } {
+            Ok(SubmitAnyBuilder::SemaphoresWait(sem)) => sem.into(),
+            _ => panic!()
+        };
+        let waits = builder.wait_dest_stages();
+        assert_eq!(waits.len(), 1);
+        assert!(waits[0].1.fragment_shader);
+        assert!(!waits[0].1.all_commands);
+    }
+}
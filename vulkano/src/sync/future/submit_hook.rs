@@ -0,0 +1,232 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+
+use buffer::BufferAccess;
+use command_buffer::submit::SubmitAnyBuilder;
+use command_buffer::submit::SubmitCommandBufferBuilder;
+use device::Device;
+use device::DeviceOwned;
+use device::Queue;
+use image::ImageAccess;
+use instance::QueueFamily;
+use swapchain::Swapchain;
+use sync::AccessFlagBits;
+use sync::FlushError;
+use sync::GpuFuture;
+use sync::PipelineStages;
+
+/// Builds a new submit hook future.
+#[inline]
+pub fn with_submit_hook<F, H>(future: F, hook: H) -> SubmitHookFuture<F, H>
+    where F: GpuFuture, H: FnMut(&mut SubmitAnyBuilder)
+{
+    SubmitHookFuture {
+        previous: future,
+        hook: Mutex::new(hook),
+        flushed: Mutex::new(false),
+        finished: AtomicBool::new(false),
+    }
+}
+
+/// Calls a function on the submission built by a previous future, right before it is submitted
+/// or handed over to the next future.
+///
+/// Created with `GpuFuture::with_submit_hook()`.
+#[must_use = "Dropping this object will immediately block the thread until the GPU has finished processing the submission"]
+pub struct SubmitHookFuture<F, H> where F: GpuFuture {
+    previous: F,
+    hook: Mutex<H>,
+    // True if the submission has been submitted by `flush()`.
+    flushed: Mutex<bool>,
+    // True if `signal_finished()` has been called.
+    finished: AtomicBool,
+}
+
+impl<F, H> SubmitHookFuture<F, H> where F: GpuFuture, H: FnMut(&mut SubmitAnyBuilder) {
+    // Builds the submission of the previous future, and passes it to the hook.
+    unsafe fn build_hooked(&self) -> Result<SubmitAnyBuilder, FlushError> {
+        let mut submission = try!(self.previous.build_submission());
+        (&mut *self.hook.lock().unwrap())(&mut submission);
+        Ok(submission)
+    }
+}
+
+unsafe impl<F, H> GpuFuture for SubmitHookFuture<F, H>
+    where F: GpuFuture, H: FnMut(&mut SubmitAnyBuilder)
+{
+    #[inline]
+    fn cleanup_finished(&mut self) {
+        self.previous.cleanup_finished();
+    }
+
+    #[inline]
+    unsafe fn build_submission(&self) -> Result<SubmitAnyBuilder, FlushError> {
+        if *self.flushed.lock().unwrap() {
+            return Ok(SubmitAnyBuilder::Empty);
+        }
+
+        self.build_hooked()
+    }
+
+    fn flush(&self) -> Result<(), FlushError> {
+        unsafe {
+            let mut flushed = self.flushed.lock().unwrap();
+            if *flushed {
+                return Ok(());
+            }
+
+            // Submitting anything else than `Empty` requires a queue.
+            match try!(self.build_hooked()) {
+                SubmitAnyBuilder::Empty => (),
+                SubmitAnyBuilder::SemaphoresWait(sem) => {
+                    let builder: SubmitCommandBufferBuilder = sem.into();
+                    try!(builder.submit(self.previous.queue().unwrap()));
+                },
+                SubmitAnyBuilder::CommandBuffer(builder) => {
+                    try!(builder.submit(self.previous.queue().unwrap()));
+                },
+                SubmitAnyBuilder::QueuePresent(present) => {
+                    try!(present.submit(self.previous.queue().unwrap()));
+                },
+            };
+
+            // Only write `true` here in order to try again next time if we failed to submit.
+            *flushed = true;
+            Ok(())
+        }
+    }
+
+    #[inline]
+    unsafe fn signal_finished(&self) {
+        self.finished.store(true, Ordering::SeqCst);
+        self.previous.signal_finished();
+    }
+
+    #[inline]
+    fn queue_change_allowed(&self) -> bool {
+        self.previous.queue_change_allowed()
+    }
+
+    #[inline]
+    fn queue(&self) -> Option<&Arc<Queue>> {
+        self.previous.queue()
+    }
+
+    #[inline]
+    fn queue_family(&self) -> Option<QueueFamily> {
+        self.previous.queue_family()
+    }
+
+    #[inline]
+    fn check_buffer_access(&self, buffer: &BufferAccess, exclusive: bool, queue: &Queue)
+                           -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        self.previous.check_buffer_access(buffer, exclusive, queue)
+    }
+
+    #[inline]
+    fn check_image_access(&self, image: &ImageAccess, exclusive: bool, queue: &Queue)
+                          -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        self.previous.check_image_access(image, exclusive, queue)
+    }
+
+    #[inline]
+    fn acquired_swapchain_image(&self, swapchain: &Swapchain) -> Option<usize> {
+        self.previous.acquired_swapchain_image(swapchain)
+    }
+
+    #[inline]
+    fn validate_resources(&self) {
+        self.previous.validate_resources();
+    }
+
+    #[inline]
+    fn wait_semaphore_count(&self) -> usize {
+        self.previous.wait_semaphore_count()
+    }
+
+    #[inline]
+    fn signals_fence(&self) -> bool {
+        self.previous.signals_fence()
+    }
+
+    #[inline]
+    fn is_flushed(&self) -> bool {
+        *self.flushed.lock().unwrap()
+    }
+
+    #[inline]
+    fn shared_identities(&self, ids: &mut Vec<usize>) {
+        self.previous.shared_identities(ids);
+    }
+}
+
+unsafe impl<F, H> DeviceOwned for SubmitHookFuture<F, H> where F: GpuFuture {
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        self.previous.device()
+    }
+}
+
+impl<F, H> Drop for SubmitHookFuture<F, H> where F: GpuFuture {
+    fn drop(&mut self) {
+        unsafe {
+            if *self.finished.get_mut() || !*self.flushed.get_mut().unwrap() {
+                // If we didn't submit anything, the previous future handles its own destruction.
+                return;
+            }
+
+            // The previous future doesn't know that its submission has been submitted, so we
+            // have to block until the queue finished before destroying it.
+            if let Some(queue) = self.previous.queue() {
+                // TODO: handle errors?
+                queue.wait().unwrap();
+            }
+            self.previous.signal_finished();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::atomic::Ordering;
+
+    use command_buffer::CommandBuffer;
+    use command_buffer::submit::SubmitAnyBuilder;
+    use sync::GpuFuture;
+
+    #[test]
+    fn hook_called_before_submit() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let cb = empty_command_buffer!(device, queue);
+
+        let count = Arc::new(AtomicUsize::new(0));
+        let future = {
+            let count = count.clone();
+            cb.execute(queue.clone()).with_submit_hook(move |submission| {
+                if let SubmitAnyBuilder::CommandBuffer(_) = *submission {
+                    count.fetch_add(1, Ordering::SeqCst);
+                }
+            })
+        };
+
+        future.flush().unwrap();
+        future.flush().unwrap();
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+    }
+}
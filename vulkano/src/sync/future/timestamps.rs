@@ -0,0 +1,341 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::mem;
+use std::os::raw::c_void;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use buffer::BufferAccess;
+use command_buffer::CommandBuffer;
+use command_buffer::commands_raw::CmdPipelineBarrier;
+use command_buffer::commands_raw::CmdResetQueryPool;
+use command_buffer::commands_raw::CmdWriteTimestamp;
+use command_buffer::submit::SubmitAnyBuilder;
+use command_buffer::submit::SubmitCommandBufferBuilder;
+use device::Device;
+use device::DeviceOwned;
+use device::Queue;
+use image::ImageAccess;
+use query::UnsafeQueryPool;
+use swapchain::Swapchain;
+use sync::AccessFlagBits;
+use sync::FlushError;
+use sync::GpuFuture;
+use sync::PipelineStages;
+use sync::future::one_shot::OneShotBuilder;
+use sync::future::one_shot::OneShotCommandBuffer;
+
+use check_errors;
+use vk;
+use OomError;
+use Success;
+use VulkanObject;
+use VulkanPointers;
+
+// Builds the future executed by `then_signal_fence_with_timestamps()`, and the queries that the
+// resulting `FenceSignalFuture` reads in `elapsed()`.
+//
+// See `then_signal_fence_with_timestamps()` for the panics.
+pub fn with_timestamps<F>(future: F, pool: Arc<UnsafeQueryPool>, start: u32, end: u32)
+                          -> Result<(TimestampFuture<F>, TimestampQueries), OomError>
+    where F: GpuFuture
+{
+    let queue = future.queue().expect("The future isn't bound to a queue").clone();
+    let valid_bits = queue.family().timestamp_valid_bits();
+    assert!(valid_bits != 0, "The queue family doesn't support timestamps");
+    assert!(start < pool.num_slots() && end < pool.num_slots());
+    assert!(start != end);
+    assert_eq!(pool.device().internal_object(), future.device().internal_object());
+
+    let start_cb = unsafe {
+        // The queries may have been used by a previous submission of the queue. As in
+        // `QueryPoolResetCommandBuffer`, query operations only need an execution dependency.
+        let mut barrier = CmdPipelineBarrier::new();
+        barrier.add_execution_dependency(PipelineStages { all_commands: true,
+                                                          .. PipelineStages::none() },
+                                         PipelineStages { transfer: true,
+                                                          .. PipelineStages::none() },
+                                         false);
+        let write = CmdWriteTimestamp::new(pool.clone(), start,
+                                           PipelineStages { top_of_pipe: true,
+                                                            .. PipelineStages::none() })
+            .expect("The query pool doesn't contain timestamp queries");
+        try!(try!(OneShotBuilder::new(&queue))
+                 .add(&barrier)
+                 .add(&CmdResetQueryPool::new(pool.clone(), start, 1))
+                 .add(&CmdResetQueryPool::new(pool.clone(), end, 1))
+                 .add(&write)
+                 .build(()))
+    };
+
+    let end_cb = unsafe {
+        let write = CmdWriteTimestamp::new(pool.clone(), end,
+                                           PipelineStages { bottom_of_pipe: true,
+                                                            .. PipelineStages::none() })
+            .expect("The query pool doesn't contain timestamp queries");
+        try!(try!(OneShotBuilder::new(&queue)).add(&write).build(()))
+    };
+
+    let period = queue.device().physical_device().limits().timestamp_period();
+    let queries = TimestampQueries {
+        pool: pool,
+        start: start,
+        end: end,
+        valid_bits: valid_bits,
+        period: period,
+    };
+
+    let timestamped = TimestampFuture {
+        previous: future,
+        queue: queue,
+        start_cb: start_cb,
+        end_cb: end_cb,
+        submitted: Mutex::new(false),
+        finished: AtomicBool::new(false),
+    };
+
+    Ok((timestamped, queries))
+}
+
+/// Represents the execution of a previous future between two timestamps.
+///
+/// The start timestamp is written before the command buffers of the previous future, and the
+/// end timestamp after them. Use `FenceSignalFuture::elapsed()` to obtain the duration between
+/// the two.
+///
+/// Created with `GpuFuture::then_signal_fence_with_timestamps()`.
+#[must_use = "Dropping this object will immediately block the thread until the GPU has finished processing the submission"]
+pub struct TimestampFuture<F> where F: GpuFuture {
+    previous: F,
+    queue: Arc<Queue>,
+    // Resets the two queries and writes the start timestamp.
+    start_cb: OneShotCommandBuffer<()>,
+    // Writes the end timestamp.
+    end_cb: OneShotCommandBuffer<()>,
+    submitted: Mutex<bool>,
+    finished: AtomicBool,
+}
+
+unsafe impl<F> GpuFuture for TimestampFuture<F> where F: GpuFuture {
+    #[inline]
+    fn cleanup_finished(&mut self) {
+        self.previous.cleanup_finished();
+    }
+
+    unsafe fn build_submission(&self) -> Result<SubmitAnyBuilder, FlushError> {
+        let mut builder = SubmitCommandBufferBuilder::new();
+        builder.add_command_buffer(self.start_cb.inner());
+
+        let mut builder = match try!(self.previous.build_submission()) {
+            SubmitAnyBuilder::Empty => builder,
+            SubmitAnyBuilder::SemaphoresWait(sem) => {
+                let sem: SubmitCommandBufferBuilder = sem.into();
+                sem.merge(builder)
+            },
+            SubmitAnyBuilder::CommandBuffer(previous) => builder.merge(previous),
+            SubmitAnyBuilder::QueuePresent(present) => {
+                try!(present.submit(&self.queue));
+                builder
+            },
+        };
+
+        builder.add_command_buffer(self.end_cb.inner());
+        Ok(SubmitAnyBuilder::CommandBuffer(builder))
+    }
+
+    #[inline]
+    fn flush(&self) -> Result<(), FlushError> {
+        unsafe {
+            let mut submitted = self.submitted.lock().unwrap();
+            if *submitted {
+                return Ok(());
+            }
+
+            match try!(self.build_submission()) {
+                SubmitAnyBuilder::CommandBuffer(builder) => {
+                    try!(builder.submit(&self.queue));
+                },
+                _ => unreachable!(),
+            };
+
+            // Only write `true` here in order to try again next time if we failed to submit.
+            *submitted = true;
+            Ok(())
+        }
+    }
+
+    #[inline]
+    unsafe fn signal_finished(&self) {
+        self.finished.store(true, Ordering::SeqCst);
+        self.previous.signal_finished();
+    }
+
+    #[inline]
+    fn queue_change_allowed(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    fn queue(&self) -> Option<&Arc<Queue>> {
+        Some(&self.queue)
+    }
+
+    #[inline]
+    fn check_buffer_access(&self, buffer: &BufferAccess, exclusive: bool, queue: &Queue)
+                           -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        self.previous.check_buffer_access(buffer, exclusive, queue)
+    }
+
+    #[inline]
+    fn check_image_access(&self, image: &ImageAccess, exclusive: bool, queue: &Queue)
+                          -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        self.previous.check_image_access(image, exclusive, queue)
+    }
+
+    #[inline]
+    fn acquired_swapchain_image(&self, swapchain: &Swapchain) -> Option<usize> {
+        self.previous.acquired_swapchain_image(swapchain)
+    }
+
+    #[inline]
+    fn validate_resources(&self) {
+        self.previous.validate_resources();
+    }
+
+    #[inline]
+    fn wait_semaphore_count(&self) -> usize {
+        self.previous.wait_semaphore_count()
+    }
+
+    #[inline]
+    fn is_flushed(&self) -> bool {
+        *self.submitted.lock().unwrap()
+    }
+
+    #[inline]
+    fn shared_identities(&self, ids: &mut Vec<usize>) {
+        self.previous.shared_identities(ids);
+    }
+}
+
+unsafe impl<F> DeviceOwned for TimestampFuture<F> where F: GpuFuture {
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        self.queue.device()
+    }
+}
+
+impl<F> Drop for TimestampFuture<F> where F: GpuFuture {
+    fn drop(&mut self) {
+        unsafe {
+            if !*self.finished.get_mut() {
+                // TODO: handle errors?
+                self.flush().unwrap();
+                // Block until the queue finished.
+                self.queue.wait().unwrap();
+                self.previous.signal_finished();
+            }
+        }
+    }
+}
+
+// The queries written by a `TimestampFuture`. Kept by the `FenceSignalFuture` after the
+// `TimestampFuture` has been destroyed, so that the results can still be read.
+pub struct TimestampQueries {
+    pool: Arc<UnsafeQueryPool>,
+    start: u32,
+    end: u32,
+    // Number of meaningful bits of the timestamps of the queue family.
+    valid_bits: u32,
+    // Number of nanoseconds per timestamp increment.
+    period: f32,
+}
+
+impl TimestampQueries {
+    // Reads the two timestamps and returns the duration between them. Must only be called once
+    // the submission that writes them is finished.
+    pub fn elapsed(&self) -> Option<Duration> {
+        let start = match self.read(self.start) { Some(t) => t, None => return None };
+        let end = match self.read(self.end) { Some(t) => t, None => return None };
+
+        // The timestamps may have wrapped around between the two writes.
+        let mask = if self.valid_bits >= 64 { !0 } else { (1u64 << self.valid_bits) - 1 };
+        let ticks = end.wrapping_sub(start) & mask;
+
+        let nanos = (ticks as f64 * self.period as f64) as u64;
+        Some(Duration::new(nanos / 1_000_000_000, (nanos % 1_000_000_000) as u32))
+    }
+
+    fn read(&self, query: u32) -> Option<u64> {
+        unsafe {
+            let mut value: u64 = 0;
+            let size = mem::size_of::<u64>();
+
+            let device = self.pool.device();
+            let vk = device.pointers();
+            let r = check_errors(vk.GetQueryPoolResults(device.internal_object(),
+                                                        self.pool.internal_object(), query, 1,
+                                                        size,
+                                                        &mut value as *mut u64 as *mut c_void,
+                                                        size as vk::DeviceSize,
+                                                        vk::QUERY_RESULT_64_BIT));
+            match r {
+                Ok(Success::Success) => Some(value),
+                _ => None,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use command_buffer::CommandBuffer;
+    use query::QueryType;
+    use query::UnsafeQueryPool;
+    use sync::GpuFuture;
+
+    #[test]
+    fn elapsed() {
+        let (device, queue) = gfx_dev_and_queue!();
+        if queue.family().timestamp_valid_bits() == 0 {
+            return;
+        }
+
+        let pool = Arc::new(UnsafeQueryPool::new(device.clone(), QueryType::Timestamp, 2)
+                                .unwrap());
+        let cb = empty_command_buffer!(device, queue);
+        let future = cb.execute(queue.clone())
+            .then_signal_fence_with_timestamps(pool, 0, 1).unwrap();
+        assert!(future.elapsed().is_none());
+
+        future.flush().unwrap();
+        future.wait(Duration::from_secs(5)).unwrap();
+        assert!(future.elapsed().is_some());
+    }
+
+    #[test]
+    #[should_panic]
+    fn same_query() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let pool = Arc::new(UnsafeQueryPool::new(device.clone(), QueryType::Timestamp, 2)
+                                .unwrap());
+        let cb = empty_command_buffer!(device, queue);
+        let _ = cb.execute(queue.clone()).then_signal_fence_with_timestamps(pool, 1, 1);
+    }
+}
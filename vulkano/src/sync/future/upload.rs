@@ -0,0 +1,223 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::error;
+use std::error::Error;
+use std::fmt;
+use std::mem;
+use std::sync::Arc;
+
+use buffer::Buffer;
+use buffer::BufferAccess;
+use buffer::BufferUsage;
+use buffer::CpuAccessibleBuffer;
+use buffer::DeviceLocalBuffer;
+use command_buffer::commands_raw::CmdCopyBuffer;
+use command_buffer::commands_raw::CmdPipelineBarrier;
+use device::Queue;
+use memory::Content;
+use sync::AccessFlagBits;
+use sync::GpuFuture;
+use sync::PipelineStages;
+use sync::future::one_shot::OneShotBuilder;
+use sync::future::one_shot::OneShotCommandBuffer;
+use sync::future::one_shot::OneShotResources;
+use OomError;
+use VulkanObject;
+
+/// Command buffer that copies data from a staging buffer to a device-local buffer.
+///
+/// Created with `GpuFuture::then_upload()`.
+pub type UploadCommandBuffer<T> = OneShotCommandBuffer<UploadResources<T>>;
+
+/// Resources of an `UploadCommandBuffer`.
+pub struct UploadResources<T> where T: 'static + Send + Sync {
+    staging: Arc<CpuAccessibleBuffer<[T]>>,
+    destination: Arc<DeviceLocalBuffer<[T]>>,
+}
+
+impl<T> OneShotCommandBuffer<UploadResources<T>> where T: Content + Copy + Send + Sync + 'static {
+    /// Builds a command buffer that uploads `data` to `destination`, for the queue family of
+    /// `queue`.
+    ///
+    /// The data is written to a new staging buffer. If `future` accesses `destination`, a
+    /// pipeline barrier is added before the copy so that the copy doesn't overwrite the buffer
+    /// while these accesses are still in progress.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if the length of `data` is not the same as the length of `destination`.
+    ///
+    pub fn new(future: &GpuFuture, queue: &Queue, data: &[T],
+               destination: Arc<DeviceLocalBuffer<[T]>>)
+               -> Result<UploadCommandBuffer<T>, UploadError>
+    {
+        assert_eq!(data.len() * mem::size_of::<T>(), destination.size());
+
+        let dest_access = destination.clone().access();
+        if !dest_access.inner().buffer.usage_transfer_dest() {
+            return Err(UploadError::DestinationMissingTransferUsage);
+        }
+
+        let staging = try!(CpuAccessibleBuffer::from_iter(queue.device(),
+                                                          &BufferUsage::transfer_source(),
+                                                          Some(queue.family()),
+                                                          data.iter().cloned()));
+
+        let builder = try!(unsafe { OneShotBuilder::new(queue) });
+
+        let builder = match future.check_buffer_access(&dest_access, true, queue) {
+            Ok(Some((stages, access))) => unsafe {
+                let mut barrier = CmdPipelineBarrier::new();
+                barrier.add_buffer_memory_barrier(&dest_access, stages, access,
+                                                  PipelineStages { transfer: true,
+                                                                   .. PipelineStages::none() },
+                                                  AccessFlagBits { transfer_write: true,
+                                                                   .. AccessFlagBits::none() },
+                                                  false, None, 0, dest_access.size());
+                builder.add(&barrier)
+            },
+            _ => builder,
+        };
+
+        let builder = {
+            // Can't fail, as the usage of the destination has been checked above and the
+            // staging buffer is a different buffer.
+            let copy = CmdCopyBuffer::new(staging.clone(), dest_access).unwrap();
+            builder.add(&copy)
+        };
+
+        Ok(try!(builder.build(UploadResources {
+            staging: staging,
+            destination: destination,
+        })))
+    }
+
+    /// Returns the buffer that the data is uploaded to.
+    #[inline]
+    pub fn destination(&self) -> &Arc<DeviceLocalBuffer<[T]>> {
+        &self.resources().destination
+    }
+}
+
+unsafe impl<T> OneShotResources for UploadResources<T>
+    where T: Content + Copy + Send + Sync + 'static
+{
+    fn submit_check(&self, future: &GpuFuture, queue: &Queue) -> Result<(), Box<Error>> {
+        assert!(self.staging.try_gpu_lock(false, queue));
+
+        let dest_access = self.destination.clone().access();
+        if future.check_buffer_access(&dest_access, true, queue).is_ok() {
+            unsafe { dest_access.increase_gpu_lock(); }
+        } else {
+            assert!(dest_access.try_gpu_lock(true, queue), "The destination of an upload is \
+                                                            already in use");
+        }
+
+        Ok(())
+    }
+
+    #[inline]
+    fn check_buffer_access(&self, buffer: &BufferAccess, _: bool, _: &Queue)
+                           -> Result<Option<(PipelineStages, AccessFlagBits)>, ()>
+    {
+        let transfer = PipelineStages { transfer: true, .. PipelineStages::none() };
+        let raw = buffer.inner().buffer.internal_object();
+
+        if raw == self.destination.clone().access().inner().buffer.internal_object() {
+            Ok(Some((transfer, AccessFlagBits { transfer_write: true, .. AccessFlagBits::none() })))
+        } else if raw == self.staging.inner().buffer.internal_object() {
+            Ok(Some((transfer, AccessFlagBits { transfer_read: true, .. AccessFlagBits::none() })))
+        } else {
+            Err(())
+        }
+    }
+}
+
+/// Error that can happen when calling `GpuFuture::then_upload()`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum UploadError {
+    /// Not enough memory to create the staging buffer or the command buffer.
+    OomError(OomError),
+
+    /// The destination buffer wasn't created with the `transfer_dest` usage.
+    DestinationMissingTransferUsage,
+}
+
+impl error::Error for UploadError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            UploadError::OomError(_) => "not enough memory",
+            UploadError::DestinationMissingTransferUsage => {
+                "the destination buffer is missing the transfer destination usage"
+            },
+        }
+    }
+
+    #[inline]
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            UploadError::OomError(ref err) => Some(err),
+            _ => None
+        }
+    }
+}
+
+impl fmt::Display for UploadError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+impl From<OomError> for UploadError {
+    #[inline]
+    fn from(err: OomError) -> UploadError {
+        UploadError::OomError(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use buffer::BufferUsage;
+    use buffer::DeviceLocalBuffer;
+    use command_buffer::CommandBuffer;
+    use sync::GpuFuture;
+    use sync::UploadError;
+
+    #[test]
+    fn upload() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let buffer = DeviceLocalBuffer::<[u32]>::array(&device, 4, &BufferUsage::transfer_dest(),
+                                                       Some(queue.family())).unwrap();
+
+        let cb = empty_command_buffer!(device, queue);
+
+        cb.execute(queue.clone())
+          .then_upload(&[1, 2, 3, 4], buffer).unwrap()
+          .then_signal_fence_and_flush().unwrap();
+    }
+
+    #[test]
+    fn missing_usage() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let buffer = DeviceLocalBuffer::<[u32]>::array(&device, 4, &BufferUsage::vertex_buffer(),
+                                                       Some(queue.family())).unwrap();
+
+        let cb = empty_command_buffer!(device, queue);
+
+        match cb.execute(queue.clone()).then_upload(&[1, 2, 3, 4], buffer) {
+            Err(UploadError::DestinationMissingTransferUsage) => (),
+            _ => panic!()
+        }
+    }
+}
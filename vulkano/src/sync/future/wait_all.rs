@@ -0,0 +1,137 @@
+// Copyright (c) 2017 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+use std::time::Duration;
+
+use command_buffer::submit::SubmitCommandBufferBuilder;
+use device::DeviceOwned;
+use sync::Fence;
+use sync::FenceWaitError;
+use sync::FlushError;
+use sync::GpuFuture;
+use VulkanObject;
+
+/// Flushes multiple futures, possibly on different queues, and blocks the current thread until
+/// all of them are finished or until `timeout` has elapsed.
+///
+/// A fence is signaled after each future that is bound to a queue, and all the fences are waited
+/// upon at once. Contrary to joining the futures and signaling a single fence, this works for any
+/// combination of queues.
+///
+/// If the timeout is reached, `FlushError::Timeout` is returned and the futures are handed over
+/// to their queues, as with `GpuFuture::leak_on_drop()`, so that this function never blocks
+/// longer than the timeout. They are destroyed by `Queue::cleanup_leaked()` or `Queue::wait()`.
+///
+/// Futures that aren't bound to a queue, such as a `DummyFuture`, are only flushed. They are
+/// destroyed once the other futures are finished, which blocks if they aren't finished yet.
+///
+/// If flushing a future fails, the error is returned and all the futures are destroyed
+/// normally, which blocks until those that have already been flushed are finished.
+///
+/// # Panic
+///
+/// - Panics if the futures don't all belong to the same device.
+///
+pub fn wait_all_queues(futures: Vec<Box<GpuFuture + Send>>, timeout: Duration)
+                       -> Result<(), FlushError>
+{
+    if let Some(first) = futures.first() {
+        let device = first.device().internal_object();
+        for future in futures.iter() {
+            assert_eq!(future.device().internal_object(), device,
+                       "The futures passed to wait_all_queues() don't all belong to the same \
+                        device");
+        }
+    }
+
+    let mut signaled = Vec::with_capacity(futures.len());
+    let mut unbound = Vec::new();
+
+    for future in futures {
+        let queue = match future.queue() {
+            Some(queue) => queue.clone(),
+            None => {
+                try!(future.flush());
+                unbound.push(future);
+                continue;
+            },
+        };
+
+        try!(future.flush());
+
+        let fence = try!(Fence::new(future.device().clone()));
+
+        // A fence signal operation waits for all the previous submissions on the same queue,
+        // therefore an empty submission is enough.
+        unsafe {
+            let mut builder = SubmitCommandBufferBuilder::new();
+            builder.set_fence_signal(&fence);
+            try!(builder.submit(&queue));
+        }
+
+        signaled.push((future, queue, fence));
+    }
+
+    match Fence::multi_wait(signaled.iter().map(|&(_, _, ref fence)| fence), timeout) {
+        Ok(()) => {
+            for (future, _, _) in signaled {
+                unsafe { future.signal_finished(); }
+            }
+            Ok(())
+        },
+        Err(FenceWaitError::Timeout) => {
+            for (future, queue, fence) in signaled {
+                // Safe because the future has been flushed and the fence is signaled after it.
+                unsafe { queue.keep_alive_until_finished(future, fence); }
+            }
+            Err(FlushError::Timeout)
+        },
+        Err(err) => Err(FlushError::from(err)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use command_buffer::CommandBuffer;
+    use sync::DummyFuture;
+    use sync::GpuFuture;
+    use sync::wait_all_queues;
+
+    #[test]
+    fn wait_all() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let cb1 = empty_command_buffer!(device, queue);
+        let cb2 = empty_command_buffer!(device, queue);
+
+        let futures: Vec<Box<GpuFuture + Send>> = vec![
+            Box::new(cb1.execute(queue.clone())),
+            Box::new(cb2.execute(queue.clone())),
+            Box::new(DummyFuture::new(device.clone())),
+        ];
+
+        wait_all_queues(futures, Duration::from_secs(5)).unwrap();
+    }
+
+    #[test]
+    #[should_panic]
+    fn different_devices() {
+        let (device1, _) = gfx_dev_and_queue!();
+        let (device2, _) = gfx_dev_and_queue!();
+
+        let futures: Vec<Box<GpuFuture + Send>> = vec![
+            Box::new(DummyFuture::new(device1)),
+            Box::new(DummyFuture::new(device2)),
+        ];
+
+        let _ = wait_all_queues(futures, Duration::from_secs(5));
+    }
+}
@@ -102,21 +102,141 @@
 //! TODO: lots of problems with how to use fences
 //! TODO: talk about fence + semaphore simultaneously
 //! TODO: talk about using fences to clean up
+//!
+//! # Strict mode
+//!
+//! Some of the checks performed by the futures verify that the futures or the submission
+//! builders uphold the safety contract of `GpuFuture`, which can be broken by a custom
+//! implementation of the trait. Since these checks have a cost and since the futures provided by
+//! vulkano never break the contract, they are only performed in debug builds.
+//!
+//! Enabling the `strict-sync` feature of the crate makes them always active, including in
+//! release builds. A failed check panics. The checks affected are:
+//!
+//! - `JoinFuture::check_buffer_access` and `JoinFuture::check_image_access` check that the two
+//!   joined futures don't both grant exclusive access to the same resource, and that they don't
+//!   access it in incompatible ways (see `AccessFlagBits::checked_or`). The cost is an extra
+//!   comparison per resource and per join in the chain, every time an access is checked.
+//! - `JoinFuture` checks that its two futures belong to the same device every time `device()` is
+//!   called.
+//! - `FenceSignalFuture` checks that the submission it builds doesn't already signal a fence, and
+//!   that a partially flushed future isn't retried with a submission other than a present. This
+//!   is done once per flush.
+//! - `SemaphoreSignalFuture` checks that the command buffer submission it builds doesn't already
+//!   signal a semaphore when it is flushed, and that it has been flushed when
+//!   `signal_finished()` is called.
+//! - `SwapchainAcquireFuture` and `PresentFuture` check that their swapchain image hasn't been
+//!   destroyed when `validate_resources()` is called, which `FenceSignalFuture` does once per
+//!   flush.
+//!
+//! Checks that protect against errors that vulkano can't rule out, such as the queue family
+//! checks, are always active.
 
 use std::sync::Arc;
 use device::Queue;
 
 pub use self::event::Event;
+pub use self::fence::Deadline;
+pub use self::fence::ExternalFenceHandleType;
 pub use self::fence::Fence;
+pub use self::fence::FencePool;
+pub use self::fence::FenceImportError;
 pub use self::fence::FenceWaitError;
+pub use self::future::AccessMaskFuture;
+pub use self::future::AddressedBufferFuture;
+pub use self::future::BarrierFuture;
+pub use self::future::BlitCommandBuffer;
+pub use self::future::BlitError;
+pub use self::future::BudgetedFuture;
+pub use self::future::BufferReadback;
+pub use self::future::CompletionTracker;
+pub use self::future::DeferredExecFuture;
+pub use self::future::DispatchCommandBuffer;
+pub use self::future::DispatchError;
 pub use self::future::DummyFuture;
+pub use self::future::EventSignalFuture;
+pub use self::future::EventWaitCommandBuffer;
+pub use self::future::ExecutionBarrierCommandBuffer;
+pub use self::future::ExternalAcquireFuture;
+pub use self::future::ExternalFenceWaitFuture;
 pub use self::future::GpuFuture;
+pub use self::future::GpuFutureMut;
 pub use self::future::SemaphoreSignalFuture;
+pub use self::future::DetachedFenceSignalFuture;
 pub use self::future::FenceSignalFuture;
+#[cfg(feature = "async")]
+pub use self::future::FenceSignalFutureAsync;
+pub use self::future::FenceSignalFutureStatus;
+pub use self::future::FlushError;
+pub use self::future::FrameToken;
+pub use self::future::HostEventWaitCommandBuffer;
+pub use self::future::HostWriteBarrierCommandBuffer;
+pub use self::future::ImageBarrierCommandBuffer;
+pub use self::future::JoinAllFuture;
 pub use self::future::JoinFuture;
+pub use self::future::LazyJoinFuture;
+pub use self::future::LeakOnDropFuture;
+pub use self::future::MaxInflightFuture;
+pub use self::future::NamedSemaphores;
+pub use self::future::OneShotCommandBuffer;
+pub use self::future::OneShotResources;
+pub use self::future::PerformanceQueryFuture;
+pub use self::future::PipelineBarrierBuilder;
+pub use self::future::PresentFenceStrategy;
+pub use self::future::QueryPoolResetCommandBuffer;
+pub use self::future::QueryResultsCopyCommandBuffer;
+pub use self::future::QueryResultsCopyError;
+pub use self::future::QueueMismatch;
+pub use self::future::QueuePinnedFuture;
+pub use self::future::ReadbackCommandBuffer;
+pub use self::future::ReadbackError;
+pub use self::future::ResourceStateCache;
+pub use self::future::ResourceStateFuture;
+pub use self::future::StillPending;
+pub use self::future::SubmissionBudget;
+pub use self::future::SubmitHookFuture;
+pub use self::future::ThrottleFuture;
+pub use self::future::TimestampFuture;
+pub use self::future::TooManyWaitSemaphoresError;
+pub use self::future::UploadCommandBuffer;
+pub use self::future::UploadError;
+pub use self::future::WaitPriorPresentFuture;
+pub use self::future::WouldExceedBudget;
+pub use self::future::join_all;
+pub use self::future::now;
+pub use self::future::wait_all_queues;
 pub use self::pipeline::AccessFlagBits;
+pub use self::pipeline::AccessFlagBitsIter;
+pub use self::pipeline::InvalidAccessCombination;
+pub use self::pipeline::MemoryBarrierDesc;
 pub use self::pipeline::PipelineStages;
+pub use self::pipeline::PipelineStagesIter;
+pub use self::pipeline::minimal_barrier;
+pub use self::pipeline::supported_access;
+pub use self::semaphore::ExternalSemaphoreHandleType;
 pub use self::semaphore::Semaphore;
+pub use self::semaphore::SemaphoreImportError;
+pub use self::semaphore::SemaphorePool;
+
+// Assertion that is checked in debug builds, and in release builds too if the `strict-sync`
+// feature is enabled. See the "Strict mode" section of the documentation of this module and
+// update it when adding a new use.
+macro_rules! sync_assert {
+    ($($arg:tt)*) => (
+        if cfg!(any(debug_assertions, feature = "strict-sync")) {
+            assert!($($arg)*);
+        }
+    );
+}
+
+// Same as `sync_assert!` but for `assert_eq!`.
+macro_rules! sync_assert_eq {
+    ($($arg:tt)*) => (
+        if cfg!(any(debug_assertions, feature = "strict-sync")) {
+            assert_eq!($($arg)*);
+        }
+    );
+}
 
 mod event;
 mod fence;
@@ -7,17 +7,25 @@
 // notice may not be copied, modified, or distributed except
 // according to those terms.
 
+use std::error;
+use std::fmt;
 use std::ops;
+use std::vec;
+use device::Device;
+use device::DeviceExtensions;
 use vk;
 
 macro_rules! pipeline_stages {
-    ($($elem:ident => $val:expr,)+) => (
-        #[derive(Debug, Copy, Clone)]
+    ($($elem:ident => $val:expr,)+ ; $($ext_elem:ident => $ext_val:expr, [$ext:ident],)*) => (
+        #[derive(Copy, Clone)]
         #[allow(missing_docs)]
         pub struct PipelineStages {
             $(
                 pub $elem: bool,
             )+
+            $(
+                pub $ext_elem: bool,
+            )*
         }
 
         impl PipelineStages {
@@ -27,7 +35,100 @@ macro_rules! pipeline_stages {
                     $(
                         $elem: false,
                     )+
+                    $(
+                        $ext_elem: false,
+                    )*
+                }
+            }
+
+            /// Returns true if all the stages that are set can be used on a device that has the
+            /// given extensions enabled.
+            ///
+            /// Some stages, such as `acceleration_structure_build`, are only valid if the
+            /// corresponding device extension is enabled.
+            pub fn is_supported_by(&self, extensions: &DeviceExtensions) -> bool {
+                $(
+                    if self.$ext_elem && !extensions.$ext { return false; }
+                )*
+                true
+            }
+
+            /// Returns true if at least one of the stages of `other` is also set in `self`.
+            #[inline]
+            pub fn intersects(&self, other: &PipelineStages) -> bool {
+                $(
+                    if self.$elem && other.$elem { return true; }
+                )+
+                $(
+                    if self.$ext_elem && other.$ext_elem { return true; }
+                )*
+                false
+            }
+
+            /// Returns true if all the stages of `other` are also set in `self`.
+            #[inline]
+            pub fn contains(&self, other: &PipelineStages) -> bool {
+                $(
+                    if other.$elem && !self.$elem { return false; }
+                )+
+                $(
+                    if other.$ext_elem && !self.$ext_elem { return false; }
+                )*
+                true
+            }
+
+            /// Returns an iterator over the names of the stages that are set, in the order in
+            /// which the fields are declared.
+            pub fn iter(&self) -> PipelineStagesIter {
+                let mut names = Vec::new();
+                $(
+                    if self.$elem { names.push(stringify!($elem)); }
+                )+
+                $(
+                    if self.$ext_elem { names.push(stringify!($ext_elem)); }
+                )*
+                PipelineStagesIter { names: names.into_iter() }
+            }
+
+            /// Returns a version of these stages that can be used with the given device.
+            ///
+            /// Stages that the device can't use are never silently dropped, as this would remove
+            /// synchronization. Instead they are replaced with a supported superset:
+            ///
+            /// - `tessellation_control_shader` and `tessellation_evaluation_shader` are replaced
+            ///   with `all_graphics` if the `tessellation_shader` feature isn't enabled.
+            /// - `geometry_shader` is replaced with `all_graphics` if the `geometry_shader`
+            ///   feature isn't enabled.
+            /// - Stages that require a device extension are replaced with `all_commands` if the
+            ///   extension isn't enabled.
+            ///
+            /// The returned value is therefore always at least as strict as `self`.
+            pub fn sanitize_for(&self, device: &Device) -> PipelineStages {
+                let features = device.enabled_features();
+                let extensions = device.loaded_extensions();
+                let mut result = *self;
+
+                if !features.tessellation_shader &&
+                    (result.tessellation_control_shader || result.tessellation_evaluation_shader)
+                {
+                    result.tessellation_control_shader = false;
+                    result.tessellation_evaluation_shader = false;
+                    result.all_graphics = true;
+                }
+
+                if !features.geometry_shader && result.geometry_shader {
+                    result.geometry_shader = false;
+                    result.all_graphics = true;
                 }
+
+                $(
+                    if result.$ext_elem && !extensions.$ext {
+                        result.$ext_elem = false;
+                        result.all_commands = true;
+                    }
+                )*
+
+                result
             }
         }
 
@@ -40,6 +141,9 @@ macro_rules! pipeline_stages {
                     $(
                         $elem: self.$elem || rhs.$elem,
                     )+
+                    $(
+                        $ext_elem: self.$ext_elem || rhs.$ext_elem,
+                    )*
                 }
             }
         }
@@ -50,6 +154,114 @@ macro_rules! pipeline_stages {
                 $(
                     self.$elem = self.$elem || rhs.$elem;
                 )+
+                $(
+                    self.$ext_elem = self.$ext_elem || rhs.$ext_elem;
+                )*
+            }
+        }
+
+        impl ops::BitAnd for PipelineStages {
+            type Output = PipelineStages;
+
+            #[inline]
+            fn bitand(self, rhs: PipelineStages) -> PipelineStages {
+                PipelineStages {
+                    $(
+                        $elem: self.$elem && rhs.$elem,
+                    )+
+                    $(
+                        $ext_elem: self.$ext_elem && rhs.$ext_elem,
+                    )*
+                }
+            }
+        }
+
+        impl ops::BitAndAssign for PipelineStages {
+            #[inline]
+            fn bitand_assign(&mut self, rhs: PipelineStages) {
+                $(
+                    self.$elem = self.$elem && rhs.$elem;
+                )+
+                $(
+                    self.$ext_elem = self.$ext_elem && rhs.$ext_elem;
+                )*
+            }
+        }
+
+        // Removes the fields of `rhs` from `self`.
+        impl ops::Sub for PipelineStages {
+            type Output = PipelineStages;
+
+            #[inline]
+            fn sub(self, rhs: PipelineStages) -> PipelineStages {
+                PipelineStages {
+                    $(
+                        $elem: self.$elem && !rhs.$elem,
+                    )+
+                    $(
+                        $ext_elem: self.$ext_elem && !rhs.$ext_elem,
+                    )*
+                }
+            }
+        }
+
+        impl ops::SubAssign for PipelineStages {
+            #[inline]
+            fn sub_assign(&mut self, rhs: PipelineStages) {
+                $(
+                    self.$elem = self.$elem && !rhs.$elem;
+                )+
+                $(
+                    self.$ext_elem = self.$ext_elem && !rhs.$ext_elem;
+                )*
+            }
+        }
+
+        /// Iterator over the names of the stages that are set in a `PipelineStages`.
+        ///
+        /// Created with `PipelineStages::iter()`.
+        #[derive(Debug, Clone)]
+        pub struct PipelineStagesIter {
+            names: vec::IntoIter<&'static str>,
+        }
+
+        impl Iterator for PipelineStagesIter {
+            type Item = &'static str;
+
+            #[inline]
+            fn next(&mut self) -> Option<&'static str> {
+                self.names.next()
+            }
+
+            #[inline]
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                self.names.size_hint()
+            }
+        }
+
+        impl ExactSizeIterator for PipelineStagesIter {
+        }
+
+        // Prints the fields that are set, for example `a | b`, or `none`.
+        impl fmt::Display for PipelineStages {
+            fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+                let mut first = true;
+                for name in self.iter() {
+                    try!(write!(fmt, "{}{}", if first { "" } else { " | " }, name));
+                    first = false;
+                }
+                if first {
+                    try!(write!(fmt, "none"));
+                }
+                Ok(())
+            }
+        }
+
+        // Only prints the fields that are set, for example `PipelineStages(a | b)`.
+        impl fmt::Debug for PipelineStages {
+            #[inline]
+            fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+                write!(fmt, "PipelineStages({})", self)
             }
         }
 
@@ -61,9 +273,27 @@ macro_rules! pipeline_stages {
                 $(
                     if self.$elem { result |= $val }
                 )+
+                $(
+                    if self.$ext_elem { result |= $ext_val }
+                )*
                 result
             }
         }
+
+        #[doc(hidden)]
+        impl From<vk::PipelineStageFlagBits> for PipelineStages {
+            #[inline]
+            fn from(val: vk::PipelineStageFlagBits) -> PipelineStages {
+                PipelineStages {
+                    $(
+                        $elem: (val & $val) != 0,
+                    )+
+                    $(
+                        $ext_elem: (val & $ext_val) != 0,
+                    )*
+                }
+            }
+        }
     );
 }
 
@@ -85,25 +315,52 @@ pipeline_stages!{
     host => vk::PIPELINE_STAGE_HOST_BIT,
     all_graphics => vk::PIPELINE_STAGE_ALL_GRAPHICS_BIT,
     all_commands => vk::PIPELINE_STAGE_ALL_COMMANDS_BIT,
+    ;
+    ray_tracing_shader => vk::PIPELINE_STAGE_RAY_TRACING_SHADER_BIT_KHR,
+        [khr_ray_tracing_pipeline],
+    acceleration_structure_build => vk::PIPELINE_STAGE_ACCELERATION_STRUCTURE_BUILD_BIT_KHR,
+        [khr_acceleration_structure],
+    transform_feedback => vk::PIPELINE_STAGE_TRANSFORM_FEEDBACK_BIT_EXT,
+        [ext_transform_feedback],
+    conditional_rendering => vk::PIPELINE_STAGE_CONDITIONAL_RENDERING_BIT_EXT,
+        [ext_conditional_rendering],
+    command_process => vk::PIPELINE_STAGE_COMMAND_PROCESS_BIT_NVX,
+        [nvx_device_generated_commands],
+    shading_rate_image => vk::PIPELINE_STAGE_SHADING_RATE_IMAGE_BIT_NV,
+        [nv_shading_rate_image],
+    fragment_density_process => vk::PIPELINE_STAGE_FRAGMENT_DENSITY_PROCESS_BIT_EXT,
+        [ext_fragment_density_map],
+    task_shader => vk::PIPELINE_STAGE_TASK_SHADER_BIT_NV,
+        [nv_mesh_shader],
+    mesh_shader => vk::PIPELINE_STAGE_MESH_SHADER_BIT_NV,
+        [nv_mesh_shader],
 }
 
 macro_rules! access_flags {
-    ($($elem:ident => $val:expr,)+) => (
-        #[derive(Debug, Copy, Clone)]
+    ($($elem:ident => $val:expr,)+ ; $($ext_elem:ident => $ext_val:expr, [$ext:ident],)*) => (
+        #[derive(Copy, Clone)]
         #[allow(missing_docs)]
         pub struct AccessFlagBits {
             $(
                 pub $elem: bool,
             )+
+            $(
+                pub $ext_elem: bool,
+            )*
         }
 
         impl AccessFlagBits {
             /// Builds an `AccessFlagBits` struct with all bits set.
+            ///
+            /// Bits that require a device extension are not set.
             pub fn all() -> AccessFlagBits {
                 AccessFlagBits {
                     $(
                         $elem: true,
                     )+
+                    $(
+                        $ext_elem: false,
+                    )*
                 }
             }
 
@@ -113,8 +370,60 @@ macro_rules! access_flags {
                     $(
                         $elem: false,
                     )+
+                    $(
+                        $ext_elem: false,
+                    )*
                 }
             }
+
+            /// Returns true if all the bits that are set can be used on a device that has the
+            /// given extensions enabled.
+            ///
+            /// Some bits, such as `acceleration_structure_read`, are only valid if the
+            /// corresponding device extension is enabled.
+            pub fn is_supported_by(&self, extensions: &DeviceExtensions) -> bool {
+                $(
+                    if self.$ext_elem && !extensions.$ext { return false; }
+                )*
+                true
+            }
+
+            /// Returns true if at least one of the bits of `other` is also set in `self`.
+            #[inline]
+            pub fn intersects(&self, other: &AccessFlagBits) -> bool {
+                $(
+                    if self.$elem && other.$elem { return true; }
+                )+
+                $(
+                    if self.$ext_elem && other.$ext_elem { return true; }
+                )*
+                false
+            }
+
+            /// Returns true if all the bits of `other` are also set in `self`.
+            #[inline]
+            pub fn contains(&self, other: &AccessFlagBits) -> bool {
+                $(
+                    if other.$elem && !self.$elem { return false; }
+                )+
+                $(
+                    if other.$ext_elem && !self.$ext_elem { return false; }
+                )*
+                true
+            }
+
+            /// Returns an iterator over the names of the access flags that are set, in the order in
+            /// which the fields are declared.
+            pub fn iter(&self) -> AccessFlagBitsIter {
+                let mut names = Vec::new();
+                $(
+                    if self.$elem { names.push(stringify!($elem)); }
+                )+
+                $(
+                    if self.$ext_elem { names.push(stringify!($ext_elem)); }
+                )*
+                AccessFlagBitsIter { names: names.into_iter() }
+            }
         }
 
         impl ops::BitOr for AccessFlagBits {
@@ -126,6 +435,9 @@ macro_rules! access_flags {
                     $(
                         $elem: self.$elem || rhs.$elem,
                     )+
+                    $(
+                        $ext_elem: self.$ext_elem || rhs.$ext_elem,
+                    )*
                 }
             }
         }
@@ -136,6 +448,114 @@ macro_rules! access_flags {
                 $(
                     self.$elem = self.$elem || rhs.$elem;
                 )+
+                $(
+                    self.$ext_elem = self.$ext_elem || rhs.$ext_elem;
+                )*
+            }
+        }
+
+        impl ops::BitAnd for AccessFlagBits {
+            type Output = AccessFlagBits;
+
+            #[inline]
+            fn bitand(self, rhs: AccessFlagBits) -> AccessFlagBits {
+                AccessFlagBits {
+                    $(
+                        $elem: self.$elem && rhs.$elem,
+                    )+
+                    $(
+                        $ext_elem: self.$ext_elem && rhs.$ext_elem,
+                    )*
+                }
+            }
+        }
+
+        impl ops::BitAndAssign for AccessFlagBits {
+            #[inline]
+            fn bitand_assign(&mut self, rhs: AccessFlagBits) {
+                $(
+                    self.$elem = self.$elem && rhs.$elem;
+                )+
+                $(
+                    self.$ext_elem = self.$ext_elem && rhs.$ext_elem;
+                )*
+            }
+        }
+
+        // Removes the fields of `rhs` from `self`.
+        impl ops::Sub for AccessFlagBits {
+            type Output = AccessFlagBits;
+
+            #[inline]
+            fn sub(self, rhs: AccessFlagBits) -> AccessFlagBits {
+                AccessFlagBits {
+                    $(
+                        $elem: self.$elem && !rhs.$elem,
+                    )+
+                    $(
+                        $ext_elem: self.$ext_elem && !rhs.$ext_elem,
+                    )*
+                }
+            }
+        }
+
+        impl ops::SubAssign for AccessFlagBits {
+            #[inline]
+            fn sub_assign(&mut self, rhs: AccessFlagBits) {
+                $(
+                    self.$elem = self.$elem && !rhs.$elem;
+                )+
+                $(
+                    self.$ext_elem = self.$ext_elem && !rhs.$ext_elem;
+                )*
+            }
+        }
+
+        /// Iterator over the names of the access flags that are set in a `AccessFlagBits`.
+        ///
+        /// Created with `AccessFlagBits::iter()`.
+        #[derive(Debug, Clone)]
+        pub struct AccessFlagBitsIter {
+            names: vec::IntoIter<&'static str>,
+        }
+
+        impl Iterator for AccessFlagBitsIter {
+            type Item = &'static str;
+
+            #[inline]
+            fn next(&mut self) -> Option<&'static str> {
+                self.names.next()
+            }
+
+            #[inline]
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                self.names.size_hint()
+            }
+        }
+
+        impl ExactSizeIterator for AccessFlagBitsIter {
+        }
+
+        // Prints the fields that are set, for example `a | b`, or `none`.
+        impl fmt::Display for AccessFlagBits {
+            fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+                let mut first = true;
+                for name in self.iter() {
+                    try!(write!(fmt, "{}{}", if first { "" } else { " | " }, name));
+                    first = false;
+                }
+                if first {
+                    try!(write!(fmt, "none"));
+                }
+                Ok(())
+            }
+        }
+
+        // Only prints the fields that are set, for example `AccessFlagBits(a | b)`.
+        impl fmt::Debug for AccessFlagBits {
+            #[inline]
+            fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+                write!(fmt, "AccessFlagBits({})", self)
             }
         }
 
@@ -147,6 +567,9 @@ macro_rules! access_flags {
                 $(
                     if self.$elem { result |= $val }
                 )+
+                $(
+                    if self.$ext_elem { result |= $ext_val }
+                )*
                 result
             }
         }
@@ -171,4 +594,423 @@ access_flags!{
     host_write => vk::ACCESS_HOST_WRITE_BIT,
     memory_read => vk::ACCESS_MEMORY_READ_BIT,
     memory_write => vk::ACCESS_MEMORY_WRITE_BIT,
+    ;
+    acceleration_structure_read => vk::ACCESS_ACCELERATION_STRUCTURE_READ_BIT_KHR,
+        [khr_acceleration_structure],
+    acceleration_structure_write => vk::ACCESS_ACCELERATION_STRUCTURE_WRITE_BIT_KHR,
+        [khr_acceleration_structure],
+}
+
+impl AccessFlagBits {
+    /// Merges two access masks, like the `|` operator, but returns an error if the result can't
+    /// possibly describe the accesses to a single resource.
+    ///
+    /// A resource can't be both a color attachment and a depth-stencil attachment, and accesses
+    /// that can only target buffers (like `index_read`) can't be mixed with accesses that can
+    /// only target images (like `input_attachment_read`).
+    pub fn checked_or(self, other: AccessFlagBits)
+                      -> Result<AccessFlagBits, InvalidAccessCombination>
+    {
+        let result = self | other;
+
+        let color = result.color_attachment_read || result.color_attachment_write;
+        let depth_stencil = result.depth_stencil_attachment_read ||
+                            result.depth_stencil_attachment_write;
+        if color && depth_stencil {
+            return Err(InvalidAccessCombination::ColorAndDepthStencilAttachment);
+        }
+
+        let buffer_only = result.indirect_command_read || result.index_read ||
+                          result.vertex_attribute_read || result.uniform_read;
+        let image_only = result.input_attachment_read || color || depth_stencil;
+        if buffer_only && image_only {
+            return Err(InvalidAccessCombination::BufferAndImageAccesses);
+        }
+
+        Ok(result)
+    }
+
+    /// Returns true if all the accesses that are set can be performed by at least one of
+    /// `stages`, according to `supported_access()`.
+    ///
+    /// Pairing an access with a stage that can't perform it in a barrier is rejected by the
+    /// validation layers.
+    #[inline]
+    pub fn is_compatible_with(&self, stages: &PipelineStages) -> bool {
+        let access: vk::AccessFlagBits = (*self).into();
+        let supported: vk::AccessFlagBits = supported_access(*stages).into();
+        (access & !supported) == 0
+    }
+}
+
+/// Returns the accesses that can be performed by at least one of `stages`, as described by the
+/// table of supported access types of the Vulkan specification.
+///
+/// `memory_read` and `memory_write` are supported by all the stages. `all_graphics` supports
+/// the accesses of all the graphics stages, and `all_commands` supports all the accesses.
+/// `top_of_pipe` and `bottom_of_pipe` don't perform any access on their own.
+pub fn supported_access(stages: PipelineStages) -> AccessFlagBits {
+    let mut result = AccessFlagBits::none();
+
+    if is_empty_stages(stages) {
+        return result;
+    }
+
+    if stages.all_commands {
+        return AccessFlagBits {
+            acceleration_structure_read: true,
+            acceleration_structure_write: true,
+            .. AccessFlagBits::all()
+        };
+    }
+
+    result.memory_read = true;
+    result.memory_write = true;
+
+    let graphics = stages.all_graphics;
+
+    let shader = graphics || stages.vertex_shader || stages.tessellation_control_shader ||
+                 stages.tessellation_evaluation_shader || stages.geometry_shader ||
+                 stages.fragment_shader || stages.compute_shader || stages.task_shader ||
+                 stages.mesh_shader || stages.ray_tracing_shader;
+    if shader {
+        result.uniform_read = true;
+        result.shader_read = true;
+        result.shader_write = true;
+    }
+
+    if graphics || stages.draw_indirect {
+        result.indirect_command_read = true;
+    }
+
+    if graphics || stages.vertex_input {
+        result.index_read = true;
+        result.vertex_attribute_read = true;
+    }
+
+    if graphics || stages.fragment_shader {
+        result.input_attachment_read = true;
+    }
+
+    if graphics || stages.color_attachment_output {
+        result.color_attachment_read = true;
+        result.color_attachment_write = true;
+    }
+
+    if graphics || stages.early_fragment_tests || stages.late_fragment_tests {
+        result.depth_stencil_attachment_read = true;
+        result.depth_stencil_attachment_write = true;
+    }
+
+    if stages.transfer {
+        result.transfer_read = true;
+        result.transfer_write = true;
+    }
+
+    if stages.host {
+        result.host_read = true;
+        result.host_write = true;
+    }
+
+    if stages.acceleration_structure_build {
+        result.indirect_command_read = true;
+        result.shader_read = true;
+        result.transfer_read = true;
+        result.transfer_write = true;
+        result.acceleration_structure_read = true;
+        result.acceleration_structure_write = true;
+    }
+
+    // Shaders read acceleration structures during ray queries and ray tracing.
+    if shader {
+        result.acceleration_structure_read = true;
+    }
+
+    result
+}
+
+/// Source and destination scopes of a memory barrier.
+///
+/// Returned by `minimal_barrier()`.
+#[derive(Debug, Copy, Clone)]
+pub struct MemoryBarrierDesc {
+    /// Stages that must have finished before the destination stages start.
+    pub source_stages: PipelineStages,
+    /// Writes of the source stages that must be made available. Empty if the barrier is only an
+    /// execution dependency.
+    pub source_access: AccessFlagBits,
+    /// Stages that wait for the source stages.
+    pub dest_stages: PipelineStages,
+    /// Accesses of the destination stages to which the writes must be made visible. Empty if the
+    /// barrier is only an execution dependency.
+    pub dest_access: AccessFlagBits,
+}
+
+/// Returns the smallest barrier that is needed between accesses `src` to a resource and
+/// accesses `dst` that follow them, or `None` if no barrier is needed.
+///
+/// - Reads that follow reads, and accesses on either side with empty stages or access masks,
+///   don't need a barrier.
+/// - Writes that follow reads only need an execution dependency, so that the reads are done
+///   before the writes start.
+/// - Accesses that follow writes need a memory dependency that makes the writes of `src`
+///   visible to all the accesses of `dst`. The reads of `src` are left out of the source access
+///   mask, as only writes can be made available.
+///
+/// Image layout transitions and queue family ownership transfers are writes that aren't
+/// described by access masks, and must be handled separately.
+pub fn minimal_barrier(src: (PipelineStages, AccessFlagBits),
+                       dst: (PipelineStages, AccessFlagBits))
+                       -> Option<MemoryBarrierDesc>
+{
+    let (src_stages, src_access) = src;
+    let (dst_stages, dst_access) = dst;
+
+    if is_empty_stages(src_stages) || is_empty_stages(dst_stages) ||
+       is_empty_access(src_access) || is_empty_access(dst_access)
+    {
+        return None;
+    }
+
+    let src_writes = src_access & write_accesses();
+    if !is_empty_access(src_writes) {
+        // Read-after-write or write-after-write.
+        return Some(MemoryBarrierDesc {
+            source_stages: src_stages,
+            source_access: src_writes,
+            dest_stages: dst_stages,
+            dest_access: dst_access,
+        });
+    }
+
+    if !is_empty_access(dst_access & write_accesses()) {
+        // Write-after-read.
+        return Some(MemoryBarrierDesc {
+            source_stages: src_stages,
+            source_access: AccessFlagBits::none(),
+            dest_stages: dst_stages,
+            dest_access: AccessFlagBits::none(),
+        });
+    }
+
+    None
+}
+
+// All the access flags that correspond to writes.
+#[inline]
+pub fn write_accesses() -> AccessFlagBits {
+    AccessFlagBits {
+        shader_write: true,
+        color_attachment_write: true,
+        depth_stencil_attachment_write: true,
+        transfer_write: true,
+        host_write: true,
+        memory_write: true,
+        acceleration_structure_write: true,
+        .. AccessFlagBits::none()
+    }
+}
+
+#[inline]
+fn is_empty_access(access: AccessFlagBits) -> bool {
+    Into::<vk::AccessFlagBits>::into(access) == 0
+}
+
+#[inline]
+fn is_empty_stages(stages: PipelineStages) -> bool {
+    Into::<vk::PipelineStageFlagBits>::into(stages) == 0
+}
+
+/// Error returned by `AccessFlagBits::checked_or`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum InvalidAccessCombination {
+    /// The result contains both color attachment and depth-stencil attachment accesses.
+    ColorAndDepthStencilAttachment,
+    /// The result contains both accesses that only apply to buffers and accesses that only apply
+    /// to images.
+    BufferAndImageAccesses,
+}
+
+impl error::Error for InvalidAccessCombination {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            InvalidAccessCombination::ColorAndDepthStencilAttachment => {
+                "a resource can't be both a color attachment and a depth-stencil attachment"
+            },
+            InvalidAccessCombination::BufferAndImageAccesses => {
+                "buffer-only and image-only accesses can't be applied to the same resource"
+            },
+        }
+    }
+}
+
+impl fmt::Display for InvalidAccessCombination {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use device::DeviceExtensions;
+    use sync::AccessFlagBits;
+    use sync::InvalidAccessCombination;
+    use sync::PipelineStages;
+    use sync::minimal_barrier;
+    use sync::supported_access;
+    use vk;
+
+    #[test]
+    fn checked_or_valid() {
+        let a = AccessFlagBits { shader_read: true, .. AccessFlagBits::none() };
+        let b = AccessFlagBits { color_attachment_write: true, .. AccessFlagBits::none() };
+        let result = a.checked_or(b).unwrap();
+        assert!(result.shader_read && result.color_attachment_write);
+    }
+
+    #[test]
+    fn checked_or_color_and_depth() {
+        let a = AccessFlagBits { color_attachment_write: true, .. AccessFlagBits::none() };
+        let b = AccessFlagBits { depth_stencil_attachment_read: true, .. AccessFlagBits::none() };
+        assert_eq!(a.checked_or(b).err(),
+                   Some(InvalidAccessCombination::ColorAndDepthStencilAttachment));
+    }
+
+    #[test]
+    fn debug_only_set_fields() {
+        let stages = PipelineStages { vertex_shader: true, fragment_shader: true,
+                                      .. PipelineStages::none() };
+        assert_eq!(format!("{:?}", stages), "PipelineStages(vertex_shader | fragment_shader)");
+        assert_eq!(format!("{:?}", AccessFlagBits::none()), "AccessFlagBits(none)");
+    }
+
+    #[test]
+    fn sanitize_widens_unsupported() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let stages = PipelineStages {
+            vertex_shader: true,
+            tessellation_control_shader: true,
+            .. PipelineStages::none()
+        };
+
+        let sanitized = stages.sanitize_for(&device);
+        assert!(sanitized.vertex_shader);
+        assert!(!sanitized.tessellation_control_shader);
+        assert!(sanitized.all_graphics);
+        assert!(!sanitized.all_commands);
+    }
+
+    #[test]
+    fn iter_and_display() {
+        let stages = PipelineStages { top_of_pipe: true, transfer: true,
+                                      .. PipelineStages::none() };
+        assert_eq!(stages.iter().collect::<Vec<_>>(), vec!["top_of_pipe", "transfer"]);
+        assert_eq!(format!("{}", stages), "top_of_pipe | transfer");
+        assert_eq!(format!("{}", PipelineStages::none()), "none");
+
+        let access = AccessFlagBits { acceleration_structure_read: true,
+                                      .. AccessFlagBits::none() };
+        assert_eq!(access.iter().len(), 1);
+        assert_eq!(format!("{}", access), "acceleration_structure_read");
+    }
+
+    #[test]
+    fn set_operations() {
+        let vertex = PipelineStages { vertex_shader: true, .. PipelineStages::none() };
+        let fragment = PipelineStages { fragment_shader: true, .. PipelineStages::none() };
+        let both = vertex | fragment;
+
+        assert!(both.contains(&vertex));
+        assert!(!vertex.contains(&both));
+        assert!(both.intersects(&fragment));
+        assert!(!vertex.intersects(&fragment));
+        assert_eq!(format!("{:?}", both & vertex), "PipelineStages(vertex_shader)");
+        assert_eq!(format!("{:?}", both - vertex), "PipelineStages(fragment_shader)");
+
+        let mut access = AccessFlagBits { shader_read: true, shader_write: true,
+                                          .. AccessFlagBits::none() };
+        access -= AccessFlagBits { shader_write: true, .. AccessFlagBits::none() };
+        assert_eq!(format!("{:?}", access), "AccessFlagBits(shader_read)");
+        assert!(access.contains(&AccessFlagBits::none()));
+        assert!(!access.intersects(&AccessFlagBits::none()));
+    }
+
+    #[test]
+    fn extension_stages() {
+        let stages = PipelineStages { mesh_shader: true, task_shader: true,
+                                      .. PipelineStages::none() };
+        assert!(!stages.is_supported_by(&DeviceExtensions::none()));
+        assert!(stages.is_supported_by(&DeviceExtensions { nv_mesh_shader: true,
+                                                           .. DeviceExtensions::none() }));
+
+        let raw: vk::PipelineStageFlagBits = stages.into();
+        assert_eq!(raw, vk::PIPELINE_STAGE_TASK_SHADER_BIT_NV |
+                        vk::PIPELINE_STAGE_MESH_SHADER_BIT_NV);
+        let back = PipelineStages::from(raw);
+        assert!(back.mesh_shader && back.task_shader && !back.transform_feedback);
+    }
+
+    #[test]
+    fn supported_access_table() {
+        let vertex = PipelineStages { vertex_shader: true, .. PipelineStages::none() };
+        let output = PipelineStages { color_attachment_output: true, .. PipelineStages::none() };
+        let write = AccessFlagBits { color_attachment_write: true, .. AccessFlagBits::none() };
+
+        assert!(!write.is_compatible_with(&vertex));
+        assert!(write.is_compatible_with(&output));
+        assert!(write.is_compatible_with(&(vertex | output)));
+        assert!(AccessFlagBits { uniform_read: true, .. AccessFlagBits::none() }
+                    .is_compatible_with(&vertex));
+
+        let all = PipelineStages { all_commands: true, .. PipelineStages::none() };
+        assert!(AccessFlagBits::all().is_compatible_with(&all));
+        assert!(AccessFlagBits::none().is_compatible_with(&PipelineStages::none()));
+        assert!(!supported_access(PipelineStages::none()).memory_read);
+    }
+
+    #[test]
+    fn minimal_barrier_read_after_read() {
+        let scope = (PipelineStages { fragment_shader: true, .. PipelineStages::none() },
+                     AccessFlagBits { shader_read: true, .. AccessFlagBits::none() });
+        assert!(minimal_barrier(scope, scope).is_none());
+    }
+
+    #[test]
+    fn minimal_barrier_write_after_read() {
+        let src = (PipelineStages { fragment_shader: true, .. PipelineStages::none() },
+                   AccessFlagBits { shader_read: true, .. AccessFlagBits::none() });
+        let dst = (PipelineStages { transfer: true, .. PipelineStages::none() },
+                   AccessFlagBits { transfer_write: true, .. AccessFlagBits::none() });
+
+        let barrier = minimal_barrier(src, dst).unwrap();
+        assert!(barrier.source_stages.fragment_shader);
+        assert!(barrier.dest_stages.transfer);
+        assert_eq!(format!("{:?}", barrier.source_access), "AccessFlagBits(none)");
+        assert_eq!(format!("{:?}", barrier.dest_access), "AccessFlagBits(none)");
+    }
+
+    #[test]
+    fn minimal_barrier_read_after_write() {
+        let src = (PipelineStages { compute_shader: true, .. PipelineStages::none() },
+                   AccessFlagBits { shader_read: true, shader_write: true,
+                                    .. AccessFlagBits::none() });
+        let dst = (PipelineStages { vertex_input: true, .. PipelineStages::none() },
+                   AccessFlagBits { vertex_attribute_read: true, .. AccessFlagBits::none() });
+
+        let barrier = minimal_barrier(src, dst).unwrap();
+        assert_eq!(format!("{:?}", barrier.source_access), "AccessFlagBits(shader_write)");
+        assert!(barrier.dest_access.vertex_attribute_read);
+    }
+
+    #[test]
+    fn minimal_barrier_empty_masks() {
+        let write = (PipelineStages { transfer: true, .. PipelineStages::none() },
+                     AccessFlagBits { transfer_write: true, .. AccessFlagBits::none() });
+        let nothing = (PipelineStages::none(), AccessFlagBits::none());
+        assert!(minimal_barrier(write, nothing).is_none());
+        assert!(minimal_barrier(nothing, write).is_none());
+    }
 }
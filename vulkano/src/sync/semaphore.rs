@@ -7,12 +7,17 @@
 // notice may not be copied, modified, or distributed except
 // according to those terms.
 
+use std::error;
+use std::fmt;
 use std::mem;
+use std::os::raw::c_int;
 use std::ptr;
 use std::sync::Arc;
+use std::sync::Mutex;
 
 use device::Device;
 use device::DeviceOwned;
+use Error;
 use OomError;
 use SafeDeref;
 use VulkanObject;
@@ -54,6 +59,137 @@ impl<D> Semaphore<D> where D: SafeDeref<Target = Device> {
             semaphore: semaphore,
         })
     }
+
+    /// Imports a semaphore payload from a POSIX file descriptor exported by another API or
+    /// process.
+    ///
+    /// If `temporary` is true, the imported payload only replaces the semaphore's payload until
+    /// the next wait operation on the semaphore. This is required for `SyncFd` handles.
+    ///
+    /// On success, the ownership of `fd` is transferred to the Vulkan implementation.
+    ///
+    /// # Safety
+    ///
+    /// - `fd` must be a valid file descriptor of the given handle type.
+    /// - The semaphore must not be in use by any pending queue operation.
+    ///
+    pub unsafe fn import_fd(&self, handle_type: ExternalSemaphoreHandleType, fd: c_int,
+                            temporary: bool)
+                            -> Result<(), SemaphoreImportError>
+    {
+        if !self.device.loaded_extensions().khr_external_semaphore_fd {
+            return Err(SemaphoreImportError::MissingExtension);
+        }
+
+        if handle_type == ExternalSemaphoreHandleType::SyncFd && !temporary {
+            return Err(SemaphoreImportError::PermanentSyncFd);
+        }
+
+        let infos = vk::ImportSemaphoreFdInfoKHR {
+            sType: vk::STRUCTURE_TYPE_IMPORT_SEMAPHORE_FD_INFO_KHR,
+            pNext: ptr::null(),
+            semaphore: self.semaphore,
+            flags: if temporary { vk::SEMAPHORE_IMPORT_TEMPORARY_BIT_KHR } else { 0 },
+            handleType: handle_type.into(),
+            fd: fd,
+        };
+
+        let vk = self.device.pointers();
+        try!(check_errors(vk.ImportSemaphoreFdKHR(self.device.internal_object(), &infos)));
+        Ok(())
+    }
+}
+
+/// Type of a handle that can be imported into a semaphore.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ExternalSemaphoreHandleType {
+    /// An opaque file descriptor, exported by Vulkan or a compatible API.
+    OpaqueFd,
+    /// A Linux sync file descriptor, for example one exported by a media driver.
+    SyncFd,
+}
+
+impl Into<vk::ExternalSemaphoreHandleTypeFlagBitsKHR> for ExternalSemaphoreHandleType {
+    #[inline]
+    fn into(self) -> vk::ExternalSemaphoreHandleTypeFlagBitsKHR {
+        match self {
+            ExternalSemaphoreHandleType::OpaqueFd => {
+                vk::EXTERNAL_SEMAPHORE_HANDLE_TYPE_OPAQUE_FD_BIT_KHR
+            },
+            ExternalSemaphoreHandleType::SyncFd => {
+                vk::EXTERNAL_SEMAPHORE_HANDLE_TYPE_SYNC_FD_BIT_KHR
+            },
+        }
+    }
+}
+
+/// Error that can happen when importing a semaphore payload.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SemaphoreImportError {
+    /// Not enough memory.
+    OomError(OomError),
+
+    /// The handle is not valid for the given handle type.
+    InvalidExternalHandle,
+
+    /// The `khr_external_semaphore_fd` extension was not enabled on the device.
+    MissingExtension,
+
+    /// Sync file descriptors can only be imported temporarily.
+    PermanentSyncFd,
+}
+
+impl error::Error for SemaphoreImportError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            SemaphoreImportError::OomError(_) => "not enough memory",
+            SemaphoreImportError::InvalidExternalHandle => {
+                "the handle is not valid for the given handle type"
+            },
+            SemaphoreImportError::MissingExtension => {
+                "the `khr_external_semaphore_fd` extension was not enabled"
+            },
+            SemaphoreImportError::PermanentSyncFd => {
+                "sync file descriptors can only be imported temporarily"
+            },
+        }
+    }
+
+    #[inline]
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            SemaphoreImportError::OomError(ref err) => Some(err),
+            _ => None
+        }
+    }
+}
+
+impl fmt::Display for SemaphoreImportError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+impl From<OomError> for SemaphoreImportError {
+    #[inline]
+    fn from(err: OomError) -> SemaphoreImportError {
+        SemaphoreImportError::OomError(err)
+    }
+}
+
+impl From<Error> for SemaphoreImportError {
+    #[inline]
+    fn from(err: Error) -> SemaphoreImportError {
+        match err {
+            e @ Error::OutOfHostMemory | e @ Error::OutOfDeviceMemory => {
+                SemaphoreImportError::OomError(e.into())
+            },
+            Error::InvalidExternalHandle => SemaphoreImportError::InvalidExternalHandle,
+            _ => panic!("unexpected error: {:?}", err)
+        }
+    }
 }
 
 unsafe impl DeviceOwned for Semaphore {
@@ -82,13 +218,114 @@ impl<D> Drop for Semaphore<D> where D: SafeDeref<Target = Device> {
     }
 }
 
+/// Pool of semaphores that can be reused instead of creating a new semaphore for each
+/// submission.
+///
+/// Cloning a `SemaphorePool` is cheap and returns a handle to the same pool, which makes it
+/// possible to share it between threads. All the semaphores of a pool belong to the same device.
+///
+/// Contrary to fences, semaphores can't be reset by the CPU. A semaphore can only be reused once
+/// every signal operation on it has been waited upon, and once these waits have finished
+/// executing on the GPU. `release()` is therefore unsafe. The
+/// `GpuFuture::then_signal_semaphore_from_pool()` method takes care of this automatically.
+#[derive(Debug, Clone)]
+pub struct SemaphorePool {
+    device: Arc<Device>,
+    semaphores: Arc<Mutex<Vec<Semaphore>>>,
+}
+
+impl SemaphorePool {
+    /// Builds a new empty pool for the given device.
+    #[inline]
+    pub fn new(device: Arc<Device>) -> SemaphorePool {
+        SemaphorePool {
+            device: device,
+            semaphores: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Returns a semaphore of the pool, or creates a new one if the pool is empty.
+    ///
+    /// The returned semaphore is unsignaled and not used by any pending submission.
+    pub fn acquire(&self) -> Result<Semaphore, OomError> {
+        let semaphore = self.semaphores.lock().unwrap().pop();
+        match semaphore {
+            Some(semaphore) => Ok(semaphore),
+            None => Semaphore::new(self.device.clone()),
+        }
+    }
+
+    /// Gives back a semaphore to the pool.
+    ///
+    /// # Safety
+    ///
+    /// - The semaphore must be unsignaled. Every signal operation submitted on it must have been
+    ///   followed with a wait operation.
+    /// - The submissions that signal or wait on the semaphore must have finished executing.
+    ///
+    /// Giving back a semaphore that is still pending means that a later submission could signal
+    /// it while it is already signaled, or wait on a signal that was meant for another
+    /// submission.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if the semaphore doesn't belong to the device of the pool.
+    ///
+    pub unsafe fn release(&self, semaphore: Semaphore) {
+        assert_eq!(semaphore.device().internal_object(), self.device.internal_object());
+        self.semaphores.lock().unwrap().push(semaphore);
+    }
+
+    /// Returns the number of semaphores that are available in the pool.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.semaphores.lock().unwrap().len()
+    }
+}
+
+unsafe impl DeviceOwned for SemaphorePool {
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use sync::ExternalSemaphoreHandleType;
     use sync::Semaphore;
+    use sync::SemaphoreImportError;
+    use sync::SemaphorePool;
+    use VulkanObject;
 
     #[test]
     fn semaphore_create() {
         let (device, _) = gfx_dev_and_queue!();
         let _ = Semaphore::new(device.clone());
     }
+
+    #[test]
+    fn semaphore_pool_reuse() {
+        let (device, _) = gfx_dev_and_queue!();
+
+        let pool = SemaphorePool::new(device.clone());
+        let semaphore = pool.acquire().unwrap();
+        let raw = semaphore.internal_object();
+        unsafe { pool.release(semaphore); }
+        assert_eq!(pool.len(), 1);
+
+        assert_eq!(pool.acquire().unwrap().internal_object(), raw);
+        assert_eq!(pool.len(), 0);
+    }
+
+    #[test]
+    fn import_fd_missing_extension() {
+        let (device, _) = gfx_dev_and_queue!();
+        let semaphore = Semaphore::new(device.clone()).unwrap();
+
+        match unsafe { semaphore.import_fd(ExternalSemaphoreHandleType::SyncFd, -1, true) } {
+            Err(SemaphoreImportError::MissingExtension) => (),
+            _ => panic!()
+        }
+    }
 }
@@ -65,3 +65,13 @@ macro_rules! gfx_dev_and_queue {
         (device, queues.next().unwrap())
     });
 }
+
+/// Builds a command buffer that doesn't contain any command, for the queue family of a queue.
+macro_rules! empty_command_buffer {
+    ($device:expr, $queue:expr) => ({
+        use command_buffer::AutoCommandBufferBuilder;
+        use command_buffer::CommandBufferBuild;
+
+        AutoCommandBufferBuilder::new($device.clone(), $queue.family()).unwrap().build().unwrap()
+    });
+}